@@ -0,0 +1,92 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Records the state of the git repositories of the evaluator and ro_scalar_set at build time.
+/// The state is passed to the application in SSE_GIT_* and SSE_RO_SCALAR_SET_GIT_* environment variables
+/// and left out when the sources are not in a git repository.
+/// The C header is checked against the C interface when building it with the cabi feature.
+fn main()
+{
+    let manifest_dir = std::env::var( "CARGO_MANIFEST_DIR" ).expect( "CARGO_MANIFEST_DIR is not set." );
+    record( Path::new( &manifest_dir ), "SSE_GIT" );
+    record( &Path::new( &manifest_dir ).join( "../ro_scalar_set" ), "SSE_RO_SCALAR_SET_GIT" );
+    if std::env::var( "CARGO_FEATURE_CABI" ).is_ok()
+    {
+        check_header( &Path::new( &manifest_dir ).join( "src/cabi.rs" ), &Path::new( &manifest_dir ).join( "include/scalar_set_eval.h" ) );
+    }
+}
+
+/// Fails the build if the functions declared in the C header differ from the functions exported by the C interface.
+/// The functions are compared by the name and the number of the parameters.
+fn check_header(
+    source: &Path,
+    header: &Path,
+)
+{
+    println!( "cargo:rerun-if-changed={}", header.display() );
+    let read = |file: &Path| std::fs::read_to_string( file ).unwrap_or_else( |_| panic!( "Failed to read {}.", file.display() ) );
+    let exported = functions( &read( source ), "pub extern \"C\" fn " );
+    let declared = functions( &read( header ), "" );
+    if exported != declared
+    {
+        panic!( "{} is out of sync with {}.\nExported: {:?}\nDeclared: {:?}", header.display(), source.display(), exported, declared );
+    }
+}
+
+/// Finds the functions starting with "sse_" after the prefix and counts their parameters.
+/// Returns the functions ordered by the name.
+fn functions(
+    text: &str,
+    prefix: &str,
+) -> Vec<( String, usize )>
+{
+    let mut functions = Vec::new();
+    let pattern = format!( "{}sse_", prefix );
+    let mut rest = text;
+    while let Some( start ) = rest.find( &pattern )
+    {
+        let declaration = &rest[ start + prefix.len().. ];
+        let open = declaration.find( '(' ).expect( "Function without parameters." );
+        let close = open + declaration[ open.. ].find( ')' ).expect( "Unterminated parameters." );
+        let name = declaration[ ..open ].trim().to_string();
+        let parameters = declaration[ open + 1..close ].trim();
+        let count = if parameters.is_empty() || parameters == "void" { 0 } else { parameters.trim_end_matches( ',' ).split( ',' ).count() };
+        functions.push( ( name, count ) );
+        rest = &declaration[ close.. ];
+    }
+    functions.sort();
+    return functions;
+}
+
+/// Emits the commit, the branch and the dirty state of the repository in the directory.
+fn record(
+    directory: &Path,
+    prefix: &str,
+)
+{
+    // The state changes with the commits and with the edits of the sources.
+    for watched in &[ ".git/HEAD", ".git/index", "src" ]
+    {
+        let watched = directory.join( watched );
+        if watched.exists()
+        {
+            println!( "cargo:rerun-if-changed={}", watched.display() );
+        }
+    }
+
+    let git = |args: &[&str]| -> Option<String> {
+        let output = Command::new( "git" ).arg( "-C" ).arg( directory ).args( args ).output().ok()?;
+        if !output.status.success()
+        {
+            return None;
+        }
+        return Some( String::from_utf8_lossy( &output.stdout ).trim().to_string() );
+    };
+    let state = ( git( &[ "rev-parse", "HEAD" ] ), git( &[ "rev-parse", "--abbrev-ref", "HEAD" ] ), git( &[ "status", "--porcelain" ] ) );
+    if let ( Some( commit ), Some( branch ), Some( status ) ) = state
+    {
+        println!( "cargo:rustc-env={}_COMMIT={}", prefix, commit );
+        println!( "cargo:rustc-env={}_BRANCH={}", prefix, branch );
+        println!( "cargo:rustc-env={}_DIRTY={}", prefix, !status.is_empty() );
+    }
+}
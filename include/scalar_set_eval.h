@@ -0,0 +1,46 @@
+/* C interface of scalar_set_eval built with the cabi feature. Mirrors src/cabi.rs. */
+#ifndef SCALAR_SET_EVAL_H
+#define SCALAR_SET_EVAL_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Set file opened for evaluation. */
+typedef struct SseHandle SseHandle;
+
+/* Token for cancelling evaluations in progress. */
+typedef struct SseToken SseToken;
+
+/* Opens the set file for evaluation. Returns NULL if the file cannot be mapped. */
+SseHandle* sse_open( const char* path );
+
+/* Counts the sets of the file with any of the given values.
+   Zero threads uses all the logical threads. Returns -1 if the evaluation fails. */
+int64_t sse_evaluate( const SseHandle* handle, const int32_t* values, size_t value_count, size_t threads );
+
+/* Counts the sets of the file with any of the given values until the token is cancelled.
+   Zero threads uses all the logical threads. Returns -1 if the evaluation fails and -2 if it was cancelled. */
+int64_t sse_evaluate_cancellable( const SseHandle* handle, const int32_t* values, size_t value_count, size_t threads,
+        const SseToken* token );
+
+/* Creates a token for cancelling evaluations from another thread. */
+SseToken* sse_token_new( void );
+
+/* Cancels the evaluations of the token. NULL tokens are ignored. */
+void sse_token_cancel( const SseToken* token );
+
+/* Frees the token after the evaluations using it have returned. NULL tokens are ignored. */
+void sse_token_free( SseToken* token );
+
+/* Closes the set file. NULL handles are ignored. */
+void sse_close( SseHandle* handle );
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,48 @@
+//! Selects the global allocator at build time for measuring its impact on the evaluation.
+
+#[cfg(all(feature="mimalloc", feature="jemalloc"))]
+compile_error!( "Only one of the mimalloc and jemalloc features can be enabled." );
+
+#[cfg(feature="mimalloc")]
+extern crate mimalloc_allocator as mimalloc;
+#[cfg(feature="jemalloc")]
+extern crate jemallocator;
+
+#[cfg(feature="mimalloc")]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[cfg(feature="jemalloc")]
+#[global_allocator]
+static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
+
+/// Gets the name of the allocator built into the application.
+pub fn name() -> &'static str
+{
+    if cfg!( feature="mimalloc" )
+    {
+        "mimalloc"
+    }
+    else if cfg!( feature="jemalloc" )
+    {
+        "jemalloc"
+    }
+    else
+    {
+        "system"
+    }
+}
+
+/// Checks that the requested allocator is the one built into the application.
+/// The global allocator cannot be changed at run time.
+pub fn check( requested: &str ) -> Result<(), String>
+{
+    match requested
+    {
+        "" => Ok( () ),
+        "system" | "mimalloc" | "jemalloc" if requested == name() => Ok( () ),
+        "system" => Err( format!( "The application was built with {}. Rebuild without the allocator features.", name() ) ),
+        "mimalloc" | "jemalloc" => Err( format!( "The application was built with {}. Rebuild with --features {}.", name(), requested ) ),
+        _ => Err( format!( "Unknown allocator: {}", requested ) ),
+    }
+}
@@ -0,0 +1,121 @@
+extern crate serde_json;
+extern crate std;
+
+use std::io::BufWriter;
+
+use encoding;
+use enumerations::*;
+
+/// Describes the sets of a generated file.
+/// The description determines the name of the file so that the files generated with different
+/// configurations can be kept in the same directory without collisions.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Artifact
+{
+    /// Prefix separating the files of different users or projects. Empty for no prefix.
+    pub namespace: String,
+    pub floats: bool,
+    pub set_count: u64,
+    pub set_size: i32,
+    /// Distribution the values are drawn from.
+    pub distribution: String,
+    pub min_value: i32,
+    pub max_value: i32,
+    /// Seed the sets must be generated with. Files generated with a random seed are named without it.
+    pub seed: Option<u64>,
+    pub encoding: String,
+}
+
+/// Metadata stored next to a generated file.
+#[derive(Serialize, Deserialize)]
+pub struct Manifest
+{
+    pub artifact: Artifact,
+    /// Seed the sets were generated with.
+    pub seed: u64,
+    /// Version of the application that generated the sets.
+    pub version: String,
+    /// Time of the generation in seconds since the epoch.
+    pub created: u64,
+}
+
+impl Artifact
+{
+    /// Describes sets of uniformly distributed values generated with a random seed.
+    pub fn new(
+        namespace: &str,
+        floats: bool,
+        set_count: u64,
+        set_size: i32,
+        min_value: i32,
+        max_value: i32,
+        encoding: Encoding,
+    ) -> Artifact
+    {
+        return Artifact {
+            namespace: namespace.to_string(),
+            floats: floats,
+            set_count: set_count,
+            set_size: set_size,
+            distribution: "uniform".to_string(),
+            min_value: min_value,
+            max_value: max_value,
+            seed: None,
+            encoding: encoding::name( encoding ).to_string(),
+        };
+    }
+
+    /// Gets the name of the file as
+    /// "[<namespace>_][<encoding>_]<type>_<count>_sets_with_<size>_values_<distribution>_<min>_to_<max>[_seed<seed>].bin".
+    /// The encoding is left out for raw sets.
+    pub fn file_name( &self ) -> String
+    {
+        let mut name = String::new();
+        if !self.namespace.is_empty()
+        {
+            name.push_str( &format!( "{}_", self.namespace ) );
+        }
+        if self.encoding != "raw"
+        {
+            name.push_str( &format!( "{}_", self.encoding ) );
+        }
+        name.push_str( &format!( "{}_{}_sets_with_{}_values_{}_{}_to_{}", if self.floats { "f32" } else { "i32" },
+                self.set_count, self.set_size, self.distribution, self.min_value, self.max_value ) );
+        if let Some( seed ) = self.seed
+        {
+            name.push_str( &format!( "_seed{}", seed ) );
+        }
+        name.push_str( ".bin" );
+        return name;
+    }
+
+    /// Stores the description and the seed of the generation next to the file.
+    pub fn write_manifest(
+        &self,
+        file: &str,
+        seed: u64,
+    )
+    {
+        let manifest = Manifest {
+            artifact: self.clone(),
+            seed: seed,
+            version: env!( "CARGO_PKG_VERSION" ).to_string(),
+            created: std::time::SystemTime::now().duration_since( std::time::UNIX_EPOCH ).map( |d| d.as_secs() ).unwrap_or( 0 ),
+        };
+        let writer = BufWriter::new( std::fs::File::create( get_manifest_file_name( file ) ).expect( "Failed to open the manifest." ) );
+        serde_json::to_writer_pretty( writer, &manifest ).expect( "Writing the manifest failed." );
+    }
+}
+
+/// Reads the manifest stored next to the file if any.
+pub fn read_manifest( file: &str ) -> Option<Manifest>
+{
+    let reader = std::fs::File::open( get_manifest_file_name( file ) ).ok()?;
+    return Some( serde_json::from_reader( reader ).expect( &format!( "Invalid manifest of {}.", file ) ) );
+}
+
+/// Gets the name of the manifest of the file.
+fn get_manifest_file_name( file: &str ) -> String
+{
+    return format!( "{}.manifest.json", file );
+}
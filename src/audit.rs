@@ -0,0 +1,100 @@
+extern crate rand;
+extern crate ro_scalar_set;
+extern crate std;
+
+use engine::{EngineRegistry, EvaluationEngine};
+use evaluation::*;
+use explain::SetMatch;
+use spill::Spool;
+use traits::*;
+
+/// Agreement of the engines on a random sample of the sets.
+pub struct Audit
+{
+    /// Engine the sample was evaluated with in addition to the main engine.
+    pub engine: String,
+    pub sets_audited: usize,
+    /// Matches found by the main engine from the sample.
+    pub match_count: u64,
+    /// Matches found by the audit engine from the sample.
+    pub audit_match_count: u64,
+    /// Indexes of the sets of the sample the engines disagree on.
+    pub mismatches: Vec<usize>,
+}
+
+impl Audit
+{
+    /// Prints the agreement of the engines.
+    pub fn print( &self )
+    {
+        println!( "Audited {} sets with the {} engine: {} matches, {} matches with the {} engine",
+                self.sets_audited, self.engine, self.match_count, self.audit_match_count, self.engine );
+        if !self.mismatches.is_empty()
+        {
+            println!( "WARNING: The engines disagree on {} sets.", self.mismatches.len() );
+        }
+    }
+}
+
+/// Evaluates each set of a random sample of the raw sets with the main engine and the audit engine of the parameters
+/// and compares the results of the sets.
+pub fn audit<T>(
+    data: &[u8],
+    test_set: &[T],
+    params: &EvaluationParams,
+    engine: &dyn EvaluationEngine<T>,
+    registry: &EngineRegistry<T>,
+    exclusion: Option<&[T]>,
+) -> Audit
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    let audit_engine = match registry.find( params.audit_engine )
+    {
+        Some( engine ) => engine,
+        None => panic!( "Unknown engine: {}", params.audit_engine ),
+    };
+    let mut filter = params.set_filter;
+    filter.sample = Some( ( rand::random(), params.audit ) );
+    let sampled = EvaluationParams { set_filter: filter, ..*params };
+    let matches = match_each( data, test_set, &sampled, engine, exclusion );
+    let audited = match_each( data, test_set, &sampled, audit_engine, exclusion );
+    return Audit {
+        engine: audit_engine.name().to_string(),
+        sets_audited: matches.len(),
+        match_count: matches.iter().filter( |m| m.matched ).count() as u64,
+        audit_match_count: audited.iter().filter( |m| m.matched ).count() as u64,
+        mismatches: disagreements( &matches, &audited ),
+    };
+}
+
+/// Evaluates each of the sets selected by the filter of the parameters once with both engines.
+/// Returns the indexes of the sets the engines disagree on.
+pub fn find_mismatches<T>(
+    data: &[u8],
+    test_set: &[T],
+    params: &EvaluationParams,
+    engine: &dyn EvaluationEngine<T>,
+    audit_engine: &dyn EvaluationEngine<T>,
+    exclusion: Option<&[T]>,
+) -> Vec<usize>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    let matches = match_each( data, test_set, params, engine, exclusion );
+    let audited = match_each( data, test_set, params, audit_engine, exclusion );
+    return disagreements( &matches, &audited );
+}
+
+/// Gets the indexes of the sets matched by only one of the engines.
+fn disagreements(
+    matches: &Spool<SetMatch>,
+    audited: &Spool<SetMatch>,
+) -> Vec<usize>
+{
+    return matches.iter()
+            .zip( audited.iter() )
+            .filter( |&( ref m, ref a )| m.matched != a.matched )
+            .map( |( m, _ )| m.index )
+            .collect();
+}
@@ -0,0 +1,181 @@
+extern crate serde_json;
+extern crate std;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::io::{BufRead, BufReader};
+use std::io::prelude::*;
+use std::process::{Command, Stdio};
+
+use results::Environment;
+use signature;
+
+/// Option appending a record of the run to the audit log.
+const AUDIT_LOG_OPTION: &'static str = "--audit-log";
+
+/// Environment variable selecting the audit log when the option is not given.
+const AUDIT_LOG_VARIABLE: &'static str = "SSE_AUDIT_LOG";
+
+/// Number of the last lines of the output kept as the summary of the result.
+const SUMMARY_LINES: usize = 20;
+
+/// Hash of a file existing before the run.
+#[derive(Serialize)]
+pub struct InputHash
+{
+    pub file: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Record of a single run appended to the audit log as a JSON line.
+#[derive(Serialize)]
+pub struct AuditRecord
+{
+    /// Start of the run in seconds since the epoch.
+    pub started: u64,
+    pub command: String,
+    /// Arguments as given on the command line without the audit log.
+    pub arguments: Vec<String>,
+    /// Arguments with the options of the environment and the profile applied.
+    pub effective_arguments: Vec<String>,
+    /// Hashes of the input files of the command before the run.
+    pub inputs: Vec<InputHash>,
+    /// Last lines of the output and the errors of the run.
+    pub summary: Vec<String>,
+    pub duration_ms: u64,
+    /// Exit code of the run or None if the run was terminated by a signal.
+    pub exit_status: Option<i32>,
+    pub environment: Environment,
+}
+
+/// Takes the audit log from the arguments or from SSE_AUDIT_LOG.
+/// Returns the rest of the arguments and the file of the audit log if any.
+pub fn take( arguments: Vec<String> ) -> ( Vec<String>, Option<String> )
+{
+    let mut remaining = Vec::with_capacity( arguments.len() );
+    let mut log = None;
+    let mut arguments = arguments.into_iter();
+    while let Some( argument ) = arguments.next()
+    {
+        if argument == AUDIT_LOG_OPTION
+        {
+            log = Some( arguments.next().expect( "The file of the audit log is missing." ) );
+        }
+        else if argument.starts_with( &format!( "{}=", AUDIT_LOG_OPTION ) )
+        {
+            log = Some( argument[ AUDIT_LOG_OPTION.len() + 1.. ].to_string() );
+        }
+        else
+        {
+            remaining.push( argument );
+        }
+    }
+    return ( remaining, log.or_else( || std::env::var( AUDIT_LOG_VARIABLE ).ok() ) );
+}
+
+/// Runs the command of the arguments in a child process and appends the record of the run to the audit log.
+/// The output and the errors of the run are passed through and their last lines are kept as the summary of the result.
+/// The input files are hashed unless they are missing, e.g. remote files.
+/// Returns the exit code of the run.
+pub fn run(
+    log: &str,
+    arguments: &Vec<String>,
+    effective_arguments: &Vec<String>,
+    input_files: &Vec<String>,
+) -> i32
+{
+    // The inputs are hashed before the run may modify them.
+    let inputs: Vec<InputHash> = input_files.iter()
+            .filter( |f| std::path::Path::new( f ).is_file() )
+            .map( |f| InputHash { file: f.clone(), bytes: std::fs::metadata( f ).map( |m| m.len() ).unwrap_or( 0 ),
+                    sha256: signature::digest( f ) } )
+            .collect();
+
+    let started = std::time::SystemTime::now().duration_since( std::time::UNIX_EPOCH ).map( |d| d.as_secs() ).unwrap_or( 0 );
+    let start = std::time::Instant::now();
+    let executable = std::env::current_exe().expect( "Failed to locate the executable." );
+    let mut child = Command::new( &executable )
+            .args( arguments.iter().skip( 1 ) )
+            .env_remove( AUDIT_LOG_VARIABLE )
+            .stdout( Stdio::piped() )
+            .stderr( Stdio::piped() )
+            .spawn()
+            .expect( "Failed to start the audited run." );
+
+    // The errors are read in a thread of their own so that neither of the pipes fills up.
+    let summary: Arc<Mutex<VecDeque<String>>> = Arc::new( Mutex::new( VecDeque::with_capacity( SUMMARY_LINES ) ) );
+    let errors = child.stderr.take().map( |errors| {
+        let summary = summary.clone();
+        std::thread::spawn( move || pass_through( errors, &summary, true ) )
+    } );
+    if let Some( output ) = child.stdout.take()
+    {
+        pass_through( output, &summary, false );
+    }
+    if let Some( errors ) = errors
+    {
+        errors.join().expect( "Reading the errors of the audited run failed." );
+    }
+    let status = child.wait().expect( "Waiting for the audited run failed." );
+    let summary = std::mem::replace( &mut *summary.lock().unwrap(), VecDeque::new() );
+    let duration = start.elapsed();
+
+    let record = AuditRecord {
+        started: started,
+        command: arguments.get( 1 ).cloned().unwrap_or( String::new() ),
+        arguments: arguments.iter().skip( 1 ).cloned().collect(),
+        effective_arguments: effective_arguments.iter().skip( 1 ).cloned().collect(),
+        inputs: inputs,
+        summary: summary.into_iter().collect(),
+        duration_ms: duration.as_secs() * 1000 + ( duration.subsec_nanos() / 1000000 ) as u64,
+        exit_status: status.code(),
+        environment: Environment::current(),
+    };
+    append( log, &record );
+    return status.code().unwrap_or( 1 );
+}
+
+/// Passes the lines of the output through and keeps the last lines in the summary.
+fn pass_through<R>(
+    output: R,
+    summary: &Mutex<VecDeque<String>>,
+    errors: bool,
+)
+where
+    R: Read,
+{
+    for line in BufReader::new( output ).lines()
+    {
+        let line = match line
+        {
+            Ok( line ) => line,
+            Err( _ ) => break,
+        };
+        if errors
+        {
+            eprintln!( "{}", line );
+        }
+        else
+        {
+            println!( "{}", line );
+        }
+        let mut summary = summary.lock().unwrap();
+        if summary.len() == SUMMARY_LINES
+        {
+            summary.pop_front();
+        }
+        summary.push_back( line );
+    }
+}
+
+/// Appends the record to the audit log as a single line.
+fn append(
+    log: &str,
+    record: &AuditRecord,
+)
+{
+    let mut file = std::fs::OpenOptions::new().create( true ).append( true ).open( log ).expect( "Failed to open the audit log." );
+    let line = serde_json::to_string( record ).expect( "Serializing the audit record failed." );
+    writeln!( &mut file, "{}", line ).expect( "Writing the audit log failed." );
+}
@@ -0,0 +1,124 @@
+extern crate rand;
+extern crate rayon;
+extern crate ro_scalar_set;
+extern crate std;
+
+use rand::distributions::Range;
+
+use encoding;
+use engine::EngineRegistry;
+use enumerations::*;
+use evaluation::*;
+use mapping::MappedFile;
+use timestamp;
+use traits::*;
+use tuple;
+use utility;
+use uuid;
+
+/// Percentiles of the latencies reported by the benchmark.
+const PERCENTILES: &'static [f64] = &[ 50.0, 90.0, 99.0, 99.9 ];
+
+/// Measures the end-to-end latency of evaluating a single test set against the attached sets repeatedly.
+/// The sets are attached and preloaded once. Each measurement covers the whole evaluation by the engine
+/// including dividing the test set into blocks and combining the results of the threads.
+/// The CPU engine evaluates in a single thread pool created before the measurements.
+pub fn bench_latency<T>(
+    params: &EvaluationParams,
+    repeat: usize,
+)
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    let file = MappedFile::open( params.file ).expect( "Failed to map the file" );
+    let data = file.as_slice();
+    if encoding::detect( data ) != Encoding::Raw || tuple::is_tuple_file( data )
+            || uuid::is_uuid_file( data ) || timestamp::is_timestamp_file( data )
+    {
+        panic!( "Only raw sets can be benchmarked." );
+    }
+    let registry = EngineRegistry::new();
+    let engine = match registry.find( params.eval_engine )
+    {
+        Some( engine ) => engine,
+        None => panic!( "Unknown engine: {}", params.eval_engine ),
+    };
+    let buffer: &[T] = file.as_slice();
+    let sets = load_data( &buffer, params.preload, params.cache_size, params.max_threads, params.io_limit, &params.set_filter );
+
+    // The same test set is evaluated every time.
+    let between = Range::new( params.min_value, params.max_value );
+    let test_set: Vec<T> = utility::generate_values( params.values_in_set, &between );
+    let mut latencies: Vec<std::time::Duration> = Vec::with_capacity( repeat );
+    let mut match_count = 0;
+    if engine.name() == "cpu"
+    {
+        let threads = rayon::ThreadPool::new(
+                rayon::Configuration:: new().num_threads( params.max_threads )
+        ).unwrap();
+        threads.install( || {
+            for _ in 0..repeat
+            {
+                let start = std::time::Instant::now();
+                match_count = evaluate_in_blocks( &sets, &test_set, params ).match_count;
+                latencies.push( std::time::Instant::now().duration_since( start ) );
+            }
+        } );
+    }
+    else
+    {
+        for _ in 0..repeat
+        {
+            let start = std::time::Instant::now();
+            match_count = engine.evaluate( &sets, &test_set, params ).match_count;
+            latencies.push( std::time::Instant::now().duration_since( start ) );
+        }
+    }
+    if latencies.is_empty()
+    {
+        return;
+    }
+    latencies.sort();
+
+    let total = latencies.iter().fold( std::time::Duration::new( 0, 0 ), |total, l| total + *l );
+    println!( "Evaluated {} values {} times, {} matches", test_set.len(), latencies.len(), match_count );
+    println!( "{:>8}: {:>10} us", "mean", as_micros( total / latencies.len() as u32 ) );
+    for p in PERCENTILES
+    {
+        println!( "{:>8}: {:>10} us", format!( "p{}", p ), as_micros( percentile( &latencies, *p ) ) );
+    }
+    println!( "{:>8}: {:>10} us", "max", as_micros( latencies[ latencies.len() - 1 ] ) );
+}
+
+/// Evaluates the test set divided into blocks like the CPU engine in the thread pool of the caller.
+fn evaluate_in_blocks<T>(
+    sets: &SetsForEvaluation<T>,
+    test_set: &[T],
+    params: &EvaluationParams,
+) -> EvaluationResult
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
+{
+    let block_size = if params.query_block > 0 { params.query_block } else { std::cmp::max( test_set.len(), 1 ) };
+    let blocks: Vec<ro_scalar_set::RoScalarSet<T>> = test_set.chunks( block_size )
+            .map( |b| ro_scalar_set::RoScalarSet::new( b ) )
+            .collect();
+    let blocks: Vec<&ro_scalar_set::RoScalarSet<T>> = blocks.iter().collect();
+    return sets.evaluate_blocks_in_current_pool( &blocks, params.preload, None );
+}
+
+/// Gets the percentile of the sorted latencies with the nearest-rank method.
+fn percentile(
+    latencies: &[std::time::Duration],
+    p: f64,
+) -> std::time::Duration
+{
+    let rank = ( p / 100.0 * latencies.len() as f64 ).ceil() as usize;
+    return latencies[ std::cmp::min( std::cmp::max( rank, 1 ), latencies.len() ) - 1 ];
+}
+
+/// Converts the duration into microseconds.
+fn as_micros( duration: std::time::Duration ) -> u64
+{
+    return duration.as_secs() * 1_000_000 + ( duration.subsec_nanos() / 1000 ) as u64;
+}
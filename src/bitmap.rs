@@ -0,0 +1,240 @@
+extern crate byteorder;
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::{BufReader, BufWriter};
+use std::io::prelude::*;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use encoding;
+use explain::SetMatch;
+use mapping::MappedFile;
+use spill::Spool;
+use traits::*;
+
+/// Identifies a bitmap file.
+const MAGIC: &'static [u8] = b"SSEBMAP2";
+
+/// Length of the SHA-256 hash of the corpus in hex.
+const CORPUS_HASH_LENGTH: usize = 64;
+
+/// Bitmaps of the sets matched by each test set.
+/// Bit i % 64 of word i / 64 of a bitmap is set when the set i of the file matched.
+pub struct Bitmaps
+{
+    /// Number of the sets in the file and the bits in each bitmap.
+    pub set_count: usize,
+    /// SHA-256 hash of the file of the sets in hex. Only the bitmaps of the same file can be combined.
+    pub corpus: String,
+    /// Bit of each set evaluated for all the test sets. The sets left out by the filters are neither matched nor unmatched.
+    pub evaluated: Vec<u64>,
+    pub bitmaps: Vec<Vec<u64>>,
+}
+
+impl Bitmaps
+{
+    /// Creates an empty collection of bitmaps of the given number of sets of the file with the hash.
+    pub fn new(
+        set_count: usize,
+        corpus: String,
+    ) -> Bitmaps
+    {
+        let mut evaluated = vec![ !0u64; words( set_count ) ];
+        mask_tail( &mut evaluated, set_count );
+        return Bitmaps { set_count: set_count, corpus: corpus, evaluated: evaluated, bitmaps: Vec::new() };
+    }
+
+    /// Adds a bitmap of the matched sets. Sets without a result were not evaluated.
+    pub fn push_matches(
+        &mut self,
+        matches: &Spool<SetMatch>,
+    )
+    {
+        let mut bitmap = vec![ 0u64; words( self.set_count ) ];
+        let mut evaluated = vec![ 0u64; words( self.set_count ) ];
+        for set in matches.iter().filter( |s| s.index < self.set_count )
+        {
+            evaluated[ set.index / 64 ] |= 1 << ( set.index % 64 );
+            if set.matched
+            {
+                bitmap[ set.index / 64 ] |= 1 << ( set.index % 64 );
+            }
+        }
+        for ( word, evaluated ) in self.evaluated.iter_mut().zip( evaluated )
+        {
+            *word &= evaluated;
+        }
+        self.bitmaps.push( bitmap );
+    }
+
+    /// Reads the bitmaps written with write.
+    /// The file must be exactly as long as its header says so that a damaged header cannot size the bitmaps.
+    pub fn read( file: &str ) -> Bitmaps
+    {
+        let opened = std::fs::File::open( file ).expect( "Failed to open the bitmap file." );
+        let length = opened.metadata().expect( "Failed to query the bitmap file." ).len();
+        let mut reader = BufReader::with_capacity( 1024 * 1024, opened );
+        let mut magic = [ 0u8; 8 ];
+        reader.read_exact( &mut magic ).expect( "Reading the bitmaps failed." );
+        if magic != MAGIC
+        {
+            panic!( "{} is not a bitmap file.", file );
+        }
+        let mut corpus = [ 0u8; CORPUS_HASH_LENGTH ];
+        reader.read_exact( &mut corpus ).expect( "Reading the bitmaps failed." );
+        let corpus = String::from_utf8( corpus.to_vec() ).expect( "Invalid hash of the corpus in the bitmap file." );
+        let set_count = reader.read_u64::<LittleEndian>().expect( "Reading the bitmaps failed." );
+        let bitmap_count = reader.read_u64::<LittleEndian>().expect( "Reading the bitmaps failed." );
+
+        // The mask of the evaluated sets precedes the bitmaps.
+        let expected = ( set_count / 64 + if set_count % 64 > 0 { 1 } else { 0 } )
+                .checked_mul( bitmap_count.saturating_add( 1 ) )
+                .and_then( |words| words.checked_mul( 8 ) )
+                .and_then( |bytes| bytes.checked_add( ( MAGIC.len() + CORPUS_HASH_LENGTH + 16 ) as u64 ) );
+        if expected != Some( length )
+        {
+            panic!( "The header of {} does not match the length of the file.", file );
+        }
+        let ( set_count, bitmap_count ) = ( set_count as usize, bitmap_count as usize );
+        let mut read_bitmap = || -> Vec<u64> {
+            ( 0..words( set_count ) )
+                    .map( |_| reader.read_u64::<LittleEndian>().expect( "Reading the bitmaps failed." ) )
+                    .collect()
+        };
+        let evaluated = read_bitmap();
+        let bitmaps = ( 0..bitmap_count ).map( |_| read_bitmap() ).collect();
+        return Bitmaps { set_count: set_count, corpus: corpus, evaluated: evaluated, bitmaps: bitmaps };
+    }
+
+    /// Keeps the sets matched in both bitmaps of each test set.
+    pub fn and(
+        &mut self,
+        other: &Bitmaps,
+    )
+    {
+        self.combine( other, |a, b| a & b );
+    }
+
+    /// Keeps the sets matched in either bitmap of each test set.
+    pub fn or(
+        &mut self,
+        other: &Bitmaps,
+    )
+    {
+        self.combine( other, |a, b| a | b );
+    }
+
+    /// Inverts the matched and the unmatched sets of each test set.
+    /// The sets that were not evaluated stay unmatched.
+    pub fn not( &mut self )
+    {
+        for bitmap in self.bitmaps.iter_mut()
+        {
+            for ( word, evaluated ) in bitmap.iter_mut().zip( &self.evaluated )
+            {
+                *word = !*word & evaluated;
+            }
+        }
+    }
+
+    /// Counts the matched sets of each test set.
+    pub fn counts( &self ) -> Vec<u64>
+    {
+        return self.bitmaps.iter().map( |b| count( b ) ).collect();
+    }
+
+    /// Counts the sets evaluated for the test sets.
+    pub fn evaluated_count( &self ) -> u64
+    {
+        return count( &self.evaluated );
+    }
+
+    /// Combines the words of the bitmaps of the same test sets.
+    /// The bitmaps must be of the same file and number of test sets.
+    /// Only the sets evaluated for both bitmaps are evaluated for the combination.
+    fn combine<F>(
+        &mut self,
+        other: &Bitmaps,
+        operation: F,
+    )
+    where
+        F: Fn( u64, u64 ) -> u64,
+    {
+        if self.corpus != other.corpus || self.set_count != other.set_count || self.bitmaps.len() != other.bitmaps.len()
+        {
+            panic!( "The bitmaps of {} sets and {} test sets of the file {} cannot be combined with the bitmaps of {} sets and {} test sets of the file {}.",
+                    self.set_count, self.bitmaps.len(), self.corpus, other.set_count, other.bitmaps.len(), other.corpus );
+        }
+        for ( word, other ) in self.evaluated.iter_mut().zip( &other.evaluated )
+        {
+            *word &= *other;
+        }
+        for ( bitmap, other ) in self.bitmaps.iter_mut().zip( &other.bitmaps )
+        {
+            for ( ( word, other ), evaluated ) in bitmap.iter_mut().zip( other ).zip( &self.evaluated )
+            {
+                *word = operation( *word, *other ) & evaluated;
+            }
+        }
+    }
+
+    /// Writes the bitmaps to the file.
+    /// The file starts with the magic "SSEBMAP2", the SHA-256 hash of the file of the sets in hex,
+    /// the number of the sets and the number of the bitmaps followed by the words of the mask of the evaluated sets
+    /// and the words of each bitmap, the numbers and the words as little-endian 64-bit integers.
+    pub fn write( &self, file: &str )
+    {
+        let mut writer = BufWriter::with_capacity(
+            1024 * 1024,
+            std::fs::File::create( file ).expect( "Failed to open the bitmap file." ),
+        );
+        writer.write_all( MAGIC ).expect( "Writing the bitmaps failed." );
+        writer.write_all( self.corpus.as_bytes() ).expect( "Writing the bitmaps failed." );
+        writer.write_u64::<LittleEndian>( self.set_count as u64 ).expect( "Writing the bitmaps failed." );
+        writer.write_u64::<LittleEndian>( self.bitmaps.len() as u64 ).expect( "Writing the bitmaps failed." );
+        for bitmap in std::iter::once( &self.evaluated ).chain( &self.bitmaps )
+        {
+            for word in bitmap
+            {
+                writer.write_u64::<LittleEndian>( *word ).expect( "Writing the bitmaps failed." );
+            }
+        }
+    }
+}
+
+/// Gets the number of words of a bitmap of the sets.
+fn words( set_count: usize ) -> usize
+{
+    return ( set_count + 63 ) / 64;
+}
+
+/// Clears the bits beyond the last set.
+fn mask_tail(
+    bitmap: &mut Vec<u64>,
+    set_count: usize,
+)
+{
+    let tail = set_count % 64;
+    if let ( true, Some( last ) ) = ( tail > 0, bitmap.last_mut() )
+    {
+        *last &= ( 1u64 << tail ) - 1;
+    }
+}
+
+/// Counts the set bits of the bitmap.
+fn count( bitmap: &[u64] ) -> u64
+{
+    return bitmap.iter().map( |w| w.count_ones() as u64 ).sum();
+}
+
+/// Counts the sets of the file for the length of the bitmaps.
+pub fn count_sets<T>( file: &String ) -> usize
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    let mapped = MappedFile::open( file ).expect( "Failed to map the file" );
+    let mut set_count = 0;
+    encoding::for_each_set( mapped.as_slice(), |_: &[T]| set_count += 1 );
+    return set_count;
+}
@@ -0,0 +1,253 @@
+extern crate byteorder;
+extern crate std;
+
+use std::io::prelude::*;
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+
+use encoding;
+use enumerations::*;
+use evaluation::{evaluate_in_pool, EvaluationResult};
+use intersect;
+use traits::*;
+
+/// Identifies a file with bit-packed sets.
+const MAGIC: &'static [u8] = b"SSEBITP1";
+
+/// Identifies a file with bit-packed sets holding their values in ascending order.
+const SORTED_MAGIC: &'static [u8] = b"SSEBITS1";
+
+/// Size of the file header: the magic, the base key and the number of bits per value.
+const HEADER_LEN: usize = 8 + 8 + 1;
+
+/// Parameters shared by all the sets of a bit-packed file.
+/// Each value is stored as the difference of its ordered key from the base key.
+#[derive(Clone, Copy)]
+pub struct Packing
+{
+    pub base: u64,
+    pub bits: u8,
+    /// The values of each set are in ascending order.
+    pub sorted: bool,
+}
+
+impl Packing
+{
+    /// Determines the packing for values between min_value and max_value.
+    pub fn for_range<T>(
+        min_value: &T,
+        max_value: &T,
+    ) -> Packing
+    where
+        T: OrderedBits,
+    {
+        let base = min_value.to_ordered_bits();
+        let span = max_value.to_ordered_bits() - base;
+        let bits = std::cmp::max( 1, 64 - span.leading_zeros() ) as u8;
+        return Packing { base: base, bits: bits, sorted: false };
+    }
+}
+
+/// Checks whether the data is in the bit-packed format.
+pub fn is_bitpacked( data: &[u8] ) -> bool
+{
+    data.starts_with( MAGIC ) || data.starts_with( SORTED_MAGIC )
+}
+
+/// Checks whether the header of the bit-packed data declares the sets sorted.
+pub fn is_sorted( data: &[u8] ) -> bool
+{
+    data.starts_with( SORTED_MAGIC )
+}
+
+/// Writes the header of a bit-packed file.
+pub fn write_header<W: Write>(
+    writer: &mut W,
+    packing: &Packing,
+) -> std::io::Result<()>
+{
+    writer.write_all( if packing.sorted { SORTED_MAGIC } else { MAGIC } )?;
+    writer.write_u64::<LittleEndian>( packing.base )?;
+    writer.write_u8( packing.bits )
+}
+
+/// Writes a single set in the bit-packed format.
+/// A set is stored as the number of values followed by the values packed into "bits" bits each.
+pub fn write_set<W, T>(
+    writer: &mut W,
+    values: &[T],
+    packing: &Packing,
+) -> std::io::Result<()>
+where
+    W: Write,
+    T: OrderedBits,
+{
+    let mut packed = vec![ 0u8; packed_len( values.len(), packing.bits ) ];
+    for ( i, v ) in values.iter().enumerate()
+    {
+        let key = v.to_ordered_bits();
+        if key < packing.base || ( key - packing.base ) >> packing.bits != 0
+        {
+            return Err( std::io::Error::new( std::io::ErrorKind::InvalidInput, "Value out of the packed range." ) );
+        }
+        write_bits( &mut packed, i * packing.bits as usize, packing.bits as usize, key - packing.base );
+    }
+    writer.write_u32::<LittleEndian>( values.len() as u32 )?;
+    writer.write_all( &packed )
+}
+
+/// Divides bit-packed data into sets.
+pub fn attach( data: &[u8] ) -> ( Packing, Vec<&[u8]> )
+{
+    let packing = Packing {
+        base: LittleEndian::read_u64( &data[ MAGIC.len().. ] ),
+        bits: data[ HEADER_LEN - 1 ],
+        sorted: is_sorted( data ),
+    };
+    let mut sets = Vec::new();
+    let mut position = HEADER_LEN;
+    while position < data.len()
+    {
+        let count = LittleEndian::read_u32( &data[ position.. ] ) as usize;
+        let length = 4 + packed_len( count, packing.bits );
+        sets.push( &data[ position..position + length ] );
+        position += length;
+    }
+    return ( packing, sets );
+}
+
+/// Decodes the ordered keys of the values of a set.
+pub fn decode(
+    set: &[u8],
+    packing: &Packing,
+) -> Vec<u64>
+{
+    let count = LittleEndian::read_u32( set ) as usize;
+    let packed = &set[ 4.. ];
+    return ( 0..count )
+        .map( |i| packing.base + read_bits( packed, i * packing.bits as usize, packing.bits as usize ) )
+        .collect();
+}
+
+/// Tests if any value of the set is found from the sorted query keys.
+/// Only the sorted sets can be evaluated with other strategies than probing.
+pub fn any(
+    set: &[u8],
+    packing: &Packing,
+    query: &[u64],
+    strategy: Strategy,
+) -> bool
+{
+    let count = LittleEndian::read_u32( set ) as usize;
+    let packed = &set[ 4.. ];
+    if packing.sorted
+    {
+        return intersect::any_sorted( count,
+                |i| packing.base + read_bits( packed, i * packing.bits as usize, packing.bits as usize ), query, strategy );
+    }
+    for i in 0..count
+    {
+        let key = packing.base + read_bits( packed, i * packing.bits as usize, packing.bits as usize );
+        if query.binary_search( &key ).is_ok()
+        {
+            return true;
+        }
+    }
+    return false;
+}
+
+/// Evaluates bit-packed sets with CPU.
+pub fn evaluate<T>(
+    data: &[u8],
+    test_set: &[T],
+    strategy: Strategy,
+    preload: PreloadMode,
+    thread_count: usize,
+) -> EvaluationResult
+where
+    T: OrderedBits,
+{
+    let query = encoding::sorted_keys( test_set );
+
+    // Load the data into the memory?
+    let preloaded;
+    let data = if preload == PreloadMode::Full
+    {
+        preloaded = data.to_vec();
+        &preloaded[..]
+    }
+    else
+    {
+        data
+    };
+    let ( packing, sets ) = attach( data );
+
+    return evaluate_in_pool( &sets, data.len() as u64, preload, thread_count, |s| any( s, &packing, &query, strategy ) );
+}
+
+/// Calls the function with the values of each set.
+pub fn for_each_set<T, F>(
+    data: &[u8],
+    mut f: F,
+)
+where
+    T: OrderedBits,
+    F: FnMut( &[T] ),
+{
+    let ( packing, sets ) = attach( data );
+    for set in sets
+    {
+        let values: Vec<T> = decode( set, &packing ).into_iter().map( |k| T::from_ordered_bits( k ) ).collect();
+        f( &values );
+    }
+}
+
+/// Gets the number of bytes required to pack the values.
+fn packed_len(
+    count: usize,
+    bits: u8,
+) -> usize
+{
+    ( count * bits as usize + 7 ) / 8
+}
+
+/// Writes the lowest "bits" bits of the value starting from the given bit offset.
+fn write_bits(
+    buffer: &mut [u8],
+    offset: usize,
+    bits: usize,
+    value: u64,
+)
+{
+    let mut written = 0;
+    while written < bits
+    {
+        let position = offset + written;
+        let shift = position % 8;
+        let chunk = std::cmp::min( 8 - shift, bits - written );
+        let mask = ( ( 1u64 << chunk ) - 1 ) as u8;
+        buffer[ position / 8 ] |= ( ( ( value >> written ) as u8 ) & mask ) << shift;
+        written += chunk;
+    }
+}
+
+/// Reads "bits" bits starting from the given bit offset.
+fn read_bits(
+    buffer: &[u8],
+    offset: usize,
+    bits: usize,
+) -> u64
+{
+    let mut value = 0;
+    let mut read = 0;
+    while read < bits
+    {
+        let position = offset + read;
+        let shift = position % 8;
+        let chunk = std::cmp::min( 8 - shift, bits - read );
+        let mask = ( 1u64 << chunk ) - 1;
+        value |= ( ( buffer[ position / 8 ] >> shift ) as u64 & mask ) << read;
+        read += chunk;
+    }
+    return value;
+}
@@ -0,0 +1,78 @@
+extern crate std;
+
+/// Upper bounds of the set size buckets in values, the last bucket has no upper bound.
+const BUCKET_LIMITS: &'static [usize] = &[ 100, 1000 ];
+
+/// Matches and evaluation time of the sets within a size bucket.
+#[derive(Clone)]
+pub struct SizeBucket
+{
+    pub set_count: u64,
+    pub match_count: u64,
+    pub duration: std::time::Duration,
+}
+
+/// Results of an evaluation broken down by the number of values in the sets.
+pub struct Breakdown
+{
+    pub buckets: Vec<SizeBucket>,
+}
+
+impl Breakdown
+{
+    /// Collects the breakdown from the number of values, the match and the evaluation time of each set.
+    pub fn collect<I>( sets: I ) -> Breakdown
+    where
+        I: Iterator<Item = ( usize, bool, std::time::Duration )>,
+    {
+        let empty = SizeBucket { set_count: 0, match_count: 0, duration: std::time::Duration::new( 0, 0 ) };
+        let mut buckets = vec![ empty; BUCKET_LIMITS.len() + 1 ];
+        for ( values, matched, duration ) in sets
+        {
+            let bucket = &mut buckets[ get_bucket( values ) ];
+            bucket.set_count += 1;
+            bucket.match_count += if matched { 1 } else { 0 };
+            bucket.duration += duration;
+        }
+        return Breakdown { buckets: buckets };
+    }
+
+    /// Prints the breakdown as a table.
+    pub fn print( &self )
+    {
+        println!( "|{:>12}|{:>12}|{:>14}|{:>16}|", "Set size", "Sets", "Matching sets", "Average per set" );
+        for ( index, bucket ) in self.buckets.iter().enumerate()
+        {
+            if bucket.set_count == 0
+            {
+                continue;
+            }
+            let duration = bucket.duration.as_secs() * 1_000_000_000 + bucket.duration.subsec_nanos() as u64;
+            println!( "|{:>12}|{:>12}|{:>14}|{:>13} ns|",
+                    get_bucket_name( index ), bucket.set_count, bucket.match_count, duration / bucket.set_count );
+        }
+    }
+}
+
+/// Gets the index of the bucket of a set with the given number of values.
+fn get_bucket( values: usize ) -> usize
+{
+    return BUCKET_LIMITS.iter().position( |l| values < *l ).unwrap_or( BUCKET_LIMITS.len() );
+}
+
+/// Gets the name of the bucket such as "<100" or "100-1000".
+fn get_bucket_name( index: usize ) -> String
+{
+    if index == 0
+    {
+        format!( "<{}", BUCKET_LIMITS[ 0 ] )
+    }
+    else if index == BUCKET_LIMITS.len()
+    {
+        format!( ">={}", BUCKET_LIMITS[ index - 1 ] )
+    }
+    else
+    {
+        format!( "{}-{}", BUCKET_LIMITS[ index - 1 ], BUCKET_LIMITS[ index ] - 1 )
+    }
+}
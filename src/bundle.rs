@@ -0,0 +1,187 @@
+extern crate byteorder;
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::{BufReader, BufWriter};
+use std::io::prelude::*;
+use std::path::{Component, Path};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use encoding;
+use mapping::MappedFile;
+use query::QueryFile;
+use traits::*;
+
+/// Identifies a bundle file.
+const MAGIC: &'static [u8] = b"SSEBNDL1";
+
+/// Name of the entry describing the other entries of the bundle.
+const MANIFEST: &'static str = "manifest.txt";
+
+/// Packs the files into a single bundle.
+/// Each entry is stored as the length of its name, the name, the length of its data and the data.
+/// The first entry is a manifest listing the type of the values, the query and the encoding, the size
+/// and the number of sets of the other entries.
+/// With a query file the query is packed as well and the manifest records the expected number of
+/// matching sets of each file for each test set of the query.
+/// The notes are packed as they are without reading them as sets.
+pub fn pack(
+    output: &String,
+    files: &Vec<String>,
+    notes: &Vec<String>,
+    query_file: Option<&String>,
+    use_floats: bool,
+)
+{
+    let mut entries: Vec<&String> = files.iter().chain( notes ).collect();
+    let mut manifest = format!( "values\t{}\n", if use_floats { "f32" } else { "i32" } );
+    for note in notes
+    {
+        manifest.push_str( &format!( "note\t{}\n", entry_name( note ) ) );
+    }
+    if let Some( query_file ) = query_file
+    {
+        manifest.push_str( &format!( "query\t{}\n", entry_name( query_file ) ) );
+        entries.push( query_file );
+    }
+    let mut names: Vec<String> = entries.iter().map( |f| entry_name( f ) ).collect();
+    names.sort();
+    if let Some( duplicate ) = names.windows( 2 ).find( |n| n[ 0 ] == n[ 1 ] )
+    {
+        panic!( "Several files of the bundle are named {}.", duplicate[ 0 ] );
+    }
+
+    let query = query_file.map( |q| QueryFile::open( q ) );
+    for file in files
+    {
+        let mapped = MappedFile::open( file ).expect( "Failed to map the file" );
+        let ( set_count, matches ) = if use_floats
+        {
+            expected_matches::<f32>( mapped.as_slice(), query.as_ref() )
+        }
+        else
+        {
+            expected_matches::<i32>( mapped.as_slice(), query.as_ref() )
+        };
+        let matches: Vec<String> = matches.iter().map( |m| m.to_string() ).collect();
+        manifest.push_str( &format!( "file\t{}\t{}\t{}\t{}\t{}\n", entry_name( file ), encoding::name( encoding::detect( mapped.as_slice() ) ),
+                mapped.len(), set_count, matches.join( "," ) ) );
+    }
+
+    let mut writer = BufWriter::with_capacity(
+        1024 * 1024,
+        std::fs::File::create( output ).expect( "Failed to open the bundle." ),
+    );
+    writer.write_all( MAGIC ).expect( "Writing the bundle failed." );
+    write_entry( &mut writer, MANIFEST, manifest.as_bytes() ).expect( "Writing the bundle failed." );
+    for file in entries
+    {
+        let mapped = MappedFile::open( file ).expect( "Failed to map the file" );
+        write_entry( &mut writer, &entry_name( file ), mapped.as_slice() ).expect( "Writing the bundle failed." );
+    }
+}
+
+/// Extracts the files of the bundle into the directory.
+/// Existing files are not overwritten and fail the extraction so that the files of the bundle are not
+/// mixed with files from elsewhere.
+pub fn unpack<P: AsRef<Path>>(
+    bundle: &String,
+    directory: P,
+)
+{
+    let mut reader = BufReader::with_capacity(
+        1024 * 1024,
+        std::fs::File::open( bundle ).expect( "Failed to open the bundle." ),
+    );
+    let mut magic = [ 0u8; 8 ];
+    reader.read_exact( &mut magic ).expect( "Reading the bundle failed." );
+    if magic != MAGIC
+    {
+        panic!( "{} is not a bundle.", bundle );
+    }
+
+    loop
+    {
+        let name_len = match reader.read_u32::<LittleEndian>()
+        {
+            Ok( len ) => len as usize,
+            Err( ref e ) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err( e ) => panic!( "Reading the bundle failed: {}", e ),
+        };
+        let mut name = vec![ 0u8; name_len ];
+        reader.read_exact( &mut name ).expect( "Reading the bundle failed." );
+        let name = String::from_utf8( name ).expect( "Invalid entry name in the bundle." );
+
+        // The entries are plain file names so that they cannot be written outside the directory.
+        let mut components = Path::new( &name ).components();
+        match ( components.next(), components.next() )
+        {
+            ( Some( Component::Normal( _ ) ), None ) => {},
+            _ => panic!( "Invalid entry name {} in the bundle.", name ),
+        }
+        let data_len = reader.read_u64::<LittleEndian>().expect( "Reading the bundle failed." );
+
+        let target = directory.as_ref().join( &name );
+        let mut data = ( &mut reader ).take( data_len );
+        if target.exists()
+        {
+            panic!( "Extracting the bundle failed: {} already exists.", target.display() );
+        }
+        println!( "Extracting {}...", name );
+        let partial = target.with_extension( "partial" );
+        {
+            let mut output = std::fs::File::create( &partial ).expect( "Failed to open the file." );
+            std::io::copy( &mut data, &mut output ).expect( "Extracting the bundle failed." );
+        }
+        std::fs::rename( &partial, &target ).expect( "Failed to rename the extracted file." );
+    }
+}
+
+/// Counts the sets of the data and the sets matching each test set of the query.
+/// The sets are decoded and compared with the values of the test sets without the engines.
+fn expected_matches<T>(
+    data: &[u8],
+    query: Option<&QueryFile>,
+) -> ( usize, Vec<u64> )
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    let test_sets: Vec<Vec<u64>> = match query
+    {
+        Some( query ) => query.attach_all::<T>().iter().map( |&( _, values )| encoding::sorted_keys( values ) ).collect(),
+        None => Vec::new(),
+    };
+    let mut set_count = 0;
+    let mut matches = vec![ 0u64; test_sets.len() ];
+    encoding::for_each_set( data, |values: &[T]| {
+        set_count += 1;
+        for ( test_set, count ) in test_sets.iter().zip( matches.iter_mut() )
+        {
+            if values.iter().any( |v| test_set.binary_search( &v.to_ordered_bits() ).is_ok() )
+            {
+                *count += 1;
+            }
+        }
+    } );
+    return ( set_count, matches );
+}
+
+/// Writes a single entry.
+fn write_entry<W: Write>(
+    writer: &mut W,
+    name: &str,
+    data: &[u8],
+) -> std::io::Result<()>
+{
+    writer.write_u32::<LittleEndian>( name.len() as u32 )?;
+    writer.write_all( name.as_bytes() )?;
+    writer.write_u64::<LittleEndian>( data.len() as u64 )?;
+    writer.write_all( data )
+}
+
+/// Gets the name of the entry of the file. The directories are not preserved.
+fn entry_name( file: &String ) -> String
+{
+    Path::new( file ).file_name().expect( "Invalid file name." ).to_string_lossy().into_owned()
+}
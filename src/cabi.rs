@@ -0,0 +1,165 @@
+extern crate std;
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+use cancel::CancellationToken;
+use enumerations::*;
+use evaluation::*;
+use mapping::MappedFile;
+
+/// Set file opened for evaluation through the C interface.
+/// Each evaluation maps the file again; the pages stay warm in the page cache between the evaluations.
+pub struct SseHandle
+{
+    file: String,
+}
+
+/// Opens the set file for evaluation.
+/// Returns null if the path is not valid UTF-8 or the file cannot be mapped.
+#[no_mangle]
+pub extern "C" fn sse_open( path: *const c_char ) -> *mut SseHandle
+{
+    if path.is_null()
+    {
+        return std::ptr::null_mut();
+    }
+    let file = match unsafe { CStr::from_ptr( path ) }.to_str()
+    {
+        Ok( file ) => file.to_string(),
+        Err( _ ) => return std::ptr::null_mut(),
+    };
+    if MappedFile::open( &file ).is_err()
+    {
+        return std::ptr::null_mut();
+    }
+    return Box::into_raw( Box::new( SseHandle { file: file } ) );
+}
+
+/// Counts the sets of the file with any of the given integer values.
+/// Zero threads uses all the logical threads. Returns -1 if the evaluation fails.
+#[no_mangle]
+pub extern "C" fn sse_evaluate(
+    handle: *const SseHandle,
+    values: *const i32,
+    value_count: usize,
+    threads: usize,
+) -> i64
+{
+    return evaluate( handle, values, value_count, threads, None );
+}
+
+/// Counts the sets of the file with any of the given integer values until the token is cancelled.
+/// Zero threads uses all the logical threads. Returns -1 if the evaluation fails and -2 if it was cancelled.
+#[no_mangle]
+pub extern "C" fn sse_evaluate_cancellable(
+    handle: *const SseHandle,
+    values: *const i32,
+    value_count: usize,
+    threads: usize,
+    token: *const CancellationToken,
+) -> i64
+{
+    if token.is_null()
+    {
+        return -1;
+    }
+    return evaluate( handle, values, value_count, threads, Some( unsafe { &*token } ) );
+}
+
+/// Creates a token for cancelling evaluations from another thread.
+#[no_mangle]
+pub extern "C" fn sse_token_new() -> *mut CancellationToken
+{
+    return Box::into_raw( Box::new( CancellationToken::new() ) );
+}
+
+/// Cancels the evaluations of the token. Null tokens are ignored.
+#[no_mangle]
+pub extern "C" fn sse_token_cancel( token: *const CancellationToken )
+{
+    if !token.is_null()
+    {
+        unsafe { &*token }.cancel();
+    }
+}
+
+/// Frees the token after the evaluations using it have returned. Null tokens are ignored.
+#[no_mangle]
+pub extern "C" fn sse_token_free( token: *mut CancellationToken )
+{
+    if !token.is_null()
+    {
+        unsafe { drop( Box::from_raw( token ) ) };
+    }
+}
+
+/// Evaluates the integer values against the sets of the handle.
+fn evaluate(
+    handle: *const SseHandle,
+    values: *const i32,
+    value_count: usize,
+    threads: usize,
+    cancellation: Option<&CancellationToken>,
+) -> i64
+{
+    if handle.is_null() || ( values.is_null() && value_count > 0 )
+    {
+        return -1;
+    }
+    let handle = unsafe { &*handle };
+    let test_set: &[i32] = if value_count == 0 { &[] } else { unsafe { std::slice::from_raw_parts( values, value_count ) } };
+    let params = EvaluationParams
+    {
+        file: &handle.file,
+        values_in_set: 0,
+        min_value: 0,
+        max_value: 0,
+        preload: PreloadMode::Disabled,
+        max_threads: threads,
+        prefetch_distance: 0,
+        cache_size: 0,
+        io_limit: 0,
+        verify_sample: 0,
+        operation: Operation::Any,
+        dimension: None,
+        transform: None,
+        query_block: 0,
+        strategy: Strategy::Auto,
+        breakdown: false,
+        explain: false,
+        set_results: false,
+        exclude_file: None,
+        set_filter: SetFilter::all(),
+        eval_engine: "cpu",
+        audit: 0.0,
+        audit_engine: "cpu",
+        repro: false,
+        gpu_compare: GpuCompare::Epsilon,
+        gpu_memory: GpuMemory::Default,
+        gpu_kernel: GpuKernel::Item,
+        top_k: 0,
+        memory_limit: 0,
+        timeout: None,
+        cancellation: cancellation,
+        signature: None,
+    };
+
+    // Panics must not unwind across the C boundary.
+    return match std::panic::catch_unwind( || evaluate_with_query( &params, test_set ) )
+    {
+        Ok( _ ) if is_cancelled( &params ) => -2,
+        Ok( result ) => result.match_count as i64,
+        Err( _ ) => -1,
+    };
+}
+
+/// Closes the set file. Null handles are ignored.
+#[no_mangle]
+pub extern "C" fn sse_close( handle: *mut SseHandle )
+{
+    if !handle.is_null()
+    {
+        unsafe { drop( Box::from_raw( handle ) ) };
+    }
+}
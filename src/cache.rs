@@ -0,0 +1,128 @@
+extern crate ro_scalar_set;
+extern crate rayon;
+extern crate std;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Keeps the most recently probed sets materialized in memory.
+/// The sets that do not fit into the cache are read directly from the file.
+/// Each thread of the pool has its own share of the capacity so that the probes do not contend on a lock.
+/// A set cached by one thread is read from the file again when another thread evaluates it.
+pub struct SetCache<'a, T>
+where
+    T: 'a + ro_scalar_set::Value,
+{
+    /// Capacity of the state of each thread.
+    capacity: usize,
+    /// The state of each thread by the index of the thread in the pool.
+    /// The lock of the state is only taken by its own thread unless there are more threads than states.
+    states: Vec<Mutex<CacheState<'a, T>>>,
+}
+
+/// Mutable state of the cache of a thread.
+struct CacheState<'a, T>
+where
+    T: 'a + ro_scalar_set::Value,
+{
+    /// Cached sets by their index in the file with the tick of their last use.
+    entries: HashMap<usize, ( u64, usize, Arc<ro_scalar_set::RoScalarSet<'a, T>> )>,
+    /// Set indexes ordered from the least recently used to the most recently used.
+    recency: BTreeMap<u64, usize>,
+    tick: u64,
+    used: usize,
+}
+
+impl<'a, T> SetCache<'a, T>
+where
+    T: 'a + ro_scalar_set::Value,
+{
+    /// Initializes a new cache which holds at most "capacity" bytes of sets divided between the threads of the pool.
+    /// Zero threads divides the cache between all the logical threads like the pool does.
+    pub fn new(
+        capacity: usize,
+        thread_count: usize,
+    ) -> SetCache<'a, T>
+    {
+        let thread_count = if thread_count > 0 { thread_count } else { rayon::current_num_threads() };
+        let states = ( 0..thread_count )
+            .map( |_| Mutex::new( CacheState {
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                tick: 0,
+                used: 0,
+            } ) )
+            .collect();
+        return SetCache { capacity: capacity / thread_count, states: states };
+    }
+
+    /// Gets the state of the current thread. The threads outside of the pool share the first state.
+    fn state( &self ) -> &Mutex<CacheState<'a, T>>
+    {
+        return &self.states[ rayon::current_thread_index().unwrap_or( 0 ) % self.states.len() ];
+    }
+
+    /// Gets a cached set and marks it as the most recently used.
+    pub fn get(
+        &self,
+        index: usize,
+    ) -> Option<Arc<ro_scalar_set::RoScalarSet<'a, T>>>
+    {
+        let mut state = self.state().lock().unwrap();
+        let state = &mut *state;
+        state.tick += 1;
+        let tick = state.tick;
+        match state.entries.get_mut( &index )
+        {
+            Some( entry ) =>
+            {
+                state.recency.remove( &entry.0 );
+                state.recency.insert( tick, index );
+                entry.0 = tick;
+                Some( entry.2.clone() )
+            },
+            None => None,
+        }
+    }
+
+    /// Materializes the set in memory, evicting the least recently used sets to make room for it.
+    pub fn insert(
+        &self,
+        index: usize,
+        set: &ro_scalar_set::RoScalarSet<'a, T>,
+        size: usize,
+    )
+    {
+        if size > self.capacity
+        {
+            return;
+        }
+
+        let mut state = self.state().lock().unwrap();
+        let state = &mut *state;
+        if state.entries.contains_key( &index )
+        {
+            return;
+        }
+
+        // Evict.
+        while state.used + size > self.capacity
+        {
+            let oldest = match state.recency.iter().next()
+            {
+                Some( ( tick, index ) ) => ( *tick, *index ),
+                None => break,
+            };
+            state.recency.remove( &oldest.0 );
+            if let Some( evicted ) = state.entries.remove( &oldest.1 )
+            {
+                state.used -= evicted.1;
+            }
+        }
+
+        state.tick += 1;
+        state.recency.insert( state.tick, index );
+        state.entries.insert( index, ( state.tick, size, Arc::new( set.clone() ) ) );
+        state.used += size;
+    }
+}
@@ -0,0 +1,83 @@
+extern crate std;
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+/// Requests an evaluation in progress to stop early.
+/// The clones of a token share the request so that one thread can cancel the evaluation running on others.
+/// The evaluation checks the token before each set and leaves the rest of the sets unmatched once it is cancelled.
+#[derive(Clone)]
+pub struct CancellationToken
+{
+    cancelled: Arc<AtomicBool>,
+    /// Set by the timers of this token and the tokens it was derived from at their deadlines.
+    /// The token is cancelled at a deadline without cancelling the token it was derived from.
+    deadlines: Vec<Arc<AtomicBool>>,
+    /// Set when the token was found cancelled by a deadline.
+    timed_out: Arc<AtomicBool>,
+    /// Stop the timers of the deadlines once the tokens sharing them are dropped.
+    timers: Vec<Arc<Mutex<Sender<()>>>>,
+}
+
+impl CancellationToken
+{
+    /// Creates a token that is not cancelled.
+    pub fn new() -> CancellationToken
+    {
+        return CancellationToken { cancelled: Arc::new( AtomicBool::new( false ) ), deadlines: Vec::new(),
+                timed_out: Arc::new( AtomicBool::new( false ) ), timers: Vec::new() };
+    }
+
+    /// Derives a token cancelled with this token or when the timeout has elapsed from now, whichever comes first.
+    /// A timer thread marks the token timed out at the deadline so that checking the token does not read the clock.
+    pub fn with_timeout(
+        &self,
+        timeout: Duration,
+    ) -> CancellationToken
+    {
+        let deadline = Arc::new( AtomicBool::new( false ) );
+        let ( stop, stopped ) = mpsc::channel::<()>();
+        let flag = deadline.clone();
+        std::thread::spawn( move || {
+            if let Err( RecvTimeoutError::Timeout ) = stopped.recv_timeout( timeout )
+            {
+                flag.store( true, Ordering::Relaxed );
+            }
+        } );
+        let mut deadlines = self.deadlines.clone();
+        deadlines.push( deadline );
+        let mut timers = self.timers.clone();
+        timers.push( Arc::new( Mutex::new( stop ) ) );
+        return CancellationToken { cancelled: self.cancelled.clone(), deadlines: deadlines,
+                timed_out: Arc::new( AtomicBool::new( false ) ), timers: timers };
+    }
+
+    /// Requests the evaluations checking the token to stop.
+    pub fn cancel( &self )
+    {
+        self.cancelled.store( true, Ordering::Relaxed );
+    }
+
+    /// Checks whether the token was cancelled or its deadline has passed.
+    pub fn is_cancelled( &self ) -> bool
+    {
+        if self.cancelled.load( Ordering::Relaxed )
+        {
+            return true;
+        }
+        if self.deadlines.iter().any( |d| d.load( Ordering::Relaxed ) )
+        {
+            self.timed_out.store( true, Ordering::Relaxed );
+            return true;
+        }
+        return false;
+    }
+
+    /// Checks whether an evaluation found the deadline of the token passed.
+    pub fn is_timed_out( &self ) -> bool
+    {
+        return self.timed_out.load( Ordering::Relaxed );
+    }
+}
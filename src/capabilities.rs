@@ -0,0 +1,110 @@
+extern crate serde_json;
+extern crate std;
+
+use allocator;
+use encoding;
+use engine::EngineRegistry;
+use enumerations::*;
+use intersect;
+
+/// Describes what the application supports on this machine.
+#[derive(Serialize)]
+pub struct Capabilities
+{
+    pub version: String,
+    /// Engines in the engine registry.
+    pub engines: Vec<String>,
+    /// The GPU evaluation is compiled in.
+    pub gpu: bool,
+    /// Reading sets from S3 and GCS is compiled in.
+    pub cloud: bool,
+    /// The server can speak the Redis protocol.
+    pub resp: bool,
+    /// Importing sets from and exporting results into SQLite is compiled in.
+    pub sqlite: bool,
+    pub allocator: String,
+    pub encodings: Vec<String>,
+    pub strategies: Vec<String>,
+    /// SIMD instruction sets available on the processor at run time.
+    pub simd: Vec<String>,
+    /// Largest file in bytes that can be mapped into the address space.
+    pub max_mmap_size: u64,
+    /// The kernel allows io_uring. The application itself reads the sets through memory maps.
+    pub io_uring: bool,
+}
+
+/// Probes the capabilities of the application and the machine.
+pub fn probe() -> Capabilities
+{
+    let encodings = [ Encoding::Raw, Encoding::Delta, Encoding::Bitpack, Encoding::Dictionary, Encoding::Weighted ];
+    let strategies = [ Strategy::Auto, Strategy::Probe, Strategy::Merge, Strategy::Galloping, Strategy::Batched ];
+    return Capabilities {
+        version: env!( "CARGO_PKG_VERSION" ).to_string(),
+        engines: EngineRegistry::<i32>::new().names().iter().map( |n| n.to_string() ).collect(),
+        gpu: cfg!( feature="gpu" ),
+        cloud: cfg!( feature="cloud" ),
+        resp: cfg!( feature="resp" ),
+        sqlite: cfg!( feature="sqlite" ),
+        allocator: allocator::name().to_string(),
+        encodings: encodings.iter().map( |e| encoding::name( *e ).to_string() ).collect(),
+        strategies: strategies.iter().map( |s| intersect::name( *s ).to_string() ).collect(),
+        simd: detect_simd(),
+        max_mmap_size: std::isize::MAX as u64,
+        io_uring: detect_io_uring(),
+    };
+}
+
+/// Prints the capabilities as JSON.
+pub fn print()
+{
+    serde_json::to_writer_pretty( std::io::stdout(), &probe() ).expect( "Writing the capabilities failed." );
+    println!( "" );
+}
+
+/// Detects the SIMD instruction sets of the processor.
+#[cfg(any(target_arch="x86", target_arch="x86_64"))]
+fn detect_simd() -> Vec<String>
+{
+    let features = [
+        ( "sse2", is_x86_feature_detected!( "sse2" ) ),
+        ( "sse4.2", is_x86_feature_detected!( "sse4.2" ) ),
+        ( "avx", is_x86_feature_detected!( "avx" ) ),
+        ( "avx2", is_x86_feature_detected!( "avx2" ) ),
+        ( "avx512f", is_x86_feature_detected!( "avx512f" ) ),
+    ];
+    return features.iter().filter( |f| f.1 ).map( |f| f.0.to_string() ).collect();
+}
+
+/// Detects the SIMD instruction sets of the processor.
+/// NEON is mandatory on 64-bit ARM.
+#[cfg(target_arch="aarch64")]
+fn detect_simd() -> Vec<String>
+{
+    return vec![ "neon".to_string() ];
+}
+
+/// Detects the SIMD instruction sets of the processor.
+#[cfg(not(any(target_arch="x86", target_arch="x86_64", target_arch="aarch64")))]
+fn detect_simd() -> Vec<String>
+{
+    return Vec::new();
+}
+
+/// Checks whether the kernel allows io_uring.
+/// io_uring is available since Linux 5.1 unless disabled with the io_uring_disabled setting.
+fn detect_io_uring() -> bool
+{
+    if !cfg!( target_os="linux" )
+    {
+        return false;
+    }
+    if let Ok( disabled ) = std::fs::read_to_string( "/proc/sys/kernel/io_uring_disabled" )
+    {
+        return disabled.trim() == "0";
+    }
+    let release = std::fs::read_to_string( "/proc/sys/kernel/osrelease" ).unwrap_or( String::new() );
+    let mut version = release.trim().split( |c: char| c == '.' || c == '-' ).map( |v| v.parse::<u32>().unwrap_or( 0 ) );
+    let major = version.next().unwrap_or( 0 );
+    let minor = version.next().unwrap_or( 0 );
+    return ( major, minor ) >= ( 5, 1 );
+}
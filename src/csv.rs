@@ -0,0 +1,209 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, BufWriter};
+use std::io::prelude::*;
+
+use encoding;
+use mapping::MappedFile;
+use timestamp;
+use traits::*;
+use uuid;
+
+/// Imports sets from a CSV file with one set per line.
+/// With strings each distinct token is assigned the next free code as its value and the strings are stored
+/// in a sidecar dictionary next to the set file so that the values can be translated back on export.
+pub fn import<T>(
+    input: &String,
+    output: &String,
+    strings: bool,
+)
+where
+    T: FromI32 + OrderedBits + ro_scalar_set::Value,
+{
+    let reader = BufReader::new( std::fs::File::open( input ).expect( "Failed to open the CSV file." ) );
+    let mut writer = BufWriter::with_capacity(
+        1024 * 1024,
+        std::fs::File::create( output ).expect( "Failed to open the file." ),
+    );
+    let mut dictionary: HashMap<String, i32> = HashMap::new();
+    let mut set_count = 0;
+    for line in reader.lines()
+    {
+        let line = line.expect( "Reading the CSV file failed." );
+        let mut values: Vec<T> = Vec::new();
+        for token in line.split( ',' ).map( |t| t.trim() ).filter( |t| !t.is_empty() )
+        {
+            let value = if strings
+            {
+                let code = dictionary.len();
+                *dictionary.entry( token.to_string() )
+                        .or_insert_with( || std::convert::TryFrom::try_from( code ).expect( "Too many distinct strings." ) )
+            }
+            else
+            {
+                token.parse::<i32>().expect( &format!( "Invalid value: {}", token ) )
+            };
+            values.push( T::from_i32( &value ) );
+        }
+        ro_scalar_set::RoScalarSet::new( &values ).serialize( &mut writer ).expect( "Writing scalar set to a file failed." );
+        set_count += 1;
+    }
+
+    if strings
+    {
+        let mut sidecar = BufWriter::new(
+            std::fs::File::create( get_dictionary_file_name( output ) ).expect( "Failed to open the dictionary." ),
+        );
+        for ( string, code ) in &dictionary
+        {
+            writeln!( &mut sidecar, "{}\t{}", code, string ).expect( "Writing the dictionary failed." );
+        }
+        println!( "Stored {} distinct strings.", dictionary.len() );
+    }
+    println!( "Imported {} sets.", set_count );
+}
+
+/// Imports sets of UUIDs from a CSV file with one set per line.
+pub fn import_uuids(
+    input: &String,
+    output: &String,
+)
+{
+    let reader = BufReader::new( std::fs::File::open( input ).expect( "Failed to open the CSV file." ) );
+    let mut writer = BufWriter::with_capacity(
+        1024 * 1024,
+        std::fs::File::create( output ).expect( "Failed to open the file." ),
+    );
+    uuid::write_header( &mut writer ).expect( "Writing the header failed." );
+    let mut set_count = 0;
+    for line in reader.lines()
+    {
+        let line = line.expect( "Reading the CSV file failed." );
+        let uuids: Vec<u128> = line.split( ',' )
+                .map( |t| t.trim() )
+                .filter( |t| !t.is_empty() )
+                .map( |t| uuid::parse( t ).expect( &format!( "Invalid UUID: {}", t ) ) )
+                .collect();
+        uuid::write_set( &mut writer, &uuids ).expect( "Writing set of UUIDs to a file failed." );
+        set_count += 1;
+    }
+    println!( "Imported {} sets.", set_count );
+}
+
+/// Imports sets of timestamps from a CSV file with one set per line.
+/// The timestamps are given in milliseconds since the epoch or in the RFC 3339 format.
+pub fn import_timestamps(
+    input: &String,
+    output: &String,
+)
+{
+    let reader = BufReader::new( std::fs::File::open( input ).expect( "Failed to open the CSV file." ) );
+    let mut writer = BufWriter::with_capacity(
+        1024 * 1024,
+        std::fs::File::create( output ).expect( "Failed to open the file." ),
+    );
+    timestamp::write_header( &mut writer ).expect( "Writing the header failed." );
+    let mut set_count = 0;
+    for line in reader.lines()
+    {
+        let line = line.expect( "Reading the CSV file failed." );
+        let timestamps: Vec<i64> = line.split( ',' )
+                .map( |t| t.trim() )
+                .filter( |t| !t.is_empty() )
+                .map( |t| timestamp::parse( t ).expect( &format!( "Invalid timestamp: {}", t ) ) )
+                .collect();
+        timestamp::write_set( &mut writer, &timestamps ).expect( "Writing set of timestamps to a file failed." );
+        set_count += 1;
+    }
+    println!( "Imported {} sets.", set_count );
+}
+
+/// Exports the sets into a CSV file with one set per line.
+/// When a query is given only the sets with any value of the query are exported.
+/// With strings the values are translated back with the sidecar dictionary.
+pub fn export<T>(
+    input: &String,
+    output: &String,
+    query: &[String],
+    strings: bool,
+)
+where
+    T: FromI32 + OrderedBits + ro_scalar_set::Value + std::fmt::Display,
+{
+    let mapped = MappedFile::open( input ).expect( "Failed to map the file" );
+    let mut writer = BufWriter::with_capacity(
+        1024 * 1024,
+        std::fs::File::create( output ).expect( "Failed to open the CSV file." ),
+    );
+    let mut exported = 0;
+
+    // UUIDs are exported in their textual format.
+    if uuid::is_uuid_file( mapped.as_slice() )
+    {
+        let query: Vec<u128> = query.iter().map( |q| uuid::parse( q ).expect( &format!( "Invalid UUID: {}", q ) ) ).collect();
+        uuid::for_each_set( mapped.as_slice(), |uuids| {
+            if query.is_empty() || uuids.iter().any( |u| query.contains( u ) )
+            {
+                let tokens: Vec<String> = uuids.iter().map( |u| uuid::format( *u ) ).collect();
+                writeln!( &mut writer, "{}", tokens.join( "," ) ).expect( "Writing the CSV file failed." );
+                exported += 1;
+            }
+        } );
+        println!( "Exported {} sets.", exported );
+        return;
+    }
+
+    // The strings of the query missing from the dictionary are found from no set.
+    let dictionary = if strings { Some( read_dictionary::<T>( input ) ) } else { None };
+    let query_keys: Vec<u64> = match dictionary
+    {
+        Some( ref dictionary ) => dictionary.iter().filter( |&( _, s )| query.contains( s ) ).map( |( k, _ )| *k ).collect(),
+        None => query.iter()
+                .map( |q| T::from_i32( &q.parse::<i32>().expect( &format!( "Invalid value: {}", q ) ) ).to_ordered_bits() )
+                .collect(),
+    };
+    encoding::for_each_set( mapped.as_slice(), |values: &[T]| {
+        if !query.is_empty() && !values.iter().any( |v| query_keys.contains( &v.to_ordered_bits() ) )
+        {
+            return;
+        }
+        let tokens: Vec<String> = values.iter()
+                .map( |v| match dictionary
+                {
+                    Some( ref dictionary ) => dictionary.get( &v.to_ordered_bits() ).cloned().unwrap_or( format!( "{}", v ) ),
+                    None => format!( "{}", v ),
+                } )
+                .collect();
+        writeln!( &mut writer, "{}", tokens.join( "," ) ).expect( "Writing the CSV file failed." );
+        exported += 1;
+    } );
+    println!( "Exported {} sets.", exported );
+}
+
+/// Gets the name of the sidecar dictionary of the set file.
+fn get_dictionary_file_name( file: &String ) -> String
+{
+    format!( "{}.strings", file )
+}
+
+/// Reads the sidecar dictionary keyed by the ordered keys of the values.
+fn read_dictionary<T>( file: &String ) -> HashMap<u64, String>
+where
+    T: FromI32 + OrderedBits,
+{
+    let reader = BufReader::new(
+        std::fs::File::open( get_dictionary_file_name( file ) ).expect( "Failed to open the dictionary." ),
+    );
+    let mut dictionary = HashMap::new();
+    for line in reader.lines()
+    {
+        let line = line.expect( "Reading the dictionary failed." );
+        let mut fields = line.splitn( 2, '\t' );
+        let code = fields.next().and_then( |c| c.parse::<i32>().ok() ).expect( "Invalid dictionary entry." );
+        let string = fields.next().expect( "Invalid dictionary entry." );
+        dictionary.insert( T::from_i32( &code ).to_ordered_bits(), string.to_string() );
+    }
+    return dictionary;
+}
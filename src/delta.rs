@@ -0,0 +1,180 @@
+extern crate std;
+
+use std::io::prelude::*;
+
+
+use encoding;
+use enumerations::*;
+use evaluation::{evaluate_in_pool, EvaluationResult};
+use intersect;
+use traits::*;
+
+/// Identifies a file with delta encoded sets (format v2).
+const MAGIC: &'static [u8] = b"SSEDELT2";
+
+/// Checks whether the data is in the delta encoded format.
+pub fn is_delta_encoded( data: &[u8] ) -> bool
+{
+    data.starts_with( MAGIC )
+}
+
+/// Writes the header of a delta encoded file.
+pub fn write_header<W: Write>( writer: &mut W ) -> std::io::Result<()>
+{
+    writer.write_all( MAGIC )
+}
+
+/// Writes a single set in the delta encoded format.
+/// A set is stored as its length in bytes followed by the number of values and
+/// the differences between the sorted values, each as a variable-length integer.
+pub fn write_set<W, T>(
+    writer: &mut W,
+    values: &[T],
+) -> std::io::Result<()>
+where
+    W: Write,
+    T: OrderedBits,
+{
+    let mut keys: Vec<u64> = values.iter().map( |v| v.to_ordered_bits() ).collect();
+    keys.sort();
+
+    let mut payload: Vec<u8> = Vec::new();
+    write_varint( &mut payload, keys.len() as u64 );
+    let mut previous = 0;
+    for key in keys
+    {
+        write_varint( &mut payload, key - previous );
+        previous = key;
+    }
+
+    let mut length: Vec<u8> = Vec::new();
+    write_varint( &mut length, payload.len() as u64 );
+    writer.write_all( &length )?;
+    writer.write_all( &payload )
+}
+
+/// Divides delta encoded data into sets.
+pub fn attach( data: &[u8] ) -> Vec<&[u8]>
+{
+    let mut sets = Vec::new();
+    let mut position = MAGIC.len();
+    while position < data.len()
+    {
+        let length = read_varint( data, &mut position ) as usize;
+        sets.push( &data[ position..position + length ] );
+        position += length;
+    }
+    return sets;
+}
+
+/// Decodes the ordered keys of the values of a set.
+pub fn decode( set: &[u8] ) -> Vec<u64>
+{
+    let mut position = 0;
+    let count = read_varint( set, &mut position );
+    let mut keys = Vec::with_capacity( count as usize );
+    let mut key = 0;
+    for _ in 0..count
+    {
+        key += read_varint( set, &mut position );
+        keys.push( key );
+    }
+    return keys;
+}
+
+/// Tests if any value of the set is found from the sorted query keys.
+/// The values are decoded on the fly and searched from the query without decoding the whole set.
+pub fn any(
+    set: &[u8],
+    query: &[u64],
+    strategy: Strategy,
+) -> bool
+{
+    let mut position = 0;
+    let count = read_varint( set, &mut position );
+    let keys = ( 0..count ).scan( 0u64, |key, _| {
+        *key += read_varint( set, &mut position );
+        Some( *key )
+    } );
+    return intersect::any_streamed( keys, query, strategy );
+}
+
+/// Evaluates delta encoded sets with CPU.
+pub fn evaluate<T>(
+    data: &[u8],
+    test_set: &[T],
+    strategy: Strategy,
+    preload: PreloadMode,
+    thread_count: usize,
+) -> EvaluationResult
+where
+    T: OrderedBits,
+{
+    let query = encoding::sorted_keys( test_set );
+
+    // Load the data into the memory?
+    let preloaded;
+    let data = if preload == PreloadMode::Full
+    {
+        preloaded = data.to_vec();
+        &preloaded[..]
+    }
+    else
+    {
+        data
+    };
+    let sets = attach( data );
+
+    return evaluate_in_pool( &sets, data.len() as u64, preload, thread_count, |s| any( s, &query, strategy ) );
+}
+
+/// Calls the function with the values of each set.
+pub fn for_each_set<T, F>(
+    data: &[u8],
+    mut f: F,
+)
+where
+    T: OrderedBits,
+    F: FnMut( &[T] ),
+{
+    for set in attach( data )
+    {
+        let values: Vec<T> = decode( set ).into_iter().map( |k| T::from_ordered_bits( k ) ).collect();
+        f( &values );
+    }
+}
+
+/// Writes a LEB128 encoded variable-length integer.
+pub fn write_varint(
+    buffer: &mut Vec<u8>,
+    mut value: u64,
+)
+{
+    while value >= 0x80
+    {
+        buffer.push( ( value as u8 ) | 0x80 );
+        value >>= 7;
+    }
+    buffer.push( value as u8 );
+}
+
+/// Reads a LEB128 encoded variable-length integer and advances the position.
+pub fn read_varint(
+    data: &[u8],
+    position: &mut usize,
+) -> u64
+{
+    let mut value = 0;
+    let mut shift = 0;
+    loop
+    {
+        let byte = data[ *position ];
+        *position += 1;
+        value |= ( ( byte & 0x7f ) as u64 ) << shift;
+        if byte & 0x80 == 0
+        {
+            return value;
+        }
+        shift += 7;
+    }
+}
@@ -0,0 +1,234 @@
+extern crate std;
+
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+
+use delta::{read_varint, write_varint};
+use enumerations::*;
+use evaluation::{evaluate_in_pool, EvaluationResult};
+use traits::*;
+
+/// Identifies a file with dictionary encoded sets.
+const MAGIC: &'static [u8] = b"SSEDICT1";
+
+/// Maps the values to codes ordered by the frequency of the values.
+/// The most frequent values get the smallest codes which are also the shortest when stored.
+pub struct Dictionary
+{
+    /// Ordered keys of the values by their code.
+    keys: Vec<u64>,
+    codes: HashMap<u64, u64>,
+}
+
+/// Counts the frequencies of the values during the first pass of the encoding.
+pub struct DictionaryBuilder
+{
+    counts: HashMap<u64, u64>,
+}
+
+impl DictionaryBuilder
+{
+    /// Initializes a new builder.
+    pub fn new() -> DictionaryBuilder
+    {
+        return DictionaryBuilder { counts: HashMap::new() };
+    }
+
+    /// Counts the values of a set.
+    pub fn add<T>(
+        &mut self,
+        values: &[T],
+    )
+    where
+        T: OrderedBits,
+    {
+        for v in values
+        {
+            *self.counts.entry( v.to_ordered_bits() ).or_insert( 0 ) += 1;
+        }
+    }
+
+    /// Assigns the codes by the frequency of the values.
+    pub fn build( self ) -> Dictionary
+    {
+        let mut counts: Vec<( u64, u64 )> = self.counts.into_iter().collect();
+        counts.sort_by( |a, b| b.1.cmp( &a.1 ).then( a.0.cmp( &b.0 ) ) );
+        let keys: Vec<u64> = counts.into_iter().map( |( key, _ )| key ).collect();
+        return Dictionary::new( keys );
+    }
+}
+
+impl Dictionary
+{
+    /// Initializes the dictionary from keys ordered by their code.
+    fn new( keys: Vec<u64> ) -> Dictionary
+    {
+        let codes = keys.iter().enumerate().map( |( code, key )| ( *key, code as u64 ) ).collect();
+        return Dictionary { keys: keys, codes: codes };
+    }
+
+    /// Gets the code of the value if it exists in the dictionary.
+    fn code_of(
+        &self,
+        key: u64,
+    ) -> Option<u64>
+    {
+        self.codes.get( &key ).cloned()
+    }
+}
+
+/// Checks whether the data is in the dictionary encoded format.
+pub fn is_dictionary_encoded( data: &[u8] ) -> bool
+{
+    data.starts_with( MAGIC )
+}
+
+/// Writes the header of a dictionary encoded file including the dictionary itself.
+pub fn write_header<W: Write>(
+    writer: &mut W,
+    dictionary: &Dictionary,
+) -> std::io::Result<()>
+{
+    let mut header: Vec<u8> = Vec::new();
+    header.extend_from_slice( MAGIC );
+    write_varint( &mut header, dictionary.keys.len() as u64 );
+    for key in &dictionary.keys
+    {
+        write_varint( &mut header, *key );
+    }
+    writer.write_all( &header )
+}
+
+/// Writes a single set in the dictionary encoded format.
+/// A set is stored as its length in bytes followed by the number of values and
+/// the codes of the values, each as a variable-length integer.
+pub fn write_set<W, T>(
+    writer: &mut W,
+    values: &[T],
+    dictionary: &Dictionary,
+) -> std::io::Result<()>
+where
+    W: Write,
+    T: OrderedBits,
+{
+    let mut payload: Vec<u8> = Vec::new();
+    write_varint( &mut payload, values.len() as u64 );
+    for v in values
+    {
+        let code = dictionary.code_of( v.to_ordered_bits() ).expect( "Value missing from the dictionary." );
+        write_varint( &mut payload, code );
+    }
+
+    let mut length: Vec<u8> = Vec::new();
+    write_varint( &mut length, payload.len() as u64 );
+    writer.write_all( &length )?;
+    writer.write_all( &payload )
+}
+
+/// Reads the dictionary and divides the rest of the data into sets.
+pub fn attach( data: &[u8] ) -> ( Dictionary, Vec<&[u8]> )
+{
+    let mut position = MAGIC.len();
+    let key_count = read_varint( data, &mut position );
+    let keys = ( 0..key_count ).map( |_| read_varint( data, &mut position ) ).collect();
+
+    let mut sets = Vec::new();
+    while position < data.len()
+    {
+        let length = read_varint( data, &mut position ) as usize;
+        sets.push( &data[ position..position + length ] );
+        position += length;
+    }
+    return ( Dictionary::new( keys ), sets );
+}
+
+/// Decodes the codes of the values of a set.
+fn decode( set: &[u8] ) -> Vec<u64>
+{
+    let mut position = 0;
+    let count = read_varint( set, &mut position );
+    return ( 0..count ).map( |_| read_varint( set, &mut position ) ).collect();
+}
+
+/// Tests if any code of the set is found from the sorted query codes.
+pub fn any(
+    set: &[u8],
+    query: &[u64],
+) -> bool
+{
+    let mut position = 0;
+    let count = read_varint( set, &mut position );
+    for _ in 0..count
+    {
+        if query.binary_search( &read_varint( set, &mut position ) ).is_ok()
+        {
+            return true;
+        }
+    }
+    return false;
+}
+
+/// Maps the test set to sorted codes.
+/// Values missing from the dictionary cannot match any set and are left out.
+pub fn query_codes<T>(
+    dictionary: &Dictionary,
+    test_set: &[T],
+) -> Vec<u64>
+where
+    T: OrderedBits,
+{
+    let mut query: Vec<u64> = test_set.iter()
+            .filter_map( |v| dictionary.code_of( v.to_ordered_bits() ) )
+            .collect();
+    query.sort();
+    query.dedup();
+    return query;
+}
+
+/// Evaluates dictionary encoded sets with CPU.
+pub fn evaluate<T>(
+    data: &[u8],
+    test_set: &[T],
+    preload: PreloadMode,
+    thread_count: usize,
+) -> EvaluationResult
+where
+    T: OrderedBits,
+{
+    // Load the data into the memory?
+    let preloaded;
+    let data = if preload == PreloadMode::Full
+    {
+        preloaded = data.to_vec();
+        &preloaded[..]
+    }
+    else
+    {
+        data
+    };
+    let ( dictionary, sets ) = attach( data );
+
+    let query = query_codes( &dictionary, test_set );
+
+    return evaluate_in_pool( &sets, data.len() as u64, preload, thread_count, |s| any( s, &query ) );
+}
+
+/// Calls the function with the values of each set.
+pub fn for_each_set<T, F>(
+    data: &[u8],
+    mut f: F,
+)
+where
+    T: OrderedBits,
+    F: FnMut( &[T] ),
+{
+    let ( dictionary, sets ) = attach( data );
+    for set in sets
+    {
+        let values: Vec<T> = decode( set ).into_iter()
+                .map( |c| T::from_ordered_bits( dictionary.keys[ c as usize ] ) )
+                .collect();
+        f( &values );
+    }
+}
@@ -0,0 +1,116 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use encoding;
+use mapping::MappedFile;
+use traits::*;
+
+/// Maximum number of individual differences printed for each category.
+const MAX_PRINTED: usize = 10;
+
+/// Compares the sets of two files regardless of their encoding.
+/// The sets are matched by the hash of their content. Sets without a match
+/// are compared with the set at the same index of the other file.
+/// Returns true if the files hold the same sets.
+pub fn diff<T>(
+    a: &String,
+    b: &String,
+) -> bool
+where
+    T: OrderedBits + ro_scalar_set::Value + std::fmt::Display,
+{
+    let sets_a = read_sets::<T>( a );
+    let sets_b = read_sets::<T>( b );
+
+    // Index the sets of the second file by their content.
+    let mut by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+    for ( i, set ) in sets_b.iter().enumerate()
+    {
+        by_hash.entry( content_hash( set ) ).or_insert_with( Vec::new ).push( i );
+    }
+
+    // Sets with identical content are consumed from the index.
+    let mut only_in_a: Vec<usize> = Vec::new();
+    for ( i, set ) in sets_a.iter().enumerate()
+    {
+        let matched = match by_hash.get_mut( &content_hash( set ) )
+        {
+            Some( candidates ) => match candidates.iter().position( |c| sets_b[ *c ] == *set )
+            {
+                Some( position ) => { candidates.remove( position ); true },
+                None => false,
+            },
+            None => false,
+        };
+        if !matched
+        {
+            only_in_a.push( i );
+        }
+    }
+    let mut only_in_b: Vec<usize> = by_hash.values().flat_map( |c| c.iter().cloned() ).collect();
+    only_in_b.sort();
+
+    println!( "{} sets in {}, {} sets in {}", sets_a.len(), a, sets_b.len(), b );
+    println!( "{} sets only in {}", only_in_a.len(), a );
+    println!( "{} sets only in {}", only_in_b.len(), b );
+
+    // Report the value differences of the unmatched sets at the same index.
+    let changed: Vec<usize> = only_in_a.iter().cloned().filter( |i| only_in_b.binary_search( i ).is_ok() ).collect();
+    for i in changed.iter().take( MAX_PRINTED )
+    {
+        let removed: Vec<u64> = sets_a[ *i ].iter().filter( |v| sets_b[ *i ].binary_search( v ).is_err() ).cloned().collect();
+        let added: Vec<u64> = sets_b[ *i ].iter().filter( |v| sets_a[ *i ].binary_search( v ).is_err() ).cloned().collect();
+        println!(
+            "Set {}: {} values removed {}, {} values added {}",
+            i,
+            removed.len(),
+            format_values::<T>( &removed ),
+            added.len(),
+            format_values::<T>( &added )
+        );
+    }
+    if changed.len() > MAX_PRINTED
+    {
+        println!( "..." );
+    }
+    return only_in_a.is_empty() && only_in_b.is_empty();
+}
+
+/// Reads the sets of the file as sorted ordered keys.
+fn read_sets<T>( file: &String ) -> Vec<Vec<u64>>
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    let mapped = MappedFile::open( file ).expect( "Failed to map the file" );
+    let mut sets = Vec::new();
+    encoding::for_each_set( mapped.as_slice(), |values: &[T]| {
+        let mut keys: Vec<u64> = values.iter().map( |v| v.to_ordered_bits() ).collect();
+        keys.sort();
+        sets.push( keys );
+    } );
+    return sets;
+}
+
+/// Calculates the hash of the content of a set.
+fn content_hash( set: &Vec<u64> ) -> u64
+{
+    let mut hasher = DefaultHasher::new();
+    set.hash( &mut hasher );
+    return hasher.finish();
+}
+
+/// Formats the values for printing.
+fn format_values<T>( keys: &[u64] ) -> String
+where
+    T: OrderedBits + ro_scalar_set::Value + std::fmt::Display,
+{
+    let values: Vec<String> = keys.iter()
+            .take( MAX_PRINTED )
+            .map( |k| format!( "{}", T::from_ordered_bits( *k ) ) )
+            .collect();
+    return format!( "[{}{}]", values.join( ", " ), if keys.len() > MAX_PRINTED { ", ..." } else { "" } );
+}
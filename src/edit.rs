@@ -0,0 +1,246 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::BufWriter;
+use std::path::Path;
+
+use encoding;
+use enumerations::*;
+use mapping::MappedFile;
+use signature;
+use traits::*;
+
+/// Determines what happens to a set when the file is rewritten.
+enum Rewrite<T>
+{
+    /// The set is written as is.
+    Keep,
+    /// The set is replaced with the values.
+    Replace( Vec<T> ),
+    /// The set is left out.
+    Drop,
+}
+
+/// Adds and removes values of a single set and rewrites the file in its original encoding.
+/// The rewritten file is signed with the key if given.
+pub fn edit<T>(
+    file: &String,
+    set_index: usize,
+    add: &[T],
+    remove: &[T],
+    key_file: Option<&String>,
+)
+where
+    T: OrderedBits + std::clone::Clone + ro_scalar_set::Value,
+{
+    // The set is located before anything is written.
+    let mapped = MappedFile::open( file ).expect( "Failed to map the file" );
+    if mapped.is_decrypted()
+    {
+        panic!( "Encrypted files cannot be rewritten." );
+    }
+    let mut set_count = 0;
+    encoding::for_each_set( mapped.as_slice(), |_: &[T]| set_count += 1 );
+    drop( mapped );
+    if set_index >= set_count
+    {
+        panic!( "Set {} not found, the file has {} sets.", set_index, set_count );
+    }
+
+    rewrite( file, key_file, |index, values: &[T]| {
+        if index != set_index
+        {
+            return Rewrite::Keep;
+        }
+        let edited = edit_values( values, add, remove );
+        println!( "Set {}: {} values before, {} values after the edit", index, values.len(), edited.len() );
+        return Rewrite::Replace( edited );
+    } );
+}
+
+/// Rewrites the file densely without the empty sets left behind by the edits
+/// and without duplicate values within the sets.
+/// The rewritten file is signed with the key if given.
+pub fn compact<T>(
+    file: &String,
+    key_file: Option<&String>,
+)
+where
+    T: OrderedBits + std::clone::Clone + ro_scalar_set::Value,
+{
+    let size_before = std::fs::metadata( file ).expect( "Failed to query the file." ).len();
+    let mut dropped = 0;
+    let set_count = rewrite( file, key_file, |_, values: &[T]| {
+        if values.is_empty()
+        {
+            dropped += 1;
+            return Rewrite::Drop;
+        }
+        let mut keys: Vec<u64> = values.iter().map( |v| v.to_ordered_bits() ).collect();
+        keys.sort();
+        keys.dedup();
+        if keys.len() == values.len()
+        {
+            return Rewrite::Keep;
+        }
+        return Rewrite::Replace( keys.into_iter().map( |k| T::from_ordered_bits( k ) ).collect() );
+    } );
+    let size_after = std::fs::metadata( file ).expect( "Failed to query the file." ).len();
+    println!(
+        "Removed {} empty sets of {}, reclaimed {} bytes ({} bytes before, {} bytes after)",
+        dropped,
+        set_count,
+        size_before.saturating_sub( size_after ),
+        size_before,
+        size_after
+    );
+}
+
+/// Writes the sets of the input file ordered by the key into the output file in the original encoding.
+/// Sets with equal keys keep their original order. The sets are held in memory while sorting.
+pub fn sort<T>(
+    input: &String,
+    output: &String,
+    key: SortKey,
+)
+where
+    T: OrderedBits + std::clone::Clone + ro_scalar_set::Value,
+{
+    let mapped = MappedFile::open( input ).expect( "Failed to map the file" );
+    if mapped.is_decrypted()
+    {
+        panic!( "Encrypted files cannot be rewritten." );
+    }
+    let data: &[u8] = mapped.as_slice();
+    let mut sets: Vec<( u64, Vec<T> )> = Vec::new();
+    encoding::for_each_set( data, |values: &[T]| {
+        let k = match key
+        {
+            SortKey::Size => values.len() as u64,
+            SortKey::MinValue => values.iter().map( |v| v.to_ordered_bits() ).min().unwrap_or( std::u64::MAX ),
+        };
+        sets.push( ( k, values.to_vec() ) );
+    } );
+    sets.sort_by_key( |&( k, _ )| k );
+
+    // The sets are written as raw sets and then encoded like the input file.
+    let encoding = encoding::detect( data );
+    let raw_file_name = if encoding == Encoding::Raw { output.clone() } else { format!( "{}.raw", output ) };
+    {
+        let mut writer = BufWriter::with_capacity(
+            1024 * 1024,
+            std::fs::File::create( &raw_file_name ).expect( "Failed to open the file." ),
+        );
+        for &( _, ref values ) in &sets
+        {
+            ro_scalar_set::RoScalarSet::new( values ).serialize( &mut writer ).expect( "Writing scalar set to a file failed." );
+        }
+    }
+    if encoding != Encoding::Raw
+    {
+        encoding::convert::<T>( &raw_file_name, output, encoding );
+        std::fs::remove_file( &raw_file_name ).expect( "Failed to remove the temporary file." );
+    }
+    println!( "Sorted {} sets.", sets.len() );
+}
+
+/// Rewrites the sets of the file in its original encoding.
+/// The signature of the file is replaced with a signature of the rewritten file made with the key,
+/// or removed without a key.
+/// Returns the number of sets in the original file.
+fn rewrite<T, F>(
+    file: &String,
+    key_file: Option<&String>,
+    mut f: F,
+) -> usize
+where
+    T: OrderedBits + std::clone::Clone + ro_scalar_set::Value,
+    F: FnMut( usize, &[T] ) -> Rewrite<T>,
+{
+    // The sets are first written as raw sets and then encoded like the original file.
+    let raw_file_name = format!( "{}.rewrite", file );
+    let mut index = 0;
+    let encoding = {
+        let input = MappedFile::open( file ).expect( "Failed to map the file" );
+        if input.is_decrypted()
+        {
+            panic!( "Encrypted files cannot be rewritten." );
+        }
+        let data: &[u8] = input.as_slice();
+        let mut output = BufWriter::with_capacity(
+            1024 * 1024,
+            std::fs::File::create( &raw_file_name ).expect( "Failed to open the file." ),
+        );
+        encoding::for_each_set( data, |values: &[T]| {
+            let written = match f( index, values )
+            {
+                Rewrite::Keep => ro_scalar_set::RoScalarSet::new( values ).serialize( &mut output ),
+                Rewrite::Replace( replaced ) => ro_scalar_set::RoScalarSet::new( &replaced ).serialize( &mut output ),
+                Rewrite::Drop => Ok( () ),
+            };
+            written.expect( "Writing scalar set to a file failed." );
+            index += 1;
+        } );
+
+        // Close the file before it is renamed.
+        drop( output );
+        encoding::detect( data )
+    };
+
+    // The original file is replaced only after the rewritten file is complete.
+    let partial_file_name = format!( "{}.partial", file );
+    if encoding == Encoding::Raw
+    {
+        std::fs::rename( &raw_file_name, &partial_file_name ).expect( "Failed to rename the rewritten file." );
+    }
+    else
+    {
+        encoding::convert::<T>( &raw_file_name, &partial_file_name, encoding );
+        std::fs::remove_file( &raw_file_name ).expect( "Failed to remove the temporary file." );
+    }
+
+    // The signature of the rewritten file is completed before the file is replaced
+    // and replaces the old signature right after the file.
+    let signature_file_name = signature::get_signature_file_name( file );
+    let partial_signature_file_name = format!( "{}.partial", signature_file_name );
+    if let Some( key_file ) = key_file
+    {
+        signature::sign_as( &partial_file_name, key_file, &partial_signature_file_name );
+    }
+    std::fs::rename( &partial_file_name, file ).expect( "Failed to replace the file." );
+    if key_file.is_some()
+    {
+        std::fs::rename( &partial_signature_file_name, &signature_file_name ).expect( "Failed to replace the signature." );
+    }
+    else if Path::new( &signature_file_name ).exists()
+    {
+        // The signature no longer matches the content.
+        std::fs::remove_file( &signature_file_name ).expect( "Failed to remove the signature." );
+        println!( "Removed the outdated signature of the rewritten file." );
+    }
+    return index;
+}
+
+/// Gets the values of the set after the edit.
+fn edit_values<T>(
+    values: &[T],
+    add: &[T],
+    remove: &[T],
+) -> Vec<T>
+where
+    T: OrderedBits + std::clone::Clone,
+{
+    let removed: Vec<u64> = remove.iter().map( |v| v.to_ordered_bits() ).collect();
+    let mut edited: Vec<T> = values.iter()
+            .filter( |v| !removed.contains( &v.to_ordered_bits() ) )
+            .cloned()
+            .collect();
+    for v in add
+    {
+        if !edited.iter().any( |e| e.to_ordered_bits() == v.to_ordered_bits() )
+        {
+            edited.push( v.clone() );
+        }
+    }
+    return edited;
+}
@@ -0,0 +1,288 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::BufWriter;
+
+use bitpack;
+use delta;
+use dictionary;
+use enumerations::*;
+use evaluation::EvaluationResult;
+use intersect;
+use mapping::MappedFile;
+use traits::*;
+use timestamp;
+use tuple;
+use uuid;
+use weighted;
+
+/// Gets the name of the encoding used on the command line and in file names.
+pub fn name( encoding: Encoding ) -> &'static str
+{
+    match encoding
+    {
+        Encoding::Raw => "raw",
+        Encoding::Delta => "delta",
+        Encoding::Bitpack => "bitpack",
+        Encoding::Dictionary => "dictionary",
+        Encoding::Weighted => "weighted",
+    }
+}
+
+/// Parses the name of an encoding.
+pub fn parse( name: &str ) -> Option<Encoding>
+{
+    match name
+    {
+        "raw" => Some( Encoding::Raw ),
+        "delta" => Some( Encoding::Delta ),
+        "bitpack" => Some( Encoding::Bitpack ),
+        "dictionary" => Some( Encoding::Dictionary ),
+        "weighted" => Some( Encoding::Weighted ),
+        _ => None,
+    }
+}
+
+/// Detects the encoding of the sets from the beginning of the data.
+pub fn detect( data: &[u8] ) -> Encoding
+{
+    if delta::is_delta_encoded( data )
+    {
+        Encoding::Delta
+    }
+    else if bitpack::is_bitpacked( data )
+    {
+        Encoding::Bitpack
+    }
+    else if dictionary::is_dictionary_encoded( data )
+    {
+        Encoding::Dictionary
+    }
+    else if weighted::is_weighted( data )
+    {
+        Encoding::Weighted
+    }
+    else
+    {
+        Encoding::Raw
+    }
+}
+
+/// Evaluates encoded sets with CPU.
+pub fn evaluate<T>(
+    encoding: Encoding,
+    data: &[u8],
+    test_set: &[T],
+    strategy: Strategy,
+    preload: PreloadMode,
+    thread_count: usize,
+) -> EvaluationResult
+where
+    T: OrderedBits,
+{
+    let strategy = intersect::resolve( encoding, strategy );
+    match encoding
+    {
+        Encoding::Delta => delta::evaluate( data, test_set, strategy, preload, thread_count ),
+        Encoding::Bitpack => bitpack::evaluate( data, test_set, strategy, preload, thread_count ),
+        Encoding::Dictionary => dictionary::evaluate( data, test_set, preload, thread_count ),
+        Encoding::Weighted => weighted::evaluate( data, test_set, None, strategy, preload, thread_count ),
+        Encoding::Raw => panic!( "Raw sets are evaluated by attaching them." ),
+    }
+}
+
+/// Evaluates the sets at the given indexes individually with the same checks the evaluation of the encoding uses.
+pub fn evaluate_sets_at<T>(
+    data: &[u8],
+    test_set: &[T],
+    indexes: &[usize],
+    strategy: Strategy,
+) -> Vec<bool>
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    let strategy = intersect::resolve( detect( data ), strategy );
+    match detect( data )
+    {
+        Encoding::Delta =>
+        {
+            let query = sorted_keys( test_set );
+            let sets = delta::attach( data );
+            indexes.iter().map( |i| delta::any( sets[ *i ], &query, strategy ) ).collect()
+        },
+        Encoding::Bitpack =>
+        {
+            let query = sorted_keys( test_set );
+            let ( packing, sets ) = bitpack::attach( data );
+            indexes.iter().map( |i| bitpack::any( sets[ *i ], &packing, &query, strategy ) ).collect()
+        },
+        Encoding::Dictionary =>
+        {
+            let ( dictionary, sets ) = dictionary::attach( data );
+            let query = dictionary::query_codes( &dictionary, test_set );
+            indexes.iter().map( |i| dictionary::any( sets[ *i ], &query ) ).collect()
+        },
+        Encoding::Weighted =>
+        {
+            let query = sorted_keys( test_set );
+            let sets = weighted::attach( data );
+            indexes.iter().map( |i| weighted::any( sets[ *i ], &query, strategy ) ).collect()
+        },
+        Encoding::Raw =>
+        {
+            let mut buffer: &[T] = as_values( data );
+            let mut sets = Vec::new();
+            while let Ok( ( set, remaining ) ) = ro_scalar_set::RoScalarSet::attach( buffer )
+            {
+                sets.push( set );
+                buffer = remaining;
+            }
+            let query = ro_scalar_set::RoScalarSet::new( test_set );
+            indexes.iter().map( |i| query.any( &sets[ *i ] ) ).collect()
+        },
+    }
+}
+
+/// Maps the test set to sorted and deduplicated ordered keys.
+pub fn sorted_keys<T>( test_set: &[T] ) -> Vec<u64>
+where
+    T: OrderedBits,
+{
+    let mut keys: Vec<u64> = test_set.iter().map( |v| v.to_ordered_bits() ).collect();
+    keys.sort();
+    keys.dedup();
+    return keys;
+}
+
+/// Calls the function with the values of each set regardless of the encoding.
+pub fn for_each_set<T, F>(
+    data: &[u8],
+    mut f: F,
+)
+where
+    T: OrderedBits + ro_scalar_set::Value,
+    F: FnMut( &[T] ),
+{
+    if tuple::is_tuple_file( data ) || uuid::is_uuid_file( data ) || timestamp::is_timestamp_file( data )
+    {
+        panic!( "Sets of tuples, UUIDs or timestamps cannot be read as sets of values." );
+    }
+    match detect( data )
+    {
+        Encoding::Delta => delta::for_each_set( data, f ),
+        Encoding::Bitpack => bitpack::for_each_set( data, f ),
+        Encoding::Dictionary => dictionary::for_each_set( data, f ),
+        Encoding::Weighted => weighted::for_each_set( data, f ),
+        Encoding::Raw =>
+        {
+            // The values are stored at the end of each serialized scalar set.
+            let mut buffer: &[T] = as_values( data );
+            loop
+            {
+                let ( set, remaining ) = match ro_scalar_set::RoScalarSet::attach( buffer )
+                {
+                    Ok( result ) => result,
+                    Err( _ ) => break,
+                };
+                let serialized = &buffer[ ..buffer.len() - remaining.len() ];
+                f( &serialized[ serialized.len() - set.size().. ] );
+                buffer = remaining;
+            }
+        },
+    }
+}
+
+/// Converts the sets of the input file into the given encoding.
+pub fn convert<T>(
+    input: &String,
+    output: &String,
+    encoding: Encoding,
+)
+where
+    T: OrderedBits + std::clone::Clone + ro_scalar_set::Value,
+{
+    let input = MappedFile::open( input ).expect( "Failed to map the file" );
+    if input.is_decrypted()
+    {
+        panic!( "Encrypted files cannot be rewritten." );
+    }
+    let data: &[u8] = input.as_slice();
+    if detect( data ) == encoding
+    {
+        panic!( "The file is already in {} encoding.", name( encoding ) );
+    }
+    let mut output = BufWriter::with_capacity(
+        1024 * 1024,
+        std::fs::File::create( output ).expect( "Failed to open the file." ),
+    );
+    match encoding
+    {
+        Encoding::Raw =>
+        {
+            for_each_set( data, |values: &[T]| {
+                ro_scalar_set::RoScalarSet::new( values )
+                    .serialize( &mut output )
+                    .expect( "Writing scalar set to a file failed." );
+            } );
+        },
+        Encoding::Delta =>
+        {
+            delta::write_header( &mut output ).expect( "Writing the header failed." );
+            for_each_set( data, |values: &[T]| {
+                delta::write_set( &mut output, values ).expect( "Writing the set failed." );
+            } );
+        },
+        Encoding::Bitpack =>
+        {
+            // The range of the values is determined with an extra pass.
+            let mut range: Option<( T, T )> = None;
+            for_each_set( data, |values: &[T]| {
+                for v in values
+                {
+                    range = match range
+                    {
+                        None => Some( ( v.clone(), v.clone() ) ),
+                        Some( ( min, max ) ) => Some( (
+                            if v.to_ordered_bits() < min.to_ordered_bits() { v.clone() } else { min },
+                            if v.to_ordered_bits() > max.to_ordered_bits() { v.clone() } else { max },
+                        ) ),
+                    };
+                }
+            } );
+            let packing = match range
+            {
+                Some( ( min, max ) ) => bitpack::Packing::for_range( &min, &max ),
+                None => bitpack::Packing { base: 0, bits: 1, sorted: false },
+            };
+            bitpack::write_header( &mut output, &packing ).expect( "Writing the header failed." );
+            for_each_set( data, |values: &[T]| {
+                bitpack::write_set( &mut output, values, &packing ).expect( "Writing the set failed." );
+            } );
+        },
+        Encoding::Dictionary =>
+        {
+            // The frequencies of the values are counted with an extra pass.
+            let mut builder = dictionary::DictionaryBuilder::new();
+            for_each_set( data, |values: &[T]| builder.add( values ) );
+            let dictionary = builder.build();
+            dictionary::write_header( &mut output, &dictionary ).expect( "Writing the header failed." );
+            for_each_set( data, |values: &[T]| {
+                dictionary::write_set( &mut output, values, &dictionary ).expect( "Writing the set failed." );
+            } );
+        },
+        Encoding::Weighted =>
+        {
+            // Sets without weights get a unit weight for each value.
+            weighted::write_header( &mut output ).expect( "Writing the header failed." );
+            for_each_set( data, |values: &[T]| {
+                weighted::write_set( &mut output, values, &vec![ 1.0; values.len() ] ).expect( "Writing the set failed." );
+            } );
+        },
+    }
+}
+
+/// Views the raw bytes as values.
+pub fn as_values<T>( data: &[u8] ) -> &[T]
+{
+    unsafe { std::slice::from_raw_parts( data.as_ptr() as *const T, data.len() / std::mem::size_of::<T>() ) }
+}
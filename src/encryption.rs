@@ -0,0 +1,221 @@
+#[cfg(feature="encrypt")]
+extern crate aes_gcm;
+#[cfg(feature="encrypt")]
+extern crate rand;
+#[cfg(feature="encrypt")]
+extern crate sha2;
+extern crate std;
+
+use std::io::prelude::*;
+
+#[cfg(feature="encrypt")]
+use self::aes_gcm::{Aes256Gcm, KeyInit};
+#[cfg(feature="encrypt")]
+use self::aes_gcm::aead::generic_array::GenericArray;
+#[cfg(feature="encrypt")]
+use self::aes_gcm::aead::stream::{DecryptorBE32, EncryptorBE32};
+#[cfg(feature="encrypt")]
+use self::sha2::{Digest, Sha256};
+
+/// Identifies an encrypted set file.
+const MAGIC: &'static [u8] = b"SSEENC01";
+
+/// Length of the random prefix of the nonces of the chunks. The rest of the nonce is the chunk counter.
+#[cfg(feature="encrypt")]
+const NONCE_PREFIX_LEN: usize = 7;
+
+/// Size of the file header: the magic and the nonce prefix.
+#[cfg(feature="encrypt")]
+const HEADER_LEN: usize = 8 + NONCE_PREFIX_LEN;
+
+/// Bytes of the file encrypted as a single chunk. The last chunk may be shorter.
+#[cfg(feature="encrypt")]
+const CHUNK_LEN: usize = 1024 * 1024;
+
+/// Length of the authentication tag following each encrypted chunk.
+#[cfg(feature="encrypt")]
+const TAG_LEN: usize = 16;
+
+/// Option selecting the key of the encrypted files read by the command.
+const DECRYPT_OPTION: &'static str = "--decrypt";
+
+/// Environment variable holding the key file of the encrypted files when the option is not given.
+const DECRYPT_VARIABLE: &'static str = "SSE_DECRYPT";
+
+/// Checks whether the data is an encrypted set file.
+pub fn is_encrypted( data: &[u8] ) -> bool
+{
+    data.starts_with( MAGIC )
+}
+
+/// Takes the key file for decrypting the files from the arguments.
+/// The key file is passed to the rest of the run and to its child processes in SSE_DECRYPT.
+pub fn take( arguments: Vec<String> ) -> Vec<String>
+{
+    let mut remaining = Vec::with_capacity( arguments.len() );
+    let mut arguments = arguments.into_iter();
+    while let Some( argument ) = arguments.next()
+    {
+        if argument == DECRYPT_OPTION
+        {
+            std::env::set_var( DECRYPT_VARIABLE, arguments.next().expect( "The key file for decrypting is missing." ) );
+        }
+        else if argument.starts_with( &format!( "{}=", DECRYPT_OPTION ) )
+        {
+            std::env::set_var( DECRYPT_VARIABLE, &argument[ DECRYPT_OPTION.len() + 1.. ] );
+        }
+        else
+        {
+            remaining.push( argument );
+        }
+    }
+    return remaining;
+}
+
+/// Wraps the output into a writer encrypting everything written to it with the key of the key file.
+/// The last chunk is written when the writer is dropped.
+#[cfg(feature="encrypt")]
+pub fn encrypt<W>(
+    mut output: W,
+    key_file: &String,
+) -> std::io::Result<Box<dyn Write + Send>>
+where
+    W: 'static + Write + Send,
+{
+    let prefix: Vec<u8> = ( 0..NONCE_PREFIX_LEN ).map( |_| rand::random::<u8>() ).collect();
+    output.write_all( MAGIC )?;
+    output.write_all( &prefix )?;
+    let cipher = Aes256Gcm::new( GenericArray::from_slice( &read_key( key_file ) ) );
+    return Ok( Box::new( EncryptingWriter { output: output,
+            encryptor: Some( EncryptorBE32::from_aead( cipher, GenericArray::from_slice( &prefix ) ) ),
+            buffer: Vec::with_capacity( CHUNK_LEN ) } ) );
+}
+
+/// The encryption is not enabled.
+#[cfg(not(feature="encrypt"))]
+pub fn encrypt<W>(
+    _output: W,
+    _key_file: &String,
+) -> std::io::Result<Box<dyn Write + Send>>
+where
+    W: 'static + Write + Send,
+{
+    panic!( "Encrypting the sets requires the encrypt feature." );
+}
+
+/// Decrypts the encrypted set file with the key of the key file given in SSE_DECRYPT.
+/// Returns the contents in words for aligning the values and the length of the contents in bytes.
+#[cfg(feature="encrypt")]
+pub fn decrypt( data: &[u8] ) -> Result<( Vec<u64>, usize ), String>
+{
+    let key_file = std::env::var( DECRYPT_VARIABLE )
+            .map_err( |_| format!( "The file is encrypted. Give the key with {}=<keyfile>.", DECRYPT_OPTION ) )?;
+    if data.len() < HEADER_LEN + TAG_LEN
+    {
+        return Err( "The encrypted file is truncated.".to_string() );
+    }
+    let cipher = Aes256Gcm::new( GenericArray::from_slice( &read_key( &key_file ) ) );
+    let mut decryptor = DecryptorBE32::from_aead( cipher, GenericArray::from_slice( &data[ MAGIC.len()..HEADER_LEN ] ) );
+
+    // Each chunk grows by the tag. The last chunk holds at most a full chunk and may be empty
+    // but always has its tag, a shorter last chunk means the file is truncated.
+    let mut encrypted = &data[ HEADER_LEN.. ];
+    let full_chunks = ( encrypted.len() - 1 ) / ( CHUNK_LEN + TAG_LEN );
+    let len = ( encrypted.len() - full_chunks * ( CHUNK_LEN + TAG_LEN ) ).checked_sub( TAG_LEN )
+            .map( |last| full_chunks * CHUNK_LEN + last )
+            .ok_or_else( || "The encrypted file is truncated.".to_string() )?;
+    let mut words = vec![ 0u64; ( len + 7 ) / 8 ];
+    let mut position = 0;
+    {
+        let contents = unsafe { std::slice::from_raw_parts_mut( words.as_mut_ptr() as *mut u8, len ) };
+        while encrypted.len() > CHUNK_LEN + TAG_LEN
+        {
+            let chunk = decryptor.decrypt_next( &encrypted[ ..CHUNK_LEN + TAG_LEN ] )
+                    .map_err( |_| "Decrypting the file failed. The key is wrong or the file is corrupted.".to_string() )?;
+            contents[ position..position + chunk.len() ].copy_from_slice( &chunk );
+            position += chunk.len();
+            encrypted = &encrypted[ CHUNK_LEN + TAG_LEN.. ];
+        }
+        let chunk = decryptor.decrypt_last( encrypted )
+                .map_err( |_| "Decrypting the file failed. The key is wrong or the file is corrupted.".to_string() )?;
+        contents[ position..position + chunk.len() ].copy_from_slice( &chunk );
+    }
+    return Ok( ( words, len ) );
+}
+
+/// The encryption is not enabled.
+#[cfg(not(feature="encrypt"))]
+pub fn decrypt( _data: &[u8] ) -> Result<( Vec<u64>, usize ), String>
+{
+    return Err( "Decrypting the sets requires the encrypt feature.".to_string() );
+}
+
+/// Writer encrypting the data in chunks with AES-256-GCM before writing it to the output.
+#[cfg(feature="encrypt")]
+struct EncryptingWriter<W>
+where
+    W: Write,
+{
+    output: W,
+    /// Taken for encrypting the last chunk.
+    encryptor: Option<EncryptorBE32<Aes256Gcm>>,
+    /// Data of the chunk not yet encrypted.
+    buffer: Vec<u8>,
+}
+
+#[cfg(feature="encrypt")]
+impl<W> Write for EncryptingWriter<W>
+where
+    W: Write,
+{
+    /// Encrypts the full chunks. A full chunk is kept back until more data arrives as the last chunk is encrypted differently.
+    fn write( &mut self, data: &[u8] ) -> std::io::Result<usize>
+    {
+        self.buffer.extend_from_slice( data );
+        while self.buffer.len() > CHUNK_LEN
+        {
+            let encrypted = self.encryptor.as_mut().expect( "The last chunk has been written." )
+                    .encrypt_next( &self.buffer[ ..CHUNK_LEN ] )
+                    .map_err( |_| std::io::Error::new( std::io::ErrorKind::Other, "Encrypting the chunk failed." ) )?;
+            self.output.write_all( &encrypted )?;
+            self.buffer.drain( ..CHUNK_LEN );
+        }
+        return Ok( data.len() );
+    }
+
+    fn flush( &mut self ) -> std::io::Result<()>
+    {
+        return self.output.flush();
+    }
+}
+
+#[cfg(feature="encrypt")]
+impl<W> Drop for EncryptingWriter<W>
+where
+    W: Write,
+{
+    /// Writes the last chunk. A writer dropped while panicking leaves the file without its last chunk.
+    fn drop( &mut self )
+    {
+        if std::thread::panicking()
+        {
+            return;
+        }
+        if let Some( encryptor ) = self.encryptor.take()
+        {
+            let encrypted = encryptor.encrypt_last( &self.buffer[..] ).expect( "Encrypting the last chunk failed." );
+            self.output.write_all( &encrypted ).and_then( |_| self.output.flush() ).expect( "Writing the last chunk failed." );
+        }
+    }
+}
+
+/// Derives the 256-bit key from the contents of the key file.
+#[cfg(feature="encrypt")]
+fn read_key( key_file: &String ) -> Vec<u8>
+{
+    let mut key = Vec::new();
+    std::fs::File::open( key_file )
+        .and_then( |mut f| f.read_to_end( &mut key ) )
+        .expect( "Failed to read the key file." );
+    return Sha256::digest( &key ).to_vec();
+}
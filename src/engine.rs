@@ -0,0 +1,360 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use encoding;
+use enumerations::*;
+use evaluation::*;
+use http;
+use topk::TopSets;
+use traits::*;
+
+/// A backend evaluating the attached sets against a test set.
+/// New backends implement this trait and are added to an EngineRegistry.
+pub trait EvaluationEngine<T>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
+{
+    /// Name used to select the engine.
+    fn name( &self ) -> &'static str;
+
+    /// Checks whether the engine can evaluate sets on this machine.
+    fn is_available( &self ) -> bool
+    {
+        true
+    }
+
+    /// Evaluates the sets against the test set.
+    fn evaluate(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_set: &[T],
+        params: &EvaluationParams,
+    ) -> EvaluationResult;
+
+    /// Evaluates the sets against each of the test sets.
+    /// Engines amortizing their overhead over several test sets evaluate the test sets together.
+    fn evaluate_batch(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_sets: &[&[T]],
+        params: &EvaluationParams,
+    ) -> Vec<EvaluationResult>
+    {
+        return test_sets.iter().map( |test_set| self.evaluate( sets, test_set, params ) ).collect();
+    }
+
+    /// Selects the k sets with the most values found from each of the test sets.
+    /// Engines without their own selection return None and the sets are selected with CPU after the evaluation.
+    fn top_sets(
+        &self,
+        _sets: &SetsForEvaluation<T>,
+        _test_sets: &[&[T]],
+        _params: &EvaluationParams,
+    ) -> Option<Vec<TopSets>>
+    {
+        None
+    }
+
+    /// Evaluates each of the sets against the test set separately.
+    /// Returns whether each set matched in the order of the sets.
+    /// Engines without their own evaluation of the separate sets evaluate the sets one at a time.
+    fn match_each(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_set: &[T],
+        params: &EvaluationParams,
+    ) -> Vec<bool>
+    {
+        return ( 0..sets.set_count() ).map( |i| self.evaluate( &sets.only( i ), test_set, params ).match_count > 0 ).collect();
+    }
+
+    /// Evaluates the sets against a test set already attached as a scalar set.
+    /// Engines not using the attached set evaluate the values of the set instead.
+    fn evaluate_attached(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        _query: &ro_scalar_set::RoScalarSet<T>,
+        values: &[T],
+        params: &EvaluationParams,
+    ) -> EvaluationResult
+    {
+        return self.evaluate( sets, values, params );
+    }
+
+    /// Evaluates the encoded sets of the file against the test set.
+    /// Engines without their own evaluation of the encoded sets return None.
+    fn evaluate_encoded(
+        &self,
+        _encoding: Encoding,
+        _data: &[u8],
+        _test_set: &[T],
+        _params: &EvaluationParams,
+    ) -> Option<EvaluationResult>
+    {
+        None
+    }
+
+    /// Evaluates each of the encoded sets of the file against the test set separately.
+    /// Returns whether each set matched in the order of the file or None if the engine does not support encoded sets.
+    fn match_each_encoded(
+        &self,
+        _data: &[u8],
+        _test_set: &[T],
+        _params: &EvaluationParams,
+    ) -> Option<Vec<bool>>
+    {
+        None
+    }
+
+    /// Evaluates the raw sets of a file on an HTTP server as they are received.
+    /// Engines without their own evaluation of the streamed sets return None.
+    fn evaluate_streamed(
+        &self,
+        _url: &str,
+        _test_set: &[T],
+        _params: &EvaluationParams,
+    ) -> Option<EvaluationResult>
+    {
+        None
+    }
+}
+
+/// Evaluates the sets in parallel with CPU.
+pub struct CpuEngine;
+
+impl<T> EvaluationEngine<T> for CpuEngine
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits,
+{
+    fn name( &self ) -> &'static str
+    {
+        "cpu"
+    }
+
+    fn evaluate(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_set: &[T],
+        params: &EvaluationParams,
+    ) -> EvaluationResult
+    {
+        // The batched strategy searches the values of the sets from the sorted test set.
+        if params.strategy == Strategy::Batched
+        {
+            return sets.evaluate_batched_with_cpu( test_set, params.preload, params.max_threads, params.prefetch_distance, params.breakdown );
+        }
+
+        // Large test sets are divided into blocks fitting in the cache.
+        if params.query_block > 0 && test_set.len() > params.query_block
+        {
+            let blocks: Vec<ro_scalar_set::RoScalarSet<T>> = test_set.chunks( params.query_block )
+                    .map( |b| ro_scalar_set::RoScalarSet::new( b ) )
+                    .collect();
+            let blocks: Vec<&ro_scalar_set::RoScalarSet<T>> = blocks.iter().collect();
+            return sets.evaluate_blocks_with_cpu( &blocks, params.preload, params.max_threads, params.prefetch_distance, params.breakdown );
+        }
+        return sets.evaluate_with_cpu( &ro_scalar_set::RoScalarSet::new( test_set ),
+                params.preload, params.max_threads, params.prefetch_distance, params.breakdown );
+    }
+
+    fn evaluate_attached(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        query: &ro_scalar_set::RoScalarSet<T>,
+        values: &[T],
+        params: &EvaluationParams,
+    ) -> EvaluationResult
+    {
+        if params.strategy == Strategy::Batched
+        {
+            return self.evaluate( sets, values, params );
+        }
+        return sets.evaluate_with_cpu( query, params.preload, params.max_threads, params.prefetch_distance, params.breakdown );
+    }
+
+    fn match_each(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_set: &[T],
+        params: &EvaluationParams,
+    ) -> Vec<bool>
+    {
+        return sets.match_each_with_cpu( &ro_scalar_set::RoScalarSet::new( test_set ), params.max_threads );
+    }
+
+    fn evaluate_encoded(
+        &self,
+        encoding: Encoding,
+        data: &[u8],
+        test_set: &[T],
+        params: &EvaluationParams,
+    ) -> Option<EvaluationResult>
+    {
+        return Some( encoding::evaluate( encoding, data, test_set, params.strategy, params.preload, params.max_threads ) );
+    }
+
+    fn match_each_encoded(
+        &self,
+        data: &[u8],
+        test_set: &[T],
+        params: &EvaluationParams,
+    ) -> Option<Vec<bool>>
+    {
+        let mut set_count = 0;
+        encoding::for_each_set( data, |_: &[T]| set_count += 1 );
+        let indexes: Vec<usize> = ( 0..set_count ).collect();
+        return Some( encoding::evaluate_sets_at( data, test_set, &indexes, params.strategy ) );
+    }
+
+    fn evaluate_streamed(
+        &self,
+        url: &str,
+        test_set: &[T],
+        _params: &EvaluationParams,
+    ) -> Option<EvaluationResult>
+    {
+        return Some( http::evaluate( url, test_set ) );
+    }
+}
+
+/// Evaluates the sets one at a time in the order of the file with a log line for each set.
+/// Used for debugging the differences to the reference implementation.
+pub struct SequentialEngine;
+
+impl<T> EvaluationEngine<T> for SequentialEngine
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
+{
+    fn name( &self ) -> &'static str
+    {
+        "sequential"
+    }
+
+    fn evaluate(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_set: &[T],
+        params: &EvaluationParams,
+    ) -> EvaluationResult
+    {
+        return sets.evaluate_sequentially( &ro_scalar_set::RoScalarSet::new( test_set ), params.preload );
+    }
+
+    fn evaluate_attached(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        query: &ro_scalar_set::RoScalarSet<T>,
+        _values: &[T],
+        params: &EvaluationParams,
+    ) -> EvaluationResult
+    {
+        return sets.evaluate_sequentially( query, params.preload );
+    }
+
+    fn match_each(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_set: &[T],
+        _params: &EvaluationParams,
+    ) -> Vec<bool>
+    {
+        return sets.match_each_with_cpu( &ro_scalar_set::RoScalarSet::new( test_set ), 1 );
+    }
+}
+
+/// Evaluates the sets with GPU.
+pub struct GpuEngine;
+
+impl<T> EvaluationEngine<T> for GpuEngine
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
+{
+    fn name( &self ) -> &'static str
+    {
+        "gpu"
+    }
+
+    fn is_available( &self ) -> bool
+    {
+        gpu_available()
+    }
+
+    fn evaluate(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_set: &[T],
+        params: &EvaluationParams,
+    ) -> EvaluationResult
+    {
+        return sets.evaluate_sets_gpu( &[ test_set ], params.gpu_compare, params.gpu_memory, params.gpu_kernel ).remove( 0 );
+    }
+
+    fn evaluate_batch(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_sets: &[&[T]],
+        params: &EvaluationParams,
+    ) -> Vec<EvaluationResult>
+    {
+        // All the test sets are evaluated in a single launch of the kernel.
+        return sets.evaluate_sets_gpu( test_sets, params.gpu_compare, params.gpu_memory, params.gpu_kernel );
+    }
+
+    fn top_sets(
+        &self,
+        sets: &SetsForEvaluation<T>,
+        test_sets: &[&[T]],
+        params: &EvaluationParams,
+    ) -> Option<Vec<TopSets>>
+    {
+        // The sets are scored and sorted on the device and only the best sets are read back.
+        return Some( sets.top_sets_gpu( test_sets, params.top_k, params.gpu_compare, params.gpu_memory, params.memory_limit ) );
+    }
+}
+
+/// The engines available for the evaluation by their name.
+pub struct EngineRegistry<T>
+{
+    engines: Vec<Box<dyn EvaluationEngine<T>>>,
+}
+
+impl<T> EngineRegistry<T>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits,
+{
+    /// Initializes a registry with the built-in engines.
+    pub fn new() -> EngineRegistry<T>
+    {
+        let mut registry = EngineRegistry { engines: Vec::new() };
+        registry.register( Box::new( CpuEngine ) );
+        registry.register( Box::new( GpuEngine ) );
+        registry.register( Box::new( SequentialEngine ) );
+        return registry;
+    }
+
+    /// Registers an engine. An engine with the same name is replaced.
+    pub fn register(
+        &mut self,
+        engine: Box<dyn EvaluationEngine<T>>,
+    )
+    {
+        let name = engine.name();
+        self.engines.retain( |e| e.name() != name );
+        self.engines.push( engine );
+    }
+
+    /// Finds an engine by its name.
+    pub fn find(
+        &self,
+        name: &str,
+    ) -> Option<&dyn EvaluationEngine<T>>
+    {
+        self.engines.iter().find( |e| e.name() == name ).map( |e| e.as_ref() )
+    }
+
+    /// Gets the names of the registered engines.
+    pub fn names( &self ) -> Vec<&'static str>
+    {
+        self.engines.iter().map( |e| e.name() ).collect()
+    }
+}
@@ -1,7 +1,108 @@
 
-/// The enegine used to evluate the set.
-pub enum EvaluationEngine
+/// Determines how much of the data is copied into memory before the evaluation.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PreloadMode
 {
-    Cpu,
-    Gpu,
+    /// All data is read directly from the file.
+    Disabled,
+    /// The sets are copied into memory.
+    Full,
+}
+
+/// The encoding of the sets in a file.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Encoding
+{
+    /// Sets serialized as attachable scalar sets.
+    Raw,
+    /// Sorted values stored as variable-length deltas (format v2).
+    Delta,
+    /// Values packed with the minimum number of bits required by the value range.
+    Bitpack,
+    /// Values replaced with codes assigned by their frequency.
+    Dictionary,
+    /// Values stored with a weight.
+    Weighted,
+}
+
+/// The operation evaluated against each set.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Operation
+{
+    /// The set matches if it has any value of the test set.
+    Any,
+    /// The set matches if the weights of its values found from the test set sum up to at least the threshold.
+    WeightedOverlap( f32 ),
+}
+
+/// Algorithm used for finding the common values of a sorted set and the test set.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Strategy
+{
+    /// The strategy of the encoding: the delta encoded sets are merged and the other sets are probed.
+    Auto,
+    /// Each value of the set is searched from the test set.
+    Probe,
+    /// The sorted set and the sorted test set are merged linearly.
+    Merge,
+    /// Each value of the test set is searched from the set with an exponential search
+    /// continuing from the position of the previous value.
+    Galloping,
+    /// Several values of the set are searched from the test set at a time with
+    /// branchless binary searches.
+    Batched,
+}
+
+/// Key the sets are ordered by when sorting a file.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortKey
+{
+    /// The number of values in the set.
+    Size,
+    /// The smallest value of the set. Empty sets are ordered last.
+    MinValue,
+}
+
+/// Protocol spoken by the server.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Protocol
+{
+    /// A line of values per request and a line with the matches and the latency per response.
+    Text,
+    /// A subset of the Redis serialization protocol.
+    Resp,
+}
+
+/// Comparison of the float values compiled into the GPU kernel.
+#[derive(Clone, Copy, PartialEq)]
+pub enum GpuCompare
+{
+    /// The values match only when they are equal like in the CPU evaluation.
+    Exact,
+    /// The values match when they differ less than a fixed epsilon.
+    Epsilon,
+    /// The values match when they are at most a few units in the last place apart.
+    Ulp,
+}
+
+/// Host memory backing the buffers of the GPU evaluation.
+#[derive(Clone, Copy, PartialEq)]
+pub enum GpuMemory
+{
+    /// The data is copied from pageable memory into buffers allocated by the driver.
+    Default,
+    /// The data is staged in page-locked host memory for faster transfers to the device.
+    Pinned,
+    /// The device accesses the host memory directly without a copy. Suits integrated GPUs sharing the memory with the CPU.
+    ZeroCopy,
+}
+
+/// Layout of the work of the GPU kernel.
+#[derive(Clone, Copy, PartialEq)]
+pub enum GpuKernel
+{
+    /// A single work-item scans each set against the whole test set.
+    Item,
+    /// A work-group scans each set. The work-items share the test set in local memory.
+    WorkGroup,
 }
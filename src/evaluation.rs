@@ -2,7 +2,6 @@ extern crate ro_scalar_set;
 extern crate std;
 extern crate rayon;
 extern crate rand;
-extern crate memmap;
 
 #[cfg(feature="gpu")]
 extern crate ocl;
@@ -14,69 +13,781 @@ use self::ocl::Buffer;
 #[cfg(feature="gpu")]
 use self::ocl::MemFlags;
 
-use std::slice;
-
-use memmap::{Mmap, Protection};
 use self::rayon::prelude::*;
 use rand::distributions::{Range};
 
+use audit::{self, Audit};
+use bitpack;
+use breakdown::Breakdown;
+use cancel::CancellationToken;
 use enumerations::*;
+use explain::{self, Explanation, SetMatch};
+use cache::SetCache;
+use engine::{EngineRegistry, EvaluationEngine};
+use http;
+use intersect;
+use encoding;
+use mapping::MappedFile;
+use metrics::{Metrics, Phase};
+use prefetch::Prefetcher;
+use query::QueryFile;
+use reduction::{self, ChunkResult, Reduction};
+use spill::Spool;
+use repro::{self, Repro};
+use signature;
+use throttle::Throttle;
 use traits::*;
+use transform::{self, Transform};
 use utility;
+use timestamp;
+use topk::{self, TopSets};
+use tuple;
+use uuid;
+use verify::{self, Verification};
+use weighted;
 
 /// Parameters for the evaluation.
+#[derive(Clone, Copy)]
 pub struct EvaluationParams<'a>
 {
     pub file: &'a String,
     pub values_in_set: i32,
     pub min_value: i32,
     pub max_value: i32,
-    pub preload_data: bool,
+    pub preload: PreloadMode,
     pub max_threads: usize,
-    pub eval_engine: &'a EvaluationEngine,
+    pub prefetch_distance: usize,
+    pub cache_size: usize,
+    /// Limit for reading the sets from the file in bytes per second. Zero disables the limit.
+    pub io_limit: usize,
+    /// Number of sets checked against the reference implementation. Zero disables the check.
+    pub verify_sample: usize,
+    pub operation: Operation,
+    /// Dimension of the tuples matched with the test set. All the dimensions are matched if not set.
+    pub dimension: Option<usize>,
+    /// Transformation applied to the values of the sets as they are read.
+    pub transform: Option<Transform>,
+    /// Number of values in a block of the test set evaluated at a time. Zero evaluates the whole test set at once.
+    pub query_block: usize,
+    /// Strategy for intersecting the sorted sets with the test set.
+    pub strategy: Strategy,
+    /// Measure each set for a breakdown of the results by the set size.
+    pub breakdown: bool,
+    /// Count the sets each value of the test set was found from after the evaluation.
+    pub explain: bool,
+    /// Record whether each set matched after the evaluation.
+    pub set_results: bool,
+    /// Sets overlapping with any value of the sets of this file never match.
+    pub exclude_file: Option<&'a String>,
+    /// Selects the sets included in the evaluation.
+    pub set_filter: SetFilter,
+    /// Name of the engine in the engine registry.
+    pub eval_engine: &'a str,
+    /// Fraction of the raw sets re-evaluated with the audit engine after the evaluation. Zero disables the audit.
+    pub audit: f64,
+    /// Name of the engine the sample of the sets is re-evaluated with.
+    pub audit_engine: &'a str,
+    /// Export the sets the engines disagree on into a bundle in the current directory for eval --repro.
+    pub repro: bool,
+    /// Comparison of the float values compiled into the GPU kernel.
+    pub gpu_compare: GpuCompare,
+    /// Host memory backing the buffers of the GPU evaluation.
+    pub gpu_memory: GpuMemory,
+    /// Layout of the work of the GPU kernel.
+    pub gpu_kernel: GpuKernel,
+    /// Number of the sets with the most values found from the test set reported after the evaluation. Zero disables the report.
+    pub top_k: usize,
+    /// Limit for the memory of the per-set results and the top sets in bytes beyond which they are spilled to temporary files.
+    /// Zero disables the limit.
+    pub memory_limit: usize,
+    /// Limit for the evaluation of each test set after which the remaining sets are left unevaluated.
+    pub timeout: Option<std::time::Duration>,
+    /// Stops the evaluation of the remaining sets once cancelled. The sets left unevaluated do not match.
+    pub cancellation: Option<&'a CancellationToken>,
+    /// The key file and the signature file the sets of the file must be signed with.
+    pub signature: Option<( &'a String, &'a String )>,
+}
+
+/// Selects the sets attached for the evaluation by their index in the file and their number of values.
+#[derive(Clone, Copy)]
+pub struct SetFilter
+{
+    /// Sets with fewer values are left out.
+    pub min_size: usize,
+    /// Sets with more values are left out. Zero disables the limit.
+    pub max_size: usize,
+    /// Range of the indexes of the sets in the file, the end is exclusive.
+    pub indexes: Option<( usize, usize )>,
+    /// Only every nth set counted from the start of the range is selected. Zero and one select every set.
+    pub every: usize,
+    /// Seed and fraction of a random sample of the sets.
+    pub sample: Option<( u64, f64 )>,
+}
+
+impl SetFilter
+{
+    /// Selects all the sets.
+    pub fn all() -> SetFilter
+    {
+        return SetFilter { min_size: 0, max_size: 0, indexes: None, every: 0, sample: None };
+    }
+
+    /// Checks whether the filter leaves out any sets.
+    pub fn is_active( &self ) -> bool
+    {
+        return self.min_size > 0 || self.max_size > 0 || self.indexes.is_some() || self.every > 1 || self.sample.is_some();
+    }
+
+    /// Checks whether the set at the index of the file with the given number of values is selected.
+    pub fn accepts(
+        &self,
+        index: usize,
+        size: usize,
+    ) -> bool
+    {
+        let first = match self.indexes
+        {
+            Some( ( first, end ) ) if index < first || index >= end => return false,
+            Some( ( first, _ ) ) => first,
+            None => 0,
+        };
+        if self.every > 1 && ( index - first ) % self.every != 0
+        {
+            return false;
+        }
+
+        // The sample is the same for the same seed.
+        if let Some( ( seed, fraction ) ) = self.sample
+        {
+            if utility::mix( seed.wrapping_add( index as u64 ) ) as f64 / std::u64::MAX as f64 >= fraction
+            {
+                return false;
+            }
+        }
+        return size >= self.min_size && ( self.max_size == 0 || size <= self.max_size );
+    }
 }
 
+/// Size of the chunks of the sets copied into memory in parallel when preloading.
+const PRELOAD_CHUNK: usize = 64 * 1024 * 1024;
+
 /// Holds the results of an evaluation
 pub struct EvaluationResult
 {
-    pub match_count: u32,
+    pub match_count: u64,
     pub duration: std::time::Duration,
-    pub data_preloaded: bool,
-    pub thread_count: usize
+    pub preload: PreloadMode,
+    pub thread_count: usize,
+    pub verification: Option<Verification>,
+    /// Results by the set size when the sets were measured individually.
+    pub breakdown: Option<Breakdown>,
+    /// Contributions of the values of the test set to the matches.
+    pub explanation: Option<Explanation>,
+    /// Durations of the phases of the evaluation and the amount of data evaluated.
+    pub metrics: Metrics,
+    /// Partial results of the chunks of the sets and the time spent on combining them.
+    pub reduction: Option<Reduction>,
+    /// Agreement of the main engine and the audit engine on a sample of the sets.
+    pub audit: Option<Audit>,
+    /// Result of each set when the results of the sets were recorded.
+    pub set_results: Option<Spool<SetMatch>>,
+    /// Fraction of the file in the page cache before the evaluation when the sets were read from the file.
+    /// Only the first query of an evaluation is annotated as the earlier queries warm the cache for the later ones.
+    pub page_cache: Option<f64>,
+    /// Sets with the most values found from the test set when requested.
+    pub top: Option<TopSets>,
+    /// The evaluation was stopped by the timeout and the matches are from the sets evaluated before it.
+    pub timed_out: bool,
+}
+
+impl EvaluationResult
+{
+    /// Initializes the result of an evaluation without the optional details.
+    pub fn new(
+        match_count: u64,
+        duration: std::time::Duration,
+        preload: PreloadMode,
+        thread_count: usize,
+        metrics: Metrics,
+    ) -> EvaluationResult
+    {
+        return EvaluationResult { match_count: match_count, duration: duration,
+                preload: preload, thread_count: thread_count, verification: None, breakdown: None, explanation: None,
+                metrics: metrics, reduction: None, audit: None, set_results: None, page_cache: None, top: None, timed_out: false };
+    }
+
+    /// Describes the page cache before the evaluation as warm, cold or partial.
+    /// The duration of a warm evaluation does not include reading the sets from the disk.
+    pub fn cache_state( &self ) -> Option<&'static str>
+    {
+        return self.page_cache.map( |cached| if cached >= WARM_CACHE { "warm" } else if cached <= COLD_CACHE { "cold" } else { "partial" } );
+    }
 }
 
-/// Evaluates integer sets.
-pub fn evaluate<'a, T>(
-    params: &EvaluationParams
+/// Evaluates the sets of an encoding other than the raw sets with CPU using the given number of threads.
+/// The sets are attached from data of the given size in bytes.
+pub fn evaluate_in_pool<S, F>(
+    sets: &[S],
+    bytes: u64,
+    preload: PreloadMode,
+    thread_count: usize,
+    matches: F,
 ) -> EvaluationResult
 where
-    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
+    S: Sync,
+    F: Fn( &S ) -> bool + Sync,
+{
+    // Limit the number of threads used in the testing.
+    let threads = rayon::ThreadPool::new(
+            rayon::Configuration:: new().num_threads( thread_count )
+    ).unwrap();
+    return threads.install( || {
+        let start = std::time::Instant::now();
+        let match_counter = sets.par_iter()
+                .map( |s| if matches( s ) { 1 } else { 0 } )
+                .sum();
+        let duration = std::time::Instant::now().duration_since( start );
+        EvaluationResult::new( match_counter, duration, preload, rayon::current_num_threads(),
+                Metrics::evaluation( duration, bytes, sets.len() as u64 ) )
+    } );
+}
+
+/// The page cache is warm when at least this fraction of the file is cached.
+const WARM_CACHE: f64 = 0.9;
+
+/// The page cache is cold when at most this fraction of the file is cached.
+const COLD_CACHE: f64 = 0.1;
+
+/// Generates the given number of random test sets of each size ordered by the size.
+/// Evaluating all of them with evaluate_with_queries loads the sets only once.
+pub fn generate_test_sets<T>(
+    min_value: i32,
+    max_value: i32,
+    sizes: &[i32],
+    query_count: usize,
+) -> Vec<Vec<T>>
+where
+    T: FromI32,
 {
-    // Construct test vector.
-    let between = Range::new( params.min_value, params.max_value );
-    let test_set = utility::generate_values( params.values_in_set, &between );
+    let between = Range::new( min_value, max_value );
+    return sizes.iter()
+            .flat_map( |size| ( 0..query_count ).map( move |_| *size ) )
+            .map( |size| utility::generate_values( size, &between ) )
+            .collect();
+}
+
+/// Evaluates the sets against the given test set.
+pub fn evaluate_with_query<T>(
+    params: &EvaluationParams,
+    test_set: &[T],
+) -> EvaluationResult
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    return evaluate_with_queries( params, &[ test_set ] ).remove( 0 );
+}
+
+/// Evaluates the sets against the inclusive ranges of values. A set matches if any of its values is within any of the ranges.
+/// The values of the sets are checked against the merged ranges instead of expanding the ranges into a test set.
+pub fn evaluate_ranges<T>(
+    params: &EvaluationParams,
+    ranges: &[( i32, i32 )],
+) -> EvaluationResult
+where
+    T: FromI32 + OrderedBits + ro_scalar_set::Value,
+{
+    if params.eval_engine != "cpu" || params.operation != Operation::Any || params.transform.is_some() || params.exclude_file.is_some()
+            || params.verify_sample > 0 || params.explain || params.set_results || params.top_k > 0 || params.audit > 0.0
+            || params.timeout.is_some() || http::is_http( params.file )
+    {
+        panic!( "Ranges are only evaluated with CPU for any value of the sets of a local file." );
+    }
+    let file = open_sets( params );
+    let data = file.as_slice();
+    if tuple::is_tuple_file( data ) || uuid::is_uuid_file( data ) || timestamp::is_timestamp_file( data )
+    {
+        panic!( "Ranges are only evaluated for sets of values." );
+    }
+
+    // The overlapping and adjacent ranges are merged so that each key falls into at most one range.
+    let mut keys: Vec<( u64, u64 )> = ranges.iter()
+            .map( |&( lo, hi )| ( T::from_i32( &lo ).to_ordered_bits(), T::from_i32( &hi ).to_ordered_bits() ) )
+            .filter( |&( lo, hi )| lo <= hi )
+            .collect();
+    keys.sort();
+    let mut merged: Vec<( u64, u64 )> = Vec::new();
+    for ( lo, hi ) in keys
+    {
+        match merged.last_mut()
+        {
+            Some( last ) if lo <= last.1.saturating_add( 1 ) => last.1 = std::cmp::max( last.1, hi ),
+            _ => merged.push( ( lo, hi ) ),
+        }
+    }
+    let within = |key: u64| match merged.binary_search_by( |&( lo, _ )| lo.cmp( &key ) )
+    {
+        Ok( _ ) => true,
+        Err( 0 ) => false,
+        Err( i ) => key <= merged[ i - 1 ].1,
+    };
+
+    let start = std::time::Instant::now();
+    let mut index = 0;
+    let mut set_count = 0;
+    let mut match_count = 0;
+    encoding::for_each_set( data, |values: &[T]| {
+        if params.set_filter.accepts( index, values.len() )
+        {
+            set_count += 1;
+            if values.iter().any( |v| within( v.to_ordered_bits() ) )
+            {
+                match_count += 1;
+            }
+        }
+        index += 1;
+    } );
+    let duration = start.elapsed();
+    return EvaluationResult::new( match_count, duration, PreloadMode::Disabled, 1,
+            Metrics::evaluation( duration, data.len() as u64, set_count ) );
+}
+
+/// Evaluates the sets of UUIDs against the UUIDs of the query.
+pub fn evaluate_with_uuids(
+    params: &EvaluationParams,
+    uuids: &[u128],
+) -> EvaluationResult
+{
+    let file = open_sets( params );
+    if !uuid::is_uuid_file( file.as_slice() )
+    {
+        panic!( "Only sets of UUIDs can be evaluated with a query of UUIDs." );
+    }
+    if params.transform.is_some()
+    {
+        panic!( "Transforms are only applied to sets of values." );
+    }
+    return uuid::evaluate_uuids( file.as_slice(), uuids, params.preload, params.max_threads );
+}
+
+/// Evaluates the sets of timestamps against the inclusive time window in milliseconds since the epoch.
+pub fn evaluate_between(
+    params: &EvaluationParams,
+    from: i64,
+    to: i64,
+) -> EvaluationResult
+{
+    let file = open_sets( params );
+    if !timestamp::is_timestamp_file( file.as_slice() )
+    {
+        panic!( "Only sets of timestamps can be evaluated with a time window." );
+    }
+    if params.transform.is_some()
+    {
+        panic!( "Transforms are only applied to sets of values." );
+    }
+    return timestamp::evaluate( file.as_slice(), from, to, params.preload, params.max_threads );
+}
+
+/// Evaluates the sets against the query set of the query file.
+/// Raw sets are evaluated with the query attached directly from the query file.
+pub fn evaluate_with_query_file<T>(
+    params: &EvaluationParams,
+    query: &QueryFile,
+) -> EvaluationResult
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    let ( attached, values ) = query.attach::<T>();
+
+    // Other kinds of sets and the operations needing the values go through the regular evaluation.
+    if http::is_http( params.file ) || params.transform.is_some() || params.operation != Operation::Any || params.verify_sample > 0 || params.explain
+            || params.exclude_file.is_some() || params.set_filter.is_active()
+    {
+        return evaluate_with_query( params, values );
+    }
+    let file = open_sets( params );
+    let data = file.as_slice();
+    if encoding::detect( data ) != Encoding::Raw || tuple::is_tuple_file( data )
+            || uuid::is_uuid_file( data ) || timestamp::is_timestamp_file( data )
+    {
+        return evaluate_with_query( params, values );
+    }
+
+    let registry = EngineRegistry::new();
+    let engine = match registry.find( params.eval_engine )
+    {
+        Some( engine ) => engine,
+        None => panic!( "Unknown engine: {}", params.eval_engine ),
+    };
+    let buffer: &[T] = file.as_slice();
+    let mut sets = load_data( &buffer, params.preload, params.cache_size, params.max_threads, params.io_limit, &SetFilter::all() );
+    if let Some( cancellation ) = params.cancellation
+    {
+        sets.cancel_with( cancellation );
+    }
+    if let Some( timeout ) = params.timeout
+    {
+        sets.time_out_after( timeout );
+    }
+    return engine.evaluate_attached( &sets, &attached, values, params );
+}
+
+/// Checks whether the evaluation with the parameters was cancelled.
+pub fn is_cancelled( params: &EvaluationParams ) -> bool
+{
+    return params.cancellation.map_or( false, |c| c.is_cancelled() );
+}
+
+/// Evaluates the sets against each of the given test sets with the built-in engines.
+pub fn evaluate_with_queries<T>(
+    params: &EvaluationParams,
+    test_sets: &[&[T]],
+) -> Vec<EvaluationResult>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    return evaluate_with_registry( params, &EngineRegistry::new(), test_sets );
+}
+
+/// Evaluates the sets against each of the given test sets with an engine from the registry.
+pub fn evaluate_with_registry<T>(
+    params: &EvaluationParams,
+    registry: &EngineRegistry<T>,
+    test_sets: &[&[T]],
+) -> Vec<EvaluationResult>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    let engine = match registry.find( params.eval_engine )
+    {
+        Some( engine ) => engine,
+        None => panic!( "Unknown engine: {}", params.eval_engine ),
+    };
+
+    // Only the raw sets evaluated with CPU support excluding and selecting the sets and the timeout.
+    let cpu = engine.name() == "cpu" || engine.name() == "sequential";
+    let filtered = params.set_filter.is_active();
+    check_options( params, cpu, None );
+
+    // Files on a web server are streamed instead of mapped.
+    if http::is_http( params.file )
+    {
+        return test_sets.iter()
+            .map( |test_set| match engine.evaluate_streamed( params.file, test_set, params )
+            {
+                Some( result ) => result,
+                None => panic!( "Engine {} does not support streamed sets.", engine.name() ),
+            } )
+            .collect();
+    }
 
     // Open file for reading.
-    let file = std::fs::File::open( params.file ).expect( "Failed to open the file." );
-    let file = Mmap::open( &file, Protection::Read ).expect( "Failed to map the file" );
+    // The residency of the file is checked before the evaluation touches it.
+    let file = open_sets( params );
+    let page_cache = if params.preload != PreloadMode::Full { file.resident_fraction() } else { None };
+    check_options( params, cpu, Some( file.as_slice() ) );
+
+    // Sets of tuples have their own evaluation.
+    if tuple::is_tuple_file( file.as_slice() )
+    {
+        return test_sets.iter()
+            .map( |test_set| tuple::evaluate( file.as_slice(), test_set, params.dimension,
+                    params.preload, params.max_threads ) )
+            .collect();
+    }
+
+    // Sets of timestamps are only queried with time windows.
+    if timestamp::is_timestamp_file( file.as_slice() )
+    {
+        panic!( "Sets of timestamps are evaluated with a time window." );
+    }
+
+    // The test sets are mapped to UUIDs for sets of UUIDs.
+    if uuid::is_uuid_file( file.as_slice() )
+    {
+        return test_sets.iter()
+            .map( |test_set| uuid::evaluate( file.as_slice(), test_set, params.preload, params.max_threads ) )
+            .collect();
+    }
+
+    // Encoded sets are decoded on the fly.
+    let encoding = encoding::detect( file.as_slice() );
+    check_sorted( params.strategy, file.as_slice() ).unwrap_or_else( |e| panic!( "{}", e ) );
+    let mut results: Vec<EvaluationResult> = if let Operation::WeightedOverlap( threshold ) = params.operation
+    {
+        if encoding != Encoding::Weighted
+        {
+            panic!( "Weighted overlap requires weighted sets." );
+        }
+        test_sets.iter()
+            .map( |test_set| weighted::evaluate( file.as_slice(), test_set, Some( threshold ),
+                    params.strategy, params.preload, params.max_threads ) )
+            .collect()
+    }
+    else if let Some( ref transform ) = params.transform
+    {
+        // The transformed sets are materialized in memory as raw sets.
+        let transformed: Vec<T> = transform::load( file.as_slice(), transform );
+        let mut sets = load_data( &transformed, params.preload, params.cache_size, params.max_threads, 0, &params.set_filter );
+        if let Some( timeout ) = params.timeout
+        {
+            sets.time_out_after( timeout );
+        }
+        test_sets.iter()
+            .map( |test_set| engine.evaluate( &sets, test_set, params ) )
+            .collect()
+    }
+    else if encoding != Encoding::Raw
+    {
+        test_sets.iter()
+            .map( |test_set| match engine.evaluate_encoded( encoding, file.as_slice(), test_set, params )
+            {
+                Some( result ) => result,
+                None => panic!( "Engine {} does not support {} encoded sets.", engine.name(), encoding::name( encoding ) ),
+            } )
+            .collect()
+    }
+    else
+    {
+        let buffer: &[T] = file.as_slice();
+
+        // Divide the buffer into sets.
+        let mut sets = load_data( &buffer, params.preload, params.cache_size, params.max_threads, params.io_limit, &params.set_filter );
+        let exclusion = params.exclude_file.map( |f| load_exclusion::<T>( f ) );
+        if let Some( ref exclusion ) = exclusion
+        {
+            sets.exclude( exclusion );
+        }
+        if let Some( cancellation ) = params.cancellation
+        {
+            sets.cancel_with( cancellation );
+        }
+        if let Some( timeout ) = params.timeout
+        {
+            sets.time_out_after( timeout );
+        }
+
+        // Run tests for each set.
+        let mut results: Vec<EvaluationResult> = engine.evaluate_batch( &sets, test_sets, params );
+
+        // Engines selecting the best sets themselves do it with the loaded sets.
+        if params.top_k > 0
+        {
+            if let Some( top ) = engine.top_sets( &sets, test_sets, params )
+            {
+                for ( result, top ) in results.iter_mut().zip( top )
+                {
+                    result.top = Some( top );
+                }
+            }
+        }
+
+        // Re-evaluate a sample of the sets with the audit engine after the measured evaluation.
+        // The partial results of a cancelled evaluation or a timed out test set are not audited.
+        if params.audit > 0.0 && !is_cancelled( params )
+        {
+            for ( result, test_set ) in results.iter_mut().zip( test_sets ).filter( |&( ref r, _ )| !r.timed_out )
+            {
+                let exclusion = exclusion.as_ref().map( |e| e.as_slice() );
+                let audit = audit::audit( file.as_slice(), test_set, params, engine, registry, exclusion );
+                if params.repro && !audit.mismatches.is_empty()
+                {
+                    repro::export( &Repro::new::<T>( params, engine.name(), &audit.engine, &audit.mismatches ), file.as_slice(), test_set,
+                            exclusion );
+                }
+                result.audit = Some( audit );
+            }
+        }
+        results
+    };
+
+    // The partial results of a cancelled evaluation are returned without the checks and reports.
+    // The test sets finished before their timeout are checked and reported like the others.
+    if is_cancelled( params )
+    {
+        return results;
+    }
+
+    // The sets are evaluated separately with the engine after the measured evaluation
+    // to check a sample of them against the reference implementation and to record their results.
+    // The reference implementation only tests for any common value of the untransformed sets without exclusions.
+    if ( params.verify_sample > 0 || params.set_results ) && params.operation == Operation::Any && params.transform.is_none()
+            && params.exclude_file.is_none()
     {
-        let integer_count = file.len() / 4;
-        let buffer: *const T = file.ptr() as *const T;
-        let buffer = as_slice( buffer, integer_count );
+        for ( result, test_set ) in results.iter_mut().zip( test_sets ).filter( |&( ref r, _ )| !r.timed_out )
         {
-            // Divide the buffer into sets.
-            let sets = load_data( &buffer, params.preload_data );
+            let matches = match_each( file.as_slice(), test_set, params, engine, None );
+            if params.verify_sample > 0
+            {
+                let verification = verify::verify( file.as_slice(), test_set, params.verify_sample, &matches );
+                if params.repro && !verification.mismatches.is_empty()
+                {
+                    repro::export( &Repro::new::<T>( params, params.eval_engine, repro::REFERENCE, &verification.mismatches ),
+                            file.as_slice(), test_set, None );
+                }
+                result.verification = Some( verification );
+            }
+            if params.set_results
+            {
+                result.set_results = Some( matches );
+            }
+        }
+    }
+
+    // The contributions of the values are counted from all the untransformed sets as well.
+    if params.explain && params.operation == Operation::Any && params.transform.is_none() && !filtered
+    {
+        for ( result, test_set ) in results.iter_mut().zip( test_sets ).filter( |&( ref r, _ )| !r.timed_out )
+        {
+            result.explanation = Some( explain::explain( file.as_slice(), test_set ) );
+        }
+    }
 
-            // Run tests for each set.
-            let result= match * params.eval_engine
+    // The best sets of the other engines are selected from the untransformed sets without exclusions.
+    if params.top_k > 0 && params.operation == Operation::Any && params.transform.is_none() && params.exclude_file.is_none()
+    {
+        for ( result, test_set ) in results.iter_mut().zip( test_sets ).filter( |&( ref r, _ )| !r.timed_out )
+        {
+            if result.top.is_none()
             {
-                EvaluationEngine::Cpu => sets.evaluate_with_cpu( &ro_scalar_set::RoScalarSet::new( &test_set ),
-                    params.preload_data, params.max_threads ),
-                EvaluationEngine::Gpu => sets.evaluate_sets_gpu( &test_set ),
-            };
-            return result;
+                result.top = Some( topk::top_sets( file.as_slice(), test_set, params.top_k, &params.set_filter, params.memory_limit ) );
+            }
+        }
+    }
+    if let Some( result ) = results.first_mut()
+    {
+        result.page_cache = page_cache;
+    }
+    return results;
+}
+
+/// Refuses the options requiring raw sets evaluated with CPU for the other sets and engines.
+/// The exclusions require untransformed sets as well. The transforms are applied to the sets of values
+/// matched with any value in any encoding. The sets are checked once the file is mapped.
+fn check_options(
+    params: &EvaluationParams,
+    cpu: bool,
+    data: Option<&[u8]>,
+)
+{
+    let values = !http::is_http( params.file ) && params.operation == Operation::Any
+            && data.map_or( true, |d| !tuple::is_tuple_file( d ) && !uuid::is_uuid_file( d ) && !timestamp::is_timestamp_file( d ) );
+    let raw = cpu && values && data.map_or( true, |d| encoding::detect( d ) == Encoding::Raw );
+    let options = [
+        ( params.transform.is_some() && !values, "Transforms are only applied to local sets of values matched with any value." ),
+        ( params.exclude_file.is_some() && params.transform.is_some(), "Exclusion sets require untransformed raw sets evaluated with CPU." ),
+        ( params.exclude_file.is_some() && !raw, "Exclusion sets require raw sets evaluated with CPU." ),
+        ( params.set_filter.is_active() && !raw, "Selecting the sets requires raw sets evaluated with CPU." ),
+        ( params.timeout.is_some() && !raw, "The timeout requires raw sets evaluated with CPU." ),
+        ( params.signature.is_some() && http::is_http( params.file ), "Signatures are only verified for local files." ),
+    ];
+    if let Some( &( _, message ) ) = options.iter().find( |&&( refused, _ )| refused )
+    {
+        panic!( "{}", message );
+    }
+}
+
+/// Maps the file of the sets for the evaluation.
+/// The signature required by the parameters is verified from the same mapping the sets are evaluated from.
+fn open_sets( params: &EvaluationParams ) -> MappedFile
+{
+    let file = MappedFile::open( params.file ).expect( "Failed to map the file" );
+    if let Some( ( key_file, signature_file ) ) = params.signature
+    {
+        if !signature::verify( &file, signature_file, key_file )
+        {
+            panic!( "The signature of {} is missing or invalid.", params.file );
+        }
+    }
+    return file;
+}
+
+/// Checks that the strategy of the parameters can intersect the sets of the file before evaluating them.
+/// The sets streamed from a web server are not checked.
+pub fn check_strategy( params: &EvaluationParams ) -> Result<(), String>
+{
+    if http::is_http( params.file )
+    {
+        return Ok( () );
+    }
+    let file = MappedFile::open( params.file ).map_err( |e| format!( "Failed to map {}: {}", params.file, e ) )?;
+    return check_sorted( params.strategy, file.as_slice() );
+}
+
+/// Checks that the sets of values in the data are sorted if the strategy requires it.
+fn check_sorted(
+    strategy: Strategy,
+    data: &[u8],
+) -> Result<(), String>
+{
+    if tuple::is_tuple_file( data ) || uuid::is_uuid_file( data ) || timestamp::is_timestamp_file( data )
+    {
+        return Ok( () );
+    }
+    let encoding = encoding::detect( data );
+    if intersect::supports( encoding, strategy ) || ( encoding == Encoding::Bitpack && bitpack::is_sorted( data ) )
+    {
+        return Ok( () );
+    }
+    return Err( format!( "Strategy {} requires sorted sets such as the delta or weighted sets. The {} sets are not sorted.",
+            intersect::name( strategy ), encoding::name( encoding ) ) );
+}
+
+/// Evaluates each of the sets of the file selected by the filter of the parameters separately with the engine
+/// or with the evaluation of the encoded sets. The transformation of the parameters is applied to the sets first.
+/// Returns the result of each set in the order of the file. The results beyond the memory limit are spilled.
+pub fn match_each<T>(
+    data: &[u8],
+    test_set: &[T],
+    params: &EvaluationParams,
+    engine: &dyn EvaluationEngine<T>,
+    exclusion: Option<&[T]>,
+) -> Spool<SetMatch>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    // The transformed sets are materialized in memory as raw sets.
+    let transformed: Vec<T> = match params.transform
+    {
+        Some( ref transform ) => transform::load( data, transform ),
+        None => Vec::new(),
+    };
+    let encoding = encoding::detect( data );
+    let matched = if encoding == Encoding::Raw || params.transform.is_some()
+    {
+        let values: &[T] = if params.transform.is_some() { &transformed } else { encoding::as_values( data ) };
+        let mut sets = load_data( values, PreloadMode::Disabled, 0, params.max_threads, 0, &params.set_filter );
+        if let Some( exclusion ) = exclusion
+        {
+            sets.exclude( exclusion );
         }
+        engine.match_each( &sets, test_set, params )
     }
+    else
+    {
+        match engine.match_each_encoded( data, test_set, params )
+        {
+            Some( matched ) => matched,
+            None => panic!( "Engine {} does not support {} encoded sets.", engine.name(), encoding::name( encoding ) ),
+        }
+    };
+
+    // The results are in the order of the selected sets.
+    let mut matched = matched.into_iter();
+    let mut matches: Spool<SetMatch> = Spool::new( params.memory_limit );
+    let mut index = 0;
+    encoding::for_each_set( data, |values: &[T]| {
+        if params.set_filter.accepts( index, values.len() )
+        {
+            matches.push( SetMatch {
+                index: index,
+                size: values.len(),
+                matched: matched.next().expect( "The engine returned fewer results than there are sets." ),
+            } );
+        }
+        index += 1;
+    } );
+    return matches.finish();
 }
 
 /// Declares a set that can be evaluated.
@@ -84,9 +795,21 @@ pub struct SetsForEvaluation<'a,T,>
 where
     T: 'a + FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu
 {
-    #[cfg(feature="gpu")]
     raw_data: &'a[T],
     sets: Vec<ro_scalar_set::RoScalarSet<'a,T>>,
+    /// Offset of each set in the raw data in values.
+    offsets: Vec<usize>,
+    cache: Option<SetCache<'a,T>>,
+    /// Limit for reading the sets from the file in bytes per second. Zero disables the limit.
+    io_limit: usize,
+    /// Sets with any of these values never match.
+    exclusion: Option<ro_scalar_set::RoScalarSet<'a,T>>,
+    /// Copying of the sets into memory when they were preloaded.
+    preload_phase: Option<Phase>,
+    /// Stops the evaluation of the remaining sets once cancelled.
+    cancellation: Option<CancellationToken>,
+    /// Limit for each evaluation of the sets.
+    timeout: Option<std::time::Duration>,
 }
 
 impl<'a,T> SetsForEvaluation<'a,T>
@@ -94,101 +817,520 @@ where
     T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
 {
     /// Initializes new set evaluator from a collection of sets.
-    #[cfg(feature="gpu")]
     pub fn new(
         raw_data: &'a[T],
         sets: Vec<ro_scalar_set::RoScalarSet<'a,T>>,
+        offsets: Vec<usize>,
+        cache: Option<SetCache<'a,T>>,
+        io_limit: usize,
+        preload_phase: Option<Phase>,
     ) -> SetsForEvaluation<'a,T>
     {
-        return SetsForEvaluation { raw_data: raw_data, sets: sets };
+        return SetsForEvaluation { raw_data: raw_data, sets: sets, offsets: offsets, cache: cache, io_limit: io_limit,
+                exclusion: None, preload_phase: preload_phase, cancellation: None,
+                timeout: None };
     }
 
-    /// Initializes new set evaluator from a collection of sets.
-    #[cfg(not(feature="gpu"))]
-    pub fn new(
-        _raw_data: &'a[T],
-        sets: Vec<ro_scalar_set::RoScalarSet<'a,T>>,
+    /// Excludes the sets with any of the values from the matches.
+    pub fn exclude(
+        &mut self,
+        values: &[T],
+    )
+    {
+        self.exclusion = Some( ro_scalar_set::RoScalarSet::new( values ) );
+    }
+
+    /// Stops the evaluations of the sets once the token is cancelled.
+    pub fn cancel_with(
+        &mut self,
+        token: &CancellationToken,
+    )
+    {
+        self.cancellation = Some( token.clone() );
+    }
+
+    /// Stops each evaluation of the sets once the timeout has elapsed and marks the result as timed out.
+    pub fn time_out_after(
+        &mut self,
+        timeout: std::time::Duration,
+    )
+    {
+        self.timeout = Some( timeout );
+    }
+
+    /// Gets the token of an evaluation starting now limited by the timeout.
+    fn start_timeout(
+        &self,
+        cancellation: Option<&CancellationToken>,
+    ) -> Option<CancellationToken>
+    {
+        return self.timeout.map( |t| cancellation.cloned().unwrap_or_else( CancellationToken::new ).with_timeout( t ) );
+    }
+
+    /// Gets the number of attached sets.
+    pub fn set_count( &self ) -> usize
+    {
+        return self.sets.len();
+    }
+
+    /// Gets the number of values from the start of the data to the end of the last set.
+    /// The data after it is not part of any complete set.
+    pub fn attached_len( &self ) -> usize
+    {
+        return match ( self.offsets.last(), self.sets.last() )
+        {
+            ( Some( offset ), Some( set ) ) => offset + serialized_len( set ),
+            _ => 0,
+        };
+    }
+
+    /// Gets the set at the index alone with the same exclusion for evaluating it separately.
+    pub fn only(
+        &self,
+        index: usize,
     ) -> SetsForEvaluation<'a,T>
     {
-        return SetsForEvaluation { sets: sets };
+        return SetsForEvaluation { raw_data: self.raw_data, sets: vec![ self.sets[ index ].clone() ], offsets: vec![ self.offsets[ index ] ],
+                cache: None,
+                io_limit: 0, exclusion: self.exclusion.clone(), preload_phase: None, cancellation: None, timeout: None };
+    }
+
+    /// Evaluates each of the sets separately with CPU without the prefetching and the limits of the evaluation.
+    /// Returns whether each set matched in the order of the sets.
+    pub fn match_each_with_cpu(
+        &self,
+        test_set: &ro_scalar_set::RoScalarSet<T>,
+        thread_count: usize,
+    ) -> Vec<bool>
+    {
+        let threads = rayon::ThreadPool::new(
+                rayon::Configuration:: new().num_threads( thread_count )
+        ).unwrap();
+        return threads.install( || self.sets.par_iter()
+                .enumerate()
+                .map( |( i, s )| self.evaluate_set( test_set, None, i, s, None, None, None ) > 0 )
+                .collect() );
+    }
+
+    /// Collects the metrics of an evaluation of the sets following the preload.
+    fn metrics(
+        &self,
+        phases: Vec<Phase>,
+    ) -> Metrics
+    {
+        return Metrics {
+            phases: self.preload_phase.iter().cloned().chain( phases ).collect(),
+            bytes_read: self.bytes_of_sets(),
+            sets_evaluated: self.sets.len() as u64,
+        };
+    }
+
+    /// Gets the phase evaluating all the attached sets.
+    fn evaluation_phase(
+        &self,
+        duration: std::time::Duration,
+    ) -> Phase
+    {
+        return Phase { name: "evaluate", duration: duration, bytes: self.bytes_of_sets() };
+    }
+
+    /// Gets the bytes of the attached sets in the file. The sets left out by the filter are not read.
+    fn bytes_of_sets( &self ) -> u64
+    {
+        let values: usize = self.sets.iter().map( |s| 1 + s.bucket_count() + 1 + s.size() ).sum();
+        return ( values * std::mem::size_of::<T>() ) as u64;
     }
 
     /// Evaluates the sets with CPU.
     pub fn evaluate_with_cpu(
         &self,
         test_set: &ro_scalar_set::RoScalarSet<T>,
-        data_preloaded: bool,
-        thread_count: usize,
-    ) -> EvaluationResult
+        preload: PreloadMode,
+        thread_count: usize,
+        prefetch_distance: usize,
+        breakdown: bool,
+    ) -> EvaluationResult
+    {
+        return self.evaluate_blocks_with_cpu( &[ test_set ], preload, thread_count, prefetch_distance, breakdown );
+    }
+
+    /// Evaluates the sets with CPU against the test set divided into blocks.
+    /// A set matches if it matches any of the blocks.
+    pub fn evaluate_blocks_with_cpu(
+        &self,
+        blocks: &[&ro_scalar_set::RoScalarSet<T>],
+        preload: PreloadMode,
+        thread_count: usize,
+        prefetch_distance: usize,
+        breakdown: bool,
+    ) -> EvaluationResult
+    {
+        return self.evaluate_in_pool( blocks, None, preload, thread_count, prefetch_distance, breakdown );
+    }
+
+    /// Evaluates the sets with CPU in a thread pool of its own.
+    /// The values of each set are searched with the probe instead of the blocks if given.
+    fn evaluate_in_pool(
+        &self,
+        blocks: &[&ro_scalar_set::RoScalarSet<T>],
+        probe: Option<&( dyn Fn( &[T] ) -> bool + Sync )>,
+        preload: PreloadMode,
+        thread_count: usize,
+        prefetch_distance: usize,
+        breakdown: bool,
+    ) -> EvaluationResult
+    {
+        // Limit the number of threads used in the testing.
+        let threads = rayon::ThreadPool::new(
+                rayon::Configuration:: new().num_threads( thread_count )
+        ).unwrap();
+
+        // Preloaded data is already in memory and does not benefit from prefetching.
+        let mut prefetcher = if preload != PreloadMode::Full && prefetch_distance > 0
+        {
+            Some( Prefetcher::start( self.raw_data, prefetch_distance ) )
+        }
+        else
+        {
+            None
+        };
+        // Preloaded data is not read from the file.
+        let throttle = if preload != PreloadMode::Full && self.io_limit > 0 { Some( Throttle::start( self.io_limit ) ) } else { None };
+        let result = threads.install(
+
+            // Run the test under the thread count limitation.
+            || self.evaluate_with_cpu_expr( blocks, probe, preload, prefetcher.as_ref(), throttle.as_ref(), self.cancellation.as_ref(), breakdown )
+        );
+        if let Some( ref mut prefetcher ) = prefetcher
+        {
+            prefetcher.finish();
+        }
+        return result;
+    }
+
+    /// Evaluates the sets with CPU in the thread pool of the caller.
+    /// Allows evaluating multiple test sets concurrently in a shared thread pool.
+    /// The evaluation stops once the given token is cancelled in addition to the token of the sets.
+    pub fn evaluate_in_current_pool(
+        &self,
+        test_set: &ro_scalar_set::RoScalarSet<T>,
+        preload: PreloadMode,
+        cancellation: Option<&CancellationToken>,
+    ) -> EvaluationResult
+    {
+        return self.evaluate_blocks_in_current_pool( &[ test_set ], preload, cancellation );
+    }
+
+    /// Evaluates the sets with CPU against the test set divided into blocks in the thread pool of the caller.
+    pub fn evaluate_blocks_in_current_pool(
+        &self,
+        blocks: &[&ro_scalar_set::RoScalarSet<T>],
+        preload: PreloadMode,
+        cancellation: Option<&CancellationToken>,
+    ) -> EvaluationResult
+    {
+        return self.evaluate_with_cpu_expr( blocks, None, preload, None, None, cancellation.or( self.cancellation.as_ref() ), false );
+    }
+
+    /// Evaluates the sets one at a time in the order of the file on the calling thread
+    /// and prints the index, the size and the result of each set.
+    /// The duration includes the printing.
+    pub fn evaluate_sequentially(
+        &self,
+        test_set: &ro_scalar_set::RoScalarSet<T>,
+        preload: PreloadMode,
+    ) -> EvaluationResult
+    {
+        let start = std::time::Instant::now();
+        let timeout = self.start_timeout( self.cancellation.as_ref() );
+        let cancellation = timeout.as_ref().or( self.cancellation.as_ref() );
+        let mut match_counter = 0;
+        for ( i, s ) in self.sets.iter().enumerate()
+        {
+            let matched = self.evaluate_set( test_set, None, i, s, None, None, cancellation ) > 0;
+            println!( "Set {}: {} values, {}", i, s.size(), if matched { "matched" } else { "no match" } );
+            if matched
+            {
+                match_counter += 1;
+            }
+        }
+        let duration = std::time::Instant::now().duration_since( start );
+        return EvaluationResult { timed_out: timeout.map_or( false, |t| t.is_timed_out() ),
+                ..EvaluationResult::new( match_counter, duration, preload, 1, self.metrics( vec![ self.evaluation_phase( duration ) ] ) ) };
+    }
+
+    /// GPU evaluation enabled?
+    #[cfg(not(feature="gpu"))]
+    pub fn evaluate_sets_gpu(
+        &self,
+        _test_sets: &[&[T]],
+        _compare: GpuCompare,
+        _memory: GpuMemory,
+        _kernel: GpuKernel,
+    ) -> Vec<EvaluationResult>
+    {
+        panic!("GPU evaluation support not enabled.");
+    }
+
+    /// Evaluates the sets against all the test sets with GPU in a single launch of the kernel.
+    /// The duration and the phases of each result are its share of the launch.
+    #[cfg(feature="gpu")]
+    pub fn evaluate_sets_gpu(
+        &self,
+        test_sets: &[&[T]],
+        compare: GpuCompare,
+        memory: GpuMemory,
+        kernel: GpuKernel,
+    ) -> Vec<EvaluationResult>
+    {
+        // Delegate to appropriate implementation depending on the data type.
+        let start = std::time::Instant::now();
+        let ( match_counters, phases ) = WithGpu::evaluate_with_gpu( self.raw_data, &self.sets, test_sets, compare, memory, kernel );
+        let stop = std::time::Instant::now();
+        let share = std::cmp::max( 1, test_sets.len() ) as u32;
+        let duration = stop.duration_since( start ) / share;
+        let phases: Vec<Phase> = phases.into_iter()
+                .map( |p| Phase { name: p.name, duration: p.duration / share, bytes: p.bytes / share as u64 } )
+                .collect();
+        return match_counters.into_iter()
+            .map( |match_counter| EvaluationResult::new( match_counter, duration, PreloadMode::Disabled, 1, self.metrics( phases.clone() ) ) )
+            .collect();
+    }
+
+    /// GPU evaluation enabled?
+    #[cfg(not(feature="gpu"))]
+    pub fn top_sets_gpu(
+        &self,
+        _test_sets: &[&[T]],
+        _k: usize,
+        _compare: GpuCompare,
+        _memory: GpuMemory,
+        _memory_limit: usize,
+    ) -> Vec<TopSets>
+    {
+        panic!("GPU evaluation support not enabled.");
+    }
+
+    /// Selects the k sets with the most values found from each of the test sets with GPU.
+    /// The selected sets beyond the memory limit in bytes are spilled to a temporary file. Zero disables the limit.
+    #[cfg(feature="gpu")]
+    pub fn top_sets_gpu(
+        &self,
+        test_sets: &[&[T]],
+        k: usize,
+        compare: GpuCompare,
+        memory: GpuMemory,
+        memory_limit: usize,
+    ) -> Vec<TopSets>
+    {
+        return WithGpu::top_with_gpu( self.raw_data, &self.sets, test_sets, k, compare, memory, memory_limit );
+    }
+
+    fn evaluate_with_cpu_expr(
+        &self,
+        blocks: &[&ro_scalar_set::RoScalarSet<T>],
+        probe: Option<&( dyn Fn( &[T] ) -> bool + Sync )>,
+        preload: PreloadMode,
+        prefetcher: Option<&Prefetcher>,
+        throttle: Option<&Throttle>,
+        cancellation: Option<&CancellationToken>,
+        breakdown: bool,
+    ) -> EvaluationResult
+    {
+        // Evaluate the sets in parallel.
+        let start = std::time::Instant::now();
+        let timeout = self.start_timeout( cancellation );
+        let cancellation = timeout.as_ref().or( cancellation );
+        let mut measured = None;
+        let ( match_counter, reduced ) = if blocks.len() == 1 && !breakdown
+        {
+            let chunks = reduction::evaluate_chunks( self.sets.len(),
+                    |i| self.evaluate_set( blocks[ 0 ], probe, i, &self.sets[ i ], prefetcher, throttle, cancellation ) );
+            reduction::reduce( chunks )
+        }
+        else
+        {
+            // All the sets are evaluated against one block at a time to keep the block in the cache.
+            // The sets matched by the previous blocks are not evaluated again.
+            // The time spent on each set is accumulated over the blocks for the breakdown.
+            let mut matched = vec![ ( false, std::time::Duration::new( 0, 0 ) ); self.sets.len() ];
+            for ( b, block ) in blocks.iter().enumerate()
+            {
+                let prefetcher = if b == 0 { prefetcher } else { None };
+                let throttle = if b == 0 { throttle } else { None };
+                matched = self.sets.par_iter()
+                        .enumerate()
+                        .zip( matched.par_iter() )
+                        .map( |( ( i, s ), &( m, d ) )| {
+                            if m
+                            {
+                                return ( m, d );
+                            }
+                            if !breakdown
+                            {
+                                return ( self.evaluate_set( block, probe, i, s, prefetcher, throttle, cancellation ) > 0, d );
+                            }
+                            let set_start = std::time::Instant::now();
+                            let m = self.evaluate_set( block, probe, i, s, prefetcher, throttle, cancellation ) > 0;
+                            ( m, d + set_start.elapsed() )
+                        } )
+                        .collect();
+            }
+            if breakdown
+            {
+                measured = Some( matched.clone() );
+            }
+
+            // The durations of the chunks are only measured for the breakdown.
+            let chunk_size = reduction::chunk_size( matched.len() );
+            let chunks: Vec<ChunkResult> = matched.chunks( chunk_size )
+                    .enumerate()
+                    .map( |( c, sets )| ChunkResult {
+                        first_set: c * chunk_size,
+                        set_count: sets.len(),
+                        match_count: sets.iter().filter( |m| m.0 ).count() as u64,
+                        duration: sets.iter().fold( std::time::Duration::new( 0, 0 ), |total, m| total + m.1 ),
+                    } )
+                    .collect();
+            reduction::reduce( chunks )
+        };
+        let stop = std::time::Instant::now();
+        let duration = stop.duration_since( start );
+
+        // The breakdown is collected after the measurement.
+        let breakdown = measured.map( |sets| Breakdown::collect(
+                self.sets.iter().zip( sets ).map( |( s, ( m, d ) )| ( s.size(), m, d ) ) ) );
+        return EvaluationResult { breakdown: breakdown, reduction: Some( reduced ), timed_out: timeout.map_or( false, |t| t.is_timed_out() ),
+                ..EvaluationResult::new( match_counter, duration, preload, rayon::current_num_threads(),
+                        self.metrics( vec![ self.evaluation_phase( duration ) ] ) ) };
+    }
+}
+
+impl<'a,T> SetsForEvaluation<'a,T>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
+{
+    /// Evaluates a single set against the test set.
+    /// The values of the set are searched with the probe instead of the test set if given.
+    fn evaluate_set(
+        &self,
+        test_set: &ro_scalar_set::RoScalarSet<T>,
+        probe: Option<&( dyn Fn( &[T] ) -> bool + Sync )>,
+        index: usize,
+        set: &ro_scalar_set::RoScalarSet<'a, T>,
+        prefetcher: Option<&Prefetcher>,
+        throttle: Option<&Throttle>,
+        cancellation: Option<&CancellationToken>,
+    ) -> u64
     {
-        // Limit the number of threads used in the testing.
-        let threads = rayon::ThreadPool::new(
-                rayon::Configuration:: new().num_threads( thread_count )
-        ).unwrap();
-        let result = threads.install(
+        // The sets left after a cancellation are not evaluated and do not match.
+        if cancellation.map_or( false, |c| c.is_cancelled() )
+        {
+            return 0;
+        }
 
-            // Run the test under the thread count limitation.
-            || SetsForEvaluation::evaluate_with_cpu_expr( &self.sets, test_set, data_preloaded )
-        );
-        return result;
-    }
+        if let Some( throttle ) = throttle
+        {
+            throttle.consume( serialized_len( set ) * std::mem::size_of::<T>() );
+        }
 
+        let mut matches = match ( probe, self.cache.as_ref() )
+        {
+            ( Some( probe ), _ ) => if probe( self.values_of( index, set ) ) { 1 } else { 0 },
+            ( None, Some( cache ) ) => evaluate_set_cached( test_set, set, index, cache ),
+            ( None, None ) => evaluate_set_cpu( test_set, set ),
+        };
 
-    /// GPU evaluation enabled?
-    #[cfg(not(feature="gpu"))]
-    pub fn evaluate_sets_gpu(
-        &self,
-        _test_set: &[T],
-    ) -> EvaluationResult
-    {
-        panic!("GPU evaluation support not enabled.");
+        // Only the matching sets are tested against the exclusion.
+        if let Some( ref exclusion ) = self.exclusion
+        {
+            if matches > 0 && evaluate_set_cpu( exclusion, set ) > 0
+            {
+                matches = 0;
+            }
+        }
+        if let Some( prefetcher ) = prefetcher
+        {
+            prefetcher.advance( serialized_len( set ) * std::mem::size_of::<T>() );
+        }
+        return matches;
     }
 
-    /// Evaluates the sets with GPU.
-    #[cfg(feature="gpu")]
-    pub fn evaluate_sets_gpu(
+    /// Gets the values of the set from the raw data.
+    /// The values follow the bucket count and the bucket boundaries of the set.
+    fn values_of(
         &self,
-        test_set: &[T],
-    ) -> EvaluationResult
+        index: usize,
+        set: &ro_scalar_set::RoScalarSet<'a, T>,
+    ) -> &'a [T]
     {
-        // Delegate to appropriate implementation depending on the data type.
-        let start = std::time::Instant::now();
-        let match_counter = WithGpu::evaluate_with_gpu( self.raw_data, &self.sets, test_set );
-        let stop = std::time::Instant::now();
-        let duration = stop.duration_since( start );
-        EvaluationResult { match_count: match_counter, duration: duration };
+        let start = self.offsets[ index ] + 1 + set.bucket_count() + 1;
+        return &self.raw_data[ start..start + set.size() ];
     }
+}
 
-    fn evaluate_with_cpu_expr(
-        sets: &Vec<ro_scalar_set::RoScalarSet<'a,T>>,
-        test_set: &ro_scalar_set::RoScalarSet<T>,
-        data_preloaded: bool,
+impl<'a,T> SetsForEvaluation<'a,T>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits,
+{
+    /// Evaluates the sets with CPU searching the values of each set from the sorted keys of the test set in batches.
+    pub fn evaluate_batched_with_cpu(
+        &self,
+        test_set: &[T],
+        preload: PreloadMode,
+        thread_count: usize,
+        prefetch_distance: usize,
+        breakdown: bool,
     ) -> EvaluationResult
     {
-        // Evaluate the sets in parallel.
-        let start = std::time::Instant::now();
-        let match_counter = sets.par_iter()
-                .map( |s| evaluate_set_cpu( test_set, &s ) )
-                .sum();
-        let stop = std::time::Instant::now();
-        let duration = stop.duration_since( start );
-        return EvaluationResult { match_count: match_counter, duration: duration,
-                data_preloaded: data_preloaded, thread_count: rayon::current_num_threads() };
+        let keys = encoding::sorted_keys( test_set );
+        let probe = |values: &[T]| intersect::any_batched( values.len(), |i| values[ i ].to_ordered_bits(), &keys );
+        return self.evaluate_in_pool( &[ &ro_scalar_set::RoScalarSet::new( test_set ) ], Some( &probe ),
+                preload, thread_count, prefetch_distance, breakdown );
     }
 }
 
+/// Largest difference of matching values with GpuCompare::Epsilon.
+#[cfg(feature="gpu")]
+const GPU_EPSILON: f32 = 0.1;
+
+/// Largest number of units in the last place between matching values with GpuCompare::Ulp.
+#[cfg(feature="gpu")]
+const GPU_MAX_ULPS: u32 = 4;
+
+/// Number of work-items scanning a set with GpuKernel::WorkGroup.
+#[cfg(feature="gpu")]
+const GPU_WORK_GROUP_SIZE: usize = 64;
+
 /// Trait for evaluating values with GPU.
 #[cfg(feature="gpu")]
  pub trait WithGpu
  where
     Self: traits::FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value
 {
-    /// Evaluates the given data set with GPU.
+    /// Evaluates the given data set against each of the test sets with GPU.
+    /// Returns the number of matching sets of each test set and the phases of the launch.
     fn evaluate_with_gpu(
         raw_data: &[Self],
         sets: &Vec<ro_scalar_set::RoScalarSet<Self>>,
-        test_set: &[Self],
-    ) -> u32;
+        test_sets: &[&[Self]],
+        compare: GpuCompare,
+        memory: GpuMemory,
+        kernel: GpuKernel,
+    ) -> ( Vec<u64>, Vec<Phase> );
+
+    /// Scores the sets by the number of their values found from each of the test sets with GPU.
+    /// The scores are sorted on the device and only the best k sets of each test set are read back
+    /// in batches within the memory limit in bytes. Zero disables the limit.
+    fn top_with_gpu(
+        raw_data: &[Self],
+        sets: &Vec<ro_scalar_set::RoScalarSet<Self>>,
+        test_sets: &[&[Self]],
+        k: usize,
+        compare: GpuCompare,
+        memory: GpuMemory,
+        memory_limit: usize,
+    ) -> Vec<TopSets>;
 }
 
 /// GPU evaluation support for integers.
@@ -199,8 +1341,25 @@ impl WithGpu for i32
     fn evaluate_with_gpu(
         _raw_data: &[i32],
         _sets: &Vec<ro_scalar_set::RoScalarSet<i32>>,
-        _test_set: &[i32],
-    ) -> u32
+        _test_sets: &[&[i32]],
+        _compare: GpuCompare,
+        _memory: GpuMemory,
+        _kernel: GpuKernel,
+    ) -> ( Vec<u64>, Vec<Phase> )
+    {
+        panic!("Not implemented");
+    }
+
+        /// Selects the best sets with GPU.
+    fn top_with_gpu(
+        _raw_data: &[i32],
+        _sets: &Vec<ro_scalar_set::RoScalarSet<i32>>,
+        _test_sets: &[&[i32]],
+        _k: usize,
+        _compare: GpuCompare,
+        _memory: GpuMemory,
+        _memory_limit: usize,
+    ) -> Vec<TopSets>
     {
         panic!("Not implemented");
     }
@@ -214,112 +1373,469 @@ impl WithGpu for f32
     fn evaluate_with_gpu(
         raw_data: &[f32],
         sets: &Vec<ro_scalar_set::RoScalarSet<f32>>,
-        test_set: &[f32],
-    ) -> u32
-    {
-        let src = r#"
-                __kernel void search(
-                    __global float* buffer,
-                    __global int* begin_indexes,
-                    __global int* end_indexes,
-                    __global float* test_set,
-                    __private int const test_set_size
-                )
+        test_sets: &[&[f32]],
+        compare: GpuCompare,
+        memory: GpuMemory,
+        kernel: GpuKernel,
+    ) -> ( Vec<u64>, Vec<Phase> )
+    {
+        // Nothing to launch.
+        if sets.is_empty() || test_sets.is_empty()
+        {
+            return ( vec![ 0; test_sets.len() ], Vec::new() );
+        }
+
+        // Prepare environment.
+        let start_build = std::time::Instant::now();
+        let pro_que = build_gpu_program( compare, ( sets.len(), test_sets.len() ) );
+        let start_transfer = std::time::Instant::now();
+        let buffers = load_gpu_buffers( &pro_que, raw_data, sets, test_sets, memory );
+
+        // Each set flags whether it matched each of the test sets.
+        let set_matches: Vec<u8> = vec![ 0; sets.len() * test_sets.len() ];
+        let matches = Buffer::builder()
+                .queue( pro_que.queue().clone() )
+                .flags( host_memory_flags( MemFlags::new().read_write(), memory ) )
+                .dims( set_matches.len() )
+                .host_data( &set_matches )
+                .build().unwrap();
+
+        let transferred = ( raw_data.len() * std::mem::size_of::<f32>() ) as u64;
+        let start_calculation = std::time::Instant::now();
+
+        // Load the program.
+        let name = match kernel
+        {
+            GpuKernel::Item => "search",
+            GpuKernel::WorkGroup => "search_wg",
+        };
+        let program = pro_que.create_kernel( name ).unwrap()
+                .arg_buf(&buffers.raw_data)
+                .arg_buf(&buffers.begin_indexes)
+                .arg_buf(&buffers.end_indexes)
+                .arg_buf(&buffers.test_sets)
+                .arg_buf(&buffers.test_set_indexes)
+                .arg_buf(&matches);
+        let kernel = match kernel
+        {
+            GpuKernel::Item => program,
+            GpuKernel::WorkGroup => program.arg_loc::<f32>( GPU_WORK_GROUP_SIZE )
+                    .gws( ( sets.len() * GPU_WORK_GROUP_SIZE, test_sets.len() ) )
+                    .lws( ( GPU_WORK_GROUP_SIZE, 1 ) ),
+        };
+
+        let start_kernel = std::time::Instant::now();
+        unsafe { kernel.enq().unwrap(); }
+        pro_que.queue().finish().unwrap();
+        let start_read = std::time::Instant::now();
+        let mut set_matches = set_matches;
+        matches.read( &mut set_matches ).enq().unwrap();
+        let stop = std::time::Instant::now();
+        let phases = vec![
+            Phase { name: "build", duration: start_transfer.duration_since( start_build ), bytes: 0 },
+            Phase { name: "transfer", duration: start_calculation.duration_since( start_transfer ), bytes: transferred },
+            Phase { name: "kernel", duration: start_read.duration_since( start_kernel ), bytes: 0 },
+            Phase { name: "read back", duration: stop.duration_since( start_read ), bytes: set_matches.len() as u64 },
+        ];
+        let match_counts = set_matches.chunks( sets.len() )
+                .map( |m| m.iter().filter( |m| **m != 0 ).count() as u64 )
+                .collect();
+        return ( match_counts, phases );
+    }
+
+        /// Selects the best sets with GPU.
+    fn top_with_gpu(
+        raw_data: &[f32],
+        sets: &Vec<ro_scalar_set::RoScalarSet<f32>>,
+        test_sets: &[&[f32]],
+        k: usize,
+        compare: GpuCompare,
+        memory: GpuMemory,
+        memory_limit: usize,
+    ) -> Vec<TopSets>
+    {
+        // Nothing to launch.
+        if sets.is_empty() || test_sets.is_empty()
+        {
+            return test_sets.iter().map( |_| TopSets { sets: Spool::from_vec( Vec::new() ) } ).collect();
+        }
+
+        // The bitonic sort requires rows of a power of two. The padding sorts after all the sets.
+        let row_length = sets.len().next_power_of_two();
+        let pro_que = build_gpu_program( compare, ( row_length, test_sets.len() ) );
+        let buffers = load_gpu_buffers( &pro_que, raw_data, sets, test_sets, memory );
+        let keys: Vec<u64> = vec![ 0; row_length * test_sets.len() ];
+        let keys = Buffer::builder()
+                .queue( pro_que.queue().clone() )
+                .flags( host_memory_flags( MemFlags::new().read_write(), memory ) )
+                .dims( keys.len() )
+                .host_data( &keys )
+                .build().unwrap();
+
+        // Score the sets.
+        let score = pro_que.create_kernel( "score" ).unwrap()
+                .arg_buf(&buffers.raw_data)
+                .arg_buf(&buffers.begin_indexes)
+                .arg_buf(&buffers.end_indexes)
+                .arg_buf(&buffers.test_sets)
+                .arg_buf(&buffers.test_set_indexes)
+                .arg_buf(&keys)
+                .arg_scl( row_length as u32 )
+                .gws( ( sets.len(), test_sets.len() ) );
+        unsafe { score.enq().unwrap(); }
+
+        // Sort the keys of each test set from the best set to the worst with a bitonic sort on the device.
+        let mut size = 2;
+        while size <= row_length
+        {
+            let mut stride = size / 2;
+            while stride > 0
+            {
+                let step = pro_que.create_kernel( "bitonic_step" ).unwrap()
+                        .arg_buf(&keys)
+                        .arg_scl( stride as u32 )
+                        .arg_scl( size as u32 )
+                        .arg_scl( row_length as u32 );
+                unsafe { step.enq().unwrap(); }
+                stride /= 2;
+            }
+            size *= 2;
+        }
+
+        // Only the best k keys of each test set are read back.
+        // Half of the memory limit holds the keys read at a time and the other half the selected sets.
+        let count = std::cmp::min( k, sets.len() );
+        let limit = if memory_limit == 0 { 0 } else { std::cmp::max( 1, memory_limit / 2 ) };
+        let batch = if limit == 0 { count } else { std::cmp::max( 1, std::cmp::min( count, limit / std::mem::size_of::<u64>() ) ) };
+        return ( 0..test_sets.len() )
+            .map( |t| {
+                let mut best = Spool::new( limit );
+                let mut batch_keys: Vec<u64> = vec![ 0; batch ];
+                let mut first = 0;
+                while first < count
+                {
+                    let read = std::cmp::min( batch, count - first );
+                    keys.read( &mut batch_keys[ ..read ] ).offset( t * row_length + first ).enq().unwrap();
+                    for key in &batch_keys[ ..read ]
+                    {
+                        best.push( topk::SetScore { index: ( std::u32::MAX - *key as u32 ) as usize, score: key >> 32 } );
+                    }
+                    first += read;
+                }
+                TopSets { sets: best.finish() }
+            } )
+            .collect();
+    }
+}
+
+/// Kernels of the GPU evaluation. The MATCHES comparison is selected with the preprocessor.
+#[cfg(feature="gpu")]
+const GPU_KERNELS: &'static str = r#"
+        #if defined( COMPARE_EXACT )
+        #define MATCHES( a, b ) ( ( a ) == ( b ) )
+        #elif defined( COMPARE_EPSILON )
+        #define MATCHES( a, b ) ( fabs( ( a ) - ( b ) ) < EPSILON )
+        #elif defined( COMPARE_ULP )
+        /* Floats of the same sign are ordered like their bits as integers. */
+        #define MATCHES( a, b ) ( ( a ) == ( b ) || \
+                ( ( as_int( a ) ^ as_int( b ) ) >= 0 && abs_diff( as_int( a ), as_int( b ) ) <= MAX_ULPS ) )
+        #endif
+
+        /* The first dimension selects the set and the second dimension the test set. */
+        __kernel void search(
+            __global float* buffer,
+            __global ulong* begin_indexes,
+            __global ulong* end_indexes,
+            __global float* test_sets,
+            __global ulong* test_set_indexes,
+            __global uchar* matches
+        )
+        {
+            /* Determine the range of values we need to scan. */
+            ulong iBegin = begin_indexes[get_global_id(0)];
+            ulong iEnd = end_indexes[get_global_id(0)];
+            ulong tBegin = test_set_indexes[get_global_id(1)];
+            ulong tEnd = test_set_indexes[get_global_id(1) + 1];
+            for( ulong i = iBegin; i < iEnd; ++i )
+            {
+                for( ulong t = tBegin; t < tEnd; ++t )
+                {
+                    if( MATCHES( buffer[ i ], test_sets[ t ] ) )
+                    {
+                        matches[get_global_id(1) * get_global_size(0) + get_global_id(0)] = 1;
+                        return;
+                    }
+                }
+            }
+
+        }
+
+        __kernel void search_wg(
+            __global float* buffer,
+            __global ulong* begin_indexes,
+            __global ulong* end_indexes,
+            __global float* test_sets,
+            __global ulong* test_set_indexes,
+            __global uchar* matches,
+            __local float* query
+        )
+        {
+            /* The work-group scans the set with the work-items taking every nth value. */
+            size_t set = get_group_id(0);
+            int local_id = get_local_id(0);
+            int local_size = get_local_size(0);
+            ulong iBegin = begin_indexes[set];
+            ulong iEnd = end_indexes[set];
+            __global float* test_set = test_sets + test_set_indexes[get_global_id(1)];
+            int test_set_size = test_set_indexes[get_global_id(1) + 1] - test_set_indexes[get_global_id(1)];
+            __local int found;
+            if( local_id == 0 )
+            {
+                found = 0;
+            }
+            for( int chunk = 0; chunk < test_set_size; chunk += local_size )
+            {
+                /* The work-items copy a chunk of the test set into the local memory together. */
+                int chunk_size = min( local_size, test_set_size - chunk );
+                barrier( CLK_LOCAL_MEM_FENCE );
+                if( local_id < chunk_size )
+                {
+                    query[ local_id ] = test_set[ chunk + local_id ];
+                }
+                barrier( CLK_LOCAL_MEM_FENCE );
+
+                /* The flag is only written between the barriers so all the work-items stop together. */
+                if( found )
                 {
-                    /* Determine the range of values we need to scan. */
-                    int iBegin = begin_indexes[get_global_id(0)];
-                    int iEnd = end_indexes[get_global_id(0)];
-                    int iMatches = 0;
-                    for( int i = iBegin; i < iEnd; ++i )
+                    break;
+                }
+                for( ulong i = iBegin + local_id; i < iEnd; i += local_size )
+                {
+                    for( int t = 0; t < chunk_size; ++t )
                     {
-                        for( int t = 0; t < test_set_size; ++t )
+                        if( MATCHES( buffer[ i ], query[ t ] ) )
                         {
-                            float f = fabs( buffer[ i ] - test_set[ t ] );
-                            if( f < 0.1 )
-                            {
-                                return;
-                            }
+                            found = 1;
                         }
                     }
+                }
+            }
+            barrier( CLK_LOCAL_MEM_FENCE );
+            if( local_id == 0 && found )
+            {
+                matches[get_global_id(1) * get_num_groups(0) + set] = 1;
+            }
+        }
 
+        /* Scores each set by the number of its values found from the test set.
+           The key holds the score in the high bits and the complement of the index of the set in the low bits
+           so that the keys sort by the score and then by the index. */
+        __kernel void score(
+            __global float* buffer,
+            __global ulong* begin_indexes,
+            __global ulong* end_indexes,
+            __global float* test_sets,
+            __global ulong* test_set_indexes,
+            __global ulong* keys,
+            __private uint const row_length
+        )
+        {
+            ulong iBegin = begin_indexes[get_global_id(0)];
+            ulong iEnd = end_indexes[get_global_id(0)];
+            ulong tBegin = test_set_indexes[get_global_id(1)];
+            ulong tEnd = test_set_indexes[get_global_id(1) + 1];
+            uint score = 0;
+            for( ulong i = iBegin; i < iEnd; ++i )
+            {
+                for( ulong t = tBegin; t < tEnd; ++t )
+                {
+                    if( MATCHES( buffer[ i ], test_sets[ t ] ) )
+                    {
+                        ++score;
+                        break;
+                    }
                 }
-            "#;
+            }
+            keys[get_global_id(1) * row_length + get_global_id(0)] = ( ( ulong ) score << 32 ) | ( 0xFFFFFFFFu - ( uint ) get_global_id(0) );
+        }
 
-        // Prepare environment.
-        let pro_que = ProQue::builder()
-            .src( src )
-            .dims( sets.len() )
+        /* A compare-and-swap step of a bitonic sort of each row of the keys into descending order. */
+        __kernel void bitonic_step(
+            __global ulong* keys,
+            __private uint const stride,
+            __private uint const size,
+            __private uint const row_length
+        )
+        {
+            uint i = get_global_id(0);
+            uint partner = i ^ stride;
+            if( partner > i )
+            {
+                __global ulong* row = keys + get_global_id(1) * row_length;
+                ulong a = row[ i ];
+                ulong b = row[ partner ];
+                bool descending = ( i & size ) == 0;
+                if( ( a < b ) == descending )
+                {
+                    row[ i ] = b;
+                    row[ partner ] = a;
+                }
+            }
+        }
+    "#;
+
+/// Buffers of the sets and the test sets on the device.
+#[cfg(feature="gpu")]
+struct GpuBuffers
+{
+    raw_data: Buffer<f32>,
+    /// Start of the values of each set in the raw data.
+    begin_indexes: Buffer<u64>,
+    /// End of the values of each set in the raw data.
+    end_indexes: Buffer<u64>,
+    /// Values of the test sets one after another.
+    test_sets: Buffer<f32>,
+    /// Start of each test set in the values followed by the end of the last test set.
+    test_set_indexes: Buffer<u64>,
+}
+
+/// Compiles the kernels with the comparison of the values for the given dimensions.
+#[cfg(feature="gpu")]
+fn build_gpu_program(
+    compare: GpuCompare,
+    dims: ( usize, usize ),
+) -> ProQue
+{
+    // The comparison is selected with the preprocessor when the kernel is compiled.
+    let defines = match compare
+    {
+        GpuCompare::Exact => "#define COMPARE_EXACT\n".to_string(),
+        GpuCompare::Epsilon => format!( "#define COMPARE_EPSILON\n#define EPSILON {:?}f\n", GPU_EPSILON ),
+        GpuCompare::Ulp => format!( "#define COMPARE_ULP\n#define MAX_ULPS {}u\n", GPU_MAX_ULPS ),
+    };
+    return ProQue::builder()
+        .src( defines + GPU_KERNELS )
+        .dims( dims )
+        .build().unwrap();
+}
+
+/// Copies the sets and the test sets to the device.
+#[cfg(feature="gpu")]
+fn load_gpu_buffers(
+    pro_que: &ProQue,
+    raw_data: &[f32],
+    sets: &Vec<ro_scalar_set::RoScalarSet<f32>>,
+    test_sets: &[&[f32]],
+    memory: GpuMemory,
+) -> GpuBuffers
+{
+    // Load raw data.
+    let raw_data_length = raw_data.len();
+    let raw_data = Buffer::builder()
+            .queue( pro_que.queue().clone() )
+            .flags( host_memory_flags( MemFlags::new().read_only(), memory ) )
+            .dims( raw_data_length )
+            .host_data( &raw_data )
             .build().unwrap();
 
-        // Load raw data.
-        let raw_data_length = raw_data.len();
-        let raw_data = Buffer::builder()
-                .queue( pro_que.queue().clone() )
-                .flags( MemFlags::new().read_only().copy_host_ptr() )
-                .dims( raw_data_length )
-                .host_data( &raw_data )
-                .build().unwrap();
+    // Calculate indexes of scalar sets in the raw buffer.
+    // These indexes will we be transmitted to the GPU.
+    // 64-bit indexes are required as the files may contain more than 2^31 values.
+    let mut begin_indexes: Vec<u64> = Vec::new();
+    let mut end_indexes: Vec<u64> = Vec::new();
+    begin_indexes.reserve( sets.len() );
+    end_indexes.reserve( sets.len() );
+    let mut set_start: u64 = 0;
+    for s in sets
+    {
+        let buckets = s.bucket_count() as u64;
+        let total_size = serialized_len( s ) as u64;
 
-        // Calculate indexes of scalar sets in the raw buffer.
-        // These indexes will we be transmitted to the GPU.
-        let mut begin_indexes: Vec<i32> = Vec::new();
-        let mut end_indexes: Vec<i32> = Vec::new();
-        begin_indexes.reserve( sets.len() );
-        end_indexes.reserve( sets.len() );
-        let mut set_start = 0;
-        for s in sets
-        {
-            let buckets = s.bucket_count();
-            let size = s.size();
-            let total_size = 1 + buckets + 1 + size;
-
-            // Calculate the indexes.
-            let begin_index = set_start + 1 + buckets as i32 + 1;
-            let end_index = set_start + total_size as i32;
-            begin_indexes.push( begin_index );
-            end_indexes.push( end_index );
-            set_start = end_index;
-        }
-
-        // Load the indexes to GPU.
-        let begin_indexes = Buffer::builder()
-                .queue( pro_que.queue().clone() )
-                .flags( MemFlags::new().read_only().copy_host_ptr() )
-                .dims( begin_indexes.len() )
-                .host_data( &begin_indexes )
-                .build().unwrap();
-        let end_indexes = Buffer::builder()
-                .queue( pro_que.queue().clone() )
-                .flags( MemFlags::new().read_only().copy_host_ptr() )
-                .dims( end_indexes.len() )
-                .host_data( &end_indexes )
-                .build().unwrap();
+        // Calculate the indexes.
+        let begin_index = set_start + 1 + buckets + 1;
+        let end_index = set_start + total_size;
+        begin_indexes.push( begin_index );
+        end_indexes.push( end_index );
+        set_start = end_index;
+    }
 
-        // Load test set.
-        let test_set = Buffer::builder()
-                .queue( pro_que.queue().clone() )
-                .flags( MemFlags::new().read_only().copy_host_ptr() )
-                .dims( test_set.len() )
-                .host_data( &test_set )
-                .build().unwrap();
+    // Load the indexes to GPU.
+    let begin_indexes = Buffer::builder()
+            .queue( pro_que.queue().clone() )
+            .flags( host_memory_flags( MemFlags::new().read_only(), memory ) )
+            .dims( begin_indexes.len() )
+            .host_data( &begin_indexes )
+            .build().unwrap();
+    let end_indexes = Buffer::builder()
+            .queue( pro_que.queue().clone() )
+            .flags( host_memory_flags( MemFlags::new().read_only(), memory ) )
+            .dims( end_indexes.len() )
+            .host_data( &end_indexes )
+            .build().unwrap();
 
-        // Load the program.
-        let kernel = pro_que.create_kernel("search").unwrap()
-                .arg_buf(&raw_data)
-                .arg_buf(&begin_indexes)
-                .arg_buf(&end_indexes)
-                .arg_buf(&test_set)
-                .arg_scl( test_set.len() as i32 );
+    // Load the test sets one after another with the index of the start of each test set.
+    let mut test_set_indexes: Vec<u64> = Vec::with_capacity( test_sets.len() + 1 );
+    let mut values: Vec<f32> = Vec::new();
+    test_set_indexes.push( 0 );
+    for test_set in test_sets
+    {
+        values.extend_from_slice( test_set );
+        test_set_indexes.push( values.len() as u64 );
+    }
 
-        let start_calculation = std::time::Instant::now();
-        unsafe { kernel.enq().unwrap(); }
-        let stop_calculation = std::time::Instant::now();
-        let calculation_duration = stop_calculation.duration_since( start_calculation );
-        println!("{}.{:06} s", calculation_duration.as_secs(), calculation_duration.subsec_nanos() / 1000 );
-        0
+    // An empty buffer cannot be created.
+    if values.is_empty()
+    {
+        values.push( 0.0 );
+    }
+    let test_set_values = Buffer::builder()
+            .queue( pro_que.queue().clone() )
+            .flags( host_memory_flags( MemFlags::new().read_only(), memory ) )
+            .dims( values.len() )
+            .host_data( &values )
+            .build().unwrap();
+    let test_set_indexes = Buffer::builder()
+            .queue( pro_que.queue().clone() )
+            .flags( host_memory_flags( MemFlags::new().read_only(), memory ) )
+            .dims( test_set_indexes.len() )
+            .host_data( &test_set_indexes )
+            .build().unwrap();
+    return GpuBuffers { raw_data: raw_data, begin_indexes: begin_indexes, end_indexes: end_indexes,
+            test_sets: test_set_values, test_set_indexes: test_set_indexes };
+}
+
+/// Adds the flags placing a buffer initialized from the host data into the host memory of the mode.
+#[cfg(feature="gpu")]
+fn host_memory_flags(
+    flags: MemFlags,
+    memory: GpuMemory,
+) -> MemFlags
+{
+    match memory
+    {
+        GpuMemory::Default => flags.copy_host_ptr(),
+        // The driver allocates the buffer from page-locked memory which the device reads with DMA.
+        GpuMemory::Pinned => flags.alloc_host_ptr().copy_host_ptr(),
+        // The data is not copied if the host memory is suitably aligned for the device.
+        GpuMemory::ZeroCopy => flags.use_host_ptr(),
     }
 }
 
+/// Checks whether any OpenCL platform has a device for the GPU evaluation.
+#[cfg(feature="gpu")]
+pub fn gpu_available() -> bool
+{
+    ocl::Platform::list().iter()
+            .any( |p| ocl::Device::list_all( p ).map( |d| !d.is_empty() ).unwrap_or( false ) )
+}
+
+/// GPU support is not included.
+#[cfg(not(feature="gpu"))]
+pub fn gpu_available() -> bool
+{
+    false
+}
+
 /// Dummy implementation when GPU support is not included.
 #[cfg(not(feature="gpu"))]
  pub trait WithGpu
@@ -338,43 +1854,235 @@ impl WithGpu for f32
 
 
 /// Attaches the buffer into scalar sets.
-fn load_data<'a, T>(
+/// Only the sets selected by the filter are attached.
+pub fn load_data<'a, T>(
     data: &'a [T],
-    preload_to_memory: bool
-) -> SetsForEvaluation<T>
+    preload: PreloadMode,
+    cache_size: usize,
+    thread_count: usize,
+    io_limit: usize,
+    filter: &SetFilter,
+) -> SetsForEvaluation<'a, T>
 where
     T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
 {
 
     // Divide to buffers.
+    // The sets are counted first to avoid growing the vector when attaching millions of sets.
     let mut buffer = data;
-    let mut buffers: Vec<ro_scalar_set::RoScalarSet<T>> = Vec::new();
+    let mut buffers: Vec<ro_scalar_set::RoScalarSet<T>> = Vec::with_capacity( count_sets( data ) );
+    let mut offsets: Vec<usize> = Vec::with_capacity( buffers.capacity() );
+    let mut index = 0;
     loop
     {
 
         // Attach scalar set to the buffer.    // Re
+        let offset = data.len() - buffer.len();
         let result = match ro_scalar_set::RoScalarSet::attach( buffer )
         {
             Ok( result ) => result,
             Err( _ ) => break,
         };
         buffer = result.1;
+        index += 1;
+        if !filter.accepts( index - 1, result.0.size() )
+        {
+            continue;
+        }
         buffers.push( result.0 );
+        offsets.push( offset );
+    }
+    return prepare_sets( data, buffers, &offsets, preload, cache_size, thread_count, io_limit );
+}
+
+/// Attaches the sets starting at the offsets of the buffer.
+/// The offsets are counted in values from the start of the buffer as returned by set_offsets
+/// which avoids walking through the sets.
+pub fn load_indexed<'a, T>(
+    data: &'a [T],
+    offsets: &[u64],
+    preload: PreloadMode,
+    cache_size: usize,
+    thread_count: usize,
+    io_limit: usize,
+) -> SetsForEvaluation<'a, T>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
+{
+    let offsets: Vec<usize> = offsets.iter().map( |o| *o as usize ).collect();
+    let buffers: Vec<ro_scalar_set::RoScalarSet<T>> = offsets.iter()
+            .map( |o| ro_scalar_set::RoScalarSet::attach( &data[ *o.. ] ).expect( "The index does not match the sets." ).0 )
+            .collect();
+    return prepare_sets( data, buffers, &offsets, preload, cache_size, thread_count, io_limit );
+}
+
+/// Gets the offset of each set of the buffer in values.
+pub fn set_offsets<T>(
+    data: &[T],
+) -> Vec<u64>
+where
+    T: ro_scalar_set::Value,
+{
+    let mut buffer = data;
+    let mut offsets: Vec<u64> = Vec::new();
+    while let Ok( ( _, remaining ) ) = ro_scalar_set::RoScalarSet::attach( buffer )
+    {
+        offsets.push( ( data.len() - buffer.len() ) as u64 );
+        buffer = remaining;
     }
+    return offsets;
+}
 
+/// Preloads the attached sets at the offsets of the buffer as requested and sets up the cache.
+/// The cache is divided between the threads evaluating the sets.
+fn prepare_sets<'a, T>(
+    data: &'a [T],
+    mut buffers: Vec<ro_scalar_set::RoScalarSet<'a, T>>,
+    offsets: &Vec<usize>,
+    preload: PreloadMode,
+    cache_size: usize,
+    thread_count: usize,
+    io_limit: usize,
+) -> SetsForEvaluation<'a, T>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
+{
     // Load the data into the memory?
-    if preload_to_memory
+    // The preloading reads the data from the file within the limit.
+    let mut preload_phase = None;
+    let throttle = if io_limit > 0 { Some( Throttle::start( io_limit ) ) } else { None };
+    match preload
+    {
+        PreloadMode::Full =>
+        {
+            let ( preloaded, phase ) = preload_sets( &buffers, throttle.as_ref() );
+            buffers = preloaded;
+            preload_phase = Some( phase );
+        },
+        PreloadMode::Disabled => {},
+    }
+
+    // Keep the recently probed sets in memory?
+    // The cache is redundant when all the data has been preloaded.
+    let cache = if preload != PreloadMode::Full && cache_size > 0 { Some( SetCache::new( cache_size, thread_count ) ) } else { None };
+    return SetsForEvaluation::new( data, buffers, offsets.clone(), cache, io_limit, preload_phase );
+}
+
+/// Reads the distinct values of all the sets in the file.
+fn load_exclusion<T>(
+    file: &String,
+) -> Vec<T>
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    let mapped = MappedFile::open( file ).expect( "Failed to map the exclusion file" );
+    let mut values: Vec<T> = Vec::new();
+    encoding::for_each_set( mapped.as_slice(), |set: &[T]| values.extend_from_slice( set ) );
+    values.sort_by_key( |v| v.to_ordered_bits() );
+    values.dedup_by_key( |v| v.to_ordered_bits() );
+    return values;
+}
+
+/// Copies the sets into memory in chunks of about PRELOAD_CHUNK bytes.
+/// Only the sets of one chunk are copied in parallel at a time which bounds the memory bandwidth
+/// taken by the copy and allows reporting the progress.
+/// Returns the copied sets and the throughput of the copy in bytes per second.
+fn preload_sets<'a, T>(
+    sets: &Vec<ro_scalar_set::RoScalarSet<'a, T>>,
+    throttle: Option<&Throttle>,
+) -> ( Vec<ro_scalar_set::RoScalarSet<'a, T>>, Phase )
+where
+    T: std::marker::Send + std::marker::Sync + ro_scalar_set::Value,
+{
+    let size = |s: &ro_scalar_set::RoScalarSet<T>| serialized_len( s ) * std::mem::size_of::<T>();
+    let total: usize = sets.iter().map( |s| size( s ) ).sum();
+    let start = std::time::Instant::now();
+    let mut preloaded = Vec::with_capacity( sets.len() );
+    let mut copied = 0;
+    let mut first = 0;
+    while first < sets.len()
+    {
+        // Collect the sets of the next chunk.
+        let mut last = first;
+        let mut chunk_size = 0;
+        while last < sets.len() && ( last == first || chunk_size < PRELOAD_CHUNK )
+        {
+            chunk_size += size( &sets[ last ] );
+            last += 1;
+        }
+
+        preloaded.extend( sets[ first..last ].par_iter()
+                .map( |s| {
+                    if let Some( throttle ) = throttle
+                    {
+                        throttle.consume( size( s ) );
+                    }
+                    s.clone()
+                } )
+                .collect::<Vec<_>>() );
+        copied += chunk_size;
+        first = last;
+        if total > PRELOAD_CHUNK
+        {
+            println!( "Preloaded {} of {} MB", copied / ( 1024 * 1024 ), total / ( 1024 * 1024 ) );
+        }
+    }
+    let duration = std::time::Instant::now().duration_since( start );
+    return ( preloaded, Phase { name: "preload", duration: duration, bytes: total as u64 } );
+}
+
+/// Counts the sets in the buffer by walking over the serialized sets.
+fn count_sets<T>(
+    data: &[T],
+) -> usize
+where
+    T: ro_scalar_set::Value,
+{
+    let mut buffer = data;
+    let mut count = 0;
+    while let Ok( ( _, remaining ) ) = ro_scalar_set::RoScalarSet::attach( buffer )
+    {
+        buffer = remaining;
+        count += 1;
+    }
+    return count;
+}
+
+/// Gets the number of values the set occupies in the file.
+/// The values are preceded by the bucket count and the bucket boundaries.
+fn serialized_len<T>(
+    set: &ro_scalar_set::RoScalarSet<T>,
+) -> usize
+where
+    T: ro_scalar_set::Value,
+{
+    return 1 + set.bucket_count() + 1 + set.size();
+}
+
+/// Evaluates a single set materializing it into the cache.
+fn evaluate_set_cached<'a, T>(
+    test_set: &ro_scalar_set::RoScalarSet<T>,
+    set: &ro_scalar_set::RoScalarSet<'a, T>,
+    index: usize,
+    cache: &SetCache<'a, T>,
+) -> u64
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value,
+{
+    if let Some( cached ) = cache.get( index )
     {
-        buffers = buffers.par_iter().map( |s| s.clone() ).collect();
+        return evaluate_set_cpu( test_set, &cached );
     }
-    return SetsForEvaluation::new( data, buffers );
+    let matches = evaluate_set_cpu( test_set, set );
+    cache.insert( index, set, serialized_len( set ) * std::mem::size_of::<T>() );
+    return matches;
 }
 
 /// Evaluates a single set.
 fn evaluate_set_cpu<T>(
     test_set: &ro_scalar_set::RoScalarSet<T>,
     set: &ro_scalar_set::RoScalarSet<T>,
-) -> u32
+) -> u64
 where
     T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value,
 {
@@ -382,14 +2090,3 @@ where
     if test_set.any( set ) { 1 } else { 0 }
 }
 
-
-/// Converts a slice to 32-bit integer.
-fn as_slice<'a, T>(
-    buffer: *const T,
-    integer_count: usize,
-) -> &'a [T]
-{
-    unsafe {
-        return slice::from_raw_parts( buffer, integer_count );
-    }
-}
@@ -0,0 +1,106 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+use encoding;
+use spill::{self, Spillable};
+use traits::*;
+
+/// Number of the most selective values listed in the explanation.
+const LISTED_VALUES: usize = 20;
+
+/// How the values of the test set contributed to the matches.
+pub struct Explanation
+{
+    pub set_count: usize,
+    /// Values of the test set found from any set and the number of sets they were found from,
+    /// the value found from the most sets first.
+    pub contributions: Vec<( String, usize )>,
+}
+
+/// Result of a single set of the file.
+#[derive(Clone, Copy)]
+pub struct SetMatch
+{
+    /// Index of the set in the file.
+    pub index: usize,
+    pub size: usize,
+    pub matched: bool,
+}
+
+impl Spillable for SetMatch
+{
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> std::io::Result<()>
+    {
+        spill::write_u64( writer, self.index as u64 )?;
+        spill::write_u64( writer, self.size as u64 )?;
+        return writer.write_all( &[ self.matched as u8 ] );
+    }
+
+    fn read_from<R: Read>( reader: &mut R ) -> std::io::Result<SetMatch>
+    {
+        let index = spill::read_u64( reader )? as usize;
+        let size = spill::read_u64( reader )? as usize;
+        let mut matched = [ 0u8 ];
+        reader.read_exact( &mut matched )?;
+        return Ok( SetMatch { index: index, size: size, matched: matched[ 0 ] != 0 } );
+    }
+}
+
+impl Explanation
+{
+    /// Prints the values found from the most sets.
+    pub fn print( &self )
+    {
+        println!( "{} of the values in the test set were found from the sets", self.contributions.len() );
+        for &( ref value, count ) in self.contributions.iter().take( LISTED_VALUES )
+        {
+            println!( "Value {} matched {} sets ({:.1} %)", value, count,
+                    100.0 * count as f64 / std::cmp::max( 1, self.set_count ) as f64 );
+        }
+        if self.contributions.len() > LISTED_VALUES
+        {
+            println!( "... and {} more values", self.contributions.len() - LISTED_VALUES );
+        }
+    }
+}
+
+/// Counts the sets each value of the test set is found from.
+pub fn explain<T>(
+    data: &[u8],
+    test_set: &[T],
+) -> Explanation
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    let query: HashMap<u64, T> = test_set.iter().map( |v| ( v.to_ordered_bits(), *v ) ).collect();
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    let mut set_count = 0;
+    encoding::for_each_set( data, |values: &[T]| {
+
+        // A value repeated in a set is counted only once.
+        let mut found: Vec<u64> = values.iter()
+                .map( |v| v.to_ordered_bits() )
+                .filter( |k| query.contains_key( k ) )
+                .collect();
+        found.sort();
+        found.dedup();
+        for key in found
+        {
+            *counts.entry( key ).or_insert( 0 ) += 1;
+        }
+        set_count += 1;
+    } );
+
+    let mut contributions: Vec<( u64, usize )> = counts.into_iter().collect();
+    contributions.sort_by( |a, b| b.1.cmp( &a.1 ).then( a.0.cmp( &b.0 ) ) );
+    let contributions = contributions.into_iter()
+            .map( |( k, count )| ( format!( "{}", query[ &k ] ), count ) )
+            .collect();
+    return Explanation { set_count: set_count, contributions: contributions };
+}
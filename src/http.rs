@@ -0,0 +1,203 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::process::Command;
+
+use encoding;
+use enumerations::*;
+use evaluation::EvaluationResult;
+use metrics::Metrics;
+
+/// Number of bytes requested from the server at a time.
+const CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Checks whether the file is served over HTTP.
+pub fn is_http( file: &str ) -> bool
+{
+    file.starts_with( "http://" ) || file.starts_with( "https://" )
+}
+
+/// Evaluates the raw sets of a file on an HTTP server without downloading the whole file first.
+/// The file is streamed with range requests and the sets are evaluated as soon as they have been received.
+pub fn evaluate<T>(
+    url: &str,
+    test_set: &[T],
+) -> EvaluationResult
+where
+    T: ro_scalar_set::Value,
+{
+    let test_set = ro_scalar_set::RoScalarSet::new( test_set );
+    let value_size = std::mem::size_of::<T>();
+
+    // Values received but not yet attached into a complete set.
+    let mut pending: Vec<T> = Vec::new();
+    let mut pending_bytes: Vec<u8> = Vec::new();
+
+    let start = std::time::Instant::now();
+    let mut match_count = 0;
+    let mut set_count = 0;
+    let mut offset = 0;
+    loop
+    {
+        let chunk = match fetch_range( url, offset, offset + CHUNK_SIZE - 1 )
+        {
+            Ok( Some( chunk ) ) => chunk,
+            Ok( None ) => break,
+            Err( e ) => panic!( "Failed to read {}: {}", url, e ),
+        };
+        if offset == 0 && is_encoded( &chunk.data )
+        {
+            panic!( "Only raw sets can be streamed over HTTP." );
+        }
+        offset += chunk.data.len() as u64;
+        pending_bytes.extend_from_slice( &chunk.data );
+
+        // Move the complete values to the pending sets.
+        let complete = pending_bytes.len() / value_size * value_size;
+        append_values( &mut pending, &pending_bytes[ ..complete ] );
+        pending_bytes.drain( ..complete );
+
+        // Evaluate the complete sets.
+        let consumed = {
+            let mut buffer: &[T] = &pending;
+            while let Ok( ( set, remaining ) ) = ro_scalar_set::RoScalarSet::attach( buffer )
+            {
+                if test_set.any( &set )
+                {
+                    match_count += 1;
+                }
+                set_count += 1;
+                buffer = remaining;
+            }
+            pending.len() - buffer.len()
+        };
+        pending.drain( ..consumed );
+
+        // The whole file has been received.
+        if offset >= chunk.total
+        {
+            break;
+        }
+    }
+    if !pending.is_empty() || !pending_bytes.is_empty()
+    {
+        panic!( "The last set of {} is incomplete.", url );
+    }
+    let duration = std::time::Instant::now().duration_since( start );
+    return EvaluationResult::new( match_count, duration, PreloadMode::Disabled, 1, Metrics::evaluation( duration, offset, set_count ) );
+}
+
+/// Part of the file received for a range request.
+struct Chunk
+{
+    data: Vec<u8>,
+    /// Size of the whole file reported by the server.
+    total: u64,
+}
+
+/// Requests the given inclusive byte range of the file.
+/// The server must answer with 206 Partial Content and the requested range. Any other response is an error
+/// so that a server ignoring the range or a connection failing midway is not mistaken for the end of the file.
+/// Returns None when the range starts at or beyond the end of the file.
+fn fetch_range(
+    url: &str,
+    first: u64,
+    last: u64,
+) -> std::io::Result<Option<Chunk>>
+{
+    let headers_file = std::env::temp_dir().join( format!( "scalar_set_eval_headers_{}", std::process::id() ) );
+    let output = Command::new( "curl" )
+        .arg( "--silent" )
+        .arg( "--show-error" )
+        .arg( "--location" )
+        .arg( "--dump-header" )
+        .arg( &headers_file )
+        .arg( "--range" )
+        .arg( format!( "{}-{}", first, last ) )
+        .arg( url )
+        .output()?;
+    let headers = std::fs::read_to_string( &headers_file );
+    std::fs::remove_file( &headers_file ).ok();
+    if !output.status.success()
+    {
+        return Err( invalid( format!( "curl failed: {}", String::from_utf8_lossy( &output.stderr ).trim() ) ) );
+    }
+
+    // The headers of the redirects precede the headers of the final response.
+    let headers = headers?;
+    let response = headers.split( "\r\n\r\n" ).filter( |h| !h.trim().is_empty() ).last().unwrap_or( "" );
+    let status = response.lines().next().and_then( |l| l.split_whitespace().nth( 1 ) ).unwrap_or( "" );
+    let content_range = response.lines()
+            .filter_map( |l| {
+                let mut parts = l.splitn( 2, ':' );
+                match ( parts.next(), parts.next() )
+                {
+                    ( Some( name ), Some( value ) ) if name.trim().eq_ignore_ascii_case( "content-range" ) => Some( value.trim() ),
+                    _ => None,
+                }
+            } )
+            .next()
+            .ok_or_else( || invalid( format!( "the response {} has no Content-Range", status ) ) )?;
+    let ( range, total ) = parse_content_range( content_range )
+            .ok_or_else( || invalid( format!( "invalid Content-Range {}", content_range ) ) )?;
+    match ( status, range )
+    {
+        // Servers answer a range beyond the end of the file with 416.
+        ( "416", None ) if first >= total => return Ok( None ),
+        ( "206", Some( ( start, end ) ) ) if start == first && end <= last && end - start + 1 == output.stdout.len() as u64 =>
+        {
+            return Ok( Some( Chunk { data: output.stdout, total: total } ) );
+        },
+        _ => return Err( invalid( format!( "expected the bytes {}-{} but the server answered {} with Content-Range {} and {} bytes",
+                first, last, status, content_range, output.stdout.len() ) ) ),
+    }
+}
+
+/// Parses a Content-Range header of bytes into the inclusive range and the size of the file.
+/// The range is None for an unsatisfied range.
+fn parse_content_range( value: &str ) -> Option<( Option<( u64, u64 )>, u64 )>
+{
+    let mut parts = value.trim_start_matches( "bytes" ).trim().splitn( 2, '/' );
+    let range = parts.next()?;
+    let total = parts.next()?.trim().parse().ok()?;
+    if range == "*"
+    {
+        return Some( ( None, total ) );
+    }
+    let mut bounds = range.splitn( 2, '-' );
+    let start = bounds.next()?.parse().ok()?;
+    let end = bounds.next()?.parse().ok()?;
+    if end < start
+    {
+        return None;
+    }
+    return Some( ( Some( ( start, end ) ), total ) );
+}
+
+/// Creates an error of invalid data received from the server.
+fn invalid( message: String ) -> std::io::Error
+{
+    return std::io::Error::new( std::io::ErrorKind::InvalidData, message );
+}
+
+/// Checks whether the data starts with the header of an encoded file.
+fn is_encoded( data: &[u8] ) -> bool
+{
+    encoding::detect( data ) != Encoding::Raw
+}
+
+/// Appends the values stored in the bytes.
+fn append_values<T>(
+    values: &mut Vec<T>,
+    bytes: &[u8],
+)
+{
+    let count = bytes.len() / std::mem::size_of::<T>();
+    values.reserve( count );
+    unsafe {
+        let end = values.as_mut_ptr().offset( values.len() as isize ) as *mut u8;
+        std::ptr::copy_nonoverlapping( bytes.as_ptr(), end, count * std::mem::size_of::<T>() );
+        let len = values.len();
+        values.set_len( len + count );
+    }
+}
@@ -0,0 +1,132 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use encoding;
+use enumerations::*;
+use mapping::MappedFile;
+use traits::*;
+
+/// Sets with a larger fraction of empty buckets are reported as skewed.
+const EMPTY_BUCKET_LIMIT: f64 = 0.5;
+
+/// Sets with a bucket this many times larger than the average bucket are reported as skewed.
+const LARGEST_BUCKET_FACTOR: f64 = 8.0;
+
+/// Number of the skewed sets listed individually.
+const LISTED_SETS: usize = 20;
+
+/// Occupancy of the buckets of a single set.
+pub struct BucketOccupancy
+{
+    /// Index of the set in the file.
+    pub index: usize,
+    pub size: usize,
+    pub bucket_count: usize,
+    pub empty_buckets: usize,
+    pub largest_bucket: usize,
+}
+
+impl BucketOccupancy
+{
+    /// Checks whether most of the buckets are empty or a single bucket holds a large part of the values.
+    /// Probing a value from a large bucket scans the whole bucket which slows down the evaluation.
+    pub fn is_skewed( &self ) -> bool
+    {
+        if self.bucket_count <= 1
+        {
+            return false;
+        }
+        let average = self.size as f64 / self.bucket_count as f64;
+        return self.empty_buckets as f64 > EMPTY_BUCKET_LIMIT * self.bucket_count as f64
+                || self.largest_bucket as f64 > LARGEST_BUCKET_FACTOR * average.max( 1.0 );
+    }
+}
+
+/// Bucket occupancy of the sets of a file.
+pub struct Inspection
+{
+    pub sets: Vec<BucketOccupancy>,
+    /// Number of buckets by their number of values. The first bin counts the empty buckets
+    /// and bin i the buckets with 2^(i-1) to 2^i - 1 values.
+    pub histogram: Vec<u64>,
+}
+
+impl Inspection
+{
+    /// Prints the distribution of the bucket sizes and the skewed sets.
+    pub fn print( &self )
+    {
+        let buckets: u64 = self.histogram.iter().sum();
+        println!( "Inspected {} sets with {} buckets", self.sets.len(), buckets );
+        println!( "|{:>16}|{:>14}|{:>9}|", "Values in bucket", "Buckets", "Share" );
+        for ( bin, count ) in self.histogram.iter().enumerate()
+        {
+            let range = match bin
+            {
+                0 => "0".to_string(),
+                1 => "1".to_string(),
+                _ => format!( "{}-{}", 1u64 << ( bin - 1 ), ( 1u64 << bin ) - 1 ),
+            };
+            println!( "|{:>16}|{:>14}|{:>7.2} %|", range, count, 100.0 * *count as f64 / std::cmp::max( 1, buckets ) as f64 );
+        }
+
+        let skewed: Vec<&BucketOccupancy> = self.sets.iter().filter( |s| s.is_skewed() ).collect();
+        println!( "{} sets have skewed buckets", skewed.len() );
+        for set in skewed.iter().take( LISTED_SETS )
+        {
+            println!( "Set {}: {} values in {} buckets, {} empty, largest bucket {} values",
+                    set.index, set.size, set.bucket_count, set.empty_buckets, set.largest_bucket );
+        }
+        if skewed.len() > LISTED_SETS
+        {
+            println!( "... and {} more sets", skewed.len() - LISTED_SETS );
+        }
+        if !skewed.is_empty()
+        {
+            println!( "The skewed sets are evaluated slowly. Consider a different bucket count or value distribution." );
+        }
+    }
+}
+
+/// Reads the bucket occupancy of each set from the bucket boundaries in the headers of the sets.
+pub fn inspect<T>(
+    file: &String,
+) -> Inspection
+where
+    T: Transformable + ro_scalar_set::Value,
+{
+    let mapped = MappedFile::open( file ).expect( "Failed to map the file" );
+    if encoding::detect( mapped.as_slice() ) != Encoding::Raw
+    {
+        panic!( "Only the buckets of raw sets can be inspected." );
+    }
+    let data: &[T] = mapped.as_slice();
+    let mut buffer = data;
+    let mut inspection = Inspection { sets: Vec::new(), histogram: Vec::new() };
+    while let Ok( ( set, remaining ) ) = ro_scalar_set::RoScalarSet::attach( buffer )
+    {
+        // The header holds the bucket count followed by the boundaries of the buckets.
+        let offset = data.len() - buffer.len();
+        let boundaries = &data[ offset + 1..offset + 1 + set.bucket_count() + 1 ];
+        let mut occupancy = BucketOccupancy { index: inspection.sets.len(), size: set.size(), bucket_count: set.bucket_count(),
+                empty_buckets: 0, largest_bucket: 0 };
+        for bounds in boundaries.windows( 2 )
+        {
+            let values = ( bounds[ 1 ].to_f64() - bounds[ 0 ].to_f64() ) as usize;
+            let bin = ( 64 - ( values as u64 ).leading_zeros() ) as usize;
+            if inspection.histogram.len() <= bin
+            {
+                inspection.histogram.resize( bin + 1, 0 );
+            }
+            inspection.histogram[ bin ] += 1;
+            if values == 0
+            {
+                occupancy.empty_buckets += 1;
+            }
+            occupancy.largest_bucket = std::cmp::max( occupancy.largest_bucket, values );
+        }
+        inspection.sets.push( occupancy );
+        buffer = remaining;
+    }
+    return inspection;
+}
@@ -0,0 +1,280 @@
+//! Strategies for intersecting a sorted set with the sorted keys of the test set.
+
+extern crate std;
+
+use enumerations::*;
+
+/// Gets the name of the strategy.
+pub fn name( strategy: Strategy ) -> &'static str
+{
+    match strategy
+    {
+        Strategy::Auto => "auto",
+        Strategy::Probe => "probe",
+        Strategy::Merge => "merge",
+        Strategy::Galloping => "galloping",
+        Strategy::Batched => "batched",
+    }
+}
+
+/// Parses the name of a strategy.
+pub fn parse( name: &str ) -> Option<Strategy>
+{
+    match name
+    {
+        "auto" => Some( Strategy::Auto ),
+        "probe" => Some( Strategy::Probe ),
+        "merge" => Some( Strategy::Merge ),
+        "galloping" => Some( Strategy::Galloping ),
+        "batched" => Some( Strategy::Batched ),
+        _ => None,
+    }
+}
+
+/// Checks whether the sets of the encoding can be evaluated with the strategy.
+/// Probing and batched probing search each value of the set and work with the sets in any order.
+/// The automatic strategy merges only the sets stored sorted.
+/// Only the delta encoded and the weighted sets are always stored sorted.
+/// Bit-packed sets are sorted when generated with --sorted which is checked from the header of the file.
+pub fn supports(
+    encoding: Encoding,
+    strategy: Strategy,
+) -> bool
+{
+    strategy == Strategy::Auto || strategy == Strategy::Probe || strategy == Strategy::Batched
+            || encoding == Encoding::Delta || encoding == Encoding::Weighted
+}
+
+/// Resolves the automatic strategy into the strategy used for the sets of the encoding.
+/// The delta encoded sets are decoded in order and merged with the test set. The other sets are probed.
+pub fn resolve(
+    encoding: Encoding,
+    strategy: Strategy,
+) -> Strategy
+{
+    match strategy
+    {
+        Strategy::Auto if encoding == Encoding::Delta => Strategy::Merge,
+        Strategy::Auto => Strategy::Probe,
+        _ => strategy,
+    }
+}
+
+/// Tests if any of the sorted keys of the set is found from the sorted query keys.
+/// The keys of the set are read with the accessor to avoid decoding the whole set.
+pub fn any_sorted<F>(
+    count: usize,
+    key_at: F,
+    query: &[u64],
+    strategy: Strategy,
+) -> bool
+where
+    F: Fn( usize ) -> u64,
+{
+    match strategy
+    {
+        Strategy::Auto | Strategy::Probe => ( 0..count ).any( |i| query.binary_search( &key_at( i ) ).is_ok() ),
+        Strategy::Merge =>
+        {
+            let mut i = 0;
+            let mut q = 0;
+            while i < count && q < query.len()
+            {
+                let key = key_at( i );
+                if key < query[ q ]
+                {
+                    i += 1;
+                }
+                else if key > query[ q ]
+                {
+                    q += 1;
+                }
+                else
+                {
+                    return true;
+                }
+            }
+            false
+        },
+        Strategy::Galloping =>
+        {
+            let mut start = 0;
+            for key in query
+            {
+                start = gallop( count, &key_at, *key, start );
+                if start == count
+                {
+                    return false;
+                }
+                if key_at( start ) == *key
+                {
+                    return true;
+                }
+            }
+            false
+        },
+        Strategy::Batched => any_batched( count, key_at, query ),
+    }
+}
+
+/// Tests if any of the keys read in ascending order is found from the sorted query keys.
+/// The keys are read one at a time for the encodings without random access to the keys.
+/// Galloping advances in the query from the position of the previous key.
+pub fn any_streamed<I>(
+    mut keys: I,
+    query: &[u64],
+    strategy: Strategy,
+) -> bool
+where
+    I: Iterator<Item = u64>,
+{
+    match strategy
+    {
+        Strategy::Auto | Strategy::Probe => keys.any( |key| query.binary_search( &key ).is_ok() ),
+        Strategy::Merge | Strategy::Galloping =>
+        {
+            let mut q = 0;
+            for key in keys
+            {
+                q = if strategy == Strategy::Merge
+                {
+                    q + query[ q.. ].iter().take_while( |k| **k < key ).count()
+                }
+                else
+                {
+                    gallop( query.len(), &|i| query[ i ], key, q )
+                };
+                if q == query.len()
+                {
+                    return false;
+                }
+                if query[ q ] == key
+                {
+                    return true;
+                }
+            }
+            false
+        },
+        Strategy::Batched =>
+        {
+            let mut batch = [ 0u64; BATCH_SIZE ];
+            loop
+            {
+                let mut count = 0;
+                while count < BATCH_SIZE
+                {
+                    match keys.next()
+                    {
+                        Some( key ) => batch[ count ] = key,
+                        None => break,
+                    }
+                    count += 1;
+                }
+                if any_batched( count, |i| batch[ i ], query )
+                {
+                    return true;
+                }
+                if count < BATCH_SIZE
+                {
+                    return false;
+                }
+            }
+        },
+    }
+}
+
+/// Tests if any of the keys is found from the sorted query keys.
+/// The keys do not need to be sorted. The matches of a batch of keys are accumulated without branching
+/// and checked once per batch.
+pub fn any_batched<F>(
+    count: usize,
+    key_at: F,
+    query: &[u64],
+) -> bool
+where
+    F: Fn( usize ) -> u64,
+{
+    if query.is_empty()
+    {
+        return false;
+    }
+    let mut i = 0;
+    while i < count
+    {
+        let end = std::cmp::min( i + BATCH_SIZE, count );
+        let mut found = 0;
+        for k in i..end
+        {
+            let key = key_at( k );
+            found |= ( query[ search_branchless( query, key ) ] == key ) as u8;
+        }
+        if found != 0
+        {
+            return true;
+        }
+        i = end;
+    }
+    return false;
+}
+
+/// Number of values of the set searched at a time by the batched strategy.
+const BATCH_SIZE: usize = 8;
+
+/// Finds the index of the last key not greater than the searched key, or zero if there is none.
+/// The loop runs a fixed number of iterations and the comparison selects the next position
+/// without a branch, which avoids the mispredictions of the regular binary search.
+fn search_branchless(
+    query: &[u64],
+    key: u64,
+) -> usize
+{
+    let mut base = 0;
+    let mut length = query.len();
+    while length > 1
+    {
+        let half = length / 2;
+        base = if query[ base + half ] <= key { base + half } else { base };
+        length -= half;
+    }
+    return base;
+}
+
+/// Finds the index of the first key not less than the searched key starting from the given index.
+/// The range is first doubled until it covers the key and then searched with a binary search,
+/// which keeps the search short when the keys are close to the start.
+fn gallop<F>(
+    count: usize,
+    key_at: &F,
+    key: u64,
+    start: usize,
+) -> usize
+where
+    F: Fn( usize ) -> u64,
+{
+    // Find the range containing the key.
+    let mut low = start;
+    let mut step = 1;
+    let mut high = start;
+    while high < count && key_at( high ) < key
+    {
+        low = high + 1;
+        high = start + step;
+        step *= 2;
+    }
+    let mut high = std::cmp::min( high, count );
+
+    // Binary search within the range.
+    while low < high
+    {
+        let middle = low + ( high - low ) / 2;
+        if key_at( middle ) < key
+        {
+            low = middle + 1;
+        }
+        else
+        {
+            high = middle;
+        }
+    }
+    return low;
+}
@@ -0,0 +1,99 @@
+//! Library interfaces for embedding the evaluation into other applications.
+//! The C interface is built with the cabi feature, the Python module with the python feature
+//! and the WebAssembly interface with the wasm feature.
+//! The C interface and the Python module share the evaluation modules with the application.
+//! Rust applications can add their own engines to an engine::EngineRegistry and evaluate with
+//! evaluation::evaluate_with_registry.
+#![cfg(any(feature="cabi", feature="python", feature="wasm"))]
+
+// The application uses the rest of the shared modules.
+#![allow(dead_code)]
+
+#[cfg(any(feature="cabi", feature="python"))]
+extern crate byteorder;
+extern crate ro_scalar_set;
+#[cfg(any(feature="cabi", feature="python"))]
+extern crate rand;
+#[cfg(any(feature="cabi", feature="python"))]
+extern crate memmap;
+#[cfg(any(feature="cabi", feature="python"))]
+extern crate rayon;
+// The code generated for the Python functions refers to the crate from the root.
+#[cfg(feature="python")]
+extern crate pyo3;
+
+#[cfg(any(feature="cabi", feature="python"))]
+mod audit;
+#[cfg(any(feature="cabi", feature="python"))]
+mod bitpack;
+#[cfg(any(feature="cabi", feature="python"))]
+mod breakdown;
+#[cfg(any(feature="cabi", feature="python"))]
+mod bundle;
+#[cfg(any(feature="cabi", feature="python"))]
+mod cache;
+#[cfg(any(feature="cabi", feature="python"))]
+mod cancel;
+#[cfg(any(feature="cabi", feature="python"))]
+mod delta;
+#[cfg(any(feature="cabi", feature="python"))]
+mod dictionary;
+#[cfg(any(feature="cabi", feature="python"))]
+mod encoding;
+#[cfg(any(feature="cabi", feature="python"))]
+mod encryption;
+#[cfg(any(feature="cabi", feature="python"))]
+pub mod engine;
+#[cfg(any(feature="cabi", feature="python"))]
+pub mod enumerations;
+#[cfg(any(feature="cabi", feature="python"))]
+pub mod evaluation;
+#[cfg(any(feature="cabi", feature="python"))]
+mod explain;
+#[cfg(any(feature="cabi", feature="python"))]
+mod http;
+#[cfg(any(feature="cabi", feature="python"))]
+mod intersect;
+#[cfg(any(feature="cabi", feature="python"))]
+mod mapping;
+#[cfg(any(feature="cabi", feature="python"))]
+mod metrics;
+#[cfg(any(feature="cabi", feature="python"))]
+mod prefetch;
+#[cfg(any(feature="cabi", feature="python"))]
+mod query;
+#[cfg(any(feature="cabi", feature="python"))]
+mod reduction;
+#[cfg(any(feature="cabi", feature="python"))]
+mod repro;
+#[cfg(any(feature="cabi", feature="python"))]
+mod signature;
+#[cfg(any(feature="cabi", feature="python"))]
+mod spill;
+#[cfg(any(feature="cabi", feature="python"))]
+mod timestamp;
+#[cfg(any(feature="cabi", feature="python"))]
+mod throttle;
+#[cfg(any(feature="cabi", feature="python"))]
+pub mod topk;
+#[cfg(any(feature="cabi", feature="python"))]
+pub mod traits;
+#[cfg(any(feature="cabi", feature="python"))]
+mod transform;
+#[cfg(any(feature="cabi", feature="python"))]
+mod tuple;
+#[cfg(any(feature="cabi", feature="python"))]
+mod utility;
+#[cfg(any(feature="cabi", feature="python"))]
+mod uuid;
+#[cfg(any(feature="cabi", feature="python"))]
+mod verify;
+#[cfg(any(feature="cabi", feature="python"))]
+mod weighted;
+
+#[cfg(feature="cabi")]
+pub mod cabi;
+#[cfg(feature="python")]
+pub mod python;
+#[cfg(feature="wasm")]
+pub mod wasm;
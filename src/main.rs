@@ -1,29 +1,122 @@
 #[macro_use]
 extern crate serde_derive;
 extern crate docopt;
+extern crate byteorder;
 extern crate ro_scalar_set;
 extern crate rand;
 extern crate memmap;
 extern crate rayon;
+extern crate serde;
+extern crate serde_json;
+extern crate hmac;
+extern crate sha2;
+extern crate toml;
 
 use docopt::Docopt;
+use serde::Deserialize;
 
+mod allocator;
+mod artifact;
+mod audit;
+mod auditlog;
+mod bench;
+mod bitmap;
+mod bitpack;
+mod breakdown;
+mod bundle;
+mod cache;
+mod cancel;
+mod capabilities;
+mod csv;
+mod delta;
+mod dictionary;
+mod diff;
+mod edit;
+mod encoding;
+mod encryption;
+mod engine;
 mod enumerations;
 mod evaluation;
+mod explain;
+mod http;
+mod inspect;
+mod intersect;
 // use evaluation::WithGpu;
+mod mapping;
+mod metrics;
+mod matrix;
+mod plan;
+mod prefetch;
+mod profile;
+mod query;
+mod reduction;
+mod remote;
+mod repro;
+mod results;
+mod runner;
+mod scenario;
+mod selftest;
+mod server;
+mod signature;
+mod spill;
+mod sqlite;
+mod stats;
 mod traits;
 mod test;
+mod throttle;
+mod timestamp;
+mod topk;
+mod transform;
+mod tune;
+mod tuple;
 mod utility;
+mod uuid;
+mod weighted;
+mod workers;
+mod verify;
 
 use enumerations::*;
 
 const USAGE: &'static str = "
 Scalar Set Evaluator.
 
+Any option of a command can also be set with an SSE_<OPTION> environment variable such as SSE_THREADS=8 or SSE_CACHE_SIZE=256.
+The options on the command line take precedence over the environment which takes precedence over the profile.
+Every command also accepts the following options, which are handled before the usage below is matched:
+  --profile=<name> applies the options of [profile.<name>] in ~/.config/scalar_set_eval/config.toml.
+  --audit-log=<file> appends a JSON line with the command, the arguments, the hashes of the input files,
+  the last lines of the output, the duration and the exit status of the run to the file.
+  --decrypt=<keyfile> decrypts the encrypted set files read by the command with the key in the file.
+
 Usage:
-  scalar_set_eval new [--floats] [--gpu] <file> <minvalue> <maxvalue> <values> <sets>
-  scalar_set_eval eval [--floats] [--gpu] <file> <minvalue> <maxvalue> <values> [<sets>]
-  scalar_set_eval test [--floats] [--gpu] <report> <minvalue> <maxvalue> [<values>] [<sets>]
+  scalar_set_eval new [--floats] [--gpu] [--encoding=<encoding>] [--tuple-width=<n>] [--uuids] [--timestamps] [--mmap-write] [--seed=<seed>] [--allow-duplicates] [--sorted] [--encrypt=<keyfile>] [--sign=<keyfile>] <file> <minvalue> <maxvalue> <values> <sets>
+  scalar_set_eval eval [--floats] [--gpu] [--sequential] [--strict] [--engine=<name>] [--allocator=<name>] [--preload=<mode>] [--prefetch=<mb>] [--cache-size=<mb>] [--io-limit=<mbps>] [--queries=<n>] [--threads=<n>] [--op=<op>] [--threshold=<w>] [--strategy=<strategy>] [--query-block=<n>] [--ranges=<ranges>] [--query-file=<file>] [--between=<window>] [--dimension=<d>] [--transform=<steps>] [--verify] [--verify-sample=<n>] [--breakdown] [--explain] [--explain-plan] [--exclude-file=<file>] [--min-set-size=<n>] [--max-set-size=<n>] [--sets=<range>] [--every=<n>] [--audit=<fraction>] [--audit-engine=<name>] [--output=<format>] [--db=<db>] [--gpu-compare=<mode>] [--gpu-memory=<mode>] [--gpu-kernel=<kernel>] [--top=<k>] [--memory-limit=<mb>] [--timeout=<ms>] [--bitmap-output=<file>] [--save-query=<file>] [--require-signature=<keyfile>] <file> <minvalue> <maxvalue> <values> [<sets>]
+  scalar_set_eval eval --repro=<bundle>
+  scalar_set_eval test [--floats] [--gpu] [--strict] [--engine=<name>] [--allocator=<name>] [--prefetch=<mb>] [--io-limit=<mbps>] [--encodings=<list>] [--strategies=<list>] [--bundle=<bundle>] [--query-file=<file>] [--save-query=<file>] [--scenarios=<file>] [--only=<tags>] [--skip=<tags>] [--max-disk=<gb>] [--retries=<n>] [--namespace=<name>] [--emit-runner=<dir>] <report> <minvalue> <maxvalue> [<values>] [<sets>]
+  scalar_set_eval test [--strict] --from-runner=<dir> [<report>]
+  scalar_set_eval convert [--floats] [--encoding=<encoding>] [--sign=<keyfile>] <file> <output>
+  scalar_set_eval encode [--floats] <file> <output>
+  scalar_set_eval import sqlite [--floats] --query=<sql> <db> <file>
+  scalar_set_eval import [--floats] [--strings] [--uuids] [--timestamps] <csv> <file>
+  scalar_set_eval export [--floats] [--strings] [--query=<values>] <file> <output>
+  scalar_set_eval edit [--floats] --set=<index> [--add=<values>] [--remove=<values>] [--sign=<keyfile>] <file>
+  scalar_set_eval compact [--floats] [--sign=<keyfile>] <file>
+  scalar_set_eval sort [--floats] [--by=<key>] <file> <output>
+  scalar_set_eval diff [--floats] <file> <other>
+  scalar_set_eval stats [--floats] [--distinct] [--top-values=<n>] <file>
+  scalar_set_eval inspect [--floats] <file>
+  scalar_set_eval matrix [--floats] [--checkpoint-rows=<n>] [--memory-limit=<mb>] <file> <output>
+  scalar_set_eval bundle [--floats] [--query-file=<file>] <output> <files>...
+  scalar_set_eval tune-eval [--floats] [--queries=<n>] <file> <minvalue> <maxvalue> <values> <output>
+  scalar_set_eval bench [--floats] [--gpu] [--strict] [--engine=<name>] [--mode=<mode>] [--repeat=<n>] [--preload=<mode>] [--cache-size=<mb>] [--threads=<n>] [--query-block=<n>] <file> <minvalue> <maxvalue> <values>
+  scalar_set_eval serve [--floats] [--preload=<mode>] [--cache-size=<mb>] [--io-limit=<mbps>] [--threads=<n>] [--address=<address>] [--protocol=<protocol>] [--timeout=<ms>] [--workers=<n>] [--corpus=<corpus>...] <file>
+  scalar_set_eval report merge <output> <files>...
+  scalar_set_eval bitmap (and | or) <file> <files>...
+  scalar_set_eval bitmap not <file> <output>
+  scalar_set_eval bitmap count <file>
+  scalar_set_eval report compare <file> <other>
+  scalar_set_eval selftest
+  scalar_set_eval capabilities
   scalar_set_eval (-h | --help)
   scalar_set_eval --version
 ro_scalar_set
@@ -33,6 +126,87 @@ Options:
   --mt          Multi-threaded
   --floats      Run tests using floating points
   --gpu         Run tests on GPU
+  --engine=<name>  Engine used in the evaluation: cpu or gpu [default: cpu]
+  --gpu-compare=<mode>  Comparison of the float values on GPU: exact like the CPU evaluation, epsilon, or ulp [default: epsilon]
+  --gpu-memory=<mode>  Host memory of the GPU buffers: default, pinned page-locked staging memory, or zerocopy for integrated GPUs [default: default]
+  --top=<k>     Report the k sets with the most values found from the test set, selected on the device with the gpu engine [default: 0]
+  --gpu-kernel=<kernel>  Layout of the GPU kernel: item scanning each set with a work-item, or wg with a work-group [default: item]
+  --allocator=<name>  Fail unless the application was built with this allocator: system, mimalloc or jemalloc
+  --encoding=<encoding>  Encoding of the sets: raw, delta, bitpack, dictionary or weighted [default: raw]
+  --encodings=<list>  Comma separated list of encodings compared with the raw sets in the tests [default: raw,delta,bitpack]
+  --strategy=<strategy>  Intersection of the sets with the test set: auto, probe, merge, galloping or batched. Auto merges the delta encoded sets and probes the others. Merge and galloping require sorted sets such as the delta or weighted sets [default: auto]
+  --query-block=<n>  Evaluate large test sets in blocks of this many values fitting in the cache, 0 disables [default: 0]
+  --strategies=<list>  Comma separated list of strategies compared in the tests on the sorted sets [default: auto]
+  --preload=<mode>  Data copied into memory before evaluation: none or full [default: none]
+  --prefetch=<mb>  Prefetch distance in MB or with a unit such as 512k or 2GiB for data read directly from file [default: 0]
+  --cache-size=<mb>  Keep the most recently probed sets in a cache of this size in MB or with a unit such as 2GiB [default: 0]
+  --io-limit=<mbps>  Limit reading the raw sets from the file to this many MB/s or a size with a unit per second to simulate slow storage, 0 disables [default: 0]
+  --queries=<n>  Number of random test sets evaluated against the loaded sets, counts accept the suffixes k, M and G such as 10k or 1.5M [default: 1]
+  --threads=<n>  Maximum number of threads used in the evaluation, 0 uses all [default: 0]
+  --bundle=<bundle>  Extract the test sets from a bundle before the tests
+  --set=<index>  Index of the edited set
+  --add=<values>  Comma separated values added to the set
+  --remove=<values>  Comma separated values removed from the set
+  --strings     Assign a code to each distinct string token as its value and keep the strings in a sidecar dictionary
+  --query=<values>  Export only the sets with any of the comma separated values
+                    With import sqlite the SQL query returning the group and the value of each row, e.g. \"SELECT group_id, value FROM members\", requires the sqlite feature
+  --encrypt=<keyfile>  Encrypt the generated sets with AES-256-GCM using a key derived from the file
+  --sign=<keyfile>  Sign the written file with HMAC-SHA256 using the key in the file
+  --require-signature=<keyfile>  Refuse to evaluate a file without a valid signature for the key. The signature of a file in an object storage is read from <uri>.sig
+  --address=<address>  Address the server listens to [default: 127.0.0.1:7878]
+  --workers=<n>  Serve with this many processes sharing the mapped file and an index of the sets, 0 serves with threads [default: 0]
+  --protocol=<protocol>  Protocol of the server: text, or resp for Redis clients when built with the resp feature [default: text]
+  --timeout=<ms>  Stop evaluating a test set or a query after this many milliseconds and report the partial matches as timed out, 0 disables [default: 0]
+  --op=<op>     Operation evaluated against each set: any or weighted-overlap [default: any]
+  --threshold=<w>  Sum of the weights of the common values required by weighted-overlap [default: 1]
+  --ranges=<ranges>  Match the sets with any value within the comma separated inclusive ranges, e.g. 10-20,55-60
+  --tuple-width=<n>  Generate sets of tuples with this many values in each tuple, 0 generates plain values [default: 0]
+  --uuids       Generate or import sets of 128-bit UUIDs
+  --query-file=<file>  Use the first set of the raw set file as the query instead of random test sets. All the sets of a file saved with --save-query are used. Sets of UUIDs are queried with a text file of UUIDs
+  --repro=<bundle>  Replay the sets exported when --verify or --audit found the engines diverging
+  --save-query=<file>  Save the random test sets of the run as raw sets for replaying them with --query-file. The test generates one test set of each size for all the scenarios
+  --timestamps  Generate or import sets of timestamps in milliseconds, <minvalue> and <maxvalue> are seconds since the epoch
+  --between=<window>  Match the sets of timestamps with any timestamp within the inclusive window, e.g. 2024-01-01..2024-02-01
+  --scenarios=<file>  Run the tests listed in the file, each line holding the values in a set, the number of sets, the test set size and optional tags
+  --only=<tags>  Run only the tests tagged with any of the comma separated tags, e.g. small,medium
+  --skip=<tags>  Skip the tests tagged with any of the comma separated tags
+  --breakdown   Measure each set and report the matches and the average time per set by the set size, the time spent on combining the results of the chunks of the sets and the duration of each phase of the evaluation
+  --explain-plan  Print the engine, strategy, chunking, preload, threads and estimated memory of the evaluation before running it
+  --explain     List the values of the test set found from the most sets after the evaluation
+  --exclude-file=<file>  Do not count the sets having any value of the sets in this file as matches
+  --corpus=<corpus>  Serve also the sets of another file under a name given as <name>=<file> or <name>:<type>=<file> with the type ints or floats
+  --mmap-write  Write the generated raw sets in parallel through a memory map of the preallocated file
+  --seed=<seed>  Seed of the generation, the same seed generates the same sets on any machine, random by default
+  --allow-duplicates  Allow the same value more than once in a generated set
+  --sorted      Write the values of each generated set in ascending order. The header of bit-packed sets records the order which lets the merge and galloping strategies evaluate them
+  --max-disk=<gb>  Abort the tests if generating the test sets would write more than this many GB or a size with a unit such as 500MB, 0 disables [default: 0]
+  --namespace=<name>  Prefix of the names of the generated test files separating the files of different users or projects in a shared directory
+  --retries=<n>  Retry a failed scenario this many times with exponential backoff before aborting the tests [default: 0]
+  --emit-runner=<dir>  Write the configuration, the seeds, the expected test files and the command lines of the test into the directory instead of running it
+  --from-runner=<dir>  Run the test written with --emit-runner identically to any other machine
+  --min-set-size=<n>  Evaluate only the sets with at least this many values [default: 0]
+  --max-set-size=<n>  Evaluate only the sets with at most this many values, 0 disables [default: 0]
+  --sets=<range>  Evaluate only the sets at the indexes of the range given as <first>..<end>, e.g. 1000..2000
+  --every=<n>   Evaluate only every nth set, 0 evaluates every set [default: 0]
+  --by=<key>    Key the sets are sorted by, size or minvalue [default: size]
+  --top-values=<n>  Print the n values found from the most sets as CSV with the number of sets instead of the statistics [default: 0]
+  --distinct    Estimate the number of distinct values of all the sets and the average number of sets per value
+  --checkpoint-rows=<n>  Rows of the matrix computed between checkpoints, an interrupted matrix resumes from the last checkpoint [default: 1024]
+  --memory-limit=<mb>  Memory for the rows of the matrix, the top sets and the per-set results in MB or a size with a unit such as 2GB. The top sets and the per-set results beyond it are spilled to temporary files, 0 disables [default: 0]
+  --mode=<mode>  Benchmark mode, latency measures the end-to-end time of a single test set evaluated repeatedly [default: latency]
+  --repeat=<n>  Number of times the test set is evaluated in the benchmark [default: 1000]
+  --audit=<fraction>  Re-evaluate this fraction of the sets with the audit engine after the evaluation and report whether the engines agree [default: 0]
+  --audit-engine=<name>  Engine the audited sets are re-evaluated with [default: cpu]
+  --output=<format>  Output of the results: text, or sqlite also writing the run, the queries and the result of each set into the database of --db.
+                     The sqlite output requires the sqlite feature [default: text]
+  --db=<db>     SQLite database the results are written into with --output sqlite
+  --bitmap-output=<file>  Write a bitmap with a bit per set of the file telling whether the set matched each test set
+  --dimension=<d>  Match the test set against this dimension of the tuples instead of whole tuples
+  --transform=<steps>  Transform the values as they are read without rewriting the file, e.g. scale=0.001,offset=-500
+  --strict      Fail instead of falling back to the cpu engine when the requested engine is not available
+  --sequential  Evaluate the sets in the order of the file on one thread and print the result of each set
+  --verify      Check the results against a slow reference implementation
+  --verify-sample=<n>  Number of sets checked with --verify [default: 1000]
 ";
 
 #[derive(Debug, Deserialize)]
@@ -40,49 +214,331 @@ struct Args
 {
     arg_file: String,
     arg_report: String,
+    arg_output: String,
+    arg_other: String,
+    arg_csv: String,
+    arg_db: String,
+    arg_files: Vec<String>,
     arg_minvalue: i32,
     arg_maxvalue: i32,
-    arg_sets: i32,
-    arg_values: i32,
+    #[serde(deserialize_with = "deserialize_count")]
+    arg_sets: u64,
+    /// Only eval accepts several numbers of values.
+    #[serde(deserialize_with = "deserialize_counts")]
+    arg_values: Vec<i32>,
     flag_version: bool,
     flag_mt: bool,
     flag_floats: bool,
     flag_gpu: bool,
+    flag_gpu_compare: String,
+    flag_gpu_memory: String,
+    flag_gpu_kernel: String,
+    flag_top: usize,
+    flag_sequential: bool,
+    flag_strict: bool,
+    flag_engine: String,
+    flag_strategy: String,
+    #[serde(deserialize_with = "deserialize_count")]
+    flag_query_block: usize,
+    /// Bytes per second.
+    #[serde(deserialize_with = "deserialize_megabytes")]
+    flag_io_limit: usize,
+    flag_strategies: String,
+    flag_allocator: String,
+    flag_encoding: String,
+    flag_encodings: String,
+    flag_preload: String,
+    /// Bytes.
+    #[serde(deserialize_with = "deserialize_megabytes")]
+    flag_prefetch: usize,
+    /// Bytes.
+    #[serde(deserialize_with = "deserialize_megabytes")]
+    flag_cache_size: usize,
+    #[serde(deserialize_with = "deserialize_count")]
+    flag_queries: usize,
+    flag_threads: usize,
+    flag_op: String,
+    flag_threshold: f32,
+    flag_ranges: String,
+    flag_tuple_width: usize,
+    flag_dimension: String,
+    flag_verify: bool,
+    flag_verify_sample: usize,
+    flag_breakdown: bool,
+    flag_explain: bool,
+    flag_explain_plan: bool,
+    flag_min_set_size: usize,
+    flag_max_set_size: usize,
+    flag_sets: String,
+    flag_every: usize,
+    flag_audit: f64,
+    flag_audit_engine: String,
+    flag_output: String,
+    flag_db: String,
+    flag_bitmap_output: String,
+    flag_by: String,
+    flag_checkpoint_rows: usize,
+    #[serde(deserialize_with = "deserialize_megabytes")]
+    flag_memory_limit: usize,
+    flag_distinct: bool,
+    #[serde(deserialize_with = "deserialize_count")]
+    flag_top_values: usize,
+    flag_mode: String,
+    #[serde(deserialize_with = "deserialize_count")]
+    flag_repeat: usize,
+    flag_exclude_file: String,
+    flag_address: String,
+    flag_protocol: String,
+    flag_timeout: u64,
+    flag_workers: usize,
+    flag_corpus: Vec<String>,
+    flag_sign: String,
+    flag_encrypt: String,
+    flag_bundle: String,
+    flag_set: usize,
+    flag_add: String,
+    flag_remove: String,
+    flag_strings: bool,
+    flag_uuids: bool,
+    flag_timestamps: bool,
+    flag_mmap_write: bool,
+    flag_seed: String,
+    flag_allow_duplicates: bool,
+    flag_sorted: bool,
+    flag_between: String,
+    flag_query_file: String,
+    flag_save_query: String,
+    flag_repro: String,
+    flag_scenarios: String,
+    flag_only: String,
+    flag_skip: String,
+    /// Bytes.
+    #[serde(deserialize_with = "deserialize_gigabytes")]
+    flag_max_disk: u64,
+    flag_retries: usize,
+    flag_namespace: String,
+    flag_emit_runner: String,
+    flag_from_runner: String,
+    flag_transform: String,
+    flag_query: String,
+    flag_require_signature: String,
     cmd_new: bool,
     cmd_eval: bool,
     cmd_test: bool,
+    cmd_convert: bool,
+    cmd_encode: bool,
+    cmd_tune_eval: bool,
+    cmd_bundle: bool,
+    cmd_bitmap: bool,
+    cmd_and: bool,
+    cmd_or: bool,
+    cmd_not: bool,
+    cmd_count: bool,
+    cmd_diff: bool,
+    cmd_edit: bool,
+    cmd_compact: bool,
+    cmd_sort: bool,
+    cmd_matrix: bool,
+    cmd_stats: bool,
+    cmd_inspect: bool,
+    cmd_import: bool,
+    cmd_sqlite: bool,
+    cmd_export: bool,
+    cmd_bench: bool,
+    cmd_serve: bool,
+    cmd_report: bool,
+    cmd_merge: bool,
+    cmd_compare: bool,
+    cmd_selftest: bool,
+    cmd_capabilities: bool,
 }
 
 fn main()
 {
 
     // Test
+    // An audited run is executed in a child process so that its exit status and output can be recorded.
+    // The options of the environment and the profile are added to the arguments before parsing them.
+    let ( arguments, audit_log ) = auditlog::take( std::env::args().collect() );
+    let arguments = encryption::take( arguments );
+    let effective_arguments = profile::apply( USAGE, arguments.clone() );
     let args: Args = Docopt::new( USAGE )
-        .and_then( |d| d.deserialize() )
+        .and_then( |d| d.argv( effective_arguments.clone() ).deserialize() )
         .unwrap_or_else( |e| e.exit() );
+    if let Some( audit_log ) = audit_log
+    {
+        std::process::exit( auditlog::run( &audit_log, &arguments, &effective_arguments, &input_files( &args ) ) );
+    }
 
-    let eval_engine = if args.flag_gpu
+    // --gpu and --sequential are shorthands for the GPU and the sequential engines.
+    let eval_engine: &str = if args.flag_gpu
     {
-        EvaluationEngine::Gpu
+        "gpu"
+    }
+    else if args.flag_sequential
+    {
+        "sequential"
     }
     else
     {
-        EvaluationEngine::Cpu
+        &args.flag_engine
     };
+    let registry = engine::EngineRegistry::<i32>::new();
+    let engines = registry.names();
+    if !engines.contains( &eval_engine )
+    {
+        eprintln!( "Unknown engine: {}. Available engines: {}", eval_engine, engines.join( ", " ) );
+        std::process::exit( 1 );
+    }
+
+    // Engines missing from the build or the machine are replaced with the CPU engine unless strict.
+    let available = registry.find( eval_engine ).map( |e| e.is_available() ).unwrap_or( false );
+    let eval_engine = if available
+    {
+        eval_engine
+    }
+    else if args.flag_strict
+    {
+        eprintln!( "The {} engine is not available in this build or on this machine.", eval_engine );
+        std::process::exit( 1 );
+    }
+    else
+    {
+        eprintln!( "WARNING: The {} engine is not available in this build or on this machine.", eval_engine );
+        eprintln!( "WARNING: Falling back to the cpu engine, the results do not measure the {} engine.", eval_engine );
+        "cpu"
+    };
+
+    // The allocator is chosen when building the application.
+    if let Err( message ) = allocator::check( &args.flag_allocator )
+    {
+        println!( "{}", message );
+        std::process::exit( 1 );
+    }
+
+    // Several sizes of the test sets are evaluated only with the random test sets of eval.
+    if args.arg_values.len() > 1 && !( args.cmd_eval && args.flag_between.is_empty()
+            && args.flag_query_file.is_empty() && args.flag_ranges.is_empty() )
+    {
+        println!( "Several numbers of values are only supported when evaluating random test sets." );
+        std::process::exit( 1 );
+    }
+    let values = args.arg_values.iter().cloned().max().unwrap_or( 0 );
+
+    // The results are written as text or into an SQLite database.
+    if !args.flag_output.is_empty() && args.flag_output != "text" && ( args.flag_output != "sqlite" || args.flag_db.is_empty() )
+    {
+        println!( "Unknown output {}, expected text, or sqlite with --db.", args.flag_output );
+        std::process::exit( 1 );
+    }
+
+    // Sets in an object storage are read from a local copy.
+    let input_file = if args.cmd_new { args.arg_file.clone() } else { remote::resolve( &args.arg_file ) };
+
+    // The values of the generated sets and the random test sets are drawn from the value range.
+    // The timestamps are drawn from the milliseconds of the range which the generation checks.
+    let drawn_values = if args.cmd_new && args.flag_timestamps
+    {
+        Some( 0 )
+    }
+    else if args.cmd_new || args.cmd_tune_eval || args.cmd_bench
+            || args.cmd_eval && args.flag_between.is_empty() && args.flag_query_file.is_empty() && args.flag_ranges.is_empty()
+            && args.flag_repro.is_empty()
+    {
+        Some( values )
+    }
+    else
+    {
+        None
+    };
+    if let Some( values ) = drawn_values
+    {
+        check_value_range( args.arg_minvalue, args.arg_maxvalue, values, args.cmd_new && args.flag_allow_duplicates );
+    }
 
     // Determine action.
     let start = std::time::Instant::now();
     if args.cmd_new
     {
+        // The same seed generates identical sets regardless of the number of threads.
+        let seed: u64 = if args.flag_seed.is_empty()
+        {
+            rand::random()
+        }
+        else
+        {
+            args.flag_seed.parse().expect( "Invalid seed." )
+        };
+
+        // The sets are encrypted as they are written.
+        let encrypt = if args.flag_encrypt.is_empty() { None } else { Some( &args.flag_encrypt ) };
+
         // Data type
-        if args.flag_floats
+        if args.flag_timestamps
+        {
+            utility::generate_timestamps( &args.arg_file, args.arg_sets, values,
+                    args.arg_minvalue, args.arg_maxvalue, seed, args.flag_allow_duplicates, encrypt );
+        }
+        else if args.flag_uuids
+        {
+            if args.flag_floats
+            {
+                utility::generate_uuids::<f32>( &args.arg_file, args.arg_sets, values,
+                        args.arg_minvalue, args.arg_maxvalue, seed, args.flag_allow_duplicates, encrypt );
+            }
+            else
+            {
+                utility::generate_uuids::<i32>( &args.arg_file, args.arg_sets, values,
+                        args.arg_minvalue, args.arg_maxvalue, seed, args.flag_allow_duplicates, encrypt );
+            }
+        }
+        else if args.flag_tuple_width > 0
+        {
+            if args.flag_floats
+            {
+                utility::generate_tuples::<f32>( &args.arg_file, args.arg_sets, values,
+                        args.flag_tuple_width, args.arg_minvalue, args.arg_maxvalue, seed, args.flag_allow_duplicates, encrypt );
+            }
+            else
+            {
+                utility::generate_tuples::<i32>( &args.arg_file, args.arg_sets, values,
+                        args.flag_tuple_width, args.arg_minvalue, args.arg_maxvalue, seed, args.flag_allow_duplicates, encrypt );
+            }
+        }
+        else if args.flag_mmap_write
+        {
+            if encrypt.is_some()
+            {
+                panic!( "Encrypted sets cannot be written through a memory map." );
+            }
+            if parse_encoding( &args.flag_encoding ) != Encoding::Raw
+            {
+                panic!( "Only raw sets can be written through a memory map." );
+            }
+            if args.flag_floats
+            {
+                utility::generate_mapped::<f32>( &args.arg_file, args.arg_sets, values,
+                        args.arg_minvalue, args.arg_maxvalue, seed, args.flag_allow_duplicates );
+            }
+            else
+            {
+                utility::generate_mapped::<i32>( &args.arg_file, args.arg_sets, values,
+                        args.arg_minvalue, args.arg_maxvalue, seed, args.flag_allow_duplicates );
+            }
+        }
+        else if args.flag_floats
         {
             utility::generate::<f32>(
                 &args.arg_file,
                 args.arg_sets,
-                args.arg_values,
+                values,
                 args.arg_minvalue,
                 args.arg_maxvalue,
+                parse_encoding( &args.flag_encoding ),
+                seed,
+                args.flag_allow_duplicates,
+                args.flag_sorted,
+                encrypt,
             );
         }
         else
@@ -90,58 +546,810 @@ fn main()
             utility::generate::<i32>(
                 &args.arg_file,
                 args.arg_sets,
-                args.arg_values,
+                values,
                 args.arg_minvalue,
                 args.arg_maxvalue,
+                parse_encoding( &args.flag_encoding ),
+                seed,
+                args.flag_allow_duplicates,
+                args.flag_sorted,
+                encrypt,
             );
         }
+
+        // The sets of values are described in a manifest next to the file.
+        if !args.flag_timestamps && !args.flag_uuids && args.flag_tuple_width == 0
+        {
+            let mut artifact = artifact::Artifact::new( "", args.flag_floats, args.arg_sets, values,
+                    args.arg_minvalue, args.arg_maxvalue, parse_encoding( &args.flag_encoding ) );
+            if !args.flag_seed.is_empty()
+            {
+                artifact.seed = Some( seed );
+            }
+            artifact.write_manifest( &args.arg_file, seed );
+        }
+        if !args.flag_sign.is_empty()
+        {
+            signature::sign( &args.arg_file, &args.flag_sign );
+        }
+    }
+    else if args.cmd_eval && !args.flag_repro.is_empty()
+    {
+        repro::replay( &args.flag_repro );
     }
     else if args.cmd_eval
     {
+        // Tampered or unsigned files are rejected by the evaluation.
+        // The signature of a file in an object storage is downloaded next to its local copy.
+        let signature_file = if args.flag_require_signature.is_empty()
+        {
+            String::new()
+        }
+        else if remote::is_remote( &args.arg_file )
+        {
+            remote::resolve_signature( &args.arg_file )
+        }
+        else
+        {
+            signature::get_signature_file_name( &input_file )
+        };
+
         // Construct parameters
         let params = evaluation::EvaluationParams
         {
-            file: &args.arg_file,
-            values_in_set: args.arg_values,
+            file: &input_file,
+            values_in_set: values,
             min_value: args.arg_minvalue,
             max_value: args.arg_maxvalue,
-            preload_data: false,
-            max_threads: 0,
-            eval_engine: &eval_engine,
+            preload: parse_preload_mode( &args.flag_preload ),
+            max_threads: args.flag_threads,
+            prefetch_distance: args.flag_prefetch,
+            cache_size: args.flag_cache_size,
+            io_limit: args.flag_io_limit,
+            verify_sample: if args.flag_verify { args.flag_verify_sample } else { 0 },
+            operation: parse_operation( &args.flag_op, args.flag_threshold ),
+            dimension: if args.flag_dimension.is_empty()
+            {
+                None
+            }
+            else
+            {
+                Some( args.flag_dimension.parse().expect( "Invalid dimension." ) )
+            },
+            transform: if args.flag_transform.is_empty()
+            {
+                None
+            }
+            else
+            {
+                Some( transform::Transform::parse( &args.flag_transform ).expect( "Invalid transform." ) )
+            },
+            query_block: args.flag_query_block,
+            strategy: parse_strategy( &args.flag_strategy ),
+            breakdown: args.flag_breakdown,
+            explain: args.flag_explain,
+            set_results: args.flag_output == "sqlite" || !args.flag_bitmap_output.is_empty(),
+            exclude_file: if args.flag_exclude_file.is_empty() { None } else { Some( &args.flag_exclude_file ) },
+            set_filter: evaluation::SetFilter
+            {
+                min_size: args.flag_min_set_size,
+                max_size: args.flag_max_set_size,
+                indexes: if args.flag_sets.is_empty() { None } else { Some( parse_index_range( &args.flag_sets ) ) },
+                every: args.flag_every,
+                sample: None,
+            },
+            eval_engine: eval_engine,
+            audit: args.flag_audit,
+            audit_engine: &args.flag_audit_engine,
+            repro: true,
+            gpu_compare: parse_gpu_compare( &args.flag_gpu_compare ),
+            gpu_memory: parse_gpu_memory( &args.flag_gpu_memory ),
+            gpu_kernel: parse_gpu_kernel( &args.flag_gpu_kernel ),
+            top_k: args.flag_top,
+            memory_limit: args.flag_memory_limit,
+            timeout: parse_timeout( args.flag_timeout ),
+            cancellation: None,
+            signature: if args.flag_require_signature.is_empty() { None } else { Some( ( &args.flag_require_signature, &signature_file ) ) },
         };
 
+        // Refuse the strategies the sets cannot be evaluated with before evaluating.
+        if let Err( e ) = evaluation::check_strategy( &params )
+        {
+            eprintln!( "{}", e );
+            std::process::exit( 1 );
+        }
+
+        // Refuse the runs whose results of the sets cannot be exported before evaluating.
+        if args.flag_output == "sqlite"
+        {
+            if let Err( e ) = sqlite::check_params( &params )
+            {
+                eprintln!( "{}", e );
+                std::process::exit( 1 );
+            }
+        }
+        if args.flag_explain_plan
+        {
+            let query_count = if args.flag_between.is_empty() && args.flag_query_file.is_empty() && args.flag_ranges.is_empty()
+            {
+                args.flag_queries * args.arg_values.len()
+            }
+            else
+            {
+                1
+            };
+            plan::print_plan( &params, query_count, if args.flag_floats { std::mem::size_of::<f32>() } else { std::mem::size_of::<i32>() } );
+        }
+
+        // A text file of UUIDs queries the sets of UUIDs directly.
+        let query_uuids = if args.flag_query_file.is_empty() { None } else { uuid::read_query( &args.flag_query_file ) };
+
         // Data type
-        if args.flag_floats
+        let results = if !args.flag_between.is_empty()
         {
-            let result= evaluation::evaluate::<f32>( &params );
-            println!(
-                "Found {} matches in {}.{:06} s",
-                result.match_count,
-                result.duration.as_secs(),
-                result.duration.subsec_nanos() / 1000
-            );
+            // The time window replaces the random test sets.
+            let ( from, to ) = match timestamp::parse_window( &args.flag_between )
+            {
+                Some( window ) => window,
+                None => panic!( "Invalid time window: {}", args.flag_between ),
+            };
+            vec![ evaluation::evaluate_between( &params, from, to ) ]
+        }
+        else if let Some( ref uuids ) = query_uuids
+        {
+            vec![ evaluation::evaluate_with_uuids( &params, uuids ) ]
+        }
+        else if !args.flag_query_file.is_empty()
+        {
+            // The query is attached from the file instead of generating the test sets.
+            // A file saved with --save-query from several test sets replays all of them.
+            let query = query::QueryFile::open( &args.flag_query_file );
+            if args.flag_floats
+            {
+                let test_sets: Vec<&[f32]> = query.attach_all::<f32>().into_iter().map( |( _, values )| values ).collect();
+                if test_sets.len() > 1
+                {
+                    evaluation::evaluate_with_queries( &params, &test_sets )
+                }
+                else
+                {
+                    vec![ evaluation::evaluate_with_query_file::<f32>( &params, &query ) ]
+                }
+            }
+            else
+            {
+                let test_sets: Vec<&[i32]> = query.attach_all::<i32>().into_iter().map( |( _, values )| values ).collect();
+                if test_sets.len() > 1
+                {
+                    evaluation::evaluate_with_queries( &params, &test_sets )
+                }
+                else
+                {
+                    vec![ evaluation::evaluate_with_query_file::<i32>( &params, &query ) ]
+                }
+            }
+        }
+        else if !args.flag_ranges.is_empty()
+        {
+            // The ranges replace the random test sets.
+            let ranges = parse_ranges( &args.flag_ranges );
+            if args.flag_floats
+            {
+                vec![ evaluation::evaluate_ranges::<f32>( &params, &ranges ) ]
+            }
+            else
+            {
+                vec![ evaluation::evaluate_ranges::<i32>( &params, &ranges ) ]
+            }
+        }
+        else if args.flag_floats
+        {
+            let test_sets = evaluation::generate_test_sets::<f32>( args.arg_minvalue, args.arg_maxvalue, &args.arg_values, args.flag_queries );
+            let test_sets: Vec<&[f32]> = test_sets.iter().map( |t| t.as_slice() ).collect();
+            save_query( &args.flag_save_query, &test_sets );
+            evaluation::evaluate_with_queries( &params, &test_sets )
         }
         else
         {
-            let result = evaluation::evaluate::<i32>( &params );
+            let test_sets = evaluation::generate_test_sets::<i32>( args.arg_minvalue, args.arg_maxvalue, &args.arg_values, args.flag_queries );
+            let test_sets: Vec<&[i32]> = test_sets.iter().map( |t| t.as_slice() ).collect();
+            save_query( &args.flag_save_query, &test_sets );
+            evaluation::evaluate_with_queries( &params, &test_sets )
+        };
+        // The results of the random test sets are ordered by the size of the test sets.
+        let queries_per_size = if args.arg_values.len() > 1 { args.flag_queries } else { results.len() };
+        let sizes: Vec<( i32, u64, std::time::Duration )> = results.iter()
+                .enumerate()
+                .map( |( i, r )| ( args.arg_values[ i / std::cmp::max( 1, queries_per_size ) ], r.match_count, r.duration ) )
+                .collect();
+        if args.flag_output == "sqlite"
+        {
+            let test_set_sizes: Vec<i32> = sizes.iter().map( |s| s.0 ).collect();
+            sqlite::export( &args.flag_db, &params, &results, &test_set_sizes );
+        }
+        if !args.flag_bitmap_output.is_empty()
+        {
+            write_bitmaps( &args.flag_bitmap_output, &input_file, args.flag_floats, &results );
+        }
+        for ( result, &( size, _, _ ) ) in results.into_iter().zip( &sizes )
+        {
+            if args.arg_values.len() > 1
+            {
+                print!( "{} values: ", size );
+            }
             println!(
                 "Found {} matches in {}.{:06} s",
                 result.match_count,
                 result.duration.as_secs(),
                 result.duration.subsec_nanos() / 1000
             );
+            if result.timed_out
+            {
+                println!( "Timed out after {} ms, the matches are from the sets evaluated before the timeout", args.flag_timeout );
+            }
+            if let ( Some( state ), Some( cached ) ) = ( result.cache_state(), result.page_cache )
+            {
+                println!( "Page cache: {} ({:.0} % of the file cached before the evaluation)", state, cached * 100.0 );
+            }
+            if let Some( throughput ) = result.metrics.throughput( "preload" )
+            {
+                println!( "Preloaded the sets at {:.1} MB/s", throughput / ( 1024.0 * 1024.0 ) );
+            }
+            if args.flag_breakdown
+            {
+                result.metrics.print();
+            }
+            if let Some( verification ) = result.verification
+            {
+                println!(
+                    "Verified {} sets against the reference, {} mismatches",
+                    verification.sets_checked,
+                    verification.mismatches.len()
+                );
+                for index in verification.mismatches
+                {
+                    println!( "Mismatch in set {}", index );
+                }
+            }
+            match result.breakdown
+            {
+                Some( breakdown ) => breakdown.print(),
+                None if args.flag_breakdown => println!( "The breakdown is only available for raw sets evaluated with CPU." ),
+                None => {},
+            }
+            if let ( true, Some( reduction ) ) = ( args.flag_breakdown, result.reduction )
+            {
+                reduction.print();
+            }
+            match result.explanation
+            {
+                Some( explanation ) => explanation.print(),
+                None if args.flag_explain => println!( "The explanation is only available for sets of values matched with any value of the test set." ),
+                None => {},
+            }
+            match result.audit
+            {
+                Some( audit ) => audit.print(),
+                None if args.flag_audit > 0.0 => println!( "The audit is only available for raw sets." ),
+                None => {},
+            }
+            match result.top
+            {
+                Some( top ) => top.print(),
+                None if args.flag_top > 0 => println!( "The top sets are only available for sets of values matched with any value of the test set." ),
+                None => {},
+            }
+        }
+
+        // Summarize the evaluations of each size of the test sets.
+        if args.arg_values.len() > 1
+        {
+            println!( "" );
+            println!( "|{:>14}|{:>14}|{:>16}|", "Values", "Matching sets", "Average" );
+            for size in &args.arg_values
+            {
+                let of_size: Vec<&( i32, u64, std::time::Duration )> = sizes.iter().filter( |s| s.0 == *size ).collect();
+                let matches: u64 = of_size.iter().map( |s| s.1 ).sum();
+                let duration = of_size.iter().fold( std::time::Duration::new( 0, 0 ), |total, s| total + s.2 ) / std::cmp::max( 1, of_size.len() ) as u32;
+                println!( "|{:>14}|{:>14}|{:>7}.{:06} s|", size, matches / std::cmp::max( 1, of_size.len() ) as u64,
+                        duration.as_secs(), duration.subsec_nanos() / 1000 );
+            }
         }
     }
+    else if args.cmd_test && !args.flag_from_runner.is_empty()
+    {
+        // The runner fixes the engine so that the results are comparable with the other machines.
+        let runner = runner::Runner::read( &args.flag_from_runner );
+        println!( "Running the test emitted on {} ({} {}, {} CPUs).", runner.environment.host, runner.environment.os,
+                runner.environment.arch, runner.environment.cpus );
+        if !registry.find( &runner.config.engine ).map( |e| e.is_available() ).unwrap_or( false )
+        {
+            println!( "The {} engine of the runner is not available in this build or on this machine.", runner.config.engine );
+            std::process::exit( 1 );
+        }
+        let query = runner.config.query_file.as_ref().map( |q| query::QueryFile::open( q ) );
+        let report = if args.arg_report.is_empty() { runner.config.report.clone() } else { args.arg_report.clone() };
+        test::run_tests(
+            &report,
+            &runner.config.namespace,
+            runner.config.min_value,
+            runner.config.max_value,
+            runner.config.floats,
+            &runner.config.encodings.iter().map( |e| parse_encoding( e ) ).collect(),
+            &runner.config.strategies.iter().map( |s| parse_strategy( s ) ).collect(),
+            runner.config.prefetch,
+            runner.config.io_limit,
+            &runner.config.engine,
+            query.as_ref(),
+            &runner.scenarios,
+            runner.config.max_disk,
+            runner.config.retries,
+            &runner.seeds(),
+            Some( runner.query_seed ),
+        );
+        runner.verify_artifacts();
+    }
     else if args.cmd_test
     {
+        // Test sets found from the bundle are not generated again.
+        if !args.flag_bundle.is_empty()
+        {
+            bundle::unpack( &args.flag_bundle, "." );
+        }
+        let scenarios = if args.flag_scenarios.is_empty() { scenario::default_scenarios() } else { scenario::read_scenarios( &args.flag_scenarios ) };
+        let scenarios = scenario::select( scenarios, &parse_tags( &args.flag_only ), &parse_tags( &args.flag_skip ) );
+
+        // The saved test sets, one of each size, replace the random test sets of the run.
+        if !args.flag_save_query.is_empty() && args.flag_query_file.is_empty()
+        {
+            let mut sizes: Vec<i32> = scenarios.iter().map( |s| s.test_set_size ).collect();
+            sizes.sort();
+            sizes.dedup();
+            if args.flag_floats
+            {
+                let test_sets = evaluation::generate_test_sets::<f32>( args.arg_minvalue, args.arg_maxvalue, &sizes, 1 );
+                save_query( &args.flag_save_query, &test_sets.iter().map( |t| t.as_slice() ).collect::<Vec<&[f32]>>() );
+            }
+            else
+            {
+                let test_sets = evaluation::generate_test_sets::<i32>( args.arg_minvalue, args.arg_maxvalue, &sizes, 1 );
+                save_query( &args.flag_save_query, &test_sets.iter().map( |t| t.as_slice() ).collect::<Vec<&[i32]>>() );
+            }
+        }
+        let query_file = if args.flag_query_file.is_empty() { &args.flag_save_query } else { &args.flag_query_file };
+        let query = if query_file.is_empty() { None } else { Some( query::QueryFile::open( query_file ) ) };
+        let largest = scenarios.iter().map( |s| std::cmp::max( s.set_size, s.test_set_size ) ).max().unwrap_or( 0 );
+        check_value_range( args.arg_minvalue, args.arg_maxvalue, largest, false );
+
+        // The runner is executed later with test --from-runner, possibly on another machine.
+        if !args.flag_emit_runner.is_empty()
+        {
+            let config = runner::RunnerConfig {
+                report: args.arg_report.clone(),
+                namespace: args.flag_namespace.clone(),
+                min_value: args.arg_minvalue,
+                max_value: args.arg_maxvalue,
+                floats: args.flag_floats,
+                encodings: args.flag_encodings.split( ',' ).map( |e| e.to_string() ).collect(),
+                strategies: args.flag_strategies.split( ',' ).map( |s| s.to_string() ).collect(),
+                prefetch: args.flag_prefetch,
+                io_limit: args.flag_io_limit,
+                engine: eval_engine.to_string(),
+                query_file: if query_file.is_empty() { None } else { Some( query_file.clone() ) },
+                max_disk: args.flag_max_disk,
+                retries: args.flag_retries,
+            };
+            runner::Runner::new( config, &scenarios ).emit( &args.flag_emit_runner );
+            println!( "Wrote the runner to {}.", args.flag_emit_runner );
+            return;
+        }
         test::run_tests(
             &args.arg_report,
+            &args.flag_namespace,
             args.arg_minvalue,
             args.arg_maxvalue,
             args.flag_floats,
-            &eval_engine,
+            &args.flag_encodings.split( ',' ).map( parse_encoding ).collect(),
+            &args.flag_strategies.split( ',' ).map( parse_strategy ).collect(),
+            args.flag_prefetch,
+            args.flag_io_limit,
+            eval_engine,
+            query.as_ref(),
+            &scenarios,
+            args.flag_max_disk,
+            args.flag_retries,
+            &std::collections::HashMap::new(),
+            None,
         );
     }
+    else if args.cmd_convert
+    {
+        // The sets are converted to the requested encoding.
+        let encoding = parse_encoding( &args.flag_encoding );
+        if args.flag_floats
+        {
+            encoding::convert::<f32>( &input_file, &args.arg_output, encoding );
+        }
+        else
+        {
+            encoding::convert::<i32>( &input_file, &args.arg_output, encoding );
+        }
+        if !args.flag_sign.is_empty()
+        {
+            signature::sign( &args.arg_output, &args.flag_sign );
+        }
+    }
+    else if args.cmd_encode
+    {
+        // Frequent values get the shortest codes.
+        if args.flag_floats
+        {
+            encoding::convert::<f32>( &input_file, &args.arg_output, Encoding::Dictionary );
+        }
+        else
+        {
+            encoding::convert::<i32>( &input_file, &args.arg_output, Encoding::Dictionary );
+        }
+    }
+    else if args.cmd_import && args.cmd_sqlite
+    {
+        if args.flag_floats
+        {
+            sqlite::import::<f32>( &args.arg_db, &args.flag_query, &args.arg_file );
+        }
+        else
+        {
+            sqlite::import::<i32>( &args.arg_db, &args.flag_query, &args.arg_file );
+        }
+    }
+    else if args.cmd_import
+    {
+        // The strings are hashed into integers which floats cannot represent exactly.
+        if args.flag_floats && args.flag_strings
+        {
+            println!( "Strings cannot be imported as floats." );
+            std::process::exit( 1 );
+        }
+        if args.flag_timestamps
+        {
+            csv::import_timestamps( &args.arg_csv, &args.arg_file );
+        }
+        else if args.flag_uuids
+        {
+            csv::import_uuids( &args.arg_csv, &args.arg_file );
+        }
+        else if args.flag_floats
+        {
+            csv::import::<f32>( &args.arg_csv, &args.arg_file, false );
+        }
+        else
+        {
+            csv::import::<i32>( &args.arg_csv, &args.arg_file, args.flag_strings );
+        }
+    }
+    else if args.cmd_export
+    {
+        let query: Vec<String> = args.flag_query.split( ',' )
+                .map( |q| q.trim().to_string() )
+                .filter( |q| !q.is_empty() )
+                .collect();
+        if args.flag_floats
+        {
+            csv::export::<f32>( &input_file, &args.arg_output, &query, args.flag_strings );
+        }
+        else
+        {
+            csv::export::<i32>( &input_file, &args.arg_output, &query, args.flag_strings );
+        }
+    }
+    else if args.cmd_edit
+    {
+        let key_file = if args.flag_sign.is_empty() { None } else { Some( &args.flag_sign ) };
+        if args.flag_floats
+        {
+            edit::edit::<f32>( &args.arg_file, args.flag_set,
+                    &parse_values( &args.flag_add ), &parse_values( &args.flag_remove ), key_file );
+        }
+        else
+        {
+            edit::edit::<i32>( &args.arg_file, args.flag_set,
+                    &parse_values( &args.flag_add ), &parse_values( &args.flag_remove ), key_file );
+        }
+    }
+    else if args.cmd_compact
+    {
+        let key_file = if args.flag_sign.is_empty() { None } else { Some( &args.flag_sign ) };
+        if args.flag_floats
+        {
+            edit::compact::<f32>( &args.arg_file, key_file );
+        }
+        else
+        {
+            edit::compact::<i32>( &args.arg_file, key_file );
+        }
+    }
+    else if args.cmd_sort
+    {
+        let key = parse_sort_key( &args.flag_by );
+        if args.flag_floats
+        {
+            edit::sort::<f32>( &input_file, &args.arg_output, key );
+        }
+        else
+        {
+            edit::sort::<i32>( &input_file, &args.arg_output, key );
+        }
+    }
+    else if args.cmd_stats
+    {
+        // The top values are counted from the frequencies of all the values.
+        let frequencies = args.flag_top_values > 0;
+        let statistics = if args.flag_floats
+        {
+            stats::collect::<f32>( &input_file, args.flag_distinct, frequencies )
+        }
+        else
+        {
+            stats::collect::<i32>( &input_file, args.flag_distinct, frequencies )
+        };
+        match statistics.frequencies
+        {
+            Some( ref frequencies ) if args.flag_top_values > 0 && args.flag_floats =>
+                    stats::print_top_values::<f32>( frequencies, args.flag_top_values ),
+            Some( ref frequencies ) if args.flag_top_values > 0 =>
+                    stats::print_top_values::<i32>( frequencies, args.flag_top_values ),
+            _ => statistics.print(),
+        }
+    }
+    else if args.cmd_inspect
+    {
+        let inspection = if args.flag_floats
+        {
+            inspect::inspect::<f32>( &input_file )
+        }
+        else
+        {
+            inspect::inspect::<i32>( &input_file )
+        };
+        inspection.print();
+    }
+    else if args.cmd_matrix
+    {
+        if args.flag_floats
+        {
+            matrix::matrix::<f32>( &input_file, &args.arg_output, args.flag_checkpoint_rows, args.flag_memory_limit );
+        }
+        else
+        {
+            matrix::matrix::<i32>( &input_file, &args.arg_output, args.flag_checkpoint_rows, args.flag_memory_limit );
+        }
+    }
+    else if args.cmd_diff
+    {
+        // Differences are reported with a failing exit code for scripts.
+        let identical = if args.flag_floats
+        {
+            diff::diff::<f32>( &input_file, &remote::resolve( &args.arg_other ) )
+        }
+        else
+        {
+            diff::diff::<i32>( &input_file, &remote::resolve( &args.arg_other ) )
+        };
+        if !identical
+        {
+            std::process::exit( 1 );
+        }
+    }
+    else if args.cmd_bundle
+    {
+        let query_file = if args.flag_query_file.is_empty() { None } else { Some( &args.flag_query_file ) };
+        bundle::pack( &args.arg_output, &args.arg_files, &Vec::new(), query_file, args.flag_floats );
+    }
+    else if args.cmd_tune_eval
+    {
+        // The best options of the eval command are saved for later runs.
+        // The bucket counts of the sets are fixed when the file is generated and are not searched.
+        if args.flag_floats
+        {
+            tune::tune::<f32>( &input_file, &args.arg_output, values,
+                    args.arg_minvalue, args.arg_maxvalue, args.flag_queries );
+        }
+        else
+        {
+            tune::tune::<i32>( &input_file, &args.arg_output, values,
+                    args.arg_minvalue, args.arg_maxvalue, args.flag_queries );
+        }
+    }
+    else if args.cmd_bench
+    {
+        if args.flag_mode != "latency"
+        {
+            panic!( "Unknown benchmark mode: {}", args.flag_mode );
+        }
+        let params = evaluation::EvaluationParams
+        {
+            file: &input_file,
+            values_in_set: values,
+            min_value: args.arg_minvalue,
+            max_value: args.arg_maxvalue,
+            preload: parse_preload_mode( &args.flag_preload ),
+            max_threads: args.flag_threads,
+            prefetch_distance: 0,
+            cache_size: args.flag_cache_size,
+            io_limit: 0,
+            verify_sample: 0,
+            operation: Operation::Any,
+            dimension: None,
+            transform: None,
+            query_block: args.flag_query_block,
+            strategy: Strategy::Auto,
+            breakdown: false,
+            explain: false,
+            set_results: false,
+            exclude_file: None,
+            set_filter: evaluation::SetFilter::all(),
+            eval_engine: eval_engine,
+            audit: 0.0,
+            audit_engine: "cpu",
+            repro: false,
+            gpu_compare: GpuCompare::Epsilon,
+            gpu_memory: GpuMemory::Default,
+            gpu_kernel: GpuKernel::Item,
+            top_k: 0,
+            memory_limit: 0,
+            timeout: None,
+            cancellation: None,
+            signature: None,
+        };
+        if args.flag_floats
+        {
+            bench::bench_latency::<f32>( &params, args.flag_repeat );
+        }
+        else
+        {
+            bench::bench_latency::<i32>( &params, args.flag_repeat );
+        }
+    }
+    else if args.cmd_serve
+    {
+        // Construct parameters
+        let params = evaluation::EvaluationParams
+        {
+            file: &input_file,
+            values_in_set: 0,
+            min_value: 0,
+            max_value: 0,
+            preload: parse_preload_mode( &args.flag_preload ),
+            max_threads: args.flag_threads,
+            prefetch_distance: 0,
+            cache_size: args.flag_cache_size,
+            io_limit: args.flag_io_limit,
+            verify_sample: 0,
+            operation: Operation::Any,
+            dimension: None,
+            transform: None,
+            query_block: 0,
+            strategy: Strategy::Auto,
+            breakdown: false,
+            explain: false,
+            set_results: false,
+            exclude_file: None,
+            set_filter: evaluation::SetFilter::all(),
+            eval_engine: "cpu",
+            audit: 0.0,
+            audit_engine: "cpu",
+            repro: false,
+            gpu_compare: GpuCompare::Epsilon,
+            gpu_memory: GpuMemory::Default,
+            gpu_kernel: GpuKernel::Item,
+            top_k: 0,
+            memory_limit: 0,
+            timeout: parse_timeout( args.flag_timeout ),
+            cancellation: None,
+            signature: None,
+        };
+
+        // The sets of the file are served as the default corpus.
+        let mut corpora = vec![ server::CorpusSpec { name: "default".to_string(), file: input_file.clone(), floats: args.flag_floats } ];
+        for spec in &args.flag_corpus
+        {
+            let mut corpus = match server::parse_corpus( spec, args.flag_floats )
+            {
+                Some( corpus ) => corpus,
+                None => panic!( "Invalid corpus: {}", spec ),
+            };
+            corpus.file = remote::resolve( &corpus.file );
+            corpora.push( corpus );
+        }
+        let protocol = match args.flag_protocol.as_str()
+        {
+            "text" => Protocol::Text,
+            "resp" if cfg!( feature="resp" ) => Protocol::Resp,
+            "resp" =>
+            {
+                println!( "The resp protocol requires building with the resp feature." );
+                std::process::exit( 1 );
+            },
+            _ => panic!( "Unknown protocol: {}", args.flag_protocol ),
+        };
+        if args.flag_workers > 0 && workers::shared_index().is_none()
+        {
+            // The workers are started with the same arguments.
+            if corpora.len() > 1
+            {
+                println!( "Worker processes serve a single corpus." );
+                std::process::exit( 1 );
+            }
+            workers::serve_with_workers( &corpora[ 0 ], &args.flag_address, args.flag_workers );
+        }
+        else
+        {
+            server::serve( &params, &corpora, &args.flag_address, protocol );
+        }
+    }
+    else if args.cmd_report && args.cmd_merge
+    {
+        // The JSON results of the test runs are combined by the environment.
+        results::merge( &args.arg_output, &args.arg_files );
+    }
+    else if args.cmd_report && args.cmd_compare
+    {
+        // The durations of the other results are compared to the results of the file.
+        results::compare( &args.arg_file, &args.arg_other );
+    }
+    else if args.cmd_bitmap && ( args.cmd_and || args.cmd_or )
+    {
+        // The last file is the output as with the other commands writing a file.
+        let ( output, inputs ) = args.arg_files.split_last().expect( "The output file is missing." );
+        if inputs.is_empty()
+        {
+            println!( "At least two bitmap files are needed." );
+            std::process::exit( 1 );
+        }
+
+        // The bitmaps of each test set are combined in the order of the files.
+        let mut bitmaps = bitmap::Bitmaps::read( &args.arg_file );
+        for file in inputs
+        {
+            let other = bitmap::Bitmaps::read( file );
+            if args.cmd_and
+            {
+                bitmaps.and( &other );
+            }
+            else
+            {
+                bitmaps.or( &other );
+            }
+        }
+        bitmaps.write( output );
+    }
+    else if args.cmd_bitmap && args.cmd_not
+    {
+        let mut bitmaps = bitmap::Bitmaps::read( &args.arg_file );
+        bitmaps.not();
+        bitmaps.write( &args.arg_output );
+    }
+    else if args.cmd_bitmap && args.cmd_count
+    {
+        let bitmaps = bitmap::Bitmaps::read( &args.arg_file );
+        for ( i, count ) in bitmaps.counts().into_iter().enumerate()
+        {
+            println!( "Test set {}: {} of {} evaluated sets matched", i, count, bitmaps.evaluated_count() );
+        }
+    }
+    else if args.cmd_capabilities
+    {
+        // Wrapper scripts check the supported features before running the evaluation.
+        capabilities::print();
+    }
+    else if args.cmd_selftest
+    {
+        if !selftest::run_selftest()
+        {
+            println!( "Self-test failed." );
+            std::process::exit( 1 );
+        }
+    }
     else
     {
         println!( "{}", "No tests selected." );
@@ -154,3 +1362,302 @@ fn main()
         duration.subsec_nanos() / 1000
     );
 }
+
+/// Parses the preload mode given on the command line.
+fn parse_preload_mode( mode: &str ) -> PreloadMode
+{
+    match mode
+    {
+        "none" => PreloadMode::Disabled,
+        "full" => PreloadMode::Full,
+        _ => panic!( "Unknown preload mode: {}", mode ),
+    }
+}
+
+/// Parses a comma separated list of values given on the command line.
+fn parse_values<T: traits::FromI32>( values: &str ) -> Vec<T>
+{
+    values.split( ',' )
+        .filter( |v| !v.trim().is_empty() )
+        .map( |v| match v.trim().parse::<i32>()
+        {
+            Ok( v ) => T::from_i32( &v ),
+            Err( _ ) => panic!( "Invalid value: {}", v ),
+        } )
+        .collect()
+}
+
+/// Parses the comma separated inclusive ranges given on the command line.
+fn parse_ranges( ranges: &str ) -> Vec<( i32, i32 )>
+{
+    ranges.split( ',' )
+        .map( |r| {
+            // The separator is searched after the first character to allow a negative lower bound.
+            let separator = r.char_indices().skip( 1 ).find( |&( _, c )| c == '-' ).map( |( i, _ )| i );
+            let bounds = match separator
+            {
+                Some( i ) => ( r[ ..i ].trim().parse::<i32>(), r[ i + 1.. ].trim().parse::<i32>() ),
+                None => ( r.trim().parse::<i32>(), r.trim().parse::<i32>() ),
+            };
+            match bounds
+            {
+                ( Ok( lo ), Ok( hi ) ) if lo <= hi => ( lo, hi ),
+                _ => panic!( "Invalid range: {}", r ),
+            }
+        } )
+        .collect()
+}
+
+/// Parses a range of set indexes given as "<first>..<end>" where the end is exclusive.
+fn parse_index_range( range: &str ) -> ( usize, usize )
+{
+    let mut bounds = range.splitn( 2, ".." );
+    match ( bounds.next().map( |b| b.trim().parse::<usize>() ), bounds.next().map( |b| b.trim().parse::<usize>() ) )
+    {
+        ( Some( Ok( first ) ), Some( Ok( end ) ) ) if first <= end => ( first, end ),
+        _ => panic!( "Invalid range of sets: {}", range ),
+    }
+}
+
+/// Parses the operation given on the command line.
+fn parse_operation(
+    operation: &str,
+    threshold: f32,
+) -> Operation
+{
+    match operation
+    {
+        "any" => Operation::Any,
+        "weighted-overlap" => Operation::WeightedOverlap( threshold ),
+        _ => panic!( "Unknown operation: {}", operation ),
+    }
+}
+
+/// Parses the encoding given on the command line.
+fn parse_encoding( name: &str ) -> Encoding
+{
+    match encoding::parse( name )
+    {
+        Some( encoding ) => encoding,
+        None => panic!( "Unknown encoding: {}", name ),
+    }
+}
+
+/// Parses the strategy given on the command line.
+fn parse_strategy( name: &str ) -> Strategy
+{
+    match intersect::parse( name )
+    {
+        Some( strategy ) => strategy,
+        None => panic!( "Unknown strategy: {}", name ),
+    }
+}
+
+/// Deserializes a count given with an optional suffix such as 10k or 1.5M.
+fn deserialize_count<'de, D, T>( deserializer: D ) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::convert::TryFrom<u64>,
+{
+    let text = String::deserialize( deserializer )?;
+    let count = utility::parse_count( &text ).map_err( serde::de::Error::custom )?;
+    return T::try_from( count ).map_err( |_| serde::de::Error::custom( format!( "{} is too large.", text ) ) );
+}
+
+/// Deserializes a comma separated list of counts such as 10,100,1k.
+fn deserialize_counts<'de, D, T>( deserializer: D ) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::convert::TryFrom<u64>,
+{
+    let text = String::deserialize( deserializer )?;
+    let mut counts = Vec::new();
+    for count in text.split( ',' ).filter( |c| !c.trim().is_empty() )
+    {
+        let parsed = utility::parse_count( count ).map_err( serde::de::Error::custom )?;
+        counts.push( T::try_from( parsed ).map_err( |_| serde::de::Error::custom( format!( "{} is too large.", count ) ) )? );
+    }
+    return Ok( counts );
+}
+
+/// Deserializes a size in bytes given in MB or with a unit such as 512k or 2GiB.
+fn deserialize_megabytes<'de, D, T>( deserializer: D ) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::convert::TryFrom<u64>,
+{
+    let text = String::deserialize( deserializer )?;
+    let size = utility::parse_size( &text, 1024 * 1024 ).map_err( serde::de::Error::custom )?;
+    return T::try_from( size ).map_err( |_| serde::de::Error::custom( format!( "{} is too large.", text ) ) );
+}
+
+/// Deserializes a size in bytes given in GB or with a unit such as 500MB.
+fn deserialize_gigabytes<'de, D, T>( deserializer: D ) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::convert::TryFrom<u64>,
+{
+    let text = String::deserialize( deserializer )?;
+    let size = utility::parse_size( &text, 1024 * 1024 * 1024 ).map_err( serde::de::Error::custom )?;
+    return T::try_from( size ).map_err( |_| serde::de::Error::custom( format!( "{} is too large.", text ) ) );
+}
+
+/// Parses the key the sets are sorted by.
+fn parse_sort_key( name: &str ) -> SortKey
+{
+    match name
+    {
+        "size" => SortKey::Size,
+        "minvalue" => SortKey::MinValue,
+        _ => panic!( "Unknown sort key: {}", name ),
+    }
+}
+
+/// Parses the comparison of the float values on GPU.
+fn parse_gpu_compare( name: &str ) -> GpuCompare
+{
+    match name
+    {
+        "exact" => GpuCompare::Exact,
+        "epsilon" => GpuCompare::Epsilon,
+        "ulp" => GpuCompare::Ulp,
+        _ => panic!( "Unknown GPU comparison: {}", name ),
+    }
+}
+
+/// Parses the host memory of the GPU buffers.
+fn parse_gpu_memory( name: &str ) -> GpuMemory
+{
+    match name
+    {
+        "default" => GpuMemory::Default,
+        "pinned" => GpuMemory::Pinned,
+        "zerocopy" => GpuMemory::ZeroCopy,
+        _ => panic!( "Unknown GPU memory: {}", name ),
+    }
+}
+
+/// Parses the layout of the GPU kernel.
+fn parse_gpu_kernel( name: &str ) -> GpuKernel
+{
+    match name
+    {
+        "item" => GpuKernel::Item,
+        "wg" => GpuKernel::WorkGroup,
+        _ => panic!( "Unknown GPU kernel: {}", name ),
+    }
+}
+
+/// Writes the bitmaps of the sets matched by each test set.
+/// Exits with an error if the results of the sets were not recorded.
+fn write_bitmaps(
+    output: &str,
+    file: &String,
+    floats: bool,
+    results: &Vec<evaluation::EvaluationResult>,
+)
+{
+    // The sets matched before the timeout are not a complete bitmap.
+    if results.iter().any( |r| r.timed_out )
+    {
+        eprintln!( "The evaluation timed out before all the sets were evaluated. No bitmap was written." );
+        std::process::exit( 1 );
+    }
+    let set_count = if floats { bitmap::count_sets::<f32>( file ) } else { bitmap::count_sets::<i32>( file ) };
+    let mut bitmaps = bitmap::Bitmaps::new( set_count, signature::digest( file ) );
+    for result in results
+    {
+        match result.set_results
+        {
+            Some( ref matches ) => bitmaps.push_matches( matches ),
+            None =>
+            {
+                eprintln!( "The bitmap is only available for sets of values matched with any value of the test set without exclusions." );
+                std::process::exit( 1 );
+            },
+        }
+    }
+    bitmaps.write( output );
+    println!( "Wrote the bitmaps of {} sets for {} test sets to {}", set_count, results.len(), output );
+}
+
+/// Gets the files read by the command of the arguments.
+/// The files rewritten by the command are not inputs.
+/// The key files are secret and their hashes are not recorded.
+fn input_files( args: &Args ) -> Vec<String>
+{
+    let mut files = Vec::new();
+    if !args.arg_file.is_empty() && !( args.cmd_new || args.cmd_edit || args.cmd_compact )
+    {
+        files.push( args.arg_file.clone() );
+    }
+    if args.cmd_bitmap
+    {
+        // The last of the files of and / or is the output.
+        files.extend( args.arg_files.iter().take( args.arg_files.len().saturating_sub( 1 ) ).cloned() );
+    }
+    else
+    {
+        files.extend( args.arg_files.iter().cloned() );
+    }
+    if args.cmd_import && args.cmd_sqlite
+    {
+        files.push( args.arg_db.clone() );
+    }
+    files.extend( args.flag_corpus.iter().filter_map( |c| server::parse_corpus( c, args.flag_floats ) ).map( |c| c.file ) );
+    if !args.flag_from_runner.is_empty()
+    {
+        files.extend( runner::Runner::files( &args.flag_from_runner ) );
+    }
+    files.extend( [ &args.arg_other, &args.arg_csv, &args.flag_query_file, &args.flag_exclude_file,
+            &args.flag_repro, &args.flag_bundle, &args.flag_scenarios ].iter()
+            .filter( |f| !f.is_empty() )
+            .map( |f| f.to_string() ) );
+    return files;
+}
+
+/// Converts the timeout in milliseconds given on the command line, zero disables the timeout.
+fn parse_timeout( milliseconds: u64 ) -> Option<std::time::Duration>
+{
+    if milliseconds == 0
+    {
+        return None;
+    }
+    return Some( std::time::Duration::from_millis( milliseconds ) );
+}
+
+/// Exits with an error if the number of values cannot be drawn from the range.
+fn check_value_range(
+    min_value: i32,
+    max_value: i32,
+    values: i32,
+    allow_duplicates: bool,
+)
+{
+    if let Err( message ) = utility::Sampler::new( min_value, max_value, allow_duplicates ).check( values )
+    {
+        println!( "{}", message );
+        std::process::exit( 1 );
+    }
+}
+
+/// Saves the random test sets into the query file if requested for replaying them with --query-file.
+fn save_query<T>(
+    file: &String,
+    test_sets: &[&[T]],
+)
+where
+    T: ro_scalar_set::Value,
+{
+    if !file.is_empty()
+    {
+        query::save( file, test_sets );
+        println!( "Saved {} test sets to {}.", test_sets.len(), file );
+    }
+}
+
+/// Parses the comma separated tags of the scenarios.
+fn parse_tags( tags: &String ) -> Vec<String>
+{
+    tags.split( ',' ).map( |t| t.trim() ).filter( |t| !t.is_empty() ).map( |t| t.to_string() ).collect()
+}
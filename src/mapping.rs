@@ -0,0 +1,183 @@
+extern crate memmap;
+extern crate std;
+
+use std::path::Path;
+use std::slice;
+
+use memmap::{Mmap, Protection};
+
+use encryption;
+
+/// Read-only memory mapping of a set file.
+/// All platform specific details of the mapping are kept behind this type.
+/// An encrypted file is decrypted into memory and its mapping is kept for verifying the stored contents.
+pub struct MappedFile
+{
+    mmap: Option<Mmap>,
+    /// Contents of the decrypted file in words for aligning the values and their length in bytes.
+    decrypted: Option<( Vec<u64>, usize )>,
+}
+
+impl MappedFile
+{
+    /// Maps the given file into memory for reading.
+    /// An encrypted file is decrypted with the key file given in SSE_DECRYPT.
+    pub fn open<P: AsRef<Path>>( path: P ) -> std::io::Result<MappedFile>
+    {
+        let mapped = MappedFile::open_stored( path )?;
+        if !encryption::is_encrypted( mapped.as_slice() )
+        {
+            return Ok( mapped );
+        }
+        let decrypted = encryption::decrypt( mapped.as_slice() )
+                .map_err( |e| std::io::Error::new( std::io::ErrorKind::InvalidData, e ) )?;
+        return Ok( MappedFile { mmap: mapped.mmap, decrypted: Some( decrypted ) } );
+    }
+
+    /// Maps the given file into memory for reading the contents as stored without decrypting them.
+    pub fn open_stored<P: AsRef<Path>>( path: P ) -> std::io::Result<MappedFile>
+    {
+        let file = std::fs::File::open( path )?;
+
+        // Windows refuses to create a mapping for an empty file.
+        if file.metadata()?.len() == 0
+        {
+            return Ok( MappedFile { mmap: None, decrypted: None } );
+        }
+
+        // The mapping keeps its own reference to the file.
+        // The file handle is closed when we return which allows the file to be
+        // renamed or replaced on Windows once the mapping has been dropped.
+        let mmap = Mmap::open( &file, Protection::Read )?;
+        return Ok( MappedFile { mmap: Some( mmap ), decrypted: None } );
+    }
+
+    /// Checks whether the contents were decrypted into memory.
+    pub fn is_decrypted( &self ) -> bool
+    {
+        return self.decrypted.is_some();
+    }
+
+    /// Gets the size of the mapping in bytes.
+    pub fn len( &self ) -> usize
+    {
+        match ( &self.mmap, &self.decrypted )
+        {
+            ( _, &Some( ( _, len ) ) ) => len,
+            ( &Some( ref mmap ), &None ) => mmap.len(),
+            ( &None, &None ) => 0,
+        }
+    }
+
+    /// Views the contents of the file as stored. The contents of an encrypted file are not decrypted.
+    pub fn as_stored_slice( &self ) -> &[u8]
+    {
+        match self.mmap
+        {
+            Some( ref mmap ) => unsafe { slice::from_raw_parts( mmap.ptr(), mmap.len() ) },
+            None => &[],
+        }
+    }
+
+    /// Gets the fraction of the pages of the file held in the page cache.
+    /// Returns None when the residency cannot be queried on the platform.
+    #[cfg(unix)]
+    pub fn resident_fraction( &self ) -> Option<f64>
+    {
+        extern "C"
+        {
+            fn mincore( addr: *mut u8, length: usize, vec: *mut u8 ) -> i32;
+            fn getpagesize() -> i32;
+        }
+        // The decrypted contents are always in memory.
+        let mmap = match ( &self.mmap, &self.decrypted )
+        {
+            ( &Some( ref mmap ), &None ) => mmap,
+            _ => return Some( 1.0 ),
+        };
+
+        // The mapping starts at a page boundary. Each page gets a byte with the lowest bit set if it is resident.
+        let page_size = unsafe { getpagesize() } as usize;
+        let pages = ( mmap.len() + page_size - 1 ) / page_size;
+        let mut residency: Vec<u8> = vec![ 0; pages ];
+        if unsafe { mincore( mmap.ptr() as *mut u8, mmap.len(), residency.as_mut_ptr() ) } != 0
+        {
+            return None;
+        }
+        let resident = residency.iter().filter( |r| **r & 1 != 0 ).count();
+        return Some( resident as f64 / pages as f64 );
+    }
+
+    /// The residency of the pages is only queried on Unix.
+    #[cfg(not(unix))]
+    pub fn resident_fraction( &self ) -> Option<f64>
+    {
+        return None;
+    }
+
+    /// Views the mapped file as a slice of values.
+    pub fn as_slice<T>( &self ) -> &[T]
+    {
+        match ( &self.mmap, &self.decrypted )
+        {
+            ( _, &Some( ( ref words, len ) ) ) =>
+            {
+                let value_count = len / std::mem::size_of::<T>();
+                unsafe { slice::from_raw_parts( words.as_ptr() as *const T, value_count ) }
+            },
+            ( &Some( ref mmap ), &None ) =>
+            {
+                let value_count = mmap.len() / std::mem::size_of::<T>();
+                unsafe { slice::from_raw_parts( mmap.ptr() as *const T, value_count ) }
+            },
+            ( &None, &None ) => &[],
+        }
+    }
+}
+
+/// Writable memory mapping of a preallocated output file.
+pub struct WritableMappedFile
+{
+    mmap: Option<Mmap>,
+}
+
+impl WritableMappedFile
+{
+    /// Creates the file with the given size in bytes and maps it into memory for writing.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        len: usize,
+    ) -> std::io::Result<WritableMappedFile>
+    {
+        let file = std::fs::OpenOptions::new().read( true ).write( true ).create( true ).truncate( true ).open( path )?;
+        file.set_len( len as u64 )?;
+
+        // Windows refuses to create a mapping for an empty file.
+        if len == 0
+        {
+            return Ok( WritableMappedFile { mmap: None } );
+        }
+        let mmap = Mmap::open( &file, Protection::ReadWrite )?;
+        return Ok( WritableMappedFile { mmap: Some( mmap ) } );
+    }
+
+    /// Views the mapped file as bytes for writing.
+    pub fn as_mut_slice( &mut self ) -> &mut [u8]
+    {
+        match self.mmap
+        {
+            Some( ref mut mmap ) => unsafe { mmap.as_mut_slice() },
+            None => &mut [],
+        }
+    }
+
+    /// Writes the modified pages back to the file.
+    pub fn flush( &mut self ) -> std::io::Result<()>
+    {
+        match self.mmap
+        {
+            Some( ref mut mmap ) => mmap.flush(),
+            None => Ok( () ),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+extern crate rayon;
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::{BufWriter, Seek, SeekFrom};
+use std::io::prelude::*;
+
+use self::rayon::prelude::*;
+
+use encoding;
+use enumerations::*;
+use mapping::MappedFile;
+use traits::*;
+
+/// Progress of the matrix written to disk.
+struct Checkpoint
+{
+    /// Number of sets in the input.
+    set_count: usize,
+    /// Number of rows completed.
+    rows: usize,
+    /// Length of the output holding the completed rows in bytes.
+    bytes: u64,
+}
+
+/// Computes which pairs of the sets have any common value.
+/// Each row of the output lists the sets after the set of the row overlapping with it as "<row>: <set>,<set>,...".
+/// The rows are computed in parallel in blocks of checkpoint_rows rows. Each completed block is written to the output
+/// and recorded in a checkpoint next to it so that an interrupted computation resumes from the last block.
+/// The blocks are made smaller when the rows of a block could exceed the memory limit in bytes. Zero disables the limit.
+pub fn matrix<T>(
+    input: &String,
+    output: &String,
+    checkpoint_rows: usize,
+    memory_limit: usize,
+)
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value,
+{
+    let file = MappedFile::open( input ).expect( "Failed to map the file" );
+    if encoding::detect( file.as_slice() ) != Encoding::Raw
+    {
+        panic!( "The matrix is only computed for raw sets." );
+    }
+    let mut buffer: &[T] = file.as_slice();
+    let mut sets: Vec<ro_scalar_set::RoScalarSet<T>> = Vec::new();
+    while let Ok( ( set, remaining ) ) = ro_scalar_set::RoScalarSet::attach( buffer )
+    {
+        sets.push( set );
+        buffer = remaining;
+    }
+
+    // Continue from the last checkpoint. Rows written after it are discarded.
+    let checkpoint_file = get_checkpoint_file_name( output );
+    let checkpoint = read_checkpoint( &checkpoint_file );
+    let mut writer = match checkpoint
+    {
+        Some( ref checkpoint ) =>
+        {
+            if checkpoint.set_count != sets.len()
+            {
+                panic!( "The checkpoint of {} was computed for {} sets instead of {}.", output, checkpoint.set_count, sets.len() );
+            }
+            println!( "Resuming from row {} of {}.", checkpoint.rows, sets.len() );
+            let mut file = std::fs::OpenOptions::new().write( true ).open( output ).expect( "Failed to open the file." );
+            file.set_len( checkpoint.bytes ).expect( "Failed to truncate the file." );
+            file.seek( SeekFrom::End( 0 ) ).expect( "Failed to seek the file." );
+            file
+        },
+        None => std::fs::File::create( output ).expect( "Failed to open the file." ),
+    };
+    let mut first = checkpoint.map( |c| c.rows ).unwrap_or( 0 );
+
+    // A row holds at most the index of every set.
+    let mut block = std::cmp::max( checkpoint_rows, 1 );
+    if memory_limit > 0
+    {
+        let row_size = std::cmp::max( 1, sets.len() * std::mem::size_of::<usize>() );
+        block = std::cmp::max( 1, std::cmp::min( block, memory_limit / row_size ) );
+    }
+    while first < sets.len()
+    {
+        let last = std::cmp::min( first + block, sets.len() );
+        let rows: Vec<Vec<usize>> = ( first..last ).into_par_iter()
+                .map( |row| ( row + 1..sets.len() ).filter( |column| sets[ row ].any( &sets[ *column ] ) ).collect() )
+                .collect();
+        {
+            let mut block_writer = BufWriter::new( &mut writer );
+            for ( row, columns ) in ( first..last ).zip( rows )
+            {
+                let columns: Vec<String> = columns.iter().map( |c| c.to_string() ).collect();
+                writeln!( block_writer, "{}: {}", row, columns.join( "," ) ).expect( "Writing the matrix failed." );
+            }
+        }
+
+        // The rows are on the disk before the checkpoint refers to them.
+        writer.sync_data().expect( "Writing the matrix failed." );
+        let bytes = writer.seek( SeekFrom::Current( 0 ) ).expect( "Failed to seek the file." );
+        write_checkpoint( &checkpoint_file, &Checkpoint { set_count: sets.len(), rows: last, bytes: bytes } );
+        first = last;
+        println!( "Completed {} of {} rows.", last, sets.len() );
+    }
+    std::fs::remove_file( &checkpoint_file ).ok();
+}
+
+/// Gets the name of the checkpoint of the output.
+fn get_checkpoint_file_name( output: &String ) -> String
+{
+    return format!( "{}.checkpoint", output );
+}
+
+/// Reads the checkpoint written as "<set count> <rows> <bytes>".
+fn read_checkpoint( file: &String ) -> Option<Checkpoint>
+{
+    let text = std::fs::read_to_string( file ).ok()?;
+    let fields: Vec<u64> = text.split_whitespace().filter_map( |f| f.parse::<u64>().ok() ).collect();
+    if fields.len() != 3
+    {
+        panic!( "Invalid checkpoint: {}", file );
+    }
+    return Some( Checkpoint { set_count: fields[ 0 ] as usize, rows: fields[ 1 ] as usize, bytes: fields[ 2 ] } );
+}
+
+/// Replaces the checkpoint. The new checkpoint is renamed over the old one to never leave a partial checkpoint.
+fn write_checkpoint(
+    file: &String,
+    checkpoint: &Checkpoint,
+)
+{
+    let partial = format!( "{}.partial", file );
+    std::fs::write( &partial, format!( "{} {} {}\n", checkpoint.set_count, checkpoint.rows, checkpoint.bytes ) )
+            .expect( "Writing the checkpoint failed." );
+    std::fs::rename( &partial, file ).expect( "Writing the checkpoint failed." );
+}
@@ -0,0 +1,70 @@
+extern crate std;
+
+/// Time spent in a single phase of an evaluation.
+#[derive(Clone)]
+pub struct Phase
+{
+    pub name: &'static str,
+    pub duration: std::time::Duration,
+    /// Bytes moved in the phase. Zero when the phase does not move data.
+    pub bytes: u64,
+}
+
+/// Measurements of the phases of an evaluation reported the same way by the CPU and GPU evaluations.
+/// The match count and the duration of the whole evaluation are the results of the evaluation and not repeated here.
+#[derive(Clone)]
+pub struct Metrics
+{
+    /// Phases of the evaluation in the order they were run.
+    pub phases: Vec<Phase>,
+    /// Bytes of the sets the evaluation read.
+    pub bytes_read: u64,
+    pub sets_evaluated: u64,
+}
+
+impl Metrics
+{
+    /// Measurements of an evaluation with a single evaluation phase.
+    pub fn evaluation(
+        duration: std::time::Duration,
+        bytes_read: u64,
+        sets_evaluated: u64,
+    ) -> Metrics
+    {
+        return Metrics {
+            phases: vec![ Phase { name: "evaluate", duration: duration, bytes: bytes_read } ],
+            bytes_read: bytes_read,
+            sets_evaluated: sets_evaluated,
+        };
+    }
+
+    /// Gets the phase with the name.
+    pub fn phase(
+        &self,
+        name: &str,
+    ) -> Option<&Phase>
+    {
+        return self.phases.iter().find( |p| p.name == name );
+    }
+
+    /// Gets the bytes per second moved in the phase.
+    pub fn throughput(
+        &self,
+        name: &str,
+    ) -> Option<f64>
+    {
+        let phase = self.phase( name )?;
+        let seconds = phase.duration.as_secs() as f64 + phase.duration.subsec_nanos() as f64 / 1e9;
+        return Some( if seconds > 0.0 { phase.bytes as f64 / seconds } else { 0.0 } );
+    }
+
+    /// Prints the duration of each phase.
+    pub fn print( &self )
+    {
+        println!( "Evaluated {} sets of {} MB", self.sets_evaluated, self.bytes_read / ( 1024 * 1024 ) );
+        for phase in &self.phases
+        {
+            println!( "{}: {}.{:06} s", phase.name, phase.duration.as_secs(), phase.duration.subsec_nanos() / 1000 );
+        }
+    }
+}
@@ -0,0 +1,81 @@
+extern crate rayon;
+extern crate std;
+
+use encoding;
+use enumerations::*;
+use evaluation::*;
+use http;
+use intersect;
+use mapping::MappedFile;
+use timestamp;
+use tuple;
+use uuid;
+
+/// Prints how the sets will be evaluated with the parameters without evaluating them.
+/// The memory estimate covers the sets held in memory and the test sets.
+pub fn print_plan(
+    params: &EvaluationParams,
+    query_count: usize,
+    value_size: usize,
+)
+{
+    let ( kind, file_size ) = if http::is_http( params.file )
+    {
+        ( "streamed from a web server".to_string(), 0 )
+    }
+    else
+    {
+        let file = MappedFile::open( params.file ).expect( "Failed to map the file" );
+        let data = file.as_slice();
+        let kind = if tuple::is_tuple_file( data )
+        {
+            "tuples".to_string()
+        }
+        else if uuid::is_uuid_file( data )
+        {
+            "UUIDs".to_string()
+        }
+        else if timestamp::is_timestamp_file( data )
+        {
+            "timestamps".to_string()
+        }
+        else
+        {
+            format!( "{} encoded values", encoding::name( encoding::detect( data ) ) )
+        };
+        ( kind, data.len() )
+    };
+
+    // Large test sets are evaluated in blocks.
+    let values_in_set = std::cmp::max( 0, params.values_in_set ) as usize;
+    let chunking = if params.query_block > 0 && values_in_set > params.query_block
+    {
+        format!( "{} blocks of {} values", ( values_in_set + params.query_block - 1 ) / params.query_block, params.query_block )
+    }
+    else
+    {
+        "whole test set at once".to_string()
+    };
+    let preload = match params.preload
+    {
+        PreloadMode::Disabled => "none, the sets are read directly from the file",
+        PreloadMode::Full => "full, the sets are copied into memory",
+    };
+    let threads = if params.max_threads > 0 { params.max_threads } else { rayon::current_num_threads() };
+    let sets_memory = match params.preload
+    {
+        PreloadMode::Full => file_size + params.cache_size,
+        _ => params.cache_size,
+    };
+    let memory = sets_memory + query_count * values_in_set * value_size;
+
+    println!( "Evaluation plan:" );
+    println!( "  Sets:       {} in {} ({} MB)", kind, params.file, file_size / ( 1024 * 1024 ) );
+    println!( "  Engine:     {}", params.eval_engine );
+    println!( "  Strategy:   {}", intersect::name( params.strategy ) );
+    println!( "  Chunking:   {}", chunking );
+    println!( "  Preload:    {}", preload );
+    println!( "  Threads:    {}", threads );
+    println!( "  Queries:    {} of {} values", query_count, values_in_set );
+    println!( "  Memory:     about {} MB", memory / ( 1024 * 1024 ) );
+}
@@ -0,0 +1,102 @@
+extern crate std;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+/// Size of a memory page touched by the prefetcher.
+const PAGE_SIZE: usize = 4096;
+
+/// Touches the pages of a memory mapped buffer ahead of the evaluation
+/// so that the evaluation threads do not stall on page faults.
+pub struct Prefetcher
+{
+    progress: Arc<AtomicUsize>,
+    done: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Prefetcher
+{
+    /// Starts prefetching the given buffer.
+    /// The prefetcher stays at most "distance" bytes ahead of the reported progress.
+    /// The buffer must outlive the prefetcher.
+    pub fn start<T>(
+        data: &[T],
+        distance: usize,
+    ) -> Prefetcher
+    {
+        let progress = Arc::new( AtomicUsize::new( 0 ) );
+        let done = Arc::new( AtomicBool::new( false ) );
+
+        // The address is passed as an integer as the thread cannot borrow the buffer.
+        // The thread is always joined before the prefetcher is dropped.
+        let address = data.as_ptr() as usize;
+        let length = data.len() * std::mem::size_of::<T>();
+        let thread = {
+            let progress = progress.clone();
+            let done = done.clone();
+            thread::spawn( move || prefetch( address, length, distance, &progress, &done ) )
+        };
+        return Prefetcher { progress: progress, done: done, thread: Some( thread ) };
+    }
+
+    /// Reports that the evaluation has consumed the given number of bytes.
+    /// Wakes up the background thread if it is waiting for the evaluation to catch up.
+    pub fn advance(
+        &self,
+        bytes: usize,
+    )
+    {
+        self.progress.fetch_add( bytes, Ordering::Release );
+        if let Some( ref thread ) = self.thread
+        {
+            thread.thread().unpark();
+        }
+    }
+
+    /// Stops the prefetching and waits for the background thread to exit.
+    pub fn finish( &mut self )
+    {
+        self.done.store( true, Ordering::Release );
+        if let Some( thread ) = self.thread.take()
+        {
+            thread.thread().unpark();
+            thread.join().expect( "Prefetch thread failed." );
+        }
+    }
+}
+
+impl Drop for Prefetcher
+{
+    fn drop( &mut self )
+    {
+        self.finish();
+    }
+}
+
+/// Reads one byte from each page of the buffer while staying within the prefetch distance.
+fn prefetch(
+    address: usize,
+    length: usize,
+    distance: usize,
+    progress: &AtomicUsize,
+    done: &AtomicBool,
+)
+{
+    let mut offset = 0;
+    while offset < length && !done.load( Ordering::Acquire )
+    {
+        // Wait for the evaluation to catch up. The thread is woken up when the evaluation advances or finishes.
+        // A wake-up given before parking is not lost as it makes the next park return immediately.
+        if offset > progress.load( Ordering::Acquire ) + distance
+        {
+            thread::park();
+            continue;
+        }
+
+        // The volatile read prevents the compiler from optimizing the access away.
+        unsafe { std::ptr::read_volatile( ( address + offset ) as *const u8 ); }
+        offset += PAGE_SIZE;
+    }
+}
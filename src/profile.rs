@@ -0,0 +1,235 @@
+extern crate std;
+extern crate toml;
+
+use std::collections::HashMap;
+
+/// Option selecting the profile.
+const PROFILE_OPTION: &'static str = "--profile";
+
+/// Prefix of the environment variables setting the options.
+const ENVIRONMENT_PREFIX: &'static str = "SSE_";
+
+/// Config file of the user.
+#[derive(Deserialize)]
+struct Config
+{
+    /// Options of each profile by the name of the profile.
+    #[serde(default)]
+    profile: HashMap<String, HashMap<String, toml::Value>>,
+}
+
+/// Applies the options set in the environment and in the profile selected with --profile to the arguments.
+/// An option is taken from the command line, then from the environment variable SSE_<OPTION> such as SSE_THREADS
+/// for --threads or SSE_CACHE_SIZE for --cache-size, then from the profile and finally from the defaults of the usage.
+/// The profile itself can be selected with SSE_PROFILE. Only the options of the usage line of the command
+/// matching the arguments are applied.
+pub fn apply(
+    usage: &str,
+    arguments: Vec<String>,
+) -> Vec<String>
+{
+    let ( mut arguments, name ) = take_profile( arguments );
+    let accepted = get_command_options( usage, &arguments );
+    let mut options: Vec<( String, String )> = Vec::new();
+    for option in accepted.keys()
+    {
+        if let Ok( value ) = std::env::var( get_environment_variable( option ) )
+        {
+            options.push( ( option[ 2.. ].to_string(), value ) );
+        }
+    }
+    options.sort();
+    if let Some( name ) = name.or_else( || std::env::var( get_environment_variable( PROFILE_OPTION ) ).ok() )
+    {
+        options.extend( read_profile_from_config( &name ) );
+    }
+
+    // The options already given take precedence over the later ones.
+    for ( option, value ) in options
+    {
+        let flag = format!( "--{}", option );
+        let takes_value = match accepted.get( &flag )
+        {
+            Some( takes_value ) => *takes_value,
+            None => continue,
+        };
+        if is_given( &arguments, &flag )
+        {
+            continue;
+        }
+        if takes_value
+        {
+            arguments.push( format!( "{}={}", flag, value ) );
+        }
+        else if is_true( &value )
+        {
+            arguments.push( flag );
+        }
+    }
+    return arguments;
+}
+
+/// Reads the options of the profile from the config file of the user.
+/// The profiles are read from the tables [profile.<name>] of ~/.config/scalar_set_eval/config.toml with
+/// the options as keys such as threads = 8 or engine = "gpu". Arrays are given as comma separated lists and
+/// options set to true as flags.
+fn read_profile_from_config( name: &str ) -> Vec<( String, String )>
+{
+    let file = get_config_file_name();
+    let text = match std::fs::read_to_string( &file )
+    {
+        Ok( text ) => text,
+        Err( e ) => panic!( "Failed to read the profiles from {}: {}", file.display(), e ),
+    };
+    return match read_profile( &text, name )
+    {
+        Some( options ) => options,
+        None => panic!( "Profile {} not found from {}.", name, file.display() ),
+    };
+}
+
+/// Gets the environment variable of the option such as SSE_CACHE_SIZE for --cache-size.
+fn get_environment_variable( option: &str ) -> String
+{
+    return format!( "{}{}", ENVIRONMENT_PREFIX, option.trim_start_matches( '-' ).replace( '-', "_" ).to_uppercase() );
+}
+
+/// Checks whether the value of a flag enables it.
+fn is_true( value: &str ) -> bool
+{
+    return match value.trim().to_lowercase().as_str()
+    {
+        "true" | "1" | "yes" | "on" => true,
+        _ => false,
+    };
+}
+
+/// Removes the profile option from the arguments and returns the name of the profile.
+fn take_profile( arguments: Vec<String> ) -> ( Vec<String>, Option<String> )
+{
+    let mut remaining = Vec::with_capacity( arguments.len() );
+    let mut name = None;
+    let mut arguments = arguments.into_iter();
+    while let Some( argument ) = arguments.next()
+    {
+        if argument == PROFILE_OPTION
+        {
+            name = Some( arguments.next().expect( "The name of the profile is missing." ) );
+        }
+        else if argument.starts_with( &format!( "{}=", PROFILE_OPTION ) )
+        {
+            name = Some( argument[ PROFILE_OPTION.len() + 1.. ].to_string() );
+        }
+        else
+        {
+            remaining.push( argument );
+        }
+    }
+    return ( remaining, name );
+}
+
+/// Gets the config file of the user.
+fn get_config_file_name() -> std::path::PathBuf
+{
+    let base = match std::env::var_os( "XDG_CONFIG_HOME" )
+    {
+        Some( config ) => std::path::PathBuf::from( config ),
+        None => std::path::PathBuf::from( std::env::var_os( "HOME" ).expect( "The home directory is not set." ) ).join( ".config" ),
+    };
+    return base.join( "scalar_set_eval" ).join( "config.toml" );
+}
+
+/// Reads the options of the profile from the text of the config file.
+fn read_profile(
+    text: &str,
+    name: &str,
+) -> Option<Vec<( String, String )>>
+{
+    let mut config: Config = match toml::from_str( text )
+    {
+        Ok( config ) => config,
+        Err( e ) => panic!( "Invalid config file: {}", e ),
+    };
+    return config.profile.remove( name ).map( |options| options.iter()
+            .map( |( option, value )| ( option.clone(), to_option_value( option, value ) ) )
+            .collect() );
+}
+
+/// Converts a TOML value into the value of a command line option.
+fn to_option_value(
+    option: &str,
+    value: &toml::Value,
+) -> String
+{
+    return match *value
+    {
+        toml::Value::String( ref text ) => text.clone(),
+        toml::Value::Integer( number ) => number.to_string(),
+        toml::Value::Float( number ) => number.to_string(),
+        toml::Value::Boolean( flag ) => flag.to_string(),
+        toml::Value::Datetime( ref time ) => time.to_string(),
+        toml::Value::Array( ref items ) => items.iter().map( |i| to_option_value( option, i ) ).collect::<Vec<String>>().join( "," ),
+        toml::Value::Table( _ ) => panic!( "The value of the option {} is a table.", option ),
+    };
+}
+
+/// Gets the options accepted by the usage line of the command matching the arguments and whether they take a value.
+/// A line matches when the arguments have all of its required commands and options.
+/// The line requiring the most of them is chosen, e.g. "eval --repro=<bundle>" over the other line of eval.
+fn get_command_options(
+    usage: &str,
+    arguments: &[String],
+) -> HashMap<String, bool>
+{
+    let command = arguments.get( 1 ).map( |c| c.as_str() ).unwrap_or( "" );
+    let mut best: Option<( usize, HashMap<String, bool> )> = None;
+    for line in usage.lines()
+    {
+        let mut words = line.split_whitespace();
+        if words.next() != Some( "scalar_set_eval" ) || words.next() != Some( command )
+        {
+            continue;
+        }
+
+        // The words outside brackets and parentheses are required.
+        let mut options = HashMap::new();
+        let mut required = 0;
+        let mut depth = 0;
+        let mut matches = true;
+        for word in words
+        {
+            let optional = depth > 0 || word.starts_with( '[' ) || word.starts_with( '(' );
+            depth += word.matches( |c| c == '[' || c == '(' ).count();
+            depth -= word.matches( |c| c == ']' || c == ')' ).count();
+            let word = word.trim_start_matches( |c| c == '[' || c == '(' ).trim_end_matches( |c| c == ']' || c == ')' || c == '.' );
+            let name = word.splitn( 2, '=' ).next().unwrap();
+            if word.starts_with( "--" )
+            {
+                options.insert( name.to_string(), word.contains( '=' ) );
+            }
+            if optional || word.starts_with( '<' )
+            {
+                continue;
+            }
+            required += 1;
+            if !( if name.starts_with( "--" ) { is_given( arguments, name ) } else { arguments.iter().skip( 2 ).any( |a| a == name ) } )
+            {
+                matches = false;
+            }
+        }
+        if matches && best.as_ref().map_or( true, |&( most, _ )| required > most )
+        {
+            best = Some( ( required, options ) );
+        }
+    }
+    return best.map( |( _, options )| options ).unwrap_or( HashMap::new() );
+}
+
+/// Checks whether the option is given in the arguments.
+fn is_given(
+    arguments: &[String],
+    flag: &str,
+) -> bool
+{
+    return arguments.iter().any( |a| a == flag || a.starts_with( &format!( "{}=", flag ) ) );
+}
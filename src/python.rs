@@ -0,0 +1,113 @@
+extern crate pyo3;
+extern crate rand;
+extern crate std;
+
+use pyo3::exceptions::PyIOError;
+use pyo3::prelude::*;
+use pyo3::wrap_pyfunction;
+
+use enumerations::*;
+use evaluation::*;
+use mapping::MappedFile;
+use utility;
+
+/// Generates a file of random sets of integers as the new command without an encoding.
+/// Returns the seed of the sets which generates the same sets again.
+#[pyfunction]
+fn generate(
+    py: Python,
+    file: String,
+    set_count: u64,
+    values_in_set: i32,
+    min_value: i32,
+    max_value: i32,
+    seed: Option<u64>,
+) -> PyResult<u64>
+{
+    let seed = seed.unwrap_or_else( rand::random );
+    py.allow_threads( || utility::generate::<i32>( &file, set_count, values_in_set, min_value, max_value,
+            Encoding::Raw, seed, false, false, None ) );
+    return Ok( seed );
+}
+
+/// Counts the sets of the file with any of the values.
+/// Zero or no threads uses all the logical threads.
+#[pyfunction]
+fn evaluate(
+    py: Python,
+    file: String,
+    values: Vec<i32>,
+    threads: Option<usize>,
+) -> PyResult<u64>
+{
+    return Ok( evaluate_batch( py, file, vec![ values ], threads )?[ 0 ] );
+}
+
+/// Counts the sets of the file with any of the values of each query.
+/// The sets are loaded once for all the queries.
+#[pyfunction]
+fn evaluate_batch(
+    py: Python,
+    file: String,
+    queries: Vec<Vec<i32>>,
+    threads: Option<usize>,
+) -> PyResult<Vec<u64>>
+{
+    if let Err( e ) = MappedFile::open( &file )
+    {
+        return Err( PyIOError::new_err( format!( "Failed to map {}: {}", file, e ) ) );
+    }
+    let params = EvaluationParams
+    {
+        file: &file,
+        values_in_set: 0,
+        min_value: 0,
+        max_value: 0,
+        preload: PreloadMode::Disabled,
+        max_threads: threads.unwrap_or( 0 ),
+        prefetch_distance: 0,
+        cache_size: 0,
+        io_limit: 0,
+        verify_sample: 0,
+        operation: Operation::Any,
+        dimension: None,
+        transform: None,
+        query_block: 0,
+        strategy: Strategy::Auto,
+        breakdown: false,
+        explain: false,
+        set_results: false,
+        exclude_file: None,
+        set_filter: SetFilter::all(),
+        eval_engine: "cpu",
+        audit: 0.0,
+        audit_engine: "cpu",
+        repro: false,
+        gpu_compare: GpuCompare::Epsilon,
+        gpu_memory: GpuMemory::Default,
+        gpu_kernel: GpuKernel::Item,
+        top_k: 0,
+        memory_limit: 0,
+        timeout: None,
+        cancellation: None,
+        signature: None,
+    };
+
+    // The other Python threads run while the sets are evaluated.
+    let test_sets: Vec<&[i32]> = queries.iter().map( |q| q.as_slice() ).collect();
+    let results = py.allow_threads( || evaluate_with_queries( &params, &test_sets ) );
+    return Ok( results.iter().map( |r| r.match_count ).collect() );
+}
+
+/// The scalar_set_eval module of Python.
+#[pymodule]
+fn scalar_set_eval(
+    _py: Python,
+    module: &PyModule,
+) -> PyResult<()>
+{
+    module.add_function( wrap_pyfunction!( generate, module )? )?;
+    module.add_function( wrap_pyfunction!( evaluate, module )? )?;
+    module.add_function( wrap_pyfunction!( evaluate_batch, module )? )?;
+    return Ok( () );
+}
@@ -0,0 +1,81 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::BufWriter;
+
+use mapping::MappedFile;
+
+/// A query set read from a file holding a raw set.
+/// The query is attached directly from the mapping without copying the values.
+/// A file saved from several test sets holds them in the order they were evaluated.
+pub struct QueryFile
+{
+    mapping: MappedFile,
+}
+
+impl QueryFile
+{
+    /// Maps the query file.
+    pub fn open( file: &String ) -> QueryFile
+    {
+        let mapping = MappedFile::open( file ).expect( "Failed to map the query file" );
+        return QueryFile { mapping: mapping };
+    }
+
+    /// Attaches the first set of the file as the query.
+    /// Returns the attached set and its values, both viewing the mapping.
+    pub fn attach<'a, T>( &'a self ) -> ( ro_scalar_set::RoScalarSet<'a, T>, &'a [T] )
+    where
+        T: ro_scalar_set::Value,
+    {
+        return match self.attach_all().into_iter().next()
+        {
+            Some( query ) => query,
+            None => panic!( "The query file does not hold a set." ),
+        };
+    }
+
+    /// Attaches all the sets of the file.
+    pub fn attach_all<'a, T>( &'a self ) -> Vec<( ro_scalar_set::RoScalarSet<'a, T>, &'a [T] )>
+    where
+        T: ro_scalar_set::Value,
+    {
+        let mut buffer: &[T] = self.mapping.as_slice();
+        let mut queries = Vec::new();
+        while let Ok( ( set, remaining ) ) = ro_scalar_set::RoScalarSet::attach( buffer )
+        {
+            // The values are stored at the end of the serialized set.
+            let serialized = &buffer[ ..buffer.len() - remaining.len() ];
+            let values = &serialized[ serialized.len() - set.size().. ];
+            queries.push( ( set, values ) );
+            buffer = remaining;
+        }
+        return queries;
+    }
+
+    /// Gets the values of the first set of the given size.
+    pub fn values_of_size<'a, T>(
+        &'a self,
+        size: usize,
+    ) -> Option<&'a [T]>
+    where
+        T: ro_scalar_set::Value,
+    {
+        return self.attach_all().into_iter().map( |( _, values )| values ).find( |v| v.len() == size );
+    }
+}
+
+/// Writes the test sets into a query file as raw sets in the given order.
+pub fn save<T>(
+    file: &String,
+    test_sets: &[&[T]],
+)
+where
+    T: ro_scalar_set::Value,
+{
+    let mut writer = BufWriter::new( std::fs::File::create( file ).expect( "Failed to open the query file." ) );
+    for test_set in test_sets
+    {
+        ro_scalar_set::RoScalarSet::new( test_set ).serialize( &mut writer ).expect( "Writing the query file failed." );
+    }
+}
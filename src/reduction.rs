@@ -0,0 +1,86 @@
+extern crate rayon;
+extern crate std;
+
+use self::rayon::prelude::*;
+
+/// Number of chunks per thread so that the threads finishing early can take the chunks of the slow ones.
+const CHUNKS_PER_THREAD: usize = 8;
+
+/// Number of consecutive sets evaluated as a chunk with a partial result of its own.
+/// The sets are split into enough chunks to keep all the threads of the current pool busy.
+pub fn chunk_size( set_count: usize ) -> usize
+{
+    let chunk_count = rayon::current_num_threads() * CHUNKS_PER_THREAD;
+    return std::cmp::max( ( set_count + chunk_count - 1 ) / chunk_count, 1 );
+}
+
+/// Partial result of evaluating a chunk of consecutive sets.
+#[derive(Clone)]
+pub struct ChunkResult
+{
+    /// Index of the first set of the chunk.
+    pub first_set: usize,
+    pub set_count: usize,
+    pub match_count: u64,
+    /// Time spent on evaluating the sets of the chunk.
+    pub duration: std::time::Duration,
+}
+
+/// Partial results of the chunks and the time spent on combining them.
+pub struct Reduction
+{
+    /// Partial results in the order of the sets.
+    pub chunks: Vec<ChunkResult>,
+    pub duration: std::time::Duration,
+}
+
+impl Reduction
+{
+    /// Prints the number of chunks, the time spent on combining them and the slowest chunk.
+    pub fn print( &self )
+    {
+        println!( "Combined the results of {} chunks in {} ns", self.chunks.len(), as_nanos( self.duration ) );
+        if let Some( slowest ) = self.chunks.iter().max_by_key( |c| c.duration )
+        {
+            println!( "Slowest chunk: sets {}..{} with {} matches in {} ns", slowest.first_set,
+                    slowest.first_set + slowest.set_count, slowest.match_count, as_nanos( slowest.duration ) );
+        }
+    }
+}
+
+/// Evaluates the chunks of the sets in parallel with the function returning the matches of the set at an index.
+pub fn evaluate_chunks<F>(
+    set_count: usize,
+    evaluate: F,
+) -> Vec<ChunkResult>
+where
+    F: Fn( usize ) -> u64 + std::marker::Sync + std::marker::Send,
+{
+    let chunk_size = chunk_size( set_count );
+    let chunk_count = ( set_count + chunk_size - 1 ) / chunk_size;
+    return ( 0..chunk_count ).into_par_iter()
+            .map( |c| {
+                let first = c * chunk_size;
+                let last = std::cmp::min( first + chunk_size, set_count );
+                let start = std::time::Instant::now();
+                let match_count = ( first..last ).map( |i| evaluate( i ) ).sum();
+                ChunkResult { first_set: first, set_count: last - first, match_count: match_count, duration: start.elapsed() }
+            } )
+            .collect();
+}
+
+/// Combines the matches of the chunks in the order of the sets.
+/// There are only a few chunks per thread so they are summed on the calling thread.
+/// Returns the total number of matches and the reduction holding the partial results.
+pub fn reduce( chunks: Vec<ChunkResult> ) -> ( u64, Reduction )
+{
+    let start = std::time::Instant::now();
+    let total = chunks.iter().map( |c| c.match_count ).sum();
+    return ( total, Reduction { chunks: chunks, duration: start.elapsed() } );
+}
+
+/// Converts the duration into nanoseconds.
+fn as_nanos( duration: std::time::Duration ) -> u64
+{
+    return duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64;
+}
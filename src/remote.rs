@@ -0,0 +1,138 @@
+extern crate std;
+
+#[cfg(feature="cloud")]
+use std::path::PathBuf;
+#[cfg(feature="cloud")]
+use std::process::Command;
+
+/// URI schemes of the supported object storages.
+const SCHEMES: &'static [&'static str] = &[ "s3://", "gs://" ];
+
+/// Checks whether the file is located in an object storage.
+pub fn is_remote( file: &str ) -> bool
+{
+    SCHEMES.iter().any( |s| file.starts_with( s ) )
+}
+
+/// Gets a local path for reading the file.
+/// Files in an object storage are downloaded into a local cache and reused on later runs.
+#[cfg(feature="cloud")]
+pub fn resolve( file: &String ) -> String
+{
+    if !is_remote( file )
+    {
+        return file.clone();
+    }
+    let local = cache_dir().join( cache_path( file ) );
+    if !local.exists()
+    {
+        download( file, &cache_path( file ) );
+    }
+    return local.to_string_lossy().into_owned();
+}
+
+/// Downloads the signature stored next to the file in the object storage and gets its local path.
+/// The signature is downloaded on every run as the object may have been signed again.
+#[cfg(feature="cloud")]
+pub fn resolve_signature( file: &String ) -> String
+{
+    let path = PathBuf::from( "signatures" ).join( cache_path( file ) );
+    download( &format!( "{}.sig", file ), &path );
+    return cache_dir().join( &path ).to_string_lossy().into_owned();
+}
+
+/// Gets the directory of the local copies.
+#[cfg(feature="cloud")]
+fn cache_dir() -> PathBuf
+{
+    return std::env::temp_dir().join( "scalar_set_eval_cache" );
+}
+
+/// Gets the path of the local copy of the file in the cache directory.
+/// The copy is located by the scheme, the bucket and the key of the object.
+#[cfg(feature="cloud")]
+fn cache_path( file: &String ) -> PathBuf
+{
+    let ( scheme, rest ) = file.split_at( file.find( "://" ).expect( "The URI has no scheme." ) );
+    let ( bucket, key ) = match rest[ 3.. ].find( '/' )
+    {
+        Some( position ) => ( &rest[ 3..3 + position ], &rest[ 3 + position + 1.. ] ),
+        None => panic!( "The URI {} does not name an object.", file ),
+    };
+    if bucket.is_empty() || key.is_empty()
+    {
+        panic!( "The URI {} does not name an object.", file );
+    }
+    return [ escape( scheme ), escape( bucket ), escape( key ) ].iter().collect();
+}
+
+/// Downloads the file into the path of the cache directory.
+#[cfg(feature="cloud")]
+fn download(
+    file: &String,
+    path: &PathBuf,
+)
+{
+    // Download into a temporary file first so that an interrupted download is never
+    // mistaken for a complete copy. The partial downloads are kept apart from the complete copies.
+    println!( "Downloading {}...", file );
+    let local = cache_dir().join( path );
+    let partial = cache_dir().join( "partial" ).join( path );
+    for target in &[ &local, &partial ]
+    {
+        std::fs::create_dir_all( target.parent().unwrap() ).expect( "Failed to create the cache directory." );
+    }
+    let status = if file.starts_with( "s3://" )
+    {
+        Command::new( "aws" ).arg( "s3" ).arg( "cp" ).arg( file ).arg( &partial ).status()
+    }
+    else
+    {
+        Command::new( "gsutil" ).arg( "cp" ).arg( file ).arg( &partial ).status()
+    };
+    match status
+    {
+        Ok( ref status ) if status.success() => {},
+        _ => panic!( "Failed to download {}.", file ),
+    }
+    std::fs::rename( &partial, &local ).expect( "Failed to rename the downloaded file." );
+}
+
+/// Escapes a part of the URI into a single file name.
+/// Every byte other than an ASCII letter, a digit, '-' or a '.' not starting the name is written as %XX
+/// so that the different parts never map to the same name and the name never refers to a directory.
+#[cfg(feature="cloud")]
+fn escape( part: &str ) -> String
+{
+    let mut escaped = String::with_capacity( part.len() );
+    for ( i, byte ) in part.bytes().enumerate()
+    {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || ( byte == b'.' && i > 0 )
+        {
+            escaped.push( byte as char );
+        }
+        else
+        {
+            escaped.push_str( &format!( "%{:02X}", byte ) );
+        }
+    }
+    return escaped;
+}
+
+/// Object storage support is not enabled.
+#[cfg(not(feature="cloud"))]
+pub fn resolve( file: &String ) -> String
+{
+    if is_remote( file )
+    {
+        panic!( "Reading {} requires the cloud feature.", file );
+    }
+    return file.clone();
+}
+
+/// Object storage support is not enabled.
+#[cfg(not(feature="cloud"))]
+pub fn resolve_signature( file: &String ) -> String
+{
+    panic!( "Reading the signature of {} requires the cloud feature.", file );
+}
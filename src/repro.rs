@@ -0,0 +1,350 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use audit;
+use bundle;
+use encoding;
+use engine::EngineRegistry;
+use enumerations::*;
+use evaluation::*;
+use mapping::MappedFile;
+use intersect;
+use query::{self, QueryFile};
+use traits::*;
+use transform::Transform;
+use verify;
+
+/// Name of the entry describing the divergence.
+const DESCRIPTION: &'static str = "repro.txt";
+
+/// Name of the entry holding the diverging sets.
+const SETS: &'static str = "repro_sets.bin";
+
+/// Name of the entry holding the query.
+const QUERY: &'static str = "repro_query.bin";
+
+/// Name of the entry holding the values of the exclusion sets.
+const EXCLUSION: &'static str = "repro_exclusion.bin";
+
+/// Engine name of the reference implementation of the verification.
+pub const REFERENCE: &'static str = "reference";
+
+/// Describes the sets the engines disagreed on.
+pub struct Repro
+{
+    /// File the sets were evaluated from.
+    pub file: String,
+    /// Type of the values, f32 or i32.
+    pub value_type: String,
+    pub engine: String,
+    /// Engine the results were compared with. The reference implementation of --verify is named "reference".
+    pub reference: String,
+    /// Indexes of the diverging sets in the file in the order they are stored in the bundle.
+    pub indexes: Vec<usize>,
+    pub operation: Operation,
+    pub dimension: Option<usize>,
+    pub transform: Option<Transform>,
+    pub query_block: usize,
+    pub strategy: Strategy,
+    pub max_threads: usize,
+    pub gpu_compare: GpuCompare,
+    pub gpu_memory: GpuMemory,
+    pub gpu_kernel: GpuKernel,
+}
+
+impl Repro
+{
+    /// Describes the sets of the file the engine and the reference disagreed on in the evaluation with the parameters.
+    pub fn new<T>(
+        params: &EvaluationParams,
+        engine: &str,
+        reference: &str,
+        indexes: &[usize],
+    ) -> Repro
+    {
+        let mut indexes = indexes.to_vec();
+        indexes.sort();
+        indexes.dedup();
+        return Repro {
+            file: params.file.to_string(),
+            value_type: std::any::type_name::<T>().to_string(),
+            engine: engine.to_string(),
+            reference: reference.to_string(),
+            indexes: indexes,
+            operation: params.operation,
+            dimension: params.dimension,
+            transform: params.transform,
+            query_block: params.query_block,
+            strategy: params.strategy,
+            max_threads: params.max_threads,
+            gpu_compare: params.gpu_compare,
+            gpu_memory: params.gpu_memory,
+            gpu_kernel: params.gpu_kernel,
+        };
+    }
+
+    /// Describes the divergence as "<key>\t<value>" lines.
+    fn to_text( &self ) -> String
+    {
+        let indexes: Vec<String> = self.indexes.iter().map( |i| i.to_string() ).collect();
+        let operation = match self.operation
+        {
+            Operation::Any => "any".to_string(),
+            Operation::WeightedOverlap( threshold ) => format!( "weighted-overlap={}", threshold ),
+        };
+        let transform = self.transform.map( |t| format!( "scale={},offset={}", t.scale, t.offset ) ).unwrap_or( String::new() );
+        let dimension = self.dimension.map( |d| d.to_string() ).unwrap_or( String::new() );
+        return format!( "file\t{}\ntype\t{}\nengine\t{}\nreference\t{}\nindexes\t{}\n\
+                operation\t{}\ndimension\t{}\ntransform\t{}\nquery-block\t{}\nstrategy\t{}\nthreads\t{}\n\
+                gpu-compare\t{}\ngpu-memory\t{}\ngpu-kernel\t{}\n",
+                self.file, self.value_type, self.engine, self.reference, indexes.join( "," ),
+                operation, dimension, transform, self.query_block, intersect::name( self.strategy ), self.max_threads,
+                gpu_compare_name( self.gpu_compare ), gpu_memory_name( self.gpu_memory ), gpu_kernel_name( self.gpu_kernel ) );
+    }
+
+    /// Reads the description written by to_text.
+    fn from_text( text: &str ) -> Repro
+    {
+        let field = |key: &str| -> String {
+            let prefix = format!( "{}\t", key );
+            match text.lines().find( |l| l.starts_with( &prefix ) )
+            {
+                Some( line ) => line[ prefix.len().. ].to_string(),
+                None => panic!( "The description of the reproduction has no {}.", key ),
+            }
+        };
+        let operation = field( "operation" );
+        let transform = field( "transform" );
+        let dimension = field( "dimension" );
+        let strategy = field( "strategy" );
+        return Repro {
+            file: field( "file" ),
+            value_type: field( "type" ),
+            engine: field( "engine" ),
+            reference: field( "reference" ),
+            indexes: field( "indexes" ).split( ',' ).filter( |i| !i.is_empty() )
+                    .map( |i| i.parse().expect( "Invalid set index in the reproduction." ) )
+                    .collect(),
+            operation: match operation.splitn( 2, '=' ).collect::<Vec<&str>>().as_slice()
+            {
+                [ "any" ] => Operation::Any,
+                [ "weighted-overlap", threshold ] => Operation::WeightedOverlap( threshold.parse().expect( "Invalid threshold in the reproduction." ) ),
+                _ => panic!( "Unknown operation in the reproduction: {}", operation ),
+            },
+            dimension: if dimension.is_empty() { None } else { Some( dimension.parse().expect( "Invalid dimension in the reproduction." ) ) },
+            transform: if transform.is_empty() { None } else { Some( Transform::parse( &transform ).expect( "Invalid transform in the reproduction." ) ) },
+            query_block: field( "query-block" ).parse().expect( "Invalid query block in the reproduction." ),
+            strategy: intersect::parse( &strategy ).expect( "Unknown strategy in the reproduction." ),
+            max_threads: field( "threads" ).parse().expect( "Invalid number of threads in the reproduction." ),
+            gpu_compare: parse_name( &field( "gpu-compare" ), &[ GpuCompare::Exact, GpuCompare::Epsilon, GpuCompare::Ulp ], gpu_compare_name ),
+            gpu_memory: parse_name( &field( "gpu-memory" ), &[ GpuMemory::Default, GpuMemory::Pinned, GpuMemory::ZeroCopy ], gpu_memory_name ),
+            gpu_kernel: parse_name( &field( "gpu-kernel" ), &[ GpuKernel::Item, GpuKernel::WorkGroup ], gpu_kernel_name ),
+        };
+    }
+}
+
+/// Gets the name of the comparison of the float values on GPU as given on the command line.
+fn gpu_compare_name( compare: GpuCompare ) -> &'static str
+{
+    match compare
+    {
+        GpuCompare::Exact => "exact",
+        GpuCompare::Epsilon => "epsilon",
+        GpuCompare::Ulp => "ulp",
+    }
+}
+
+/// Gets the name of the host memory of the GPU buffers as given on the command line.
+fn gpu_memory_name( memory: GpuMemory ) -> &'static str
+{
+    match memory
+    {
+        GpuMemory::Default => "default",
+        GpuMemory::Pinned => "pinned",
+        GpuMemory::ZeroCopy => "zerocopy",
+    }
+}
+
+/// Gets the name of the layout of the GPU kernel as given on the command line.
+fn gpu_kernel_name( kernel: GpuKernel ) -> &'static str
+{
+    match kernel
+    {
+        GpuKernel::Item => "item",
+        GpuKernel::WorkGroup => "wg",
+    }
+}
+
+/// Finds the option with the name.
+fn parse_name<O: Copy>(
+    name: &str,
+    options: &[O],
+    name_of: fn( O ) -> &'static str,
+) -> O
+{
+    match options.iter().find( |o| name_of( **o ) == name )
+    {
+        Some( option ) => *option,
+        None => panic!( "Unknown option in the reproduction: {}", name ),
+    }
+}
+
+/// Exports the diverging sets of the data in its encoding, the query and the values of the exclusion sets
+/// into a bundle in the current directory. The sets are exported before the exclusion.
+/// Returns the name of the bundle.
+pub fn export<T>(
+    repro: &Repro,
+    data: &[u8],
+    test_set: &[T],
+    exclusion: Option<&[T]>,
+) -> String
+where
+    T: OrderedBits + std::clone::Clone + ro_scalar_set::Value,
+{
+    let directory = create_directory();
+    let file = |name: &str| directory.join( name ).to_string_lossy().into_owned();
+
+    // The sets are copied as raw sets and then converted into the encoding of the data.
+    let raw = file( "repro_raw.bin" );
+    {
+        let mut writer = BufWriter::new( std::fs::File::create( &raw ).expect( "Failed to open the reproduction." ) );
+        let mut index = 0;
+        let mut next = 0;
+        encoding::for_each_set( data, |values: &[T]| {
+            if next < repro.indexes.len() && repro.indexes[ next ] == index
+            {
+                ro_scalar_set::RoScalarSet::new( values ).serialize( &mut writer ).expect( "Writing the reproduction failed." );
+                next += 1;
+            }
+            index += 1;
+        } );
+    }
+    match encoding::detect( data )
+    {
+        Encoding::Raw => std::fs::rename( &raw, file( SETS ) ).expect( "Failed to rename the reproduction." ),
+        encoding =>
+        {
+            encoding::convert::<T>( &raw, &file( SETS ), encoding );
+            std::fs::remove_file( &raw ).ok();
+        },
+    }
+    query::save( &file( QUERY ), &[ test_set ] );
+    std::fs::write( file( DESCRIPTION ), repro.to_text() ).expect( "Writing the reproduction failed." );
+    let mut notes = vec![ file( DESCRIPTION ) ];
+    if let Some( exclusion ) = exclusion
+    {
+        query::save( &file( EXCLUSION ), &[ exclusion ] );
+        notes.push( file( EXCLUSION ) );
+    }
+
+    let seconds = std::time::SystemTime::now().duration_since( std::time::UNIX_EPOCH ).map( |d| d.as_secs() ).unwrap_or( 0 );
+    let name = Path::new( &repro.file ).file_name().map( |n| n.to_string_lossy().into_owned() ).unwrap_or( "sets".to_string() );
+    let bundle_file = format!( "{}.repro-{}.bundle", name, seconds );
+    bundle::pack( &bundle_file, &vec![ file( SETS ) ], &notes, Some( &file( QUERY ) ),
+            repro.value_type == std::any::type_name::<f32>() );
+    std::fs::remove_dir_all( &directory ).ok();
+    println!( "Exported {} diverging sets to {}. Replay them with eval --repro={}", repro.indexes.len(), bundle_file, bundle_file );
+    return bundle_file;
+}
+
+/// Replays the reproduction of the bundle with the engines that disagreed and prints the sets still diverging.
+pub fn replay( bundle_file: &String )
+{
+    let directory = create_directory();
+    bundle::unpack( bundle_file, &directory );
+    let text = std::fs::read_to_string( directory.join( DESCRIPTION ) ).expect( "The bundle has no description of the reproduction." );
+    let repro = Repro::from_text( &text );
+    println!( "Replaying {} sets of {} that diverged between the {} engine and the {}.", repro.indexes.len(), repro.file,
+            repro.engine, repro.reference );
+    let diverging = if repro.value_type == std::any::type_name::<f32>()
+    {
+        replay_sets::<f32>( &repro, &directory )
+    }
+    else
+    {
+        replay_sets::<i32>( &repro, &directory )
+    };
+    std::fs::remove_dir_all( &directory ).ok();
+    for index in &diverging
+    {
+        println!( "Set {} diverges", repro.indexes[ *index ] );
+    }
+    println!( "{} of {} sets diverge", diverging.len(), repro.indexes.len() );
+}
+
+/// Evaluates the unpacked sets and returns the positions of the sets still diverging.
+fn replay_sets<T>(
+    repro: &Repro,
+    directory: &Path,
+) -> Vec<usize>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    let sets_file = directory.join( SETS ).to_string_lossy().into_owned();
+    let sets = MappedFile::open( &sets_file ).expect( "Failed to map the reproduction." );
+    let query = QueryFile::open( &directory.join( QUERY ).to_string_lossy().into_owned() );
+    let ( _, test_set ) = query.attach::<T>();
+    let exclusion_file = directory.join( EXCLUSION ).to_string_lossy().into_owned();
+    let exclusion = if Path::new( &exclusion_file ).exists() { Some( QueryFile::open( &exclusion_file ) ) } else { None };
+    let exclusion = exclusion.as_ref().map( |e| e.attach::<T>().1 );
+    // The engines disagreeing in the audit are compared on each set.
+    let registry = EngineRegistry::<T>::new();
+    let find = |name: &str| match registry.find( name )
+    {
+        Some( engine ) => engine,
+        None => panic!( "Unknown engine: {}", name ),
+    };
+    let params = EvaluationParams
+    {
+        file: &sets_file,
+        values_in_set: test_set.len() as i32,
+        min_value: 0,
+        max_value: 0,
+        preload: PreloadMode::Full,
+        max_threads: repro.max_threads,
+        prefetch_distance: 0,
+        cache_size: 0,
+        io_limit: 0,
+        verify_sample: 0,
+        operation: repro.operation,
+        dimension: repro.dimension,
+        transform: repro.transform,
+        query_block: repro.query_block,
+        strategy: repro.strategy,
+        breakdown: false,
+        explain: false,
+        set_results: false,
+        exclude_file: None,
+        set_filter: SetFilter::all(),
+        eval_engine: &repro.engine,
+        audit: 0.0,
+        audit_engine: &repro.reference,
+        repro: false,
+        gpu_compare: repro.gpu_compare,
+        gpu_memory: repro.gpu_memory,
+        gpu_kernel: repro.gpu_kernel,
+        top_k: 0,
+        memory_limit: 0,
+        timeout: None,
+        cancellation: None,
+        signature: None,
+    };
+    if repro.reference == REFERENCE
+    {
+        let matches = match_each( sets.as_slice(), test_set, &params, find( &repro.engine ), exclusion );
+        return verify::verify( sets.as_slice(), test_set, std::usize::MAX, &matches ).mismatches;
+    }
+    return audit::find_mismatches( sets.as_slice(), test_set, &params, find( &repro.engine ), find( &repro.reference ), exclusion );
+}
+
+/// Creates an empty directory for the entries of a bundle.
+fn create_directory() -> PathBuf
+{
+    let directory = std::env::temp_dir().join( format!( "scalar_set_eval_repro_{}", std::process::id() ) );
+    std::fs::remove_dir_all( &directory ).ok();
+    std::fs::create_dir_all( &directory ).expect( "Failed to create the directory of the reproduction." );
+    return directory;
+}
@@ -0,0 +1,297 @@
+extern crate rayon;
+extern crate serde_json;
+extern crate std;
+
+use std::io::{BufReader, BufWriter};
+use std::io::prelude::*;
+
+use allocator;
+
+/// Describes the machine and the build the tests were run with.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Environment
+{
+    pub host: String,
+    pub os: String,
+    pub arch: String,
+    pub cpus: usize,
+    pub allocator: String,
+    /// Model name of the processor.
+    #[serde(default)]
+    pub cpu: String,
+    /// Total memory in MB.
+    #[serde(default)]
+    pub memory_mb: u64,
+    /// Frequency scaling governor of the processor.
+    #[serde(default)]
+    pub governor: String,
+    /// Version of the evaluator.
+    #[serde(default)]
+    pub version: String,
+    /// Commit of the evaluator when built from a git repository.
+    #[serde(default)]
+    pub git: Option<GitInfo>,
+    /// Commit of ro_scalar_set when built from a git repository.
+    #[serde(default)]
+    pub ro_scalar_set_git: Option<GitInfo>,
+}
+
+/// Describes the state of a git repository.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct GitInfo
+{
+    pub commit: String,
+    pub branch: String,
+    /// The working tree has uncommitted changes.
+    pub dirty: bool,
+}
+
+impl GitInfo
+{
+    /// Gets the state of the git repository recorded by the build script.
+    /// Returns None when the sources were not built from a git repository.
+    pub fn built(
+        commit: Option<&str>,
+        branch: Option<&str>,
+        dirty: Option<&str>,
+    ) -> Option<GitInfo>
+    {
+        return Some( GitInfo {
+            commit: commit?.to_string(),
+            branch: branch?.to_string(),
+            dirty: dirty? == "true",
+        } );
+    }
+
+    /// Formats the state as "<commit> (<branch>)" with a "dirty" mark for uncommitted changes.
+    pub fn describe( info: &Option<GitInfo> ) -> String
+    {
+        match *info
+        {
+            Some( ref info ) => format!( "{} ({}{})", info.commit, info.branch, if info.dirty { ", dirty" } else { "" } ),
+            None => "unknown".to_string(),
+        }
+    }
+}
+
+/// Result of a single test.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TestRecord
+{
+    pub set_size: i32,
+    pub set_count: u64,
+    pub test_set_size: i32,
+    pub encoding: String,
+    pub strategy: String,
+    pub preload: String,
+    pub thread_count: usize,
+    pub file_size: u64,
+    pub match_count: u64,
+    pub duration_us: u64,
+    /// Number of times the test was retried after a failure.
+    #[serde(default)]
+    pub retries: usize,
+    /// State of the page cache before the evaluation: warm, cold or partial. Empty when the sets were preloaded.
+    #[serde(default)]
+    pub page_cache: String,
+    /// Layout of the GPU kernel: item or wg. Empty for the other engines.
+    #[serde(default)]
+    pub gpu_kernel: String,
+}
+
+/// Results of the tests run in a single environment.
+#[derive(Serialize, Deserialize)]
+pub struct EnvironmentResults
+{
+    pub environment: Environment,
+    pub results: Vec<TestRecord>,
+}
+
+impl Environment
+{
+    /// Describes the current environment.
+    pub fn current() -> Environment
+    {
+        return Environment {
+            host: host_name().unwrap_or( "unknown".to_string() ),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpus: rayon::current_num_threads(),
+            allocator: allocator::name().to_string(),
+            cpu: read_field( "/proc/cpuinfo", "model name" ).unwrap_or( "unknown".to_string() ),
+            memory_mb: read_field( "/proc/meminfo", "MemTotal" )
+                    .and_then( |m| m.trim_end_matches( "kB" ).trim().parse::<u64>().ok() )
+                    .map( |kb| kb / 1024 )
+                    .unwrap_or( 0 ),
+            governor: std::fs::read_to_string( "/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor" )
+                    .map( |g| g.trim().to_string() )
+                    .unwrap_or( "unknown".to_string() ),
+            version: env!( "CARGO_PKG_VERSION" ).to_string(),
+            git: GitInfo::built( option_env!( "SSE_GIT_COMMIT" ), option_env!( "SSE_GIT_BRANCH" ), option_env!( "SSE_GIT_DIRTY" ) ),
+            ro_scalar_set_git: GitInfo::built( option_env!( "SSE_RO_SCALAR_SET_GIT_COMMIT" ), option_env!( "SSE_RO_SCALAR_SET_GIT_BRANCH" ),
+                    option_env!( "SSE_RO_SCALAR_SET_GIT_DIRTY" ) ),
+        };
+    }
+
+    /// Lists the differences to the other environment as "<field>: <this> != <other>".
+    pub fn diff( &self, other: &Environment ) -> Vec<String>
+    {
+        let fields = vec![
+            ( "host", self.host.clone(), other.host.clone() ),
+            ( "os", self.os.clone(), other.os.clone() ),
+            ( "arch", self.arch.clone(), other.arch.clone() ),
+            ( "cpu", self.cpu.clone(), other.cpu.clone() ),
+            ( "cpus", self.cpus.to_string(), other.cpus.to_string() ),
+            ( "memory", format!( "{} MB", self.memory_mb ), format!( "{} MB", other.memory_mb ) ),
+            ( "governor", self.governor.clone(), other.governor.clone() ),
+            ( "allocator", self.allocator.clone(), other.allocator.clone() ),
+            ( "version", self.version.clone(), other.version.clone() ),
+            ( "commit", GitInfo::describe( &self.git ), GitInfo::describe( &other.git ) ),
+            ( "ro_scalar_set commit", GitInfo::describe( &self.ro_scalar_set_git ), GitInfo::describe( &other.ro_scalar_set_git ) ),
+        ];
+        return fields.into_iter()
+                .filter( |&( _, ref a, ref b )| a != b )
+                .map( |( name, a, b )| format!( "{}: {} != {}", name, a, b ) )
+                .collect();
+    }
+}
+
+/// Gets the name of the host from the kernel or the system configuration.
+/// The COMPUTERNAME variable is only used on Windows where neither file exists.
+fn host_name() -> Option<String>
+{
+    return [ "/proc/sys/kernel/hostname", "/etc/hostname" ].iter()
+            .filter_map( |f| std::fs::read_to_string( f ).ok() )
+            .map( |h| h.trim().to_string() )
+            .find( |h| !h.is_empty() )
+            .or_else( || std::env::var( "COMPUTERNAME" ).ok() );
+}
+
+/// Reads the value of a "<name> : <value>" field from a file such as /proc/cpuinfo.
+fn read_field(
+    file: &str,
+    name: &str,
+) -> Option<String>
+{
+    let content = std::fs::read_to_string( file ).ok()?;
+    return content.lines()
+            .filter( |l| l.starts_with( name ) )
+            .filter_map( |l| l.splitn( 2, ':' ).nth( 1 ) )
+            .map( |v| v.trim().to_string() )
+            .next();
+}
+
+/// Gets the name of the JSON results written next to the report.
+pub fn get_results_file_name( report: &str ) -> String
+{
+    format!( "{}.json", report )
+}
+
+/// Gets the name of the JSON stream the results are appended to as the tests finish.
+pub fn get_stream_file_name( report: &str ) -> String
+{
+    format!( "{}.jsonl", report )
+}
+
+/// Appends a result to the JSON stream as a line of its own.
+/// The file is reopened for each result so that the results written so far survive a crash.
+pub fn append(
+    file: &str,
+    record: &TestRecord,
+)
+{
+    let mut writer = std::fs::OpenOptions::new().create( true ).append( true ).open( file )
+            .expect( "Failed to open the result stream." );
+    let mut line = serde_json::to_string( record ).expect( "Serializing the result failed." );
+    line.push( '\n' );
+    writer.write_all( line.as_bytes() ).expect( "Writing the result stream failed." );
+}
+
+/// Writes the results into a JSON file.
+/// A file holds a list of environments so that merged files can be merged again.
+pub fn write(
+    file: &str,
+    results: &Vec<EnvironmentResults>,
+)
+{
+    let writer = BufWriter::new( std::fs::File::create( file ).expect( "Failed to open the results." ) );
+    serde_json::to_writer_pretty( writer, results ).expect( "Writing the results failed." );
+}
+
+/// Reads the results from a JSON file.
+pub fn read( file: &str ) -> Vec<EnvironmentResults>
+{
+    let reader = BufReader::new( std::fs::File::open( file ).expect( "Failed to open the results." ) );
+    return serde_json::from_reader( reader ).expect( &format!( "Invalid results in {}.", file ) );
+}
+
+/// Combines the results of the files into one file grouped by the environment.
+pub fn merge(
+    output: &str,
+    files: &Vec<String>,
+)
+{
+    let mut merged: Vec<EnvironmentResults> = Vec::new();
+    for file in files
+    {
+        for results in read( file )
+        {
+            match merged.iter_mut().position( |m| m.environment == results.environment )
+            {
+                Some( index ) => merged[ index ].results.extend( results.results ),
+                None => merged.push( results ),
+            }
+        }
+    }
+
+    // Keep the results of an environment in the same order as in the reports of the tests.
+    for results in &mut merged
+    {
+        results.results.sort_by_key( |r| ( r.set_size, r.set_count, r.test_set_size ) );
+    }
+    write( output, &merged );
+    println!( "Merged {} files into {} environments.", files.len(), merged.len() );
+}
+
+/// Compares the durations of the tests in the candidate results to the baseline results.
+/// The environments of the results are compared first and any differences are warned about
+/// as the durations measured in different environments are not comparable.
+pub fn compare(
+    baseline: &str,
+    candidate: &str,
+)
+{
+    let baseline = read( baseline );
+    let candidate = read( candidate );
+    let environments: Vec<&Environment> = baseline.iter().chain( &candidate ).map( |r| &r.environment ).collect();
+    let differences: Vec<String> = environments.iter()
+            .skip( 1 )
+            .flat_map( |e| environments[ 0 ].diff( e ) )
+            .collect();
+    if !differences.is_empty()
+    {
+        println!( "WARNING: The results were measured in different environments and the durations are not comparable." );
+        for difference in &differences
+        {
+            println!( "WARNING:   {}", difference );
+        }
+        println!( "" );
+    }
+
+    println!( "|{:>12}|{:>12}|{:>14}|{:>10}|{:>10}|{:>8}|{:>8}|{:>14}|{:>14}|{:>9}|",
+            "Set size", "Sets", "Test set size", "Encoding", "Strategy", "Preload", "Threads", "Baseline", "Candidate", "Change" );
+    let candidates: Vec<&TestRecord> = candidate.iter().flat_map( |r| &r.results ).collect();
+    for b in baseline.iter().flat_map( |r| &r.results )
+    {
+        let c = candidates.iter().find( |c| c.set_size == b.set_size && c.set_count == b.set_count &&
+                c.test_set_size == b.test_set_size && c.encoding == b.encoding && c.strategy == b.strategy &&
+                c.preload == b.preload && c.thread_count == b.thread_count && c.gpu_kernel == b.gpu_kernel );
+        if let Some( c ) = c
+        {
+            let change = ( c.duration_us as f64 - b.duration_us as f64 ) * 100.0 / std::cmp::max( 1, b.duration_us ) as f64;
+            println!( "|{:>12}|{:>12}|{:>14}|{:>10}|{:>10}|{:>8}|{:>8}|{:>11} us|{:>11} us|{:>8.1}%|",
+                    b.set_size, b.set_count, b.test_set_size, b.encoding, b.strategy, b.preload, b.thread_count,
+                    b.duration_us, c.duration_us, change );
+        }
+    }
+}
@@ -0,0 +1,183 @@
+extern crate rand;
+extern crate serde_json;
+extern crate std;
+
+use std::collections::HashMap;
+use std::io::BufWriter;
+use std::path::Path;
+
+use artifact::{self, Artifact};
+use encoding;
+use enumerations::*;
+use results::Environment;
+use scenario::Scenario;
+
+/// Name of the run manifest in the directory of the runner.
+const MANIFEST: &'static str = "runner.json";
+
+/// Name of the copy of the query file in the directory of the runner.
+const QUERY: &'static str = "query.bin";
+
+/// Configuration of the test executed by the runner.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RunnerConfig
+{
+    pub report: String,
+    pub namespace: String,
+    pub min_value: i32,
+    pub max_value: i32,
+    pub floats: bool,
+    pub encodings: Vec<String>,
+    pub strategies: Vec<String>,
+    /// Prefetch distance in bytes.
+    pub prefetch: usize,
+    /// Read limit in bytes per second.
+    pub io_limit: usize,
+    pub engine: String,
+    /// Query file copied into the directory of the runner if any.
+    pub query_file: Option<String>,
+    /// Limit for the bytes written when generating the test sets in bytes.
+    pub max_disk: u64,
+    pub retries: usize,
+}
+
+/// Sets generated for the test with a fixed seed and the files expected from them.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RunnerArtifact
+{
+    pub set_size: i32,
+    pub set_count: u64,
+    pub seed: u64,
+    /// Files of the sets in each encoding.
+    pub files: Vec<String>,
+}
+
+/// Self-contained description of a test run that executes identically on any machine.
+#[derive(Serialize, Deserialize)]
+pub struct Runner
+{
+    /// Version of the application that emitted the runner.
+    pub version: String,
+    /// Environment the runner was emitted in.
+    pub environment: Environment,
+    pub config: RunnerConfig,
+    pub scenarios: Vec<Scenario>,
+    pub artifacts: Vec<RunnerArtifact>,
+    /// Seed the test sets are generated with when the runner has no query file.
+    pub query_seed: u64,
+    /// Command lines generating the artifacts and executing the test.
+    pub commands: Vec<String>,
+}
+
+impl Runner
+{
+    /// Describes the test with a fixed seed for each of the generated sets.
+    pub fn new(
+        config: RunnerConfig,
+        scenarios: &Vec<Scenario>,
+    ) -> Runner
+    {
+        let mut set_files: Vec<( i32, u64 )> = scenarios.iter().map( |s| ( s.set_size, s.set_count ) ).collect();
+        set_files.sort();
+        set_files.dedup();
+        let encodings: Vec<Encoding> = config.encodings.iter()
+                .map( |e| encoding::parse( e ).expect( &format!( "Unknown encoding: {}", e ) ) )
+                .collect();
+
+        // The raw sets are generated first and then converted into the other encodings.
+        let mut artifacts = Vec::new();
+        let mut commands = Vec::new();
+        for ( set_size, set_count ) in set_files
+        {
+            let seed: u64 = rand::random();
+            let describe = |encoding: Encoding| Artifact {
+                seed: Some( seed ),
+                ..Artifact::new( &config.namespace, config.floats, set_count, set_size, config.min_value, config.max_value, encoding )
+            };
+            let raw = describe( Encoding::Raw ).file_name();
+            commands.push( format!( "scalar_set_eval new{} --seed={} {} {} {} {} {}", if config.floats { " --floats" } else { "" },
+                    seed, raw, config.min_value, config.max_value, set_size, set_count ) );
+            let mut files = vec![ raw.clone() ];
+            for encoding in encodings.iter().filter( |e| **e != Encoding::Raw )
+            {
+                let file = describe( *encoding ).file_name();
+                commands.push( format!( "scalar_set_eval convert{} --encoding={} {} {}", if config.floats { " --floats" } else { "" },
+                        encoding::name( *encoding ), raw, file ) );
+                files.push( file );
+            }
+            artifacts.push( RunnerArtifact { set_size: set_size, set_count: set_count, seed: seed, files: files } );
+        }
+        return Runner {
+            version: env!( "CARGO_PKG_VERSION" ).to_string(),
+            environment: Environment::current(),
+            config: config,
+            scenarios: scenarios.clone(),
+            artifacts: artifacts,
+            query_seed: rand::random(),
+            commands: commands,
+        };
+    }
+
+    /// Writes the runner into the directory together with the query file of the test.
+    pub fn emit(
+        mut self,
+        directory: &String,
+    )
+    {
+        std::fs::create_dir_all( directory ).expect( "Failed to create the directory of the runner." );
+        if let Some( query ) = self.config.query_file.take()
+        {
+            std::fs::copy( &query, Path::new( directory ).join( QUERY ) ).expect( "Failed to copy the query file." );
+            self.config.query_file = Some( QUERY.to_string() );
+        }
+        self.commands.push( format!( "scalar_set_eval test --from-runner={}", directory ) );
+        let writer = BufWriter::new( std::fs::File::create( Path::new( directory ).join( MANIFEST ) )
+                .expect( "Failed to open the run manifest." ) );
+        serde_json::to_writer_pretty( writer, &self ).expect( "Writing the run manifest failed." );
+    }
+
+    /// Reads the runner from the directory. The query file is resolved relative to the directory.
+    pub fn read( directory: &String ) -> Runner
+    {
+        let reader = std::fs::File::open( Path::new( directory ).join( MANIFEST ) ).expect( "Failed to open the run manifest." );
+        let mut runner: Runner = serde_json::from_reader( reader ).expect( &format!( "Invalid run manifest in {}.", directory ) );
+        if runner.version != env!( "CARGO_PKG_VERSION" )
+        {
+            println!( "WARNING: The runner was emitted by version {} instead of {}.", runner.version, env!( "CARGO_PKG_VERSION" ) );
+        }
+        runner.config.query_file = runner.config.query_file
+                .map( |q| Path::new( directory ).join( q ).to_string_lossy().into_owned() );
+        return runner;
+    }
+
+    /// Gets the files of the runner in the directory read by the test.
+    pub fn files( directory: &String ) -> Vec<String>
+    {
+        return [ MANIFEST, QUERY ].iter()
+                .map( |f| Path::new( directory ).join( f ).to_string_lossy().into_owned() )
+                .collect();
+    }
+
+    /// Gets the seed of the sets of each set size and set count.
+    pub fn seeds( &self ) -> HashMap<( i32, u64 ), u64>
+    {
+        return self.artifacts.iter().map( |a| ( ( a.set_size, a.set_count ), a.seed ) ).collect();
+    }
+
+    /// Checks that the expected files were generated with the seeds of the runner.
+    pub fn verify_artifacts( &self )
+    {
+        for artifact in &self.artifacts
+        {
+            for file in &artifact.files
+            {
+                match artifact::read_manifest( file )
+                {
+                    Some( ref manifest ) if manifest.seed == artifact.seed => {},
+                    Some( manifest ) => panic!( "{} was generated with seed {} instead of {}.", file, manifest.seed, artifact.seed ),
+                    None => panic!( "The expected artifact {} is missing.", file ),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+extern crate std;
+
+use std::io::{BufRead, BufReader};
+
+/// A single scenario of the test matrix.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Scenario
+{
+    pub set_size: i32,
+    pub set_count: u64,
+    pub test_set_size: i32,
+    pub tags: Vec<String>,
+}
+
+/// Gets the built-in test matrix.
+pub fn default_scenarios() -> Vec<Scenario>
+{
+    let mut scenarios = Vec::new();
+    for set_size in &[ 10, 100, 1000, 10000 ]
+    {
+        for set_count in &[ 10, 100, 1000, 10000, 100000 ]
+        {
+            for test_set_size in &[ 10, 100, 1000, 10000 ]
+            {
+                scenarios.push( new_scenario( *set_size, *set_count, *test_set_size, Vec::new() ) );
+            }
+        }
+    }
+    return scenarios;
+}
+
+/// Reads the scenarios from a file.
+/// Each line holds the number of values in a set, the number of sets and the size of the test set
+/// followed by optional tags, e.g. "1000 100000 10 large gpu-only". Lines starting with '#' are comments.
+pub fn read_scenarios( file: &String ) -> Vec<Scenario>
+{
+    let reader = BufReader::new( std::fs::File::open( file ).expect( "Failed to open the scenario file." ) );
+    let mut scenarios = Vec::new();
+    for line in reader.lines()
+    {
+        let line = line.expect( "Reading the scenario file failed." );
+        let line = line.trim();
+        if line.is_empty() || line.starts_with( '#' )
+        {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3
+        {
+            panic!( "Invalid scenario: {}", line );
+        }
+        let set_size = fields[ 0 ].parse().expect( &format!( "Invalid scenario: {}", line ) );
+        let set_count = fields[ 1 ].parse().expect( &format!( "Invalid scenario: {}", line ) );
+        let test_set_size = fields[ 2 ].parse().expect( &format!( "Invalid scenario: {}", line ) );
+        let tags = fields[ 3.. ].iter().map( |t| t.to_string() ).collect();
+        scenarios.push( new_scenario( set_size, set_count, test_set_size, tags ) );
+    }
+
+    // The report groups the results by the number of values in a set.
+    scenarios.sort_by_key( |s| ( s.set_size, s.set_count, s.test_set_size ) );
+    return scenarios;
+}
+
+/// Selects the scenarios with any of the "only" tags and none of the "skip" tags.
+/// All the scenarios pass the "only" filter when no tags are given.
+pub fn select(
+    scenarios: Vec<Scenario>,
+    only: &[String],
+    skip: &[String],
+) -> Vec<Scenario>
+{
+    scenarios.into_iter()
+        .filter( |s| only.is_empty() || s.tags.iter().any( |t| only.contains( t ) ) )
+        .filter( |s| !s.tags.iter().any( |t| skip.contains( t ) ) )
+        .collect()
+}
+
+/// Creates a scenario tagged also with the size class of its sets.
+fn new_scenario(
+    set_size: i32,
+    set_count: u64,
+    test_set_size: i32,
+    mut tags: Vec<String>,
+) -> Scenario
+{
+    let size = get_size_tag( set_size, set_count ).to_string();
+    if !tags.contains( &size )
+    {
+        tags.push( size );
+    }
+    return Scenario { set_size: set_size, set_count: set_count, test_set_size: test_set_size, tags: tags };
+}
+
+/// Classifies the scenario by the total number of values in its sets.
+fn get_size_tag(
+    set_size: i32,
+    set_count: u64,
+) -> &'static str
+{
+    let values = set_size as u64 * set_count;
+    if values < 1_000_000
+    {
+        "small"
+    }
+    else if values < 100_000_000
+    {
+        "medium"
+    }
+    else
+    {
+        "large"
+    }
+}
@@ -0,0 +1,131 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::BufWriter;
+
+use enumerations::*;
+use evaluation::*;
+use mapping::MappedFile;
+
+/// Number of sets written to the self-test file.
+const SET_COUNT: i32 = 100;
+
+/// Number of values in each set of the self-test file.
+const VALUES_IN_SET: i32 = 10;
+
+/// Validates the file generation, memory mapping and evaluation path on the current platform.
+/// Returns true if all checks passed.
+pub fn run_selftest() -> bool
+{
+    let file_name = std::env::temp_dir()
+        .join( "scalar_set_eval_selftest.bin" )
+        .to_string_lossy()
+        .into_owned();
+    println!( "Running self-test with {}...", file_name );
+
+    // Set i contains values [ i * VALUES_IN_SET, ( i + 1 ) * VALUES_IN_SET ).
+    {
+        let file = std::fs::File::create( &file_name ).expect( "Failed to create the self-test file." );
+        let mut file = BufWriter::new( file );
+        for s in 0..SET_COUNT
+        {
+            let values: Vec<i32> = ( s * VALUES_IN_SET..( s + 1 ) * VALUES_IN_SET ).collect();
+            let set = ro_scalar_set::RoScalarSet::new( values.as_slice() );
+            set.serialize( &mut file ).expect( "Writing scalar set to a file failed." );
+        }
+    }
+
+    let mut passed = check_mapping( &file_name );
+
+    // Queries and the number of sets they are expected to match.
+    let queries: Vec<( Vec<i32>, u64 )> = vec![
+        ( vec![ 35 ], 1 ),
+        ( ( 0..SET_COUNT ).map( |s| s * VALUES_IN_SET * 10 ).collect(), 10 ),
+        ( vec![ SET_COUNT * VALUES_IN_SET + 1 ], 0 ),
+    ];
+    for preload in vec![ PreloadMode::Disabled, PreloadMode::Full ]
+    {
+        for &( ref query, expected ) in &queries
+        {
+            let params = EvaluationParams
+            {
+                file: &file_name,
+                values_in_set: query.len() as i32,
+                min_value: 0,
+                max_value: SET_COUNT * VALUES_IN_SET,
+                preload: preload,
+                max_threads: 0,
+                prefetch_distance: 0,
+                cache_size: 0,
+                io_limit: 0,
+                verify_sample: 0,
+                operation: Operation::Any,
+                dimension: None,
+                transform: None,
+                query_block: 0,
+                strategy: Strategy::Auto,
+                breakdown: false,
+                explain: false,
+                set_results: false,
+                exclude_file: None,
+                set_filter: SetFilter::all(),
+                eval_engine: "cpu",
+                audit: 0.0,
+                audit_engine: "cpu",
+                repro: false,
+                gpu_compare: GpuCompare::Epsilon,
+                gpu_memory: GpuMemory::Default,
+                gpu_kernel: GpuKernel::Item,
+                top_k: 0,
+                memory_limit: 0,
+                timeout: None,
+                cancellation: None,
+                signature: None,
+            };
+            let result = evaluate_with_query( &params, query );
+            passed &= report(
+                &format!( "{} query values, preload: {}", query.len(), preload_name( preload ) ),
+                result.match_count == expected,
+            );
+        }
+    }
+
+    // The file must not remain locked after the evaluation has released the mapping.
+    passed &= report(
+        "Removing the mapped file",
+        std::fs::remove_file( &file_name ).is_ok(),
+    );
+    return passed;
+}
+
+/// Checks that the file maps with the expected size.
+fn check_mapping( file_name: &String ) -> bool
+{
+    let file = MappedFile::open( file_name ).expect( "Failed to map the file" );
+    let bytes = std::fs::metadata( file_name ).expect( "Failed to query the file." ).len();
+    let values: &[i32] = file.as_slice();
+    return report(
+        "Mapping the file",
+        file.len() as u64 == bytes && values.len() as u64 * 4 == bytes,
+    );
+}
+
+/// Gets a printable name for the preload mode.
+fn preload_name( preload: PreloadMode ) -> &'static str
+{
+    match preload
+    {
+        PreloadMode::Disabled => "none",
+        PreloadMode::Full => "full",
+    }
+}
+
+/// Prints the outcome of a single check.
+fn report(
+    check: &str,
+    passed: bool,
+) -> bool
+{
+    println!( "{}: {}", check, if passed { "OK" } else { "FAILED" } );
+    return passed;
+}
@@ -0,0 +1,732 @@
+extern crate rayon;
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, BufWriter};
+use std::io::prelude::*;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex, RwLock};
+
+use cancel::CancellationToken;
+use encoding;
+use enumerations::*;
+use evaluation::*;
+use mapping::MappedFile;
+use traits::*;
+use workers;
+
+/// A set file served under a name.
+pub struct CorpusSpec
+{
+    pub name: String,
+    pub file: String,
+    /// The sets hold floating point values instead of integers.
+    pub floats: bool,
+}
+
+/// Sets of a corpus attached with the type of their values.
+enum Sets<'a>
+{
+    Integers( SetsForEvaluation<'a, i32> ),
+    Floats( SetsForEvaluation<'a, f32> ),
+}
+
+/// Options for attaching the sets of a served file.
+#[derive(Clone, Copy)]
+struct AttachOptions
+{
+    preload: PreloadMode,
+    cache_size: usize,
+    /// Threads of the pool evaluating the queries which share the cache.
+    thread_count: usize,
+    io_limit: usize,
+    timeout: Option<std::time::Duration>,
+}
+
+/// A corpus attached for serving.
+struct Corpus<'a>
+{
+    name: String,
+    /// File checked for appended sets before each query. The sets of the shared index and of encrypted files are not extended.
+    file: Option<String>,
+    floats: bool,
+    /// Sets of the file in the order they were appended to it. The first segment holds the sets of the file when the server started.
+    segments: RwLock<Vec<&'a Sets<'a>>>,
+    /// Size of the file in bytes up to the end of the last attached set.
+    attached_bytes: Mutex<u64>,
+    /// Match counts of the earlier queries and the number of the segments they cover.
+    results: Mutex<HashMap<Vec<u64>, ( usize, u64 )>>,
+    queries: AtomicUsize,
+    matches: AtomicUsize,
+    /// Number of the queries stopped by the timeout.
+    timeouts: AtomicUsize,
+    /// Total latency of the queries in microseconds.
+    latency: AtomicUsize,
+}
+
+/// Largest number of the queries with their match counts kept per corpus. The counts are forgotten when the limit is reached.
+const MAX_CACHED_RESULTS: usize = 10000;
+
+/// Outcome of evaluating a query.
+enum Outcome
+{
+    /// Number of matching sets, latency since the start in microseconds and whether the evaluation was stopped by the timeout.
+    Answered( u64, u64, bool ),
+    Invalid,
+    /// The client disconnected during the evaluation.
+    Cancelled,
+}
+
+/// Parses a corpus given as "<name>=<file>" or "<name>:<type>=<file>" where the type is "ints" or "floats".
+/// The type defaults to the type of the other sets.
+pub fn parse_corpus(
+    spec: &str,
+    floats: bool,
+) -> Option<CorpusSpec>
+{
+    let mut parts = spec.splitn( 2, '=' );
+    let name = parts.next()?;
+    let file = parts.next()?;
+    let mut name_parts = name.splitn( 2, ':' );
+    let name = name_parts.next()?;
+    let floats = match name_parts.next()
+    {
+        None => floats,
+        Some( "ints" ) => false,
+        Some( "floats" ) => true,
+        Some( _ ) => return None,
+    };
+    if name.is_empty() || file.is_empty()
+    {
+        return None;
+    }
+    return Some( CorpusSpec { name: name.to_string(), file: file.to_string(), floats: floats } );
+}
+
+/// Serves queries against the sets of the corpora over TCP.
+/// Each request is a line of comma or whitespace separated values optionally preceded by "corpus=<name>"
+/// and each response is a line with the number of matching sets and the latency of the request in microseconds.
+/// A query stopped by the timeout of the parameters is answered with the sets matched before it followed by "timeout".
+/// Requests without a corpus are evaluated against the first corpus. A "stats" request is answered
+/// with a line of statistics per corpus followed by an empty line.
+/// The sets are attached once and shared by all the connections. Sets appended to a raw file, for example by
+/// concatenating the file of another "new" command to it, are attached before the next query and the repeated queries
+/// evaluate only the appended sets adding them to the earlier match counts. Files rewritten in place are not supported.
+/// Each connection is read on its own thread
+/// and the queries are evaluated in a thread pool bounded by max_threads so that idle connections never hold
+/// the threads of the evaluation. In a worker process the sets of the single corpus are attached
+/// from the shared index and the connections are accepted from the inherited socket. With the RESP protocol the connections speak a subset of
+/// the Redis protocol instead. The evaluation of a query is cancelled when its client closes the connection
+/// so the clients must keep the connection open until they have read the response.
+pub fn serve(
+    params: &EvaluationParams,
+    corpora: &Vec<CorpusSpec>,
+    address: &str,
+    protocol: Protocol,
+)
+{
+    // The server runs until the process exits so the mapped files, the attached sets and the thread pool
+    // are kept for the rest of the process and shared by the connection threads.
+    let files: &'static Vec<MappedFile> = Box::leak( Box::new( corpora.iter()
+            .map( |c| MappedFile::open( &c.file ).expect( "Failed to map the file" ) )
+            .collect() ) );
+    let index = workers::shared_index();
+    let options = AttachOptions { preload: params.preload, cache_size: params.cache_size, thread_count: params.max_threads, io_limit: params.io_limit, timeout: params.timeout };
+    let mut attached: Vec<Corpus<'static>> = Vec::new();
+    for ( spec, file ) in corpora.iter().zip( files )
+    {
+        if encoding::detect( file.as_slice() ) != Encoding::Raw
+        {
+            panic!( "Only raw sets can be served." );
+        }
+        if attached.iter().any( |c| c.name == spec.name )
+        {
+            panic!( "Corpus {} is defined more than once.", spec.name );
+        }
+        let start = std::time::Instant::now();
+        let offsets = index.as_ref().map( |&( _, ref index )| index.as_slice::<u64>() );
+        let sets = if spec.floats
+        {
+            Sets::Floats( attach( file.as_slice(), offsets, &options ) )
+        }
+        else
+        {
+            Sets::Integers( attach( file.as_slice(), offsets, &options ) )
+        };
+        let attached_bytes = match sets
+        {
+            Sets::Integers( ref sets ) => sets.attached_len() * std::mem::size_of::<i32>(),
+            Sets::Floats( ref sets ) => sets.attached_len() * std::mem::size_of::<f32>(),
+        };
+        if let Some( ( worker, _ ) ) = index
+        {
+            let elapsed = std::time::Instant::now().duration_since( start );
+            println!( "Worker {} attached the sets from the shared index in {} ms", worker,
+                    elapsed.as_secs() * 1000 + ( elapsed.subsec_nanos() / 1000000 ) as u64 );
+        }
+        // The sets appended to a shared index or an encrypted file are not followed.
+        let segment: &'static Sets<'static> = Box::leak( Box::new( sets ) );
+        let followed = index.is_none() && !file.is_decrypted();
+        attached.push( Corpus { name: spec.name.clone(), file: if followed { Some( spec.file.clone() ) } else { None },
+                floats: spec.floats, segments: RwLock::new( vec![ segment ] ), attached_bytes: Mutex::new( attached_bytes as u64 ),
+                results: Mutex::new( HashMap::new() ), queries: AtomicUsize::new( 0 ), matches: AtomicUsize::new( 0 ), timeouts: AtomicUsize::new( 0 ),
+                latency: AtomicUsize::new( 0 ) } );
+    }
+
+    if index.is_some() && corpora.len() > 1
+    {
+        panic!( "Worker processes serve a single corpus." );
+    }
+    let listener = if index.is_some()
+    {
+        workers::inherited_listener()
+    }
+    else
+    {
+        let listener = TcpListener::bind( address ).expect( "Failed to bind the address." );
+        for spec in corpora
+        {
+            println!( "Serving {} as {} at {}...", spec.file, spec.name, address );
+        }
+        listener
+    };
+
+    // All the corpora share the same thread pool which only evaluates the queries.
+    let threads: &'static rayon::ThreadPool = Box::leak( Box::new( rayon::ThreadPool::new(
+            rayon::Configuration:: new().num_threads( params.max_threads )
+    ).unwrap() ) );
+    let corpora: &'static Vec<Corpus<'static>> = Box::leak( Box::new( attached ) );
+
+    // The calling thread only accepts the connections.
+    for stream in listener.incoming()
+    {
+        match stream
+        {
+            Ok( stream ) =>
+            {
+                std::thread::spawn( move || {
+                    let handled = match protocol
+                    {
+                        Protocol::Text => handle_connection( stream, corpora, threads, options ),
+                        Protocol::Resp => handle_resp_connection( stream, corpora, threads, options ),
+                    };
+                    if let Err( e ) = handled
+                    {
+                        println!( "Connection failed: {}", e );
+                    }
+                } );
+            },
+            Err( e ) => println!( "Accepting a connection failed: {}", e ),
+        }
+    }
+}
+
+/// Attaches the sets of the file for serving, from the offsets of the shared index when given.
+fn attach<'a, T>(
+    data: &'a [T],
+    offsets: Option<&[u64]>,
+    options: &AttachOptions,
+) -> SetsForEvaluation<'a, T>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu,
+{
+    let mut sets = match offsets
+    {
+        Some( offsets ) => load_indexed( data, offsets, options.preload, options.cache_size, options.thread_count, options.io_limit ),
+        None => load_data( data, options.preload, options.cache_size, options.thread_count, options.io_limit, &SetFilter::all() ),
+    };
+    if let Some( timeout ) = options.timeout
+    {
+        sets.time_out_after( timeout );
+    }
+    return sets;
+}
+
+/// Attaches the sets appended to the file of the corpus since the last attached set as a new segment.
+/// The file is mapped again for the appended sets and the earlier mappings are kept for the earlier segments.
+/// A set still being written is attached once it is complete.
+fn attach_appended(
+    corpus: &Corpus<'static>,
+    options: &AttachOptions,
+)
+{
+    let file = match corpus.file
+    {
+        Some( ref file ) => file,
+        None => return,
+    };
+    let length = match std::fs::metadata( file )
+    {
+        Ok( metadata ) => metadata.len(),
+        Err( _ ) => return,
+    };
+    let mut attached_bytes = corpus.attached_bytes.lock().unwrap();
+    if length <= *attached_bytes
+    {
+        return;
+    }
+    let mapped: &'static MappedFile = match MappedFile::open( file )
+    {
+        Ok( mapped ) => Box::leak( Box::new( mapped ) ),
+        Err( e ) =>
+        {
+            println!( "Mapping the grown file {} failed: {}", file, e );
+            return;
+        },
+    };
+    let start = *attached_bytes as usize;
+    let ( sets, appended_bytes, set_count ) = if corpus.floats
+    {
+        let sets = attach( &mapped.as_slice::<f32>()[ start / std::mem::size_of::<f32>().. ], None, options );
+        let appended = sets.attached_len() * std::mem::size_of::<f32>();
+        let count = sets.set_count();
+        ( Sets::Floats( sets ), appended, count )
+    }
+    else
+    {
+        let sets = attach( &mapped.as_slice::<i32>()[ start / std::mem::size_of::<i32>().. ], None, options );
+        let appended = sets.attached_len() * std::mem::size_of::<i32>();
+        let count = sets.set_count();
+        ( Sets::Integers( sets ), appended, count )
+    };
+    if set_count == 0
+    {
+        return;
+    }
+    *attached_bytes += appended_bytes as u64;
+    corpus.segments.write().unwrap().push( Box::leak( Box::new( sets ) ) );
+    println!( "Attached {} sets appended to {} as {}", set_count, file, corpus.name );
+}
+
+/// Answers the queries of a single connection until it is closed.
+fn handle_connection(
+    stream: TcpStream,
+    corpora: &'static Vec<Corpus<'static>>,
+    threads: &'static rayon::ThreadPool,
+    options: AttachOptions,
+) -> std::io::Result<()>
+{
+    let reader = BufReader::new( stream.try_clone()? );
+    let mut writer = BufWriter::new( stream );
+    for line in reader.lines()
+    {
+        let line = line?;
+        let start = std::time::Instant::now();
+        if line.trim() == "stats"
+        {
+            for corpus in corpora
+            {
+                let queries = corpus.queries.load( Ordering::Relaxed );
+                writeln!(
+                    &mut writer,
+                    "{} queries={} matches={} timeouts={} average_latency={}",
+                    corpus.name,
+                    queries,
+                    corpus.matches.load( Ordering::Relaxed ),
+                    corpus.timeouts.load( Ordering::Relaxed ),
+                    corpus.latency.load( Ordering::Relaxed ) / std::cmp::max( 1, queries )
+                )?;
+            }
+            writeln!( &mut writer, "" )?;
+            writer.flush()?;
+            continue;
+        }
+
+        // Route the query to the named corpus.
+        let ( corpus, query ) = if line.trim_start().starts_with( "corpus=" )
+        {
+            let mut parts = line.trim_start()[ "corpus=".len().. ].splitn( 2, |c: char| c.is_whitespace() );
+            let name = parts.next().unwrap_or( "" );
+            ( corpora.iter().find( |c| c.name == name ), parts.next().unwrap_or( "" ) )
+        }
+        else
+        {
+            ( corpora.first(), &line[..] )
+        };
+        let corpus = match corpus
+        {
+            Some( corpus ) => corpus,
+            None =>
+            {
+                writeln!( &mut writer, "error: unknown corpus" )?;
+                writer.flush()?;
+                continue;
+            },
+        };
+
+        match evaluate_query( corpus, query.to_string(), &options, start, writer.get_ref(), threads )
+        {
+            Outcome::Answered( match_count, latency, false ) => writeln!( &mut writer, "{} {}", match_count, latency )?,
+            Outcome::Answered( match_count, latency, true ) => writeln!( &mut writer, "{} {} timeout", match_count, latency )?,
+            Outcome::Invalid => writeln!( &mut writer, "error: invalid query" )?,
+            Outcome::Cancelled => writeln!( &mut writer, "error: cancelled" )?,
+        }
+        writer.flush()?;
+    }
+    return Ok( () );
+}
+
+/// Answers the Redis commands of a single connection until it is closed.
+/// "QUERY <values...>" replies with the number of sets of the first corpus with any of the values and
+/// "SISMEMBERLIKE <corpus> <value>" with the number of sets of the corpus with the value.
+/// PING, QUIT and COMMAND are answered for the client libraries.
+#[cfg(feature="resp")]
+fn handle_resp_connection(
+    stream: TcpStream,
+    corpora: &'static Vec<Corpus<'static>>,
+    threads: &'static rayon::ThreadPool,
+    options: AttachOptions,
+) -> std::io::Result<()>
+{
+    let mut reader = BufReader::new( stream.try_clone()? );
+    let mut writer = BufWriter::new( stream );
+    loop
+    {
+        let command = match read_resp_command( &mut reader )
+        {
+            Ok( Some( command ) ) => command,
+            Ok( None ) => break,
+
+            // The rest of the connection cannot be parsed after a protocol error.
+            Err( ref e ) if e.kind() == std::io::ErrorKind::InvalidData =>
+            {
+                write!( &mut writer, "-ERR Protocol error: {}\r\n", e )?;
+                writer.flush()?;
+                break;
+            },
+            Err( e ) => return Err( e ),
+        };
+        let start = std::time::Instant::now();
+        let name = command.first().map( |c| c.to_uppercase() ).unwrap_or( String::new() );
+        let ( corpus, query ) = match name.as_str()
+        {
+            "" => continue,
+            "PING" =>
+            {
+                write!( &mut writer, "+PONG\r\n" )?;
+                writer.flush()?;
+                continue;
+            },
+            "QUIT" =>
+            {
+                write!( &mut writer, "+OK\r\n" )?;
+                writer.flush()?;
+                break;
+            },
+            "COMMAND" =>
+            {
+                write!( &mut writer, "*0\r\n" )?;
+                writer.flush()?;
+                continue;
+            },
+            "QUERY" => ( corpora.first(), command[ 1.. ].join( " " ) ),
+            "SISMEMBERLIKE" if command.len() == 3 => ( corpora.iter().find( |c| c.name == command[ 1 ] ), command[ 2 ].clone() ),
+            "SISMEMBERLIKE" =>
+            {
+                write!( &mut writer, "-ERR wrong number of arguments for 'sismemberlike' command\r\n" )?;
+                writer.flush()?;
+                continue;
+            },
+            _ =>
+            {
+                write!( &mut writer, "-ERR unknown command '{}'\r\n", command[ 0 ] )?;
+                writer.flush()?;
+                continue;
+            },
+        };
+        match corpus.map( |corpus| evaluate_query( corpus, query, &options, start, writer.get_ref(), threads ) )
+        {
+            Some( Outcome::Answered( match_count, _, false ) ) => write!( &mut writer, ":{}\r\n", match_count )?,
+            Some( Outcome::Answered( match_count, _, true ) ) =>
+                    write!( &mut writer, "-TIMEOUT {} sets matched before the timeout\r\n", match_count )?,
+            Some( Outcome::Invalid ) => write!( &mut writer, "-ERR invalid query\r\n" )?,
+            Some( Outcome::Cancelled ) => write!( &mut writer, "-ERR cancelled\r\n" )?,
+            None => write!( &mut writer, "-ERR unknown corpus\r\n" )?,
+        }
+        writer.flush()?;
+    }
+    return Ok( () );
+}
+
+/// The RESP protocol is not enabled.
+#[cfg(not(feature="resp"))]
+fn handle_resp_connection(
+    _stream: TcpStream,
+    _corpora: &'static Vec<Corpus<'static>>,
+    _threads: &'static rayon::ThreadPool,
+    _options: AttachOptions,
+) -> std::io::Result<()>
+{
+    panic!( "Serving the RESP protocol requires the resp feature." );
+}
+
+/// Largest number of the arguments of a command as in Redis.
+#[cfg(feature="resp")]
+const MAX_ARGUMENTS: usize = 1024 * 1024;
+
+/// Largest bulk string in bytes as in Redis.
+#[cfg(feature="resp")]
+const MAX_BULK_LENGTH: usize = 512 * 1024 * 1024;
+
+/// Largest inline command or header line in bytes as in Redis.
+#[cfg(feature="resp")]
+const MAX_INLINE_LENGTH: u64 = 64 * 1024;
+
+/// Reads a command sent as an array of bulk strings or as an inline command.
+/// Returns None when the connection is closed. Malformed or oversized commands fail with InvalidData
+/// before anything is allocated for them.
+#[cfg(feature="resp")]
+fn read_resp_command<R>( reader: &mut R ) -> std::io::Result<Option<Vec<String>>>
+where
+    R: BufRead,
+{
+    let mut line = String::new();
+    if read_resp_line( reader, &mut line )? == 0
+    {
+        return Ok( None );
+    }
+    let line = line.trim_end();
+    if !line.starts_with( '*' )
+    {
+        return Ok( Some( line.split_whitespace().map( |t| t.to_string() ).collect() ) );
+    }
+    let count = parse_resp_length( &line[ 1.. ], MAX_ARGUMENTS, "invalid multibulk length" )?;
+    let mut arguments = Vec::new();
+    for _ in 0..count
+    {
+        let mut header = String::new();
+        read_resp_line( reader, &mut header )?;
+        let header = header.trim_end();
+        if !header.starts_with( '$' )
+        {
+            return Err( std::io::Error::new( std::io::ErrorKind::InvalidData, "expected '$'" ) );
+        }
+
+        // The bulk string is followed by CRLF. The buffer grows only as the data arrives.
+        let length = parse_resp_length( &header[ 1.. ], MAX_BULK_LENGTH, "invalid bulk length" )?;
+        let length = length.checked_add( 2 )
+                .ok_or( std::io::Error::new( std::io::ErrorKind::InvalidData, "invalid bulk length" ) )?;
+        let mut argument = Vec::new();
+        reader.by_ref().take( length as u64 ).read_to_end( &mut argument )?;
+        if argument.len() != length
+        {
+            return Err( std::io::Error::new( std::io::ErrorKind::UnexpectedEof, "The bulk string was cut short." ) );
+        }
+        argument.truncate( length - 2 );
+        arguments.push( String::from_utf8_lossy( &argument ).into_owned() );
+    }
+    return Ok( Some( arguments ) );
+}
+
+/// Reads a line of at most MAX_INLINE_LENGTH bytes.
+#[cfg(feature="resp")]
+fn read_resp_line<R>(
+    reader: &mut R,
+    line: &mut String,
+) -> std::io::Result<usize>
+where
+    R: BufRead,
+{
+    let read = reader.by_ref().take( MAX_INLINE_LENGTH ).read_line( line )?;
+    if read as u64 == MAX_INLINE_LENGTH && !line.ends_with( '\n' )
+    {
+        return Err( std::io::Error::new( std::io::ErrorKind::InvalidData, "too big inline request" ) );
+    }
+    return Ok( read );
+}
+
+/// Parses the length of an array or a bulk string of at most the given limit.
+#[cfg(feature="resp")]
+fn parse_resp_length(
+    text: &str,
+    limit: usize,
+    error: &str,
+) -> std::io::Result<usize>
+{
+    return match text.parse::<usize>()
+    {
+        Ok( length ) if length <= limit => Ok( length ),
+        _ => Err( std::io::Error::new( std::io::ErrorKind::InvalidData, error ) ),
+    };
+}
+
+/// Evaluates the query against the corpus in the thread pool and records it in the statistics of the corpus.
+/// The connection thread watches the connection while the evaluation runs and cancels the evaluation
+/// if the client disconnects.
+fn evaluate_query(
+    corpus: &'static Corpus<'static>,
+    query: String,
+    options: &AttachOptions,
+    start: std::time::Instant,
+    stream: &TcpStream,
+    threads: &'static rayon::ThreadPool,
+) -> Outcome
+{
+    attach_appended( corpus, options );
+    let cancellation = CancellationToken::new();
+    let ( sender, receiver ) = mpsc::channel();
+    let token = cancellation.clone();
+    let preload = options.preload;
+    threads.spawn( move || {
+        let result = if corpus.floats
+        {
+            parse_query::<f32>( &query ).map( |test_set| {
+                let set = ro_scalar_set::RoScalarSet::new( &test_set );
+                evaluate_segments( corpus, &test_set, &token, |segment| match *segment
+                {
+                    Sets::Floats( ref sets ) => sets.evaluate_in_current_pool( &set, preload, Some( &token ) ),
+                    Sets::Integers( _ ) => panic!( "The corpus holds floating point values." ),
+                } )
+            } )
+        }
+        else
+        {
+            parse_query::<i32>( &query ).map( |test_set| {
+                let set = ro_scalar_set::RoScalarSet::new( &test_set );
+                evaluate_segments( corpus, &test_set, &token, |segment| match *segment
+                {
+                    Sets::Integers( ref sets ) => sets.evaluate_in_current_pool( &set, preload, Some( &token ) ),
+                    Sets::Floats( _ ) => panic!( "The corpus holds integer values." ),
+                } )
+            } )
+        };
+        let _ = sender.send( result );
+    } );
+
+    // A client that had already shut down its side when the query started half-closed the connection
+    // after sending its queries and still reads the replies.
+    let half_closed = hang_up( stream ).0;
+    let result = loop
+    {
+        match receiver.recv_timeout( std::time::Duration::from_millis( DISCONNECT_POLL_MILLISECONDS ) )
+        {
+            Ok( result ) => break result,
+            Err( mpsc::RecvTimeoutError::Timeout ) =>
+            {
+                let ( shut_down, reset ) = hang_up( stream );
+                if reset || ( shut_down && !half_closed )
+                {
+                    cancellation.cancel();
+                }
+            },
+            Err( mpsc::RecvTimeoutError::Disconnected ) => panic!( "The evaluation of the query failed." ),
+        }
+    };
+    let ( match_count, timed_out ) = match result
+    {
+        Some( result ) => result,
+        None => return Outcome::Invalid,
+    };
+    if cancellation.is_cancelled()
+    {
+        println!( "Cancelled the query of a disconnected client." );
+        return Outcome::Cancelled;
+    }
+    let latency = std::time::Instant::now().duration_since( start );
+    let latency = latency.as_secs() * 1000000 + ( latency.subsec_nanos() / 1000 ) as u64;
+    corpus.queries.fetch_add( 1, Ordering::Relaxed );
+    corpus.matches.fetch_add( match_count as usize, Ordering::Relaxed );
+    corpus.latency.fetch_add( latency as usize, Ordering::Relaxed );
+    if timed_out
+    {
+        corpus.timeouts.fetch_add( 1, Ordering::Relaxed );
+    }
+    return Outcome::Answered( match_count, latency, timed_out );
+}
+
+/// Evaluates the segments of the corpus not covered by the earlier match count of the same values with the function.
+/// Returns the match count of all the segments and whether the evaluation was stopped by the timeout.
+/// The counts of the evaluations stopped by the timeout or cancelled are not kept.
+fn evaluate_segments<T, F>(
+    corpus: &Corpus<'static>,
+    test_set: &[T],
+    cancellation: &CancellationToken,
+    evaluate: F,
+) -> ( u64, bool )
+where
+    T: OrderedBits,
+    F: Fn( &Sets ) -> EvaluationResult,
+{
+    let mut key: Vec<u64> = test_set.iter().map( |v| v.to_ordered_bits() ).collect();
+    key.sort();
+    key.dedup();
+    let segments = corpus.segments.read().unwrap().clone();
+    let ( covered, mut match_count ) = corpus.results.lock().unwrap().get( &key ).cloned().unwrap_or( ( 0, 0 ) );
+    let mut timed_out = false;
+    for segment in &segments[ covered.. ]
+    {
+        let result = evaluate( segment );
+        match_count += result.match_count;
+        timed_out |= result.timed_out;
+    }
+    if !timed_out && !cancellation.is_cancelled() && covered < segments.len()
+    {
+        let mut results = corpus.results.lock().unwrap();
+        if results.len() >= MAX_CACHED_RESULTS
+        {
+            results.clear();
+        }
+        results.insert( key, ( segments.len(), match_count ) );
+    }
+    return ( match_count, timed_out );
+}
+
+/// Interval of checking whether the client closed the connection during an evaluation.
+const DISCONNECT_POLL_MILLISECONDS: u64 = 50;
+
+/// Checks the connection for a hang-up without reading from it so that pipelined queries are left for the reader.
+/// Returns whether the client has shut down its side of the connection and whether the connection was reset or hung up.
+#[cfg(target_os="linux")]
+fn hang_up( stream: &TcpStream ) -> ( bool, bool )
+{
+    use std::os::unix::io::AsRawFd;
+
+    /// Descriptor polled with poll.
+    #[repr(C)]
+    struct PollFd
+    {
+        fd: i32,
+        events: i16,
+        revents: i16,
+    }
+    extern "C"
+    {
+        fn poll( fds: *mut PollFd, count: std::os::raw::c_ulong, timeout: i32 ) -> i32;
+    }
+    const POLLERR: i16 = 0x008;
+    const POLLHUP: i16 = 0x010;
+    const POLLRDHUP: i16 = 0x2000;
+
+    let mut descriptor = PollFd { fd: stream.as_raw_fd(), events: POLLRDHUP, revents: 0 };
+    if unsafe { poll( &mut descriptor, 1, 0 ) } <= 0
+    {
+        return ( false, false );
+    }
+    return ( descriptor.revents & POLLRDHUP != 0, descriptor.revents & ( POLLERR | POLLHUP ) != 0 );
+}
+
+/// The hang-up of the client is only detected on Linux.
+#[cfg(not(target_os="linux"))]
+fn hang_up( _stream: &TcpStream ) -> ( bool, bool )
+{
+    return ( false, false );
+}
+
+/// Parses the values of a query.
+fn parse_query<T>( line: &str ) -> Option<Vec<T>>
+where
+    T: FromI32,
+{
+    let mut values = Vec::new();
+    for token in line.split( |c: char| c == ',' || c.is_whitespace() ).filter( |t| !t.is_empty() )
+    {
+        match token.parse::<i32>()
+        {
+            Ok( v ) => values.push( T::from_i32( &v ) ),
+            Err( _ ) => return None,
+        }
+    }
+    return Some( values );
+}
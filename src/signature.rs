@@ -0,0 +1,112 @@
+extern crate hmac;
+extern crate sha2;
+extern crate std;
+
+use std::io::prelude::*;
+
+use self::hmac::{Hmac, Mac};
+use self::sha2::{Digest, Sha256};
+
+use mapping::MappedFile;
+
+/// HMAC-SHA256 keyed with the contents of the key file.
+type HmacSha256 = Hmac<Sha256>;
+
+/// Gets the name of the file holding the signature of the set file.
+pub fn get_signature_file_name( file: &String ) -> String
+{
+    format!( "{}.sig", file )
+}
+
+/// Signs the set file with the key. The signature is written next to the file.
+pub fn sign(
+    file: &String,
+    key_file: &String,
+)
+{
+    sign_as( file, key_file, &get_signature_file_name( file ) );
+}
+
+/// Signs the set file with the key into the signature file.
+pub fn sign_as(
+    file: &String,
+    key_file: &String,
+    signature_file: &String,
+)
+{
+    let signature = calculate( file, key_file );
+    let mut output = std::fs::File::create( signature_file ).expect( "Failed to open the signature file." );
+    writeln!( &mut output, "{}", signature ).expect( "Writing the signature failed." );
+}
+
+/// Checks that the contents of the mapped file as stored have a valid signature in the signature file for the key.
+/// The mapping evaluated is verified so that the file cannot be replaced in between.
+pub fn verify(
+    mapped: &MappedFile,
+    signature_file: &String,
+    key_file: &String,
+) -> bool
+{
+    let mut stored = String::new();
+    match std::fs::File::open( signature_file )
+    {
+        Ok( mut signature_file ) => signature_file.read_to_string( &mut stored ).expect( "Reading the signature failed." ),
+        Err( _ ) => return false,
+    };
+    let stored = match from_hex( stored.trim() )
+    {
+        Some( stored ) => stored,
+        None => return false,
+    };
+
+    // The comparison takes the same time regardless of where the signatures differ.
+    return mac( mapped.as_stored_slice(), key_file ).verify_slice( &stored ).is_ok();
+}
+
+/// Calculates the SHA-256 hash of the contents of the file as a hex string.
+pub fn digest( file: &String ) -> String
+{
+    let data = MappedFile::open_stored( file ).expect( "Failed to map the file" );
+    return to_hex( &Sha256::digest( data.as_stored_slice() ) );
+}
+
+/// Calculates the signature of the set file as a hex string.
+fn calculate(
+    file: &String,
+    key_file: &String,
+) -> String
+{
+    let data = MappedFile::open_stored( file ).expect( "Failed to map the file" );
+    return to_hex( &mac( data.as_stored_slice(), key_file ).finalize().into_bytes() );
+}
+
+/// Calculates HMAC-SHA256 of the contents of the set file with the key of the key file.
+fn mac(
+    data: &[u8],
+    key_file: &String,
+) -> HmacSha256
+{
+    let mut key = Vec::new();
+    std::fs::File::open( key_file )
+        .and_then( |mut f| f.read_to_end( &mut key ) )
+        .expect( "Failed to read the key file." );
+    let mut mac = HmacSha256::new_from_slice( &key ).expect( "Invalid key." );
+    mac.update( data );
+    return mac;
+}
+
+/// Formats the bytes as a lowercase hex string.
+fn to_hex( bytes: &[u8] ) -> String
+{
+    return bytes.iter().map( |b| format!( "{:02x}", b ) ).collect();
+}
+
+/// Parses a hex string into bytes. Returns None if the string is not valid hex.
+fn from_hex( text: &str ) -> Option<Vec<u8>>
+{
+    if text.len() % 2 != 0 || !text.is_ascii()
+    {
+        return None;
+    }
+    return ( 0..text.len() ).step_by( 2 ).map( |i| u8::from_str_radix( &text[ i..i + 2 ], 16 ).ok() ).collect();
+}
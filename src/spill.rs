@@ -0,0 +1,178 @@
+extern crate byteorder;
+extern crate std;
+
+use std::io::{BufReader, BufWriter};
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+/// Size of the buffers of the spill files in bytes unless given.
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Distinguishes the spill files of the same process.
+static SPILL_COUNTER: AtomicUsize = AtomicUsize::new( 0 );
+
+/// A record that can be spilled to a file.
+pub trait Spillable: Sized + Copy
+{
+    /// Writes the record to the spill file.
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> std::io::Result<()>;
+
+    /// Reads a record written with write_to.
+    fn read_from<R: Read>( reader: &mut R ) -> std::io::Result<Self>;
+}
+
+/// Records kept in memory until they reach the memory limit after which the rest are spilled to a temporary file.
+/// The records are read back in the order they were pushed. The spill file is removed with the spool.
+pub struct Spool<R>
+{
+    records: Vec<R>,
+    /// Number of records kept in memory. All the records are kept in memory without a limit.
+    capacity: Option<usize>,
+    spill: Option<( PathBuf, BufWriter<std::fs::File> )>,
+    /// Number of records in the spill file.
+    spilled: usize,
+    /// Size of the buffers for writing and reading the spill file in bytes.
+    buffer_size: usize,
+}
+
+impl<R> Spool<R>
+where
+    R: Spillable,
+{
+    /// Creates a spool keeping at most memory_limit bytes of records in memory. Zero disables the limit.
+    pub fn new( memory_limit: usize ) -> Spool<R>
+    {
+        let capacity = if memory_limit == 0 { None } else { Some( std::cmp::max( 1, memory_limit / std::mem::size_of::<R>() ) ) };
+        return Spool { records: Vec::new(), capacity: capacity, spill: None, spilled: 0, buffer_size: BUFFER_SIZE };
+    }
+
+    /// Creates a spool spilling all the records to the file through buffers of the given size in bytes.
+    pub fn on_disk_buffered( buffer_size: usize ) -> Spool<R>
+    {
+        return Spool { records: Vec::new(), capacity: Some( 0 ), spill: None, spilled: 0, buffer_size: buffer_size };
+    }
+
+    /// Creates a spool holding the records in memory.
+    pub fn from_vec( records: Vec<R> ) -> Spool<R>
+    {
+        return Spool { records: records, capacity: None, spill: None, spilled: 0, buffer_size: BUFFER_SIZE };
+    }
+
+    /// Appends the record.
+    pub fn push(
+        &mut self,
+        record: R,
+    )
+    {
+        if self.capacity.map( |c| self.records.len() < c ).unwrap_or( true )
+        {
+            self.records.push( record );
+            return;
+        }
+        if self.spill.is_none()
+        {
+            let path = std::env::temp_dir().join( format!( "scalar_set_eval_spill_{}_{}", std::process::id(),
+                    SPILL_COUNTER.fetch_add( 1, Ordering::SeqCst ) ) );
+            let file = std::fs::File::create( &path ).expect( "Failed to open the spill file." );
+            self.spill = Some( ( path, BufWriter::with_capacity( self.buffer_size, file ) ) );
+        }
+        if let Some( ( _, ref mut writer ) ) = self.spill
+        {
+            record.write_to( writer ).expect( "Writing the spill file failed." );
+        }
+        self.spilled += 1;
+    }
+
+    /// Writes the spilled records to the file so that they can be read.
+    pub fn finish( mut self ) -> Spool<R>
+    {
+        if let Some( ( _, ref mut writer ) ) = self.spill
+        {
+            writer.flush().expect( "Writing the spill file failed." );
+        }
+        return self;
+    }
+
+    /// Gets the number of records.
+    pub fn len( &self ) -> usize
+    {
+        return self.records.len() + self.spilled;
+    }
+
+    /// Checks whether there are no records.
+    pub fn is_empty( &self ) -> bool
+    {
+        return self.len() == 0;
+    }
+
+    /// Iterates the records in the order they were pushed.
+    pub fn iter<'a>( &'a self ) -> SpoolIter<'a, R>
+    {
+        let reader = self.spill.as_ref()
+                .map( |&( ref path, _ )| BufReader::with_capacity( self.buffer_size,
+                        std::fs::File::open( path ).expect( "Failed to open the spill file." ) ) );
+        return SpoolIter { records: self.records.iter(), reader: reader, remaining: self.spilled };
+    }
+}
+
+impl<R> Drop for Spool<R>
+{
+    fn drop( &mut self )
+    {
+        if let Some( ( ref path, _ ) ) = self.spill
+        {
+            std::fs::remove_file( path ).ok();
+        }
+    }
+}
+
+/// Reads the records of a spool from the memory and then from the spill file.
+pub struct SpoolIter<'a, R: 'a>
+{
+    records: std::slice::Iter<'a, R>,
+    reader: Option<BufReader<std::fs::File>>,
+    remaining: usize,
+}
+
+impl<'a, R> Iterator for SpoolIter<'a, R>
+where
+    R: Spillable,
+{
+    type Item = R;
+
+    fn next( &mut self ) -> Option<R>
+    {
+        if let Some( record ) = self.records.next()
+        {
+            return Some( *record );
+        }
+        if self.remaining == 0
+        {
+            return None;
+        }
+        self.remaining -= 1;
+        let reader = self.reader.as_mut().expect( "The spill file is missing." );
+        return Some( R::read_from( reader ).expect( "Reading the spill file failed." ) );
+    }
+}
+
+/// Writes a 64-bit unsigned integer field of a record.
+pub fn write_u64<W: Write>(
+    writer: &mut W,
+    value: u64,
+) -> std::io::Result<()>
+{
+    return writer.write_u64::<LittleEndian>( value );
+}
+
+/// Reads a 64-bit unsigned integer field of a record.
+pub fn read_u64<R: Read>( reader: &mut R ) -> std::io::Result<u64>
+{
+    return reader.read_u64::<LittleEndian>();
+}
@@ -0,0 +1,271 @@
+extern crate ro_scalar_set;
+#[cfg(feature="sqlite")]
+extern crate rusqlite;
+extern crate std;
+
+#[cfg(feature="sqlite")]
+use std::collections::HashMap;
+#[cfg(feature="sqlite")]
+use std::io::BufWriter;
+
+use enumerations::*;
+use evaluation::{EvaluationParams, EvaluationResult};
+#[cfg(feature="sqlite")]
+use results;
+
+/// Converts the values of the rows into the values of the sets without loss.
+#[cfg(feature="sqlite")]
+pub trait SqlValue: Sized
+{
+    fn from_integer( value: i64 ) -> Option<Self>;
+    fn from_real( value: f64 ) -> Option<Self>;
+}
+
+/// Integers out of range and reals with a fraction do not fit.
+#[cfg(feature="sqlite")]
+impl SqlValue for i32
+{
+    fn from_integer( value: i64 ) -> Option<i32>
+    {
+        if value < std::i32::MIN as i64 || value > std::i32::MAX as i64
+        {
+            return None;
+        }
+        return Some( value as i32 );
+    }
+
+    fn from_real( value: f64 ) -> Option<i32>
+    {
+        if value.fract() != 0.0 || value < std::i32::MIN as f64 || value > std::i32::MAX as f64
+        {
+            return None;
+        }
+        return Some( value as i32 );
+    }
+}
+
+/// Floats take any number at the precision of f32.
+#[cfg(feature="sqlite")]
+impl SqlValue for f32
+{
+    fn from_integer( value: i64 ) -> Option<f32>
+    {
+        return Some( value as f32 );
+    }
+
+    fn from_real( value: f64 ) -> Option<f32>
+    {
+        return Some( value as f32 );
+    }
+}
+
+/// Imports sets from the rows returned by the query from an SQLite database.
+/// The query returns the group and the value of each row, e.g. "SELECT group_id, value FROM members".
+/// The rows of a group form a set. The sets are written in the order the groups first appear in the rows.
+#[cfg(feature="sqlite")]
+pub fn import<T>(
+    database: &String,
+    query: &String,
+    output: &String,
+)
+where
+    T: SqlValue + ro_scalar_set::Value,
+{
+    let connection = rusqlite::Connection::open_with_flags( database, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY )
+            .expect( &format!( "Failed to open {}.", database ) );
+    let mut statement = connection.prepare( query ).expect( "Invalid query." );
+    if statement.column_count() < 2
+    {
+        panic!( "The query must return the group and the value of each row." );
+    }
+    let mut rows = statement.query( [] ).expect( &format!( "Querying {} failed.", database ) );
+
+    // The rows of a group may appear anywhere in the result.
+    // The groups are told apart by both the type and the value of the column.
+    let mut groups: HashMap<String, usize> = HashMap::new();
+    let mut sets: Vec<Vec<T>> = Vec::new();
+    let mut row_count = 0;
+    while let Some( row ) = rows.next().expect( "Reading the rows failed." )
+    {
+        let group: rusqlite::types::Value = row.get( 0 ).expect( "Reading the group failed." );
+        let value = match row.get_ref( 1 ).expect( "Reading the value failed." )
+        {
+            rusqlite::types::ValueRef::Null => continue,
+            rusqlite::types::ValueRef::Integer( value ) => T::from_integer( value ),
+            rusqlite::types::ValueRef::Real( value ) => T::from_real( value ),
+            _ => panic!( "The value of the group {:?} is not a number.", group ),
+        };
+        let value = match value
+        {
+            Some( value ) => value,
+            None => panic!( "A value of the group {:?} does not fit in the values of the sets.", group ),
+        };
+        let next = sets.len();
+        let index = *groups.entry( format!( "{:?}", group ) ).or_insert( next );
+        if index == next
+        {
+            sets.push( Vec::new() );
+        }
+        sets[ index ].push( value );
+        row_count += 1;
+    }
+
+    let mut writer = BufWriter::with_capacity(
+        1024 * 1024,
+        std::fs::File::create( output ).expect( "Failed to open the file." ),
+    );
+    for values in &sets
+    {
+        ro_scalar_set::RoScalarSet::new( values ).serialize( &mut writer ).expect( "Writing scalar set to a file failed." );
+    }
+    println!( "Imported {} sets from {} rows.", sets.len(), row_count );
+}
+
+/// SQLite support is not enabled.
+#[cfg(not(feature="sqlite"))]
+pub fn import<T>(
+    database: &String,
+    _query: &String,
+    _output: &String,
+)
+where
+    T: ro_scalar_set::Value,
+{
+    panic!( "Importing from {} requires the sqlite feature.", database );
+}
+
+/// Tables of the exported results. A run is one invocation of eval, a query one test set evaluated in the run
+/// and a set result whether a set of the file matched the test set. The sets are identified by their index in the file
+/// which is the order of the groups of the import.
+#[cfg(feature="sqlite")]
+const SCHEMA: &'static str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started INTEGER NOT NULL,
+    host TEXT NOT NULL,
+    version TEXT NOT NULL,
+    git_commit TEXT,
+    file TEXT NOT NULL,
+    engine TEXT NOT NULL,
+    operation TEXT NOT NULL,
+    preload TEXT NOT NULL,
+    threads INTEGER NOT NULL,
+    min_value INTEGER NOT NULL,
+    max_value INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS queries (
+    run_id INTEGER NOT NULL REFERENCES runs( id ),
+    query INTEGER NOT NULL,
+    test_set_size INTEGER NOT NULL,
+    match_count INTEGER NOT NULL,
+    duration_us INTEGER NOT NULL,
+    PRIMARY KEY ( run_id, query )
+);
+CREATE TABLE IF NOT EXISTS set_results (
+    run_id INTEGER NOT NULL,
+    query INTEGER NOT NULL,
+    set_index INTEGER NOT NULL,
+    set_size INTEGER NOT NULL,
+    matched INTEGER NOT NULL,
+    PRIMARY KEY ( run_id, query, set_index ),
+    FOREIGN KEY ( run_id, query ) REFERENCES queries( run_id, query )
+);
+";
+
+/// Checks that the results of each set can be exported with the parameters of the evaluation.
+/// The results of the sets are only recorded for sets matched with any value of the untransformed test set without exclusions.
+pub fn check_params( params: &EvaluationParams ) -> Result<(), String>
+{
+    if !cfg!( feature="sqlite" )
+    {
+        return Err( "Writing the results into SQLite requires the sqlite feature.".to_string() );
+    }
+    if params.operation != Operation::Any || params.transform.is_some() || params.exclude_file.is_some()
+    {
+        return Err( "The results of the sets are only exported for sets matched with any value without transforms or exclusions.".to_string() );
+    }
+    return Ok( () );
+}
+
+/// Exports the results of the evaluation and the metadata of the run into an SQLite database.
+/// The tables are created unless they exist. The sizes are the sizes of the test sets of the results.
+/// The database is written in a single transaction.
+#[cfg(feature="sqlite")]
+pub fn export(
+    database: &String,
+    params: &EvaluationParams,
+    results: &[EvaluationResult],
+    sizes: &[i32],
+)
+{
+    // Results without the result of each set would leave the set results of their queries silently empty.
+    if let Some( query ) = results.iter().position( |r| r.set_results.is_none() )
+    {
+        panic!( "The results of the sets of query {} are missing. Nothing was written to {}.", query, database );
+    }
+    let mut connection = rusqlite::Connection::open( database ).expect( &format!( "Failed to open {}.", database ) );
+    let transaction = connection.transaction().expect( "Failed to start a transaction." );
+    write_results( &transaction, params, results, sizes ).expect( &format!( "Writing the results to {} failed.", database ) );
+    transaction.commit().expect( &format!( "Writing the results to {} failed.", database ) );
+    let set_count: usize = results.iter().map( |r| r.set_results.as_ref().map( |s| s.len() ).unwrap_or( 0 ) ).sum();
+    println!( "Exported {} queries and {} set results to {}.", results.len(), set_count, database );
+}
+
+/// SQLite support is not enabled.
+#[cfg(not(feature="sqlite"))]
+pub fn export(
+    database: &String,
+    _params: &EvaluationParams,
+    _results: &[EvaluationResult],
+    _sizes: &[i32],
+)
+{
+    panic!( "Writing the results to {} requires the sqlite feature.", database );
+}
+
+/// Inserts the run, the queries and the set results.
+#[cfg(feature="sqlite")]
+fn write_results(
+    transaction: &rusqlite::Transaction,
+    params: &EvaluationParams,
+    results: &[EvaluationResult],
+    sizes: &[i32],
+) -> rusqlite::Result<()>
+{
+    let environment = results::Environment::current();
+    let started = std::time::SystemTime::now().duration_since( std::time::UNIX_EPOCH ).map( |d| d.as_secs() ).unwrap_or( 0 );
+    let operation = match params.operation
+    {
+        Operation::Any => "any".to_string(),
+        Operation::WeightedOverlap( threshold ) => format!( "weighted-overlap {}", threshold ),
+    };
+    let preload = match params.preload
+    {
+        PreloadMode::Disabled => "none",
+        PreloadMode::Full => "full",
+    };
+    let commit = environment.git.as_ref().map( |g| g.commit.clone() );
+
+    transaction.execute_batch( SCHEMA )?;
+    transaction.execute(
+            "INSERT INTO runs ( started, host, version, git_commit, file, engine, operation, preload, threads, min_value, max_value ) \
+            VALUES ( ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11 )",
+            rusqlite::params![ started as i64, environment.host, environment.version, commit, params.file,
+                    params.eval_engine, operation, preload, params.max_threads as i64, params.min_value, params.max_value ] )?;
+    let run = transaction.last_insert_rowid();
+    let mut insert_query = transaction.prepare( "INSERT INTO queries VALUES ( ?1, ?2, ?3, ?4, ?5 )" )?;
+    let mut insert_set = transaction.prepare( "INSERT INTO set_results VALUES ( ?1, ?2, ?3, ?4, ?5 )" )?;
+    for ( query, ( result, size ) ) in results.iter().zip( sizes ).enumerate()
+    {
+        let duration_us = result.duration.as_secs() * 1_000_000 + ( result.duration.subsec_nanos() / 1000 ) as u64;
+        insert_query.execute( rusqlite::params![ run, query as i64, size, result.match_count as i64, duration_us as i64 ] )?;
+        if let Some( ref set_results ) = result.set_results
+        {
+            for set in set_results.iter()
+            {
+                insert_set.execute( rusqlite::params![ run, query as i64, set.index as i64, set.size as i64, set.matched ] )?;
+            }
+        }
+    }
+    return Ok( () );
+}
@@ -0,0 +1,163 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::collections::HashMap;
+
+use encoding;
+use mapping::MappedFile;
+use traits::*;
+use utility;
+
+/// Number of the registers of the distinct value sketch as a power of two.
+/// 2^14 registers give a standard error of about 0.8 %.
+const PRECISION: u32 = 14;
+
+/// Streaming estimate of the number of distinct values with HyperLogLog.
+pub struct DistinctSketch
+{
+    /// Largest position of the first set bit seen in the hashes of each register.
+    registers: Vec<u8>,
+}
+
+impl DistinctSketch
+{
+    /// Creates an empty sketch.
+    pub fn new() -> DistinctSketch
+    {
+        return DistinctSketch { registers: vec![ 0; 1 << PRECISION ] };
+    }
+
+    /// Adds the value to the sketch.
+    pub fn add<T>(
+        &mut self,
+        value: &T,
+    )
+    where
+        T: OrderedBits,
+    {
+        // The high bits of the hash select the register and the rest give the rank.
+        let hash = utility::mix( value.to_ordered_bits() );
+        let register = ( hash >> ( 64 - PRECISION ) ) as usize;
+        let rank = ( ( hash << PRECISION ) | ( 1 << ( PRECISION - 1 ) ) ).leading_zeros() as u8 + 1;
+        if rank > self.registers[ register ]
+        {
+            self.registers[ register ] = rank;
+        }
+    }
+
+    /// Estimates the number of distinct values added to the sketch.
+    pub fn estimate( &self ) -> f64
+    {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / ( 1.0 + 1.079 / m );
+        let sum: f64 = self.registers.iter().map( |r| ( -( *r as f64 ) ).exp2() ).sum();
+        let estimate = alpha * m * m / sum;
+
+        // Small cardinalities are estimated more accurately from the empty registers.
+        let empty = self.registers.iter().filter( |r| **r == 0 ).count();
+        if estimate <= 2.5 * m && empty > 0
+        {
+            return m * ( m / empty as f64 ).ln();
+        }
+        return estimate;
+    }
+}
+
+/// Statistics of the sets of a file.
+pub struct Statistics
+{
+    pub set_count: usize,
+    pub value_count: u64,
+    pub min_set_size: usize,
+    pub max_set_size: usize,
+    /// Estimated number of distinct values in all the sets.
+    pub distinct: Option<f64>,
+    /// Number of sets with each value keyed by the ordered bits of the value.
+    pub frequencies: Option<HashMap<u64, u64>>,
+}
+
+impl Statistics
+{
+    /// Prints the statistics.
+    pub fn print( &self )
+    {
+        println!( "Sets: {}", self.set_count );
+        println!( "Values: {}", self.value_count );
+        println!( "Set size: {} min, {} max, {:.1} average", self.min_set_size, self.max_set_size,
+                self.value_count as f64 / std::cmp::max( 1, self.set_count ) as f64 );
+        if let Some( distinct ) = self.distinct
+        {
+            // The duplication factor is the average number of times a distinct value occurs in the sets.
+            println!( "Distinct values: about {:.0}", distinct );
+            println!( "Duplication factor: {:.2}", self.value_count as f64 / distinct.max( 1.0 ) );
+        }
+    }
+}
+
+/// Collects the statistics of the sets in a single pass over the file.
+/// The distinct values are estimated with a sketch of a fixed size when requested.
+/// Counting the sets of each value holds every distinct value in memory.
+pub fn collect<T>(
+    file: &String,
+    distinct: bool,
+    frequencies: bool,
+) -> Statistics
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    let mapped = MappedFile::open( file ).expect( "Failed to map the file" );
+    let mut sketch = if distinct { Some( DistinctSketch::new() ) } else { None };
+    let mut counts: Option<HashMap<u64, u64>> = if frequencies { Some( HashMap::new() ) } else { None };
+    let mut statistics = Statistics { set_count: 0, value_count: 0, min_set_size: std::usize::MAX, max_set_size: 0,
+            distinct: None, frequencies: None };
+    encoding::for_each_set( mapped.as_slice(), |values: &[T]| {
+        statistics.set_count += 1;
+        statistics.value_count += values.len() as u64;
+        statistics.min_set_size = std::cmp::min( statistics.min_set_size, values.len() );
+        statistics.max_set_size = std::cmp::max( statistics.max_set_size, values.len() );
+        if let Some( ref mut sketch ) = sketch
+        {
+            for value in values
+            {
+                sketch.add( value );
+            }
+        }
+
+        // A value repeated in a set is counted only once.
+        if let Some( ref mut counts ) = counts
+        {
+            let mut keys: Vec<u64> = values.iter().map( |v| v.to_ordered_bits() ).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys
+            {
+                *counts.entry( key ).or_insert( 0 ) += 1;
+            }
+        }
+    } );
+    if statistics.set_count == 0
+    {
+        statistics.min_set_size = 0;
+    }
+    statistics.distinct = sketch.map( |s| s.estimate() );
+    statistics.frequencies = counts;
+    return statistics;
+}
+
+/// Prints the values found from the most sets as CSV with the number of sets of each value,
+/// the most frequent value first.
+pub fn print_top_values<T>(
+    frequencies: &HashMap<u64, u64>,
+    count: usize,
+)
+where
+    T: OrderedBits + std::fmt::Display,
+{
+    let mut values: Vec<( &u64, &u64 )> = frequencies.iter().collect();
+    values.sort_by( |a, b| b.1.cmp( a.1 ).then( a.0.cmp( b.0 ) ) );
+    println!( "value,sets" );
+    for ( key, sets ) in values.into_iter().take( count )
+    {
+        println!( "{},{}", T::from_ordered_bits( *key ), sets );
+    }
+}
@@ -1,24 +1,58 @@
-extern crate rayon;
+extern crate rand;
+extern crate ro_scalar_set;
 extern crate std;
 
+use std::collections::HashMap;
 use std::io::BufWriter;
 use std::io::prelude::*;
 use std::path::Path;
 
+use rand::distributions::Range;
+
+use allocator;
+use artifact::{self, Artifact};
+use encoding;
+use intersect;
+use query::QueryFile;
+use results::*;
+use scenario::Scenario;
 use evaluation::*;
 use enumerations::*;
+use traits::*;
 use utility::*;
 
 /// Configurable parameters for the test.
 struct Parameters<'a>
 {
     report: &'a String,
+    /// Prefix of the names of the generated files.
+    namespace: &'a String,
+    /// JSON stream the results are appended to as soon as they are available.
+    stream: &'a String,
     min_value: i32,
     max_value: i32,
     use_floats: bool,
-    preload_data: bool,
+    encodings: &'a Vec<Encoding>,
+    strategies: &'a Vec<Strategy>,
+    preload: PreloadMode,
     thread_count: usize,
-    engine: &'a EvaluationEngine,
+    prefetch_distance: usize,
+    io_limit: usize,
+    engine: &'a str,
+    /// Scenarios of the test matrix selected for the test.
+    scenarios: &'a Vec<Scenario>,
+    /// Query used in all the tests instead of the generated test sets.
+    query: Option<&'a QueryFile>,
+    /// Limit for the bytes written when generating the test sets. Zero disables the limit.
+    max_disk: u64,
+    /// Number of times a failed scenario is retried before the tests are aborted.
+    retries: usize,
+    /// Seeds the sets of each set size and set count are generated with. The other sets get a random seed.
+    seeds: &'a HashMap<( i32, u64 ), u64>,
+    /// Seed the test sets are generated with. The test sets are random without a seed.
+    query_seed: Option<u64>,
+    /// Results of the earlier runs with fewer threads for computing the scaling of the evaluation.
+    baseline: &'a Vec<TestRecord>,
 }
 
 /// Results of a single test.
@@ -26,255 +60,692 @@ struct Parameters<'a>
 /// * set_size Number of values in a set.
 /// * set_count Number of sets
 /// * test_set_size Number of values in the test set
+/// * encoding The encoding of the sets
+/// * strategy The strategy for intersecting the sets with the test set
+/// * gpu_kernel The layout of the GPU kernel when evaluated with GPU
+/// * file_size The size of the file holding the sets
 /// * duration The length of the evaluation
 /// * matches The number of sets that have a value matching with a value in the test set.
+/// * retries The number of times the test was retried after a failure
 struct TestResult
 {
     set_size: i32,
-    set_count: i32,
+    set_count: u64,
     test_set_size: i32,
-    eval_result: EvaluationResult
+    encoding: Encoding,
+    strategy: Strategy,
+    gpu_kernel: Option<GpuKernel>,
+    file_size: u64,
+    eval_result: EvaluationResult,
+    retries: usize,
 }
 
 pub fn run_tests(
     report_name: &String,
+    namespace: &String,
     min_value: i32,
     max_value: i32,
     floats: bool,
-    eval_engine: &EvaluationEngine,
+    encodings: &Vec<Encoding>,
+    strategies: &Vec<Strategy>,
+    prefetch_distance: usize,
+    io_limit: usize,
+    eval_engine: &str,
+    query: Option<&QueryFile>,
+    scenarios: &Vec<Scenario>,
+    max_disk: u64,
+    retries: usize,
+    seeds: &HashMap<( i32, u64 ), u64>,
+    query_seed: Option<u64>,
 )
 {
+    // Refuse plans that cannot fit in the disk quota before generating anything.
+    let estimate = estimate_generated_bytes( &get_set_files( scenarios ), encodings,
+            &Artifact::new( namespace, floats, 0, 0, min_value, max_value, Encoding::Raw ), seeds );
+    if max_disk > 0 && estimate > max_disk
+    {
+        panic!( "Generating the test sets requires at least {} MB which exceeds the limit of {} MB.",
+                estimate / ( 1024 * 1024 ), max_disk / ( 1024 * 1024 ) );
+    }
+
     // Run the non-preloaded cases before loading the data into memory.
     // NOTE: Some operating systems will keep the test material in file system cache
     // in which the this option is not that relevant.
-    let preload = vec![false,true];
+    let preload = vec![PreloadMode::Disabled, PreloadMode::Full];
 
     // Determine the thread counts we can use for testing.
-    // The maximum number of threads is limited by the number of logical threads
-    // available in the system.
-    let mut thread_counts: Vec<usize> = Vec::new();
-    {
-        let max_threads = rayon::current_num_threads();
-        let mut last = 1;
-        thread_counts.push( last );
-        while last < max_threads
-        {
-            // Double the number of threads for each test until
-            // max_threads is reached.
-            let next = std::cmp::min( last * 2, max_threads );
-            thread_counts.push( next );
-            last = next;
-        }
-    }
+    let thread_counts = get_thread_counts();
     // thread_counts = vec![ 1, 8, 16];
 
+    // Start a new stream of results for this run.
+    let stream = get_stream_file_name( report_name );
+    std::fs::File::create( &stream ).expect( "Failed to open the result stream." );
+
     // Run all different scenarios.
+    let mut records: Vec<TestRecord> = Vec::new();
     for pr in preload
     {
         for thread_count in &thread_counts
         {
             // Determine file name for this test scenario.
-            let execution_params;
-            if pr { execution_params = format!("{}-threads_with_preload", thread_count )}
-            else { execution_params = format!( "{}-threads_no_preload", thread_count )};
+            let execution_params = match pr
+            {
+                PreloadMode::Full => format!( "{}-threads_with_preload", thread_count ),
+                PreloadMode::Disabled => format!( "{}-threads_no_preload", thread_count ),
+            };
             let report = format!( "{}_{}.md", report_name, execution_params );
 
             // Execute the test.
             let params = Parameters {
                 report: &report,
+                namespace: namespace,
+                stream: &stream,
                 min_value: min_value,
                 max_value: max_value,
                 use_floats: floats,
-                preload_data: pr,
+                encodings: encodings,
+                strategies: strategies,
+                preload: pr,
                 thread_count: *thread_count,
+                prefetch_distance: prefetch_distance,
+                io_limit: io_limit,
                 engine: eval_engine,
+                query: query,
+                scenarios: scenarios,
+                max_disk: max_disk,
+                retries: retries,
+                seeds: seeds,
+                query_seed: query_seed,
+                baseline: &records,
             };
-            run_test( params );
+            let run_records = run_test( params );
+            records.extend( run_records );
         }
     }
 
+    // The results of all the scenarios are also stored as JSON for merging the results of several runs.
+    let results = vec![ EnvironmentResults { environment: Environment::current(), results: records } ];
+    write( &get_results_file_name( report_name ), &results );
 }
 
 /// Executes one test with the given parameters.
-fn run_test( parameters: Parameters )
+fn run_test( parameters: Parameters ) -> Vec<TestRecord>
 {
-    // Define test material.
-    // let set_sizes: Vec<i32> = vec! { 10, 100, 1000 };
-    // let set_counts: Vec<i32> = vec! { 10, 100, 1000  };
-    let set_sizes: Vec<i32> = vec![10, 100, 1000, 10000];
-    let set_counts: Vec<i32> = vec![10, 100, 1000, 10000, 100000];
-    let test_set_sizes: Vec<i32> = vec![10, 100, 1000, 10000];
-
-    // Generate test files.
-    generate_test_files( &set_sizes, &set_counts, &parameters );
-
-    // Run the tests.
-    let mut results: Vec<TestResult> = Vec::new();
-    for set_size in &set_sizes
+    // The size of the test set is fixed by a query file holding a single set.
+    // A file saved with --save-query holds a test set of each size.
+    let mut scenarios: Vec<Scenario> = parameters.scenarios.clone();
+    let query_count = match parameters.query
+    {
+        Some( query ) if parameters.use_floats => query.attach_all::<f32>().len(),
+        Some( query ) => query.attach_all::<i32>().len(),
+        None => 0,
+    };
+    if let Some( query ) = parameters.query.filter( |_| query_count == 1 )
     {
-        for set_count in &set_counts
+        let query_size = if parameters.use_floats { query.attach::<f32>().1.len() } else { query.attach::<i32>().1.len() };
+        for s in &mut scenarios
         {
-            for test_set_size in &test_set_sizes
-            {
-                // Identify the current test.
-                let file_name = get_set_file_name( set_count, set_size, &parameters.use_floats );
-                if !Path::new( &file_name ).exists()
-                {
-                    panic!( "Generated file not found." );
-                }
+            s.test_set_size = query_size as i32;
+        }
+        scenarios.dedup_by_key( |s| ( s.set_size, s.set_count ) );
+    }
 
-                // Construct parameters
-                let params = EvaluationParams
-                {
-                    file: &file_name,
-                    values_in_set: *test_set_size,
-                    min_value: parameters.min_value,
-                    max_value: parameters.max_value,
-                    preload_data: parameters.preload_data,
-                    max_threads: parameters.thread_count,
-                    eval_engine: parameters.engine,
-                };
+    // The raw sets are always evaluated first as the baseline the encodings are compared against.
+    let encodings: Vec<Encoding> = std::iter::once( Encoding::Raw )
+            .chain( parameters.encodings.iter()
+                    .cloned()
+                    .filter( |e| *e != Encoding::Raw ) )
+            .collect();
 
-                // Run and measure.
-                println!( "Running test set {}...", file_name );
-                let evaluation_result;
-                if parameters.use_floats
+    // The raw sets are probed and each other encoding is evaluated with the strategies applicable to its sets.
+    // The layouts of the GPU kernel are compared with each other with the gpu engine.
+    let kernels = if parameters.engine == "gpu" { vec![ GpuKernel::Item, GpuKernel::WorkGroup ] } else { vec![ GpuKernel::Item ] };
+    let variants: Vec<( Encoding, Strategy, GpuKernel )> = encodings.iter()
+            .flat_map( |e| {
+                let strategies = match *e
                 {
-                    evaluation_result = evaluate::<f32>( &params );
-                }
-                else
-                {
-                    evaluation_result = evaluate::<i32>( &params );
-                }
-                let result = evaluation_result;
-
-                // Collect results for       reporting.
-                let result = TestResult {
-                    set_size: *set_size,
-                    set_count: *set_count,
-                    test_set_size: *test_set_size,
-                    eval_result: result,
+                    Encoding::Raw => vec![ Strategy::Probe ],
+                    _ => parameters.strategies.iter().cloned().filter( |s| intersect::supports( *e, *s ) ).collect(),
                 };
-                results.push( result );
-            }
-        }
+                strategies.into_iter().map( move |s| ( *e, s ) )
+            } )
+            .flat_map( |( e, s )| kernels.iter().map( move |k| ( e, s, *k ) ) )
+            .collect();
+
+    // Generate test files.
+    let set_files = get_set_files( &scenarios );
+    let generated = generate_test_files( &set_files, &parameters );
+
+    // Nothing to report when all the scenarios were filtered out.
+    if scenarios.is_empty()
+    {
+        return Vec::new();
     }
 
-    // Report the results.
+    // Each result is written to the report as soon as its scenario finishes.
     let report = std::fs::File::create( parameters.report ).expect( "Failed to open the report." );
     let mut report = BufWriter::with_capacity( 1024 * 1024, report );
 
-    let mut current_set_size = results[0].set_size;
-    let mut write_header: bool = true;
-    for result in &results
+    writeln!( &mut report, "Allocator: {}", allocator::name() ).expect( "Writing report failed." );
+    let environment = Environment::current();
+    writeln!( &mut report, "Commit: {}", GitInfo::describe( &environment.git ) ).expect( "Writing report failed." );
+    writeln!( &mut report, "ro_scalar_set commit: {}", GitInfo::describe( &environment.ro_scalar_set_git ) )
+            .expect( "Writing report failed." );
+    let corpus_size: u64 = set_files.iter()
+            .flat_map( |&( size, count )| encodings.iter().map( move |e| ( size, count, *e ) ) )
+            .map( |( size, count, e )| get_artifact( count, size, e, &parameters ).file_name() )
+            .map( |f| std::fs::metadata( f ).expect( "Failed to query the file." ).len() )
+            .sum();
+    writeln!( &mut report, "Corpus size: {} MB ({} MB generated)", corpus_size / ( 1024 * 1024 ), generated / ( 1024 * 1024 ) )
+            .expect( "Writing report failed." );
+    report.flush().expect( "Writing report failed." );
+
+    // Run the tests.
+    let mut records: Vec<TestRecord> = Vec::new();
+    let mut current_set_size: Option<i32> = None;
+    for scenario in &scenarios
     {
+        let set_size = &scenario.set_size;
+        let set_count = &scenario.set_count;
+        let test_set_size = &scenario.test_set_size;
 
-        // Always write header when we ancounter a new set size.
-        if result.set_size != current_set_size
+        // Identify the current test.
+        let file_names: Vec<String> = variants.iter()
+                .map( |&( e, _, _ )| get_artifact( *set_count, *set_size, e, &parameters ).file_name() )
+                .collect();
+        let layouts: Vec<( Strategy, GpuKernel )> = variants.iter().map( |&( _, s, k )| ( s, k ) ).collect();
+        for file_name in &file_names
         {
-            write_header = true;
-            current_set_size = result.set_size;
+            if !Path::new( file_name ).exists()
+            {
+                panic!( "Generated file not found." );
+            }
         }
 
-        // Write header?
-        if write_header
+        // Construct parameters
+        let params = EvaluationParams
         {
+            file: &file_names[ 0 ],
+            values_in_set: *test_set_size,
+            min_value: parameters.min_value,
+            max_value: parameters.max_value,
+            preload: parameters.preload,
+            max_threads: parameters.thread_count,
+            prefetch_distance: parameters.prefetch_distance,
+            cache_size: 0,
+            io_limit: parameters.io_limit,
+            verify_sample: 0,
+            operation: Operation::Any,
+            dimension: None,
+            transform: None,
+            query_block: 0,
+            strategy: Strategy::Auto,
+            breakdown: false,
+            explain: false,
+            set_results: false,
+            exclude_file: None,
+            set_filter: SetFilter::all(),
+            eval_engine: parameters.engine,
+            audit: 0.0,
+            audit_engine: "cpu",
+            repro: false,
+            gpu_compare: GpuCompare::Epsilon,
+            gpu_memory: GpuMemory::Default,
+            gpu_kernel: GpuKernel::Item,
+            top_k: 0,
+            memory_limit: 0,
+            timeout: None,
+            cancellation: None,
+            signature: None,
+        };
+
+        // Run and measure.
+        println!( "Running test set {}...", file_names[ 0 ] );
+        let ( evaluation_results, retries ) = evaluate_with_retries( &params, &file_names, &layouts, &parameters );
+
+        // Collect results for       reporting.
+        for ( ( &( encoding, strategy, kernel ), file_name ), result ) in variants.iter().zip( &file_names ).zip( evaluation_results )
+        {
+            let result = TestResult {
+                set_size: *set_size,
+                set_count: *set_count,
+                test_set_size: *test_set_size,
+                encoding: encoding,
+                strategy: strategy,
+                gpu_kernel: if parameters.engine == "gpu" { Some( kernel ) } else { None },
+                file_size: std::fs::metadata( file_name ).expect( "Failed to query the file." ).len(),
+                eval_result: result,
+                retries: retries,
+            };
+
+            // The scaling is relative to the same test with the fewest threads.
+            let record = to_record( &result );
+            let baseline = parameters.baseline.iter().find( |b| b.set_size == record.set_size &&
+                    b.set_count == record.set_count && b.test_set_size == record.test_set_size &&
+                    b.encoding == record.encoding && b.strategy == record.strategy && b.preload == record.preload &&
+                    b.gpu_kernel == record.gpu_kernel );
+            let ( speedup, efficiency ) = match baseline
+            {
+                Some( b ) =>
+                {
+                    let speedup = b.duration_us as f64 / std::cmp::max( 1, record.duration_us ) as f64;
+                    ( speedup, speedup * b.thread_count as f64 / record.thread_count as f64 )
+                },
+                None => ( 1.0, 1.0 ),
+            };
+
+            // Always write header when we ancounter a new set size.
+            write_result( &mut report, &result, speedup, efficiency, current_set_size != Some( result.set_size ) );
+            report.flush().expect( "Writing report failed." );
+            current_set_size = Some( result.set_size );
+
+            append( parameters.stream, &record );
+            records.push( record );
+        }
+    }
+    return records;
+}
+
+/// Writes the result of a single test into the report preceded by a header when requested.
+/// The speedup and the parallel efficiency are relative to the same test with the fewest threads.
+fn write_result<W>(
+    report: &mut W,
+    result: &TestResult,
+    speedup: f64,
+    efficiency: f64,
+    write_header: bool,
+)
+where
+    W: Write,
+{
+    // Write header?
+    if write_header
+    {
+        writeln!( report, "" ).expect( "Writing report failed." );
+        match result.eval_result.preload
+        {
+            PreloadMode::Full => writeln!( report, "Data preloaded into memory for evaluation." ),
+            PreloadMode::Disabled => writeln!( report, "Data read directly from file for evalution." ),
+        }.expect( "Writing report failed." );
+        writeln!( report, "" ).expect( "Writing report failed." );
+        writeln!(
+            report,
+            "Number of threads: {}",
+            result.eval_result.thread_count,
+        ).expect( "Writing report failed." );;
+        writeln!(
+            report,
+            "Number of values in a set: {}",
+            result.set_size
+        ).expect( "Writing report failed." );;
+        writeln!( report, "" ).expect( "Writing report failed." );
+        writeln!(
+            report,
+            "|{:14}|{:14}|{:14}|{:14}|{:14}|{:14}|{:14}|{:14}|{:14}|{:14}|{:14}|{:14}|",
+            "Sets",
+            "Test set size",
+            "Encoding",
+            "Strategy",
+            "Kernel",
+            "File size",
+            "Matching sets",
+            "Duration",
+            "Speedup",
+            "Efficiency",
+            "Retries",
+            "Page cache",
+        ).expect( "Writing report failed." );
+        writeln!(
+            report,
+            "|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|",
+            "-",
+            "-",
+            "-",
+            "-",
+            "-",
+            "-",
+            "-",
+            "-",
+            "-",
+            "-",
+            "-",
+            "-"
+        ).expect( "Writing report failed." );
+    }
+
+    // Report results of a single test.
+    writeln!(
+        report,
+        "|{:14}|{:14}|{:>14}|{:>14}|{:>14}|{:14}|{:14}|{:5}.{:06} s|{:13.2}x|{:13.0}%|{:14}|{:>14}|",
+        result.set_count,
+        result.test_set_size,
+        encoding::name( result.encoding ),
+        intersect::name( result.strategy ),
+        get_kernel_name( result.gpu_kernel ).unwrap_or( "-" ),
+        result.file_size,
+        result.eval_result.match_count,
+        result.eval_result.duration.as_secs(),
+        result.eval_result.duration.subsec_nanos() / 1000,
+        speedup,
+        efficiency * 100.0,
+        result.retries,
+        result.eval_result.cache_state().unwrap_or( "-" )
+    ).expect( "Writing report failed." );
+}
+
+/// Converts the result of a test into a record of the JSON results.
+fn to_record( result: &TestResult ) -> TestRecord
+{
+    let preload = match result.eval_result.preload
+    {
+        PreloadMode::Disabled => "none",
+        PreloadMode::Full => "full",
+    };
+    let duration = result.eval_result.duration;
+    return TestRecord {
+        set_size: result.set_size,
+        set_count: result.set_count,
+        test_set_size: result.test_set_size,
+        encoding: encoding::name( result.encoding ).to_string(),
+        strategy: intersect::name( result.strategy ).to_string(),
+        preload: preload.to_string(),
+        thread_count: result.eval_result.thread_count,
+        file_size: result.file_size,
+        match_count: result.eval_result.match_count,
+        duration_us: duration.as_secs() * 1_000_000 + ( duration.subsec_nanos() / 1000 ) as u64,
+        retries: result.retries,
+        page_cache: result.eval_result.cache_state().unwrap_or( "" ).to_string(),
+        gpu_kernel: get_kernel_name( result.gpu_kernel ).unwrap_or( "" ).to_string(),
+    };
+}
+
+/// Gets the name of the layout of the GPU kernel the test was evaluated with.
+fn get_kernel_name( kernel: Option<GpuKernel> ) -> Option<&'static str>
+{
+    match kernel
+    {
+        Some( GpuKernel::Item ) => Some( "item" ),
+        Some( GpuKernel::WorkGroup ) => Some( "wg" ),
+        None => None,
+    }
+}
 
-            writeln!( &mut report, "" ).expect( "Writing report failed." );
-            if result.eval_result.data_preloaded
+/// Evaluates the test set against each of the files retrying with exponential backoff when the evaluation fails.
+/// Returns the results and the number of retries needed.
+fn evaluate_with_retries(
+    params: &EvaluationParams,
+    file_names: &Vec<String>,
+    layouts: &Vec<( Strategy, GpuKernel )>,
+    parameters: &Parameters,
+) -> ( Vec<EvaluationResult>, usize )
+{
+    let mut retries = 0;
+    loop
+    {
+        let attempt = std::panic::catch_unwind( std::panic::AssertUnwindSafe( || {
+            if parameters.use_floats
             {
-                writeln!( &mut report, "Data preloaded into memory for evaluation." ).expect( "Writing report failed." );
+                evaluate_encodings::<f32>( params, file_names, layouts, parameters.query, parameters.query_seed )
             }
             else
             {
-                 writeln!( &mut report, "Data read directly from file for evalution." ).expect( "Writing report failed." );
+                evaluate_encodings::<i32>( params, file_names, layouts, parameters.query, parameters.query_seed )
             }
-            writeln!( &mut report, "" ).expect( "Writing report failed." );
-            writeln!(
-                &mut report,
-                "Number of threads: {}",
-                result.eval_result.thread_count,
-            ).expect( "Writing report failed." );;
-            writeln!(
-                &mut report,
-                "Number of values in a set: {}",
-                current_set_size
-            ).expect( "Writing report failed." );;
-            writeln!( &mut report, "" ).expect( "Writing report failed." );
-            writeln!(
-                &mut report,
-                "|{:14}|{:14}|{:14}|{:14}|",
-                "Sets",
-                "Test set size",
-                "Matching sets",
-                "Duration",
-            ).expect( "Writing report failed." );
-            writeln!(
-                &mut report,
-                "|{:-<13}:|{:-<13}:|{:-<13}:|{:-<13}:|",
-                "-",
-                "-",
-                "-",
-                "-"
-            ).expect( "Writing report failed." );
-
-            write_header = false;
+        } ) );
+        match attempt
+        {
+            Ok( results ) => return ( results, retries ),
+            Err( e ) =>
+            {
+                if retries >= parameters.retries
+                {
+                    std::panic::resume_unwind( e );
+                }
+                let backoff = std::time::Duration::from_secs( 1 << std::cmp::min( retries, 6 ) );
+                println!( "Test set {} failed, retrying in {} s...", file_names[ 0 ], backoff.as_secs() );
+                std::thread::sleep( backoff );
+                retries += 1;
+            },
         }
+    }
+}
 
-        // Report results of a single test.
-        writeln!(
-            &mut report,
-            "|{:14}|{:14}|{:14}|{:5}.{:06} s|",
-            result.set_count,
-            result.test_set_size,
-            result.eval_result.match_count,
-            result.eval_result.duration.as_secs(),
-            result.eval_result.duration.subsec_nanos() / 1000
-        ).expect( "Writing report failed." );
-
+/// Evaluates the same test set against each of the files.
+/// Each file is evaluated with the strategy and the GPU kernel at the same index.
+/// The query of the query file is used instead of a generated test set when given.
+/// The generated test set depends only on the seed and the size of the test set when seeded.
+fn evaluate_encodings<T>(
+    params: &EvaluationParams,
+    file_names: &Vec<String>,
+    layouts: &Vec<( Strategy, GpuKernel )>,
+    query: Option<&QueryFile>,
+    query_seed: Option<u64>,
+) -> Vec<EvaluationResult>
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    if let Some( query ) = query.filter( |q| q.attach_all::<T>().len() > 1 )
+    {
+        let test_set = match query.values_of_size::<T>( params.values_in_set as usize )
+        {
+            Some( test_set ) => test_set,
+            None => panic!( "The query file has no test set of {} values.", params.values_in_set ),
+        };
+        return file_names.iter().zip( layouts )
+            .map( |( file_name, &( strategy, kernel ) )| {
+                let params = EvaluationParams { file: file_name, strategy: strategy, gpu_kernel: kernel, ..*params };
+                evaluate_with_query( &params, test_set )
+            } )
+            .collect();
+    }
+    if let Some( query ) = query
+    {
+        return file_names.iter().zip( layouts )
+            .map( |( file_name, &( strategy, kernel ) )| {
+                let params = EvaluationParams { file: file_name, strategy: strategy, gpu_kernel: kernel, ..*params };
+                evaluate_with_query_file::<T>( &params, query )
+            } )
+            .collect();
     }
+    let between = Range::new( params.min_value, params.max_value );
+    let test_set: Vec<T> = match query_seed
+    {
+        Some( seed ) => generate_values_with( params.values_in_set, &between, &mut set_rng( seed, params.values_in_set as u64 ) ),
+        None => generate_values( params.values_in_set, &between ),
+    };
+    return file_names.iter().zip( layouts )
+        .map( |( file_name, &( strategy, kernel ) )| {
+            let params = EvaluationParams { file: file_name, strategy: strategy, gpu_kernel: kernel, ..*params };
+            evaluate_with_query( &params, &test_set )
+        } )
+        .collect();
 }
 
 /// Generates test files for a test.
+/// The sets are generated once and then converted into each encoding
+/// so that all the encodings hold the same sets.
+/// Returns the number of bytes written.
 fn generate_test_files(
-    set_sizes: &Vec<i32>,
-    set_counts: &Vec<i32>,
+    set_files: &Vec<( i32, u64 )>,
     parameters: &Parameters,
-)
+) -> u64
 {
     // Generate test files.
-    for set_size in set_sizes
+    let mut written = 0;
+    for &( ref set_size, ref set_count ) in set_files
     {
-        for set_count in set_counts
+        // Reuse existing files if available.
+        let artifact = get_artifact( *set_count, *set_size, Encoding::Raw, parameters );
+        let file_name = artifact.file_name();
+        if !Path::new( &file_name ).exists()
         {
-            // Reuse existing files if available.
-            let file_name = get_set_file_name( set_count, set_size, &parameters.use_floats );
-            if Path::new( &file_name ).exists()
-            {
-                continue;
-            }
-
+            // Generate into a temporary file first so that an interrupted generation is never
+            // mistaken for a complete test set on the next run.
+            // Renaming only after the file has been closed also keeps Windows from
+            // rejecting the operation due to an open handle.
             println!( "Generating test set {}...", file_name );
+            let partial_file_name = format!( "{}.partial", file_name );
+            let seed: u64 = artifact.seed.unwrap_or_else( || rand::random() );
             if parameters.use_floats
             {
                 generate::<f32>(
-                    &file_name,
+                    &partial_file_name,
                     *set_count,
                     *set_size,
                     parameters.min_value,
                     parameters.max_value,
+                    Encoding::Raw,
+                    seed,
+                    false,
+                    false,
+                    None,
                 );
             }
             else
             {
                 generate::<i32>(
-                    &file_name,
+                    &partial_file_name,
                     *set_count,
                     *set_size,
                     parameters.min_value,
                     parameters.max_value,
+                    Encoding::Raw,
+                    seed,
+                    false,
+                    false,
+                    None,
                 );
             }
+            std::fs::rename( &partial_file_name, &file_name ).expect( "Failed to rename the test set." );
+            artifact.write_manifest( &file_name, seed );
+            written += account_disk_usage( &file_name, written, parameters.max_disk );
         }
+
+        // Encode the generated sets.
+        for encoding in parameters.encodings.iter().filter( |e| **e != Encoding::Raw )
+        {
+            let encoded_artifact = get_artifact( *set_count, *set_size, *encoding, parameters );
+            let encoded_file_name = encoded_artifact.file_name();
+            if Path::new( &encoded_file_name ).exists()
+            {
+                continue;
+            }
+
+            println!( "Encoding test set {}...", encoded_file_name );
+            let partial_file_name = format!( "{}.partial", encoded_file_name );
+            if parameters.use_floats
+            {
+                encoding::convert::<f32>( &file_name, &partial_file_name, *encoding );
+            }
+            else
+            {
+                encoding::convert::<i32>( &file_name, &partial_file_name, *encoding );
+            }
+            std::fs::rename( &partial_file_name, &encoded_file_name ).expect( "Failed to rename the test set." );
+
+            // The encoded sets share the seed of the raw sets.
+            if let Some( manifest ) = artifact::read_manifest( &file_name )
+            {
+                encoded_artifact.write_manifest( &encoded_file_name, manifest.seed );
+            }
+            written += account_disk_usage( &encoded_file_name, written, parameters.max_disk );
+        }
+    }
+    return written;
+}
+
+/// Describes the generated file of the sets.
+/// The files of the sets generated with a fixed seed are named with the seed.
+fn get_artifact(
+    set_count: u64,
+    set_size: i32,
+    encoding: Encoding,
+    parameters: &Parameters,
+) -> Artifact
+{
+    return Artifact {
+        seed: parameters.seeds.get( &( set_size, set_count ) ).cloned(),
+        ..Artifact::new( parameters.namespace, parameters.use_floats, set_count, set_size,
+                parameters.min_value, parameters.max_value, encoding )
+    };
+}
+
+/// Gets the size of a generated file and aborts if the bytes written exceed the limit.
+fn account_disk_usage(
+    file_name: &String,
+    written: u64,
+    max_disk: u64,
+) -> u64
+{
+    let size = std::fs::metadata( file_name ).expect( "Failed to query the file." ).len();
+    if max_disk > 0 && written + size > max_disk
+    {
+        panic!( "Generating the test sets exceeded the limit of {} MB.", max_disk / ( 1024 * 1024 ) );
+    }
+    return size;
+}
+
+/// Gets the distinct combinations of the set size and the set count of the scenarios.
+fn get_set_files( scenarios: &Vec<Scenario> ) -> Vec<( i32, u64 )>
+{
+    let mut set_files: Vec<( i32, u64 )> = scenarios.iter().map( |s| ( s.set_size, s.set_count ) ).collect();
+    set_files.sort();
+    set_files.dedup();
+    return set_files;
+}
+
+/// Estimates the bytes written when generating the missing test files.
+/// Each set is counted with the smallest size its encoding can store it in which makes the estimate a lower bound.
+/// The files are described by the template.
+fn estimate_generated_bytes(
+    set_files: &Vec<( i32, u64 )>,
+    encodings: &Vec<Encoding>,
+    template: &Artifact,
+    seeds: &HashMap<( i32, u64 ), u64>,
+) -> u64
+{
+    let mut estimate = 0;
+    for &( set_size, set_count ) in set_files
+    {
+        let mut file_encodings = encodings.clone();
+        if !file_encodings.contains( &Encoding::Raw )
+        {
+            file_encodings.push( Encoding::Raw );
+        }
+        for encoding in file_encodings
+        {
+            let artifact = Artifact { set_count: set_count, set_size: set_size, encoding: encoding::name( encoding ).to_string(),
+                    seed: seeds.get( &( set_size, set_count ) ).cloned(), ..template.clone() };
+            if !Path::new( &artifact.file_name() ).exists()
+            {
+                estimate += set_count * min_set_bytes( encoding, set_size as u64 );
+            }
+        }
+    }
+    return estimate;
+}
+
+/// Gets the fewest bytes a set of unique values can be stored in with the encoding.
+fn min_set_bytes(
+    encoding: Encoding,
+    set_size: u64,
+) -> u64
+{
+    match encoding
+    {
+        // The bucket count, at least two bucket boundaries and the values.
+        Encoding::Raw => ( 3 + set_size ) * 4,
+        // The count and at least a byte for each difference.
+        Encoding::Delta => 1 + set_size,
+        // The count and at least enough bits for telling the unique values apart.
+        Encoding::Bitpack =>
+        {
+            let bits = 64 - set_size.saturating_sub( 1 ).leading_zeros() as u64;
+            4 + ( set_size * bits + 7 ) / 8
+        },
+        // The length, the count and at least a byte for each code.
+        Encoding::Dictionary => 2 + set_size,
+        // The count and the key and the weight of each value.
+        Encoding::Weighted => 4 + set_size * 12,
     }
 }
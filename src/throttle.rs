@@ -0,0 +1,38 @@
+extern crate std;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Limits the rate at which the data is consumed to simulate slow storage.
+/// The threads report the bytes they read and sleep until the reads fit within the limit.
+pub struct Throttle
+{
+    bytes_per_second: usize,
+    start: Instant,
+    consumed: AtomicUsize,
+}
+
+impl Throttle
+{
+    /// Starts limiting the reads to the given number of bytes per second.
+    pub fn start( bytes_per_second: usize ) -> Throttle
+    {
+        return Throttle { bytes_per_second: bytes_per_second, start: Instant::now(), consumed: AtomicUsize::new( 0 ) };
+    }
+
+    /// Reports that the given number of bytes was read and waits until the read is within the limit.
+    pub fn consume(
+        &self,
+        bytes: usize,
+    )
+    {
+        let consumed = self.consumed.fetch_add( bytes, Ordering::Relaxed ) + bytes;
+        let due = consumed as f64 / self.bytes_per_second as f64;
+        let due = Duration::new( due as u64, ( due.fract() * 1e9 ) as u32 );
+        let elapsed = self.start.elapsed();
+        if due > elapsed
+        {
+            std::thread::sleep( due - elapsed );
+        }
+    }
+}
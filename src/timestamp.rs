@@ -0,0 +1,251 @@
+extern crate byteorder;
+extern crate std;
+
+use std::io::prelude::*;
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+
+use enumerations::*;
+use evaluation::{evaluate_in_pool, EvaluationResult};
+
+/// Identifies a file with sets of timestamps.
+const MAGIC: &'static [u8] = b"SSETIME1";
+
+/// Size of a single timestamp in milliseconds since the epoch.
+const TIMESTAMP_LEN: usize = 8;
+
+/// Checks whether the data holds sets of timestamps.
+pub fn is_timestamp_file( data: &[u8] ) -> bool
+{
+    data.starts_with( MAGIC )
+}
+
+/// Writes the header of a file with sets of timestamps.
+pub fn write_header<W: Write>( writer: &mut W ) -> std::io::Result<()>
+{
+    writer.write_all( MAGIC )
+}
+
+/// Writes a single set of timestamps.
+/// A set is stored as the number of timestamps followed by the timestamps in ascending order.
+pub fn write_set<W: Write>(
+    writer: &mut W,
+    timestamps: &[i64],
+) -> std::io::Result<()>
+{
+    let mut sorted = timestamps.to_vec();
+    sorted.sort();
+    writer.write_u32::<LittleEndian>( sorted.len() as u32 )?;
+    for timestamp in sorted
+    {
+        writer.write_i64::<LittleEndian>( timestamp )?;
+    }
+    return Ok( () );
+}
+
+/// Divides the data into sets of timestamps.
+/// A set extending past the end of the data is refused as the file is truncated or corrupted.
+pub fn attach( data: &[u8] ) -> Vec<&[u8]>
+{
+    let mut sets = Vec::new();
+    let mut position = MAGIC.len();
+    while position < data.len()
+    {
+        if data.len() - position < 4
+        {
+            panic!( "The set of timestamps at byte {} is truncated.", position );
+        }
+        let count = LittleEndian::read_u32( &data[ position.. ] ) as usize;
+        let length = count.checked_mul( TIMESTAMP_LEN ).and_then( |l| l.checked_add( 4 ) )
+                .filter( |l| *l <= data.len() - position )
+                .unwrap_or_else( || panic!( "The set of timestamps at byte {} is truncated.", position ) );
+        sets.push( &data[ position..position + length ] );
+        position += length;
+    }
+    return sets;
+}
+
+/// Tests if any timestamp of the set is within the inclusive window.
+/// The timestamps are sorted which allows a binary search for the first timestamp not before the window.
+fn any_between(
+    set: &[u8],
+    from: i64,
+    to: i64,
+) -> bool
+{
+    let count = LittleEndian::read_u32( set ) as usize;
+    let read = |i: usize| LittleEndian::read_i64( &set[ 4 + i * TIMESTAMP_LEN.. ] );
+    let mut low = 0;
+    let mut high = count;
+    while low < high
+    {
+        let middle = ( low + high ) / 2;
+        if read( middle ) < from
+        {
+            low = middle + 1;
+        }
+        else
+        {
+            high = middle;
+        }
+    }
+    return low < count && read( low ) <= to;
+}
+
+/// Evaluates sets of timestamps against the inclusive time window with CPU.
+pub fn evaluate(
+    data: &[u8],
+    from: i64,
+    to: i64,
+    preload: PreloadMode,
+    thread_count: usize,
+) -> EvaluationResult
+{
+    // Load the data into the memory?
+    let preloaded;
+    let data = if preload == PreloadMode::Full
+    {
+        preloaded = data.to_vec();
+        &preloaded[..]
+    }
+    else
+    {
+        data
+    };
+    let sets = attach( data );
+
+    return evaluate_in_pool( &sets, data.len() as u64, preload, thread_count, |s| any_between( s, from, to ) );
+}
+
+/// Parses a timestamp given either in milliseconds since the epoch or in the RFC 3339 format.
+/// A plain date is accepted as the midnight of the day in UTC.
+pub fn parse( text: &str ) -> Option<i64>
+{
+    let text = text.trim();
+    if let Ok( milliseconds ) = text.parse::<i64>()
+    {
+        return Some( milliseconds );
+    }
+    if text.len() < 10 || !text.is_char_boundary( 10 ) || !text[ ..10 ].is_ascii()
+    {
+        return None;
+    }
+
+    // Date. The fields are sliced by bytes which requires ASCII.
+    let date = &text[ ..10 ];
+    if &date[ 4..5 ] != "-" || &date[ 7..8 ] != "-"
+    {
+        return None;
+    }
+    let year: i64 = date[ 0..4 ].parse().ok()?;
+    let month: i64 = date[ 5..7 ].parse().ok()?;
+    let day: i64 = date[ 8..10 ].parse().ok()?;
+    if month < 1 || month > 12 || day < 1 || day > days_in_month( year, month )
+    {
+        return None;
+    }
+    let days = days_from_civil( year, month, day );
+    let rest = &text[ 10.. ];
+    if rest.is_empty()
+    {
+        return Some( days * 86_400_000 );
+    }
+
+    // Time of the day, the separator can be 'T', 't' or a space.
+    let separated = rest.chars().next().map_or( false, |c| "Tt ".contains( c ) );
+    if !separated || rest.len() < 9 || !rest.is_char_boundary( 9 ) || !rest[ ..9 ].is_ascii()
+            || &rest[ 3..4 ] != ":" || &rest[ 6..7 ] != ":"
+    {
+        return None;
+    }
+    let hours: i64 = rest[ 1..3 ].parse().ok()?;
+    let minutes: i64 = rest[ 4..6 ].parse().ok()?;
+    let seconds: i64 = rest[ 7..9 ].parse().ok()?;
+    if hours < 0 || hours > 23 || minutes < 0 || minutes > 59 || seconds < 0 || seconds > 59
+    {
+        return None;
+    }
+    let mut rest = &rest[ 9.. ];
+
+    // Fraction of a second, only milliseconds are kept.
+    let mut milliseconds = 0;
+    if rest.starts_with( '.' )
+    {
+        let digits = rest[ 1.. ].chars().take_while( |c| c.is_ascii_digit() ).count();
+        if digits == 0
+        {
+            return None;
+        }
+        let fraction = &rest[ 1..1 + digits ];
+        let padded = format!( "{:0<3}", &fraction[ ..std::cmp::min( 3, digits ) ] );
+        milliseconds = padded.parse().ok()?;
+        rest = &rest[ 1 + digits.. ];
+    }
+
+    // Offset from UTC.
+    let offset = if rest == "Z" || rest == "z"
+    {
+        0
+    }
+    else if rest.len() == 6 && rest.is_ascii() && ( rest.starts_with( '+' ) || rest.starts_with( '-' ) ) && &rest[ 3..4 ] == ":"
+    {
+        let sign = if rest.starts_with( '-' ) { -1 } else { 1 };
+        let offset_hours: i64 = rest[ 1..3 ].parse().ok()?;
+        let offset_minutes: i64 = rest[ 4..6 ].parse().ok()?;
+        if offset_hours < 0 || offset_hours > 23 || offset_minutes < 0 || offset_minutes > 59
+        {
+            return None;
+        }
+        sign * ( offset_hours * 60 + offset_minutes )
+    }
+    else
+    {
+        return None;
+    };
+
+    let seconds = days * 86_400 + hours * 3_600 + ( minutes - offset ) * 60 + seconds;
+    return Some( seconds * 1000 + milliseconds );
+}
+
+/// Parses the inclusive time window given as two timestamps separated with "..".
+pub fn parse_window( text: &str ) -> Option<( i64, i64 )>
+{
+    let mut bounds = text.splitn( 2, ".." );
+    let from = parse( bounds.next()? )?;
+    let to = parse( bounds.next()? )?;
+    if from > to
+    {
+        return None;
+    }
+    return Some( ( from, to ) );
+}
+
+/// Counts the days in the month of the year in the proleptic Gregorian calendar.
+fn days_in_month(
+    year: i64,
+    month: i64,
+) -> i64
+{
+    return match month
+    {
+        2 if year % 4 == 0 && ( year % 100 != 0 || year % 400 == 0 ) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+}
+
+/// Counts the days since the epoch of the date in the proleptic Gregorian calendar.
+fn days_from_civil(
+    year: i64,
+    month: i64,
+    day: i64,
+) -> i64
+{
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = ( 153 * ( if month > 2 { month - 3 } else { month + 9 } ) + 2 ) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    return era * 146_097 + day_of_era - 719_468;
+}
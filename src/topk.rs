@@ -0,0 +1,231 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::io::prelude::*;
+
+use encoding;
+use evaluation::SetFilter;
+use spill::{self, Spillable, Spool};
+use traits::*;
+
+/// Smallest buffer of a run read or written by the merge in bytes.
+const MIN_MERGE_BUFFER: usize = 4096;
+
+/// Largest number of runs merged at a time.
+const MAX_FAN_IN: usize = 64;
+
+/// Score of a single set of the file.
+#[derive(Clone, Copy)]
+pub struct SetScore
+{
+    /// Index of the set in the file.
+    pub index: usize,
+    /// Number of the values of the set found from the test set.
+    pub score: u64,
+}
+
+impl SetScore
+{
+    /// Orders the sets from the best to the worst. Sets with the same score are ordered by their index.
+    fn rank( &self ) -> ( u64, Reverse<usize> )
+    {
+        return ( self.score, Reverse( self.index ) );
+    }
+}
+
+impl Spillable for SetScore
+{
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> std::io::Result<()>
+    {
+        spill::write_u64( writer, self.index as u64 )?;
+        return spill::write_u64( writer, self.score );
+    }
+
+    fn read_from<R: Read>( reader: &mut R ) -> std::io::Result<SetScore>
+    {
+        let index = spill::read_u64( reader )? as usize;
+        return Ok( SetScore { index: index, score: spill::read_u64( reader )? } );
+    }
+}
+
+/// Sets with the most values found from the test set, the best set first.
+pub struct TopSets
+{
+    pub sets: Spool<SetScore>,
+}
+
+impl TopSets
+{
+    /// Prints the sets with their scores.
+    pub fn print( &self )
+    {
+        if self.sets.is_empty()
+        {
+            println!( "No sets were selected for the top sets." );
+            return;
+        }
+        println!( "Top {} sets by the values found from the test set", self.sets.len() );
+        for set in self.sets.iter()
+        {
+            println!( "Set {}: {} values", set.index, set.score );
+        }
+    }
+}
+
+/// Selects the k sets selected by the filter with the most values found from the test set.
+/// Sets with the same score are ordered by their index.
+/// The sets are selected through temporary files when k sets do not fit in the memory limit in bytes.
+/// Zero disables the limit.
+pub fn top_sets<T>(
+    data: &[u8],
+    test_set: &[T],
+    k: usize,
+    filter: &SetFilter,
+    memory_limit: usize,
+) -> TopSets
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    let query: HashSet<u64> = test_set.iter().map( |v| v.to_ordered_bits() ).collect();
+    let capacity = memory_limit / std::mem::size_of::<SetScore>();
+    if memory_limit > 0 && k >= capacity
+    {
+        return top_sets_spilled::<T>( data, &query, k, filter, memory_limit );
+    }
+
+    // The heap holds the best sets found so far with the worst of them on top.
+    let mut heap: BinaryHeap<Reverse<( u64, Reverse<usize> )>> = BinaryHeap::with_capacity( k + 1 );
+    let mut index = 0;
+    encoding::for_each_set( data, |values: &[T]| {
+        if filter.accepts( index, values.len() )
+        {
+            let score = values.iter().filter( |v| query.contains( &v.to_ordered_bits() ) ).count() as u64;
+            heap.push( Reverse( ( score, Reverse( index ) ) ) );
+            if heap.len() > k
+            {
+                heap.pop();
+            }
+        }
+        index += 1;
+    } );
+    let mut sets: Vec<SetScore> = heap.into_iter()
+            .map( |Reverse( ( score, Reverse( index ) ) )| SetScore { index: index, score: score } )
+            .collect();
+    sets.sort_by( |a, b| b.rank().cmp( &a.rank() ) );
+    return TopSets { sets: Spool::from_vec( sets ) };
+}
+
+/// Selects the best sets with an external merge sort.
+/// The scores are sorted in runs fitting the memory limit which are spilled to temporary files
+/// and merged into the k best sets in passes of as many runs as their buffers fit in the memory limit.
+fn top_sets_spilled<T>(
+    data: &[u8],
+    query: &HashSet<u64>,
+    k: usize,
+    filter: &SetFilter,
+    memory_limit: usize,
+) -> TopSets
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    // The buffer of each run read by the merge and the buffer of the merged run count against the limit.
+    let buffer_size = std::cmp::max( MIN_MERGE_BUFFER, memory_limit / ( MAX_FAN_IN + 1 ) );
+    let fan_in = std::cmp::max( 2, std::cmp::min( MAX_FAN_IN, ( memory_limit / buffer_size ).saturating_sub( 1 ) ) );
+
+    // The run being sorted shares the limit with the buffer it is spilled through.
+    let run_length = std::cmp::max( 1, memory_limit.saturating_sub( buffer_size ) / std::mem::size_of::<SetScore>() );
+    let mut runs: Vec<Spool<SetScore>> = Vec::new();
+    let mut run: Vec<SetScore> = Vec::with_capacity( run_length );
+    let mut index = 0;
+    encoding::for_each_set( data, |values: &[T]| {
+        if filter.accepts( index, values.len() )
+        {
+            let score = values.iter().filter( |v| query.contains( &v.to_ordered_bits() ) ).count() as u64;
+            run.push( SetScore { index: index, score: score } );
+            if run.len() == run_length
+            {
+                runs.push( spill_run( &mut run, k, buffer_size ) );
+            }
+        }
+        index += 1;
+    } );
+    if !run.is_empty()
+    {
+        runs.push( spill_run( &mut run, k, buffer_size ) );
+    }
+    drop( run );
+
+    while runs.len() > 1
+    {
+        let mut merged = Vec::with_capacity( ( runs.len() + fan_in - 1 ) / fan_in );
+        let mut remaining = runs.into_iter();
+        loop
+        {
+            let group: Vec<Spool<SetScore>> = remaining.by_ref().take( fan_in ).collect();
+            match group.len()
+            {
+                0 => break,
+                1 => merged.extend( group ),
+                _ => merged.push( merge_runs( &group, k, buffer_size ) ),
+            }
+        }
+        runs = merged;
+    }
+    return TopSets { sets: runs.pop().unwrap_or_else( || Spool::from_vec( Vec::new() ) ) };
+}
+
+/// Merges the sorted runs into a run of their k best sets.
+fn merge_runs(
+    runs: &[Spool<SetScore>],
+    k: usize,
+    buffer_size: usize,
+) -> Spool<SetScore>
+{
+    // The heap holds the best remaining set of each run.
+    let mut heads: Vec<_> = runs.iter().map( |r| r.iter() ).collect();
+    let mut heap: BinaryHeap<( ( u64, Reverse<usize> ), usize )> = BinaryHeap::with_capacity( heads.len() );
+    for ( r, head ) in heads.iter_mut().enumerate()
+    {
+        if let Some( set ) = head.next()
+        {
+            heap.push( ( set.rank(), r ) );
+        }
+    }
+    let mut best = Spool::on_disk_buffered( buffer_size );
+    while best.len() < k
+    {
+        let ( ( score, Reverse( index ) ), r ) = match heap.pop()
+        {
+            Some( head ) => head,
+            None => break,
+        };
+        best.push( SetScore { index: index, score: score } );
+        if let Some( set ) = heads[ r ].next()
+        {
+            heap.push( ( set.rank(), r ) );
+        }
+    }
+    return best.finish();
+}
+
+/// Sorts the scores of the run from the best to the worst and spills the k best of them to a temporary file.
+/// The rest of the sets cannot be among the k best sets.
+fn spill_run(
+    run: &mut Vec<SetScore>,
+    k: usize,
+    buffer_size: usize,
+) -> Spool<SetScore>
+{
+    run.sort_by( |a, b| b.rank().cmp( &a.rank() ) );
+    let mut spool = Spool::on_disk_buffered( buffer_size );
+    for set in run.drain( .. ).take( k )
+    {
+        spool.push( set );
+    }
+    return spool.finish();
+}
@@ -22,3 +22,77 @@ impl FromI32 for i32
         return value.clone();
     }
 }
+
+/// Trait for mapping values to unsigned keys which sort in the same order as the values.
+pub trait OrderedBits
+{
+    fn to_ordered_bits( &self ) -> u64;
+    fn from_ordered_bits( bits: u64 ) -> Self;
+}
+
+/// Negative floats are inverted and the sign bit of positive floats is set.
+impl OrderedBits for f32
+{
+    fn to_ordered_bits( &self ) -> u64
+    {
+        let bits = self.to_bits();
+        let bits = if bits & 0x8000_0000 != 0 { !bits } else { bits | 0x8000_0000 };
+        return bits as u64;
+    }
+
+    fn from_ordered_bits( bits: u64 ) -> f32
+    {
+        let bits = bits as u32;
+        let bits = if bits & 0x8000_0000 != 0 { bits & 0x7fff_ffff } else { !bits };
+        return f32::from_bits( bits );
+    }
+}
+
+/// The sign bit of integers is flipped.
+impl OrderedBits for i32
+{
+    fn to_ordered_bits( &self ) -> u64
+    {
+        return ( ( *self as u32 ) ^ 0x8000_0000 ) as u64;
+    }
+
+    fn from_ordered_bits( bits: u64 ) -> i32
+    {
+        return ( ( bits as u32 ) ^ 0x8000_0000 ) as i32;
+    }
+}
+
+/// Trait for converting values to and from floating point numbers for arithmetic transformations.
+pub trait Transformable
+{
+    fn to_f64( &self ) -> f64;
+    fn from_f64( value: f64 ) -> Self;
+}
+
+/// Floats are converted directly.
+impl Transformable for f32
+{
+    fn to_f64( &self ) -> f64
+    {
+        return *self as f64;
+    }
+
+    fn from_f64( value: f64 ) -> f32
+    {
+        return value as f32;
+    }
+}
+
+/// Integers are rounded to the nearest integer.
+impl Transformable for i32
+{
+    fn to_f64( &self ) -> f64
+    {
+        return *self as f64;
+    }
+
+    fn from_f64( value: f64 ) -> i32
+    {
+        return value.round() as i32;
+    }
+}
@@ -0,0 +1,73 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use encoding;
+use traits::*;
+
+/// Linear transformation applied to the values of the sets as they are read.
+#[derive(Clone, Copy)]
+pub struct Transform
+{
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl Transform
+{
+    /// Parses the comma separated steps of the transformation, e.g. "scale=0.001,offset=-500".
+    /// The values are scaled before the offset is added.
+    pub fn parse( text: &str ) -> Option<Transform>
+    {
+        let mut transform = Transform { scale: 1.0, offset: 0.0 };
+        for step in text.split( ',' ).map( |s| s.trim() ).filter( |s| !s.is_empty() )
+        {
+            let mut parts = step.splitn( 2, '=' );
+            let name = parts.next()?.trim();
+            let value: f64 = parts.next()?.trim().parse().ok()?;
+            match name
+            {
+                "scale" => transform.scale = value,
+                "offset" => transform.offset = value,
+                _ => return None,
+            }
+        }
+        return Some( transform );
+    }
+
+    /// Applies the transformation to a single value.
+    pub fn apply<T>(
+        &self,
+        value: &T,
+    ) -> T
+    where
+        T: Transformable,
+    {
+        return T::from_f64( value.to_f64() * self.scale + self.offset );
+    }
+}
+
+/// Reads the sets of the file in any encoding and serializes the transformed values into raw sets.
+/// The transformed sets are kept in memory which leaves the file itself untouched.
+pub fn load<T>(
+    data: &[u8],
+    transform: &Transform,
+) -> Vec<T>
+where
+    T: OrderedBits + Transformable + ro_scalar_set::Value,
+{
+    let mut serialized: Vec<u8> = Vec::new();
+    encoding::for_each_set( data, |values: &[T]| {
+        let transformed: Vec<T> = values.iter().map( |v| transform.apply( v ) ).collect();
+        ro_scalar_set::RoScalarSet::new( &transformed ).serialize( &mut serialized ).expect( "Serializing the transformed set failed." );
+    } );
+
+    // Copy into a buffer aligned for the values.
+    let value_count = serialized.len() / std::mem::size_of::<T>();
+    let mut values: Vec<T> = Vec::with_capacity( value_count );
+    unsafe
+    {
+        std::ptr::copy_nonoverlapping( serialized.as_ptr(), values.as_mut_ptr() as *mut u8, value_count * std::mem::size_of::<T>() );
+        values.set_len( value_count );
+    }
+    return values;
+}
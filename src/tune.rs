@@ -0,0 +1,155 @@
+extern crate rand;
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::prelude::*;
+
+use rand::distributions::Range;
+
+use encoding;
+use enumerations::*;
+use evaluation::*;
+use mapping::MappedFile;
+use traits::*;
+use utility::*;
+
+/// Prefetch distances in MB tried for the data read directly from the file.
+const PREFETCH_DISTANCES: &'static [usize] = &[ 0, 16, 64 ];
+
+/// Sizes of the blocks of the test set tried for the raw sets, zero evaluates the whole test set at once.
+const QUERY_BLOCKS: &'static [usize] = &[ 0, 4096, 65536 ];
+
+/// A combination of the evaluation parameters tried by the search.
+#[derive(Clone, Copy)]
+struct Configuration
+{
+    preload: PreloadMode,
+    thread_count: usize,
+    prefetch: usize,
+    query_block: usize,
+}
+
+/// Searches the grid of the options of the eval command for the fastest configuration on this data and hardware.
+/// The preload mode, the thread count, the prefetch distance and the query block are searched.
+/// The best configuration is written to the output file as options of the eval command.
+pub fn tune<T>(
+    file: &String,
+    output: &String,
+    values_in_set: i32,
+    min_value: i32,
+    max_value: i32,
+    query_count: usize,
+)
+where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + WithGpu + OrderedBits + Transformable,
+{
+    // Every configuration is measured with the same test sets.
+    let between = Range::new( min_value, max_value );
+    let test_sets: Vec<Vec<T>> = ( 0..query_count )
+            .map( |_| generate_values( values_in_set, &between ) )
+            .collect();
+    let test_sets: Vec<&[T]> = test_sets.iter().map( |t| t.as_slice() ).collect();
+
+    let mut best: Option<( Configuration, std::time::Duration )> = None;
+    for configuration in get_configurations( file )
+    {
+        let params = EvaluationParams
+        {
+            file: file,
+            values_in_set: values_in_set,
+            min_value: min_value,
+            max_value: max_value,
+            preload: configuration.preload,
+            max_threads: configuration.thread_count,
+            prefetch_distance: configuration.prefetch * 1024 * 1024,
+            cache_size: 0,
+            io_limit: 0,
+            verify_sample: 0,
+            operation: Operation::Any,
+            dimension: None,
+            transform: None,
+            query_block: configuration.query_block,
+            strategy: Strategy::Auto,
+            breakdown: false,
+            explain: false,
+            set_results: false,
+            exclude_file: None,
+            set_filter: SetFilter::all(),
+            eval_engine: "cpu",
+            audit: 0.0,
+            audit_engine: "cpu",
+            repro: false,
+            gpu_compare: GpuCompare::Epsilon,
+            gpu_memory: GpuMemory::Default,
+            gpu_kernel: GpuKernel::Item,
+            top_k: 0,
+            memory_limit: 0,
+            timeout: None,
+            cancellation: None,
+            signature: None,
+        };
+        let duration = evaluate_with_queries( &params, &test_sets ).iter()
+                .fold( std::time::Duration::new( 0, 0 ), |total, r| total + r.duration );
+        println!(
+            "{} took {}.{:06} s",
+            format_options( &configuration ),
+            duration.as_secs(),
+            duration.subsec_nanos() / 1000
+        );
+        best = match best
+        {
+            Some( ( _, best_duration ) ) if best_duration <= duration => best,
+            _ => Some( ( configuration, duration ) ),
+        };
+    }
+
+    let ( configuration, _ ) = best.expect( "No configurations to try." );
+    println!( "Best configuration: {}", format_options( &configuration ) );
+    let mut output = std::fs::File::create( output ).expect( "Failed to open the configuration file." );
+    writeln!( &mut output, "{}", format_options( &configuration ) ).expect( "Writing the configuration failed." );
+}
+
+/// Gets the configurations applicable to the file.
+fn get_configurations( file: &String ) -> Vec<Configuration>
+{
+    // Only the raw sets are prefetched and evaluated in blocks.
+    let raw = {
+        let mapped = MappedFile::open( file ).expect( "Failed to map the file" );
+        encoding::detect( mapped.as_slice() ) == Encoding::Raw
+    };
+    let preload = vec![ PreloadMode::Disabled, PreloadMode::Full ];
+
+    let mut configurations = Vec::new();
+    for pr in preload
+    {
+        // Preloaded data is not prefetched and only the raw sets support prefetching.
+        let prefetch: Vec<usize> = if raw && pr != PreloadMode::Full { PREFETCH_DISTANCES.to_vec() } else { vec![ 0 ] };
+
+        // Only the raw sets are evaluated in blocks of the test set.
+        let query_blocks: Vec<usize> = if raw { QUERY_BLOCKS.to_vec() } else { vec![ 0 ] };
+        for thread_count in get_thread_counts()
+        {
+            for distance in &prefetch
+            {
+                for query_block in &query_blocks
+                {
+                    configurations.push( Configuration { preload: pr, thread_count: thread_count,
+                            prefetch: *distance, query_block: *query_block } );
+                }
+            }
+        }
+    }
+    return configurations;
+}
+
+/// Formats the configuration as options of the eval command.
+fn format_options( configuration: &Configuration ) -> String
+{
+    let preload = match configuration.preload
+    {
+        PreloadMode::Disabled => "none",
+        PreloadMode::Full => "full",
+    };
+    return format!( "--preload={} --threads={} --prefetch={} --query-block={}",
+            preload, configuration.thread_count, configuration.prefetch, configuration.query_block );
+}
@@ -0,0 +1,165 @@
+extern crate byteorder;
+extern crate std;
+
+use std::io::prelude::*;
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+
+use encoding;
+use enumerations::*;
+use evaluation::{evaluate_in_pool, EvaluationResult};
+use traits::*;
+
+/// Identifies a file with sets of tuples.
+const MAGIC: &'static [u8] = b"SSETUPL1";
+
+/// Size of the file header: the magic and the number of values in a tuple.
+const HEADER_LEN: usize = 8 + 4;
+
+/// Checks whether the data holds sets of tuples.
+pub fn is_tuple_file( data: &[u8] ) -> bool
+{
+    data.starts_with( MAGIC )
+}
+
+/// Writes the header of a file with sets of tuples.
+pub fn write_header<W: Write>(
+    writer: &mut W,
+    width: usize,
+) -> std::io::Result<()>
+{
+    writer.write_all( MAGIC )?;
+    writer.write_u32::<LittleEndian>( width as u32 )
+}
+
+/// Writes a single set of tuples.
+/// The values of the tuples are given interleaved. A set is stored as the number of tuples
+/// followed by the ordered keys of the values of the tuples in ascending order of the tuples.
+pub fn write_set<W, T>(
+    writer: &mut W,
+    values: &[T],
+    width: usize,
+) -> std::io::Result<()>
+where
+    W: Write,
+    T: OrderedBits,
+{
+    let mut tuples = to_tuples( values, width );
+    tuples.sort();
+    writer.write_u32::<LittleEndian>( tuples.len() as u32 )?;
+    for key in tuples.iter().flat_map( |t| t.iter() )
+    {
+        writer.write_u64::<LittleEndian>( *key )?;
+    }
+    return Ok( () );
+}
+
+/// Reads the width of the tuples and divides the rest of the data into sets.
+pub fn attach( data: &[u8] ) -> ( usize, Vec<&[u8]> )
+{
+    let width = LittleEndian::read_u32( &data[ MAGIC.len().. ] ) as usize;
+    let mut sets = Vec::new();
+    let mut position = HEADER_LEN;
+    while position < data.len()
+    {
+        let count = LittleEndian::read_u32( &data[ position.. ] ) as usize;
+        let length = 4 + count * width * 8;
+        sets.push( &data[ position..position + length ] );
+        position += length;
+    }
+    return ( width, sets );
+}
+
+/// Tests if any tuple of the set matches the query.
+/// Without a dimension the sorted query holds whole tuples, otherwise
+/// it holds the keys matched against the chosen dimension.
+fn any(
+    set: &[u8],
+    width: usize,
+    query: &[Vec<u64>],
+    dimension: Option<usize>,
+) -> bool
+{
+    let count = LittleEndian::read_u32( set ) as usize;
+    let mut tuple = vec![ 0u64; width ];
+    for i in 0..count
+    {
+        let start = 4 + i * width * 8;
+        match dimension
+        {
+            Some( d ) =>
+            {
+                let key = vec![ LittleEndian::read_u64( &set[ start + d * 8.. ] ) ];
+                if query.binary_search( &key ).is_ok()
+                {
+                    return true;
+                }
+            },
+            None =>
+            {
+                for ( v, key ) in tuple.iter_mut().enumerate()
+                {
+                    *key = LittleEndian::read_u64( &set[ start + v * 8.. ] );
+                }
+                if query.binary_search( &tuple ).is_ok()
+                {
+                    return true;
+                }
+            },
+        }
+    }
+    return false;
+}
+
+/// Evaluates sets of tuples with CPU.
+/// Without a dimension the test set holds interleaved tuples, otherwise
+/// it holds values matched against the chosen dimension of the tuples.
+pub fn evaluate<T>(
+    data: &[u8],
+    test_set: &[T],
+    dimension: Option<usize>,
+    preload: PreloadMode,
+    thread_count: usize,
+) -> EvaluationResult
+where
+    T: OrderedBits,
+{
+    // Load the data into the memory?
+    let preloaded;
+    let data = if preload == PreloadMode::Full
+    {
+        preloaded = data.to_vec();
+        &preloaded[..]
+    }
+    else
+    {
+        data
+    };
+    let ( width, sets ) = attach( data );
+
+    let mut query = match dimension
+    {
+        Some( d ) if d >= width => panic!( "The tuples have only {} dimensions.", width ),
+        Some( _ ) => encoding::sorted_keys( test_set ).into_iter().map( |k| vec![ k ] ).collect(),
+        None => to_tuples( test_set, width ),
+    };
+    query.sort();
+    query.dedup();
+
+    return evaluate_in_pool( &sets, data.len() as u64, preload, thread_count, |s| any( s, width, &query, dimension ) );
+}
+
+/// Groups the interleaved values into tuples of ordered keys.
+/// Values not filling a whole tuple at the end are ignored.
+fn to_tuples<T>(
+    values: &[T],
+    width: usize,
+) -> Vec<Vec<u64>>
+where
+    T: OrderedBits,
+{
+    values.chunks( width )
+        .filter( |c| c.len() == width )
+        .map( |c| c.iter().map( |v| v.to_ordered_bits() ).collect() )
+        .collect()
+}
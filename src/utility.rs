@@ -5,49 +5,341 @@ extern crate rayon;
 
 
 use std::collections::HashSet;
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
 
 use rayon::prelude::*;
 
+use rand::{Rng, SeedableRng, XorShiftRng};
 use rand::distributions::{IndependentSample, Range};
 
+use bitpack;
+use delta;
+use encryption;
+use dictionary;
+use enumerations::*;
+use mapping::WritableMappedFile;
 use traits::*;
+use timestamp;
+use tuple;
+use uuid;
+use weighted;
 
 pub fn generate<T>(
     file: &String,
-    set_count: i32,
+    set_count: u64,
     values_in_set: i32,
     min_value: i32,
     max_value: i32,
+    encoding: Encoding,
+    seed: u64,
+    allow_duplicates: bool,
+    sorted: bool,
+    encrypt: Option<&String>,
 ) where
-    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value,
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + OrderedBits,
 {
 
-    println!( "Generating {} sets to {}...", set_count, file );
-    let mut file = BufWriter::with_capacity(
-        1024 * 1024,
-        std::fs::File::create( file ).expect( "Failed to open the file." ),
-    );
+    println!( "Generating {} sets to {} with seed {}...", set_count, file, seed );
+    let mut file = BufWriter::with_capacity( 1024 * 1024, create_output( file, encrypt ) );
 
     // Prepare RNG.
-    let between = Range::new( min_value, max_value );
+    let sampler = Sampler::new( min_value, max_value, allow_duplicates ).sorted( sorted );
+    sampler.check( values_in_set ).unwrap_or_else( |message| panic!( "{}", message ) );
 
     // Prepare array for holding the results.
-    let sets: Vec<i32> = ( 0..set_count ).collect();
-    let sets: Vec<_> = sets.par_iter()
-        .map( |_| {
-            let values = generate_values::<T>( values_in_set, &between );
-            let result = ro_scalar_set::ro_scalar_set::RoScalarSet::new( values.as_slice() );
-            return result;
+    let sets: Vec<u64> = ( 0..set_count ).collect();
+    match encoding
+    {
+        Encoding::Raw =>
+        {
+            let sets: Vec<_> = sets.par_iter()
+                .map( |index| {
+                    let values = sampler.sample::<T, _>( values_in_set, &mut set_rng( seed, *index ) );
+                    let result = ro_scalar_set::ro_scalar_set::RoScalarSet::new( values.as_slice() );
+                    return result;
+                } )
+                .collect();
+
+            // Serialize the sets to a file.
+            for set in sets
+            {
+                set.serialize( &mut file ).expect(
+                    "Writing scalar set to a file failed.",
+                );
+            }
+        },
+        Encoding::Delta =>
+        {
+            let sets: Vec<_> = sets.par_iter()
+                .map( |index| sampler.sample::<T, _>( values_in_set, &mut set_rng( seed, *index ) ) )
+                .collect();
+
+            // Serialize the sets to a file.
+            delta::write_header( &mut file ).expect( "Writing the header failed." );
+            for set in sets
+            {
+                delta::write_set( &mut file, &set ).expect(
+                    "Writing delta encoded set to a file failed.",
+                );
+            }
+        },
+        Encoding::Bitpack =>
+        {
+            let sets: Vec<_> = sets.par_iter()
+                .map( |index| sampler.sample::<T, _>( values_in_set, &mut set_rng( seed, *index ) ) )
+                .collect();
+
+            // Serialize the sets to a file.
+            let packing = bitpack::Packing { sorted: sorted, ..bitpack::Packing::for_range( &T::from_i32( &min_value ), &T::from_i32( &max_value ) ) };
+            bitpack::write_header( &mut file, &packing ).expect( "Writing the header failed." );
+            for set in sets
+            {
+                bitpack::write_set( &mut file, &set, &packing ).expect(
+                    "Writing bit-packed set to a file failed.",
+                );
+            }
+        },
+        Encoding::Dictionary =>
+        {
+            let sets: Vec<_> = sets.par_iter()
+                .map( |index| sampler.sample::<T, _>( values_in_set, &mut set_rng( seed, *index ) ) )
+                .collect();
+
+            // Serialize the sets to a file.
+            let mut builder = dictionary::DictionaryBuilder::new();
+            for set in &sets
+            {
+                builder.add( set );
+            }
+            let dictionary = builder.build();
+            dictionary::write_header( &mut file, &dictionary ).expect( "Writing the header failed." );
+            for set in sets
+            {
+                dictionary::write_set( &mut file, &set, &dictionary ).expect(
+                    "Writing dictionary encoded set to a file failed.",
+                );
+            }
+        },
+        Encoding::Weighted =>
+        {
+            // The values have random weights between 0 and 1.
+            let weights = Range::new( 0.0f32, 1.0f32 );
+            let sets: Vec<_> = sets.par_iter()
+                .map( |index| {
+                    let mut rng = set_rng( seed, *index );
+                    let values = sampler.sample::<T, _>( values_in_set, &mut rng );
+                    let set_weights: Vec<f32> = values.iter().map( |_| weights.ind_sample( &mut rng ) ).collect();
+                    ( values, set_weights )
+                } )
+                .collect();
+
+            // Serialize the sets to a file.
+            weighted::write_header( &mut file ).expect( "Writing the header failed." );
+            for ( set, set_weights ) in sets
+            {
+                weighted::write_set( &mut file, &set, &set_weights ).expect(
+                    "Writing weighted set to a file failed.",
+                );
+            }
+        },
+    }
+}
+
+/// Generates raw sets into a preallocated file written through a writable memory map.
+/// The sets are generated first and their offsets in the file are computed from their sizes
+/// which lets the workers serialize the sets into their own ranges of the file in parallel.
+pub fn generate_mapped<T>(
+    file: &String,
+    set_count: u64,
+    values_in_set: i32,
+    min_value: i32,
+    max_value: i32,
+    seed: u64,
+    allow_duplicates: bool,
+) where
+    T: FromI32 + std::clone::Clone + std::marker::Send + std::marker::Sync + ro_scalar_set::Value + OrderedBits,
+{
+    println!( "Generating {} sets to {} through a memory map with seed {}...", set_count, file, seed );
+    let sampler = Sampler::new( min_value, max_value, allow_duplicates );
+    sampler.check( values_in_set ).unwrap_or_else( |message| panic!( "{}", message ) );
+    let sets: Vec<_> = ( 0..set_count ).into_par_iter()
+        .map( |index| ro_scalar_set::ro_scalar_set::RoScalarSet::new(
+                sampler.sample::<T, _>( values_in_set, &mut set_rng( seed, index ) ).as_slice() ) )
+        .collect();
+
+    // A serialized set holds the bucket count, the bucket boundaries and the values.
+    let sizes: Vec<usize> = sets.iter()
+        .map( |s| ( 1 + s.bucket_count() + 1 + s.size() ) * std::mem::size_of::<T>() )
+        .collect();
+    let total: usize = sizes.iter().sum();
+    let mut output = WritableMappedFile::create( file, total ).expect( "Failed to open the file." );
+
+    // Divide the mapping into the ranges of the sets.
+    let mut ranges: Vec<&mut [u8]> = Vec::with_capacity( sets.len() );
+    {
+        let mut remaining = output.as_mut_slice();
+        for size in &sizes
+        {
+            let ( range, rest ) = remaining.split_at_mut( *size );
+            ranges.push( range );
+            remaining = rest;
+        }
+    }
+    sets.par_iter()
+        .zip( ranges.into_par_iter() )
+        .for_each( |( set, mut range )| {
+            set.serialize( &mut range ).expect( "Writing scalar set to a file failed." );
+            if !range.is_empty()
+            {
+                panic!( "The serialized set did not fill its range of the file." );
+            }
+        } );
+    output.flush().expect( "Flushing the file failed." );
+}
+
+/// Generates sets of tuples with independently random values in each dimension.
+pub fn generate_tuples<T>(
+    file: &String,
+    set_count: u64,
+    tuples_in_set: i32,
+    width: usize,
+    min_value: i32,
+    max_value: i32,
+    seed: u64,
+    allow_duplicates: bool,
+    encrypt: Option<&String>,
+) where
+    T: FromI32 + std::clone::Clone + std::marker::Send + OrderedBits,
+{
+    println!( "Generating {} sets of {}-tuples to {} with seed {}...", set_count, width, file, seed );
+    let mut file = BufWriter::with_capacity( 1024 * 1024, create_output( file, encrypt ) );
+
+    // The values of a dimension are unique within a set.
+    let sampler = Sampler::new( min_value, max_value, allow_duplicates );
+    sampler.check( tuples_in_set ).unwrap_or_else( |message| panic!( "{}", message ) );
+    let sets: Vec<u64> = ( 0..set_count ).collect();
+    let sets: Vec<Vec<T>> = sets.par_iter()
+        .map( |index| {
+            let mut rng = set_rng( seed, *index );
+            let dimensions: Vec<Vec<T>> = ( 0..width ).map( |_| sampler.sample( tuples_in_set, &mut rng ) ).collect();
+            let mut values = Vec::with_capacity( tuples_in_set as usize * width );
+            for t in 0..tuples_in_set as usize
+            {
+                for d in 0..width
+                {
+                    values.push( dimensions[ d ][ t ].clone() );
+                }
+            }
+            values
         } )
         .collect();
 
-    // Serialize the sets to a file.
+    tuple::write_header( &mut file, width ).expect( "Writing the header failed." );
     for set in sets
     {
-        set.serialize( &mut file ).expect(
-            "Writing scalar set to a file failed.",
-        );
+        tuple::write_set( &mut file, &set, width ).expect( "Writing set of tuples to a file failed." );
+    }
+}
+
+/// Generates sets of UUIDs.
+/// The UUIDs are derived from random values so that test sets drawn from the same range can match.
+pub fn generate_uuids<T>(
+    file: &String,
+    set_count: u64,
+    values_in_set: i32,
+    min_value: i32,
+    max_value: i32,
+    seed: u64,
+    allow_duplicates: bool,
+    encrypt: Option<&String>,
+) where
+    T: FromI32 + OrderedBits,
+{
+    println!( "Generating {} sets of UUIDs to {} with seed {}...", set_count, file, seed );
+    let mut file = BufWriter::with_capacity( 1024 * 1024, create_output( file, encrypt ) );
+
+    let sampler = Sampler::new( min_value, max_value, allow_duplicates );
+    sampler.check( values_in_set ).unwrap_or_else( |message| panic!( "{}", message ) );
+    let sets: Vec<u64> = ( 0..set_count ).collect();
+    let sets: Vec<Vec<u128>> = sets.par_iter()
+        .map( |index| sampler.sample::<T, _>( values_in_set, &mut set_rng( seed, *index ) ).iter()
+                .map( |v| uuid::uuid_of( v.to_ordered_bits() ) )
+                .collect() )
+        .collect();
+
+    uuid::write_header( &mut file ).expect( "Writing the header failed." );
+    for set in sets
+    {
+        uuid::write_set( &mut file, &set ).expect( "Writing set of UUIDs to a file failed." );
+    }
+}
+
+/// Generates sets of timestamps in milliseconds since the epoch.
+/// The timestamps are drawn from the range given in seconds since the epoch.
+pub fn generate_timestamps(
+    file: &String,
+    set_count: u64,
+    values_in_set: i32,
+    min_value: i32,
+    max_value: i32,
+    seed: u64,
+    allow_duplicates: bool,
+    encrypt: Option<&String>,
+)
+{
+    // The timestamps are unique within a set unless duplicates are allowed.
+    let start = min_value as i64 * 1000;
+    let end = max_value as i64 * 1000;
+    let range_size = if end > start { ( end - start ) as u64 } else { 0 };
+    if range_size == 0
+    {
+        panic!( "The maximum value {} must be greater than the minimum value {}.", max_value, min_value );
+    }
+    if !allow_duplicates && values_in_set as u64 > range_size
+    {
+        panic!( "Cannot draw {} unique timestamps from the {} milliseconds between {} and {}, use --allow-duplicates.",
+                values_in_set, range_size, min_value, max_value );
+    }
+
+    println!( "Generating {} sets of timestamps to {} with seed {}...", set_count, file, seed );
+    let mut file = BufWriter::with_capacity( 1024 * 1024, create_output( file, encrypt ) );
+
+    let between = Range::new( start, end );
+    let sets: Vec<u64> = ( 0..set_count ).collect();
+    let sets: Vec<Vec<i64>> = sets.par_iter()
+        .map( |index| {
+            let mut rng = set_rng( seed, *index );
+            let count = values_in_set as usize;
+            if allow_duplicates
+            {
+                return ( 0..count ).map( |_| between.ind_sample( &mut rng ) ).collect();
+            }
+
+            // Timestamps filling at least a quarter of the range are drawn with a partial shuffle of the range.
+            if ( count as u64 ) * 4 >= range_size
+            {
+                let mut range: Vec<i64> = ( start..end ).collect();
+                for i in 0..count
+                {
+                    let j = rng.gen_range( i, range.len() );
+                    range.swap( i, j );
+                }
+                range.truncate( count );
+                return range;
+            }
+            let mut timestamps: HashSet<i64> = HashSet::new();
+            while timestamps.len() < count
+            {
+                timestamps.insert( between.ind_sample( &mut rng ) );
+            }
+            timestamps.into_iter().collect()
+        } )
+        .collect();
+
+    timestamp::write_header( &mut file ).expect( "Writing the header failed." );
+    for set in sets
+    {
+        timestamp::write_set( &mut file, &set ).expect( "Writing set of timestamps to a file failed." );
     }
 }
 
@@ -58,43 +350,254 @@ pub fn generate_values<T>(
 where
     T: FromI32,
 {
+    return generate_values_with( values_in_set, between, &mut rand::thread_rng() );
+}
+
+/// Generates unique random values with the given random number generator.
+/// The values are kept in the order they were drawn so that the same generator state
+/// always produces the same set.
+pub fn generate_values_with<T, R>(
+    values_in_set: i32,
+    between: &Range<i32>,
+    rng: &mut R,
+) -> Vec<T>
+where
+    T: FromI32,
+    R: Rng,
+{
 
     // Collect random values.
-    let mut rng = rand::thread_rng();
     let mut generated_values: HashSet<i32> = HashSet::new();
     generated_values.reserve( values_in_set as usize );
+    let mut values: Vec<T> = Vec::with_capacity( values_in_set as usize );
     while generated_values.len() < values_in_set as usize
     {
 
-        let v = between.ind_sample( &mut rng );
-        generated_values.insert( v );
+        // Convert to appropriate type.
+        let v = between.ind_sample( rng );
+        if generated_values.insert( v )
+        {
+            values.push( T::from_i32( &v ) );
+        }
+    }
+    return values;
+}
+
+/// Draws the values of the generated sets from the range [min_value, max_value).
+#[derive(Clone, Copy)]
+pub struct Sampler
+{
+    min_value: i32,
+    max_value: i32,
+    allow_duplicates: bool,
+    /// The values of each set are returned in ascending order instead of the order they were drawn.
+    sorted: bool,
+}
+
+impl Sampler
+{
+    /// Creates a sampler drawing unique values unless duplicates are allowed.
+    pub fn new(
+        min_value: i32,
+        max_value: i32,
+        allow_duplicates: bool,
+    ) -> Sampler
+    {
+        return Sampler { min_value: min_value, max_value: max_value, allow_duplicates: allow_duplicates, sorted: false };
+    }
+
+    /// Sorts the drawn values of each set in ascending order.
+    pub fn sorted(
+        self,
+        sorted: bool,
+    ) -> Sampler
+    {
+        return Sampler { sorted: sorted, ..self };
     }
 
-    // Convert to appropriate type.
-    let mut values: Vec<T> = Vec::new();
-    for v in &generated_values
+    /// Checks that the number of values can be drawn from the range.
+    pub fn check(
+        &self,
+        values_in_set: i32,
+    ) -> Result<(), String>
     {
-        values.push( T::from_i32( v ) );
+        let range_size = self.range_size();
+        if range_size == 0
+        {
+            return Err( format!( "The maximum value {} must be greater than the minimum value {}.", self.max_value, self.min_value ) );
+        }
+        if !self.allow_duplicates && values_in_set as u64 > range_size
+        {
+            return Err( format!( "Cannot draw {} unique values from the {} values between {} and {}, use --allow-duplicates.",
+                    values_in_set, range_size, self.min_value, self.max_value ) );
+        }
+        return Ok( () );
+    }
+
+    /// Draws the values of a set.
+    /// Unique values filling at least a quarter of the range are drawn with a partial shuffle of the range
+    /// which takes linear time unlike retrying the values already drawn.
+    pub fn sample<T, R>(
+        &self,
+        values_in_set: i32,
+        rng: &mut R,
+    ) -> Vec<T>
+    where
+        T: FromI32,
+        R: Rng,
+    {
+        let mut values = self.draw( values_in_set, rng );
+        if self.sorted
+        {
+            values.sort();
+        }
+        return values.iter().map( |v| T::from_i32( v ) ).collect();
+    }
+
+    /// Draws the values of a set in the order they were drawn.
+    fn draw<R>(
+        &self,
+        values_in_set: i32,
+        rng: &mut R,
+    ) -> Vec<i32>
+    where
+        R: Rng,
+    {
+        let between = Range::new( self.min_value, self.max_value );
+        if self.allow_duplicates
+        {
+            return ( 0..values_in_set ).map( |_| between.ind_sample( rng ) ).collect();
+        }
+        let count = values_in_set as usize;
+        if ( count as u64 ) * 4 < self.range_size()
+        {
+            return generate_values_with( values_in_set, &between, rng );
+        }
+        let mut range: Vec<i32> = ( self.min_value..self.max_value ).collect();
+        for i in 0..count
+        {
+            let j = rng.gen_range( i, range.len() );
+            range.swap( i, j );
+        }
+        range.truncate( count );
+        return range;
+    }
+
+    /// Gets the number of distinct values in the range.
+    fn range_size( &self ) -> u64
+    {
+        return if self.max_value > self.min_value { ( self.max_value as i64 - self.min_value as i64 ) as u64 } else { 0 };
     }
-    return values;
 }
 
-/// Gets file name for a set.
-pub fn get_set_file_name(
-    set_count: &i32,
-    set_size: &i32,
-    floats: &bool,
-) -> String
+/// Creates the file of the generated sets. The sets are encrypted with the key of the key file when one is given.
+fn create_output(
+    file: &String,
+    encrypt: Option<&String>,
+) -> Box<dyn Write + Send>
 {
-    let file_name;
-    if *floats
+    let output = std::fs::File::create( file ).expect( "Failed to open the file." );
+    return match encrypt
     {
-        file_name = format!( "f32_{}_sets_with_{}_values.bin", set_count, set_size,  );
-    }
-    else
+        Some( key_file ) => encryption::encrypt( output, key_file ).expect( "Writing the header failed." ),
+        None => Box::new( output ),
+    };
+}
+
+/// Creates the random number generator of a set from the seed of the generation and the index of the set.
+/// The generator of a set does not depend on the thread generating the set which makes
+/// the generated sets identical regardless of the number of threads.
+pub fn set_rng(
+    seed: u64,
+    index: u64,
+) -> XorShiftRng
+{
+    let high = mix( seed ^ mix( index ) );
+    let low = mix( high );
+
+    // XorShift must not be seeded with all zeros.
+    return XorShiftRng::from_seed( [ ( high >> 32 ) as u32, high as u32, ( low >> 32 ) as u32, low as u32 | 1 ] );
+}
+
+/// Scrambles the bits of the value (SplitMix64 finalizer).
+pub fn mix( value: u64 ) -> u64
+{
+    let mut z = value.wrapping_add( 0x9e3779b97f4a7c15 );
+    z = ( z ^ ( z >> 30 ) ).wrapping_mul( 0xbf58476d1ce4e5b9 );
+    z = ( z ^ ( z >> 27 ) ).wrapping_mul( 0x94d049bb133111eb );
+    return z ^ ( z >> 31 );
+}
+
+/// Gets the thread counts used when comparing the performance with different number of threads.
+/// The maximum number of threads is limited by the number of logical threads
+/// available in the system.
+pub fn get_thread_counts() -> Vec<usize>
+{
+    let mut thread_counts: Vec<usize> = Vec::new();
+    let max_threads = rayon::current_num_threads();
+    let mut last = 1;
+    thread_counts.push( last );
+    while last < max_threads
     {
-        file_name = format!( "i32_{}_sets_with_{}_values.bin", set_count, set_size,  );
+        // Double the number of threads for each test until
+        // max_threads is reached.
+        let next = std::cmp::min( last * 2, max_threads );
+        thread_counts.push( next );
+        last = next;
     }
+    return thread_counts;
+}
+
+/// Parses a count such as "10k" or "1.5M" with the decimal suffixes k, M and G.
+/// An empty text is parsed as zero.
+pub fn parse_count( text: &str ) -> Result<u64, String>
+{
+    let ( number, unit ) = split_unit( text )?;
+    let multiplier: f64 = match unit
+    {
+        "" => 1.0,
+        "k" | "K" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        _ => return Err( format!( "Unknown unit in {}.", text ) ),
+    };
+    return Ok( ( number * multiplier ).round() as u64 );
+}
 
-    file_name
-}
\ No newline at end of file
+/// Parses a size in bytes such as "512k", "2GiB" or "1.5 MB" with the binary units k, M, G and T.
+/// Numbers without a unit are given in the default unit. An empty text is parsed as zero.
+pub fn parse_size(
+    text: &str,
+    default_unit: u64,
+) -> Result<u64, String>
+{
+    let ( number, unit ) = split_unit( text )?;
+    let unit = unit.trim_end_matches( "iB" ).trim_end_matches( 'B' );
+    let multiplier: u64 = match unit
+    {
+        "" if text.trim().ends_with( 'B' ) => 1,
+        "" => default_unit,
+        "k" | "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err( format!( "Unknown unit in {}.", text ) ),
+    };
+    return Ok( ( number * multiplier as f64 ).round() as u64 );
+}
+
+/// Splits the text into a non-negative number and the unit following it.
+fn split_unit( text: &str ) -> Result<( f64, &str ), String>
+{
+    let text = text.trim();
+    if text.is_empty()
+    {
+        return Ok( ( 0.0, "" ) );
+    }
+    let end = text.find( |c: char| !c.is_digit( 10 ) && c != '.' ).unwrap_or( text.len() );
+    return match text[ ..end ].parse::<f64>()
+    {
+        Ok( number ) => Ok( ( number, text[ end.. ].trim() ) ),
+        Err( _ ) => Err( format!( "Invalid number: {}", text ) ),
+    };
+}
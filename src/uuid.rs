@@ -0,0 +1,193 @@
+extern crate byteorder;
+extern crate std;
+
+use std::io::prelude::*;
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+
+use enumerations::*;
+use evaluation::{evaluate_in_pool, EvaluationResult};
+use traits::*;
+use utility::mix;
+
+/// Identifies a file with sets of UUIDs.
+const MAGIC: &'static [u8] = b"SSEUUID1";
+
+/// Size of a single UUID stored as two 64-bit halves.
+const UUID_LEN: usize = 16;
+
+/// Checks whether the data holds sets of UUIDs.
+pub fn is_uuid_file( data: &[u8] ) -> bool
+{
+    data.starts_with( MAGIC )
+}
+
+/// Writes the header of a file with sets of UUIDs.
+pub fn write_header<W: Write>( writer: &mut W ) -> std::io::Result<()>
+{
+    writer.write_all( MAGIC )
+}
+
+/// Writes a single set of UUIDs.
+/// A set is stored as the number of UUIDs followed by the UUIDs in ascending order,
+/// each as its high and low 64-bit half.
+pub fn write_set<W: Write>(
+    writer: &mut W,
+    uuids: &[u128],
+) -> std::io::Result<()>
+{
+    let mut sorted = uuids.to_vec();
+    sorted.sort();
+    writer.write_u32::<LittleEndian>( sorted.len() as u32 )?;
+    for uuid in sorted
+    {
+        writer.write_u64::<LittleEndian>( ( uuid >> 64 ) as u64 )?;
+        writer.write_u64::<LittleEndian>( uuid as u64 )?;
+    }
+    return Ok( () );
+}
+
+/// Divides the data into sets of UUIDs.
+pub fn attach( data: &[u8] ) -> Vec<&[u8]>
+{
+    let mut sets = Vec::new();
+    let mut position = MAGIC.len();
+    while position < data.len()
+    {
+        let count = LittleEndian::read_u32( &data[ position.. ] ) as usize;
+        let length = 4 + count * UUID_LEN;
+        sets.push( &data[ position..position + length ] );
+        position += length;
+    }
+    return sets;
+}
+
+/// Decodes the UUIDs of a set.
+fn decode( set: &[u8] ) -> Vec<u128>
+{
+    let count = LittleEndian::read_u32( set ) as usize;
+    return ( 0..count ).map( |i| read_uuid( &set[ 4 + i * UUID_LEN.. ] ) ).collect();
+}
+
+/// Reads a single UUID.
+fn read_uuid( entry: &[u8] ) -> u128
+{
+    ( LittleEndian::read_u64( entry ) as u128 ) << 64 | LittleEndian::read_u64( &entry[ 8.. ] ) as u128
+}
+
+/// Tests if any UUID of the set is found from the sorted query.
+fn any(
+    set: &[u8],
+    query: &[u128],
+) -> bool
+{
+    let count = LittleEndian::read_u32( set ) as usize;
+    for i in 0..count
+    {
+        if query.binary_search( &read_uuid( &set[ 4 + i * UUID_LEN.. ] ) ).is_ok()
+        {
+            return true;
+        }
+    }
+    return false;
+}
+
+/// Evaluates sets of UUIDs with CPU.
+/// The values of the test set are mapped to UUIDs the same way as on generation.
+pub fn evaluate<T>(
+    data: &[u8],
+    test_set: &[T],
+    preload: PreloadMode,
+    thread_count: usize,
+) -> EvaluationResult
+where
+    T: OrderedBits,
+{
+    let query: Vec<u128> = test_set.iter().map( |v| uuid_of( v.to_ordered_bits() ) ).collect();
+    return evaluate_uuids( data, &query, preload, thread_count );
+}
+
+/// Evaluates sets of UUIDs against the UUIDs of the query with CPU.
+pub fn evaluate_uuids(
+    data: &[u8],
+    query: &[u128],
+    preload: PreloadMode,
+    thread_count: usize,
+) -> EvaluationResult
+{
+    let mut query = query.to_vec();
+    query.sort();
+    query.dedup();
+
+    // Load the data into the memory?
+    let preloaded;
+    let data = if preload == PreloadMode::Full
+    {
+        preloaded = data.to_vec();
+        &preloaded[..]
+    }
+    else
+    {
+        data
+    };
+    let sets = attach( data );
+
+    return evaluate_in_pool( &sets, data.len() as u64, preload, thread_count, |s| any( s, &query ) );
+}
+
+/// Calls the function with the UUIDs of each set.
+pub fn for_each_set<F>(
+    data: &[u8],
+    mut f: F,
+)
+where
+    F: FnMut( &[u128] ),
+{
+    for set in attach( data )
+    {
+        f( &decode( set ) );
+    }
+}
+
+/// Maps the ordered key of a value to a random looking version 4 UUID.
+/// The mapping is deterministic so that the generated sets and the test sets
+/// drawn from the same value range can match.
+pub fn uuid_of( key: u64 ) -> u128
+{
+    let high = mix( key );
+    let low = mix( high );
+    let uuid = ( high as u128 ) << 64 | low as u128;
+
+    // Set the version and the variant bits.
+    return uuid & !( 0xf << 76 ) & !( 0x3 << 62 ) | ( 0x4 << 76 ) | ( 0x2 << 62 );
+}
+
+/// Reads a text query file of UUIDs in the hyphenated hex format separated by commas or whitespace.
+/// Returns None if the file is not such a text file.
+pub fn read_query( file: &String ) -> Option<Vec<u128>>
+{
+    let text = std::fs::read_to_string( file ).ok()?;
+    let uuids: Option<Vec<u128>> = text.split( |c: char| c == ',' || c.is_whitespace() )
+            .filter( |t| !t.is_empty() )
+            .map( parse )
+            .collect();
+    return uuids.filter( |u| !u.is_empty() );
+}
+
+/// Parses a UUID in the hyphenated hex format.
+pub fn parse( text: &str ) -> Option<u128>
+{
+    let hex: String = text.chars().filter( |c| *c != '-' ).collect();
+    if hex.len() != 32
+    {
+        return None;
+    }
+    return u128::from_str_radix( &hex, 16 ).ok();
+}
+
+/// Formats the UUID in the hyphenated hex format.
+pub fn format( uuid: u128 ) -> String
+{
+    let hex = format!( "{:032x}", uuid );
+    return format!( "{}-{}-{}-{}-{}", &hex[ 0..8 ], &hex[ 8..12 ], &hex[ 12..16 ], &hex[ 16..20 ], &hex[ 20..32 ] );
+}
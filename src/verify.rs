@@ -0,0 +1,66 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::collections::HashSet;
+
+use encoding;
+use explain::SetMatch;
+use spill::Spool;
+use traits::*;
+
+/// Outcome of comparing the evaluation against the reference implementation.
+pub struct Verification
+{
+    pub sets_checked: usize,
+    /// Indexes of the sets for which the engines disagreed.
+    pub mismatches: Vec<usize>,
+}
+
+/// Compares the results of the sets from the engine against the reference implementation on a sample of the sets.
+pub fn verify<T>(
+    data: &[u8],
+    test_set: &[T],
+    sample_size: usize,
+    engine: &Spool<SetMatch>,
+) -> Verification
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    // Pick evenly spaced sets.
+    let step = std::cmp::max( 1, engine.len() / std::cmp::max( 1, sample_size ) );
+    let sample: Vec<SetMatch> = engine.iter().step_by( step ).take( sample_size ).collect();
+    let indexes: Vec<usize> = sample.iter().map( |s| s.index ).collect();
+
+    let expected = reference_matches( data, test_set, &indexes );
+    let mismatches = sample.iter()
+            .zip( expected.iter() )
+            .filter( |&( s, e )| s.matched != *e )
+            .map( |( s, _ )| s.index )
+            .collect();
+    return Verification { sets_checked: indexes.len(), mismatches: mismatches };
+}
+
+/// Evaluates the sets at the given indexes with a linear scan of the values against a hash set.
+/// The implementation is slow but obviously correct.
+fn reference_matches<T>(
+    data: &[u8],
+    test_set: &[T],
+    indexes: &[usize],
+) -> Vec<bool>
+where
+    T: OrderedBits + ro_scalar_set::Value,
+{
+    let query: HashSet<u64> = test_set.iter().map( |v| v.to_ordered_bits() ).collect();
+    let mut matches = Vec::with_capacity( indexes.len() );
+    let mut index = 0;
+    let mut next = 0;
+    encoding::for_each_set( data, |values: &[T]| {
+        if next < indexes.len() && indexes[ next ] == index
+        {
+            matches.push( values.iter().any( |v| query.contains( &v.to_ordered_bits() ) ) );
+            next += 1;
+        }
+        index += 1;
+    } );
+    return matches;
+}
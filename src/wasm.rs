@@ -0,0 +1,67 @@
+extern crate ro_scalar_set;
+extern crate std;
+
+/// Allocates a buffer for passing the sets or the test set from JavaScript.
+/// The buffer is aligned for the values and must be released with sse_free.
+#[no_mangle]
+pub extern "C" fn sse_alloc( len: usize ) -> *mut u8
+{
+    let mut buffer: Vec<i32> = Vec::with_capacity( value_count( len ) );
+    let pointer = buffer.as_mut_ptr() as *mut u8;
+    std::mem::forget( buffer );
+    return pointer;
+}
+
+/// Releases a buffer allocated with sse_alloc.
+#[no_mangle]
+pub extern "C" fn sse_free(
+    pointer: *mut u8,
+    len: usize,
+)
+{
+    if !pointer.is_null()
+    {
+        unsafe { drop( Vec::from_raw_parts( pointer as *mut i32, 0, value_count( len ) ) ) };
+    }
+}
+
+/// Counts the raw integer sets of the buffer with any of the given values.
+/// The buffer holds the contents of a set file generated without an encoding.
+/// The sets are evaluated sequentially as the browsers do not provide the threads for the thread pool.
+#[no_mangle]
+pub extern "C" fn sse_evaluate_buffer(
+    data: *const u8,
+    data_len: usize,
+    values: *const i32,
+    value_count: usize,
+) -> u32
+{
+    if data.is_null() || values.is_null()
+    {
+        return 0;
+    }
+    let mut buffer: &[i32] = unsafe { std::slice::from_raw_parts( data as *const i32, data_len / 4 ) };
+    let test_set = unsafe { std::slice::from_raw_parts( values, value_count ) };
+    let test_set = ro_scalar_set::RoScalarSet::new( test_set );
+    let mut match_count = 0;
+    loop
+    {
+        let ( set, remaining ) = match ro_scalar_set::RoScalarSet::attach( buffer )
+        {
+            Ok( result ) => result,
+            Err( _ ) => break,
+        };
+        if test_set.any( &set )
+        {
+            match_count += 1;
+        }
+        buffer = remaining;
+    }
+    return match_count;
+}
+
+/// Gets the number of values required to hold the bytes.
+fn value_count( len: usize ) -> usize
+{
+    ( len + 3 ) / 4
+}
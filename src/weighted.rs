@@ -0,0 +1,166 @@
+extern crate byteorder;
+extern crate std;
+
+use std::io::prelude::*;
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+
+use encoding;
+use enumerations::*;
+use evaluation::{evaluate_in_pool, EvaluationResult};
+use intersect;
+use traits::*;
+
+/// Identifies a file with weighted sets.
+const MAGIC: &'static [u8] = b"SSEWGHT1";
+
+/// Size of a single value and its weight.
+const ENTRY_LEN: usize = 8 + 4;
+
+/// Checks whether the data is in the weighted format.
+pub fn is_weighted( data: &[u8] ) -> bool
+{
+    data.starts_with( MAGIC )
+}
+
+/// Writes the header of a weighted file.
+pub fn write_header<W: Write>( writer: &mut W ) -> std::io::Result<()>
+{
+    writer.write_all( MAGIC )
+}
+
+/// Writes a single set in the weighted format.
+/// A set is stored as the number of values followed by the ordered keys of the values
+/// in ascending order, each with its weight.
+pub fn write_set<W, T>(
+    writer: &mut W,
+    values: &[T],
+    weights: &[f32],
+) -> std::io::Result<()>
+where
+    W: Write,
+    T: OrderedBits,
+{
+    let mut entries: Vec<( u64, f32 )> = values.iter()
+            .map( |v| v.to_ordered_bits() )
+            .zip( weights.iter().cloned() )
+            .collect();
+    entries.sort_by_key( |e| e.0 );
+
+    writer.write_u32::<LittleEndian>( entries.len() as u32 )?;
+    for ( key, weight ) in entries
+    {
+        writer.write_u64::<LittleEndian>( key )?;
+        writer.write_f32::<LittleEndian>( weight )?;
+    }
+    return Ok( () );
+}
+
+/// Divides weighted data into sets.
+pub fn attach( data: &[u8] ) -> Vec<&[u8]>
+{
+    let mut sets = Vec::new();
+    let mut position = MAGIC.len();
+    while position < data.len()
+    {
+        let count = LittleEndian::read_u32( &data[ position.. ] ) as usize;
+        let length = 4 + count * ENTRY_LEN;
+        sets.push( &data[ position..position + length ] );
+        position += length;
+    }
+    return sets;
+}
+
+/// Decodes the ordered keys and the weights of the values of a set.
+pub fn decode( set: &[u8] ) -> Vec<( u64, f32 )>
+{
+    let count = LittleEndian::read_u32( set ) as usize;
+    return ( 0..count )
+        .map( |i| {
+            let entry = &set[ 4 + i * ENTRY_LEN.. ];
+            ( LittleEndian::read_u64( entry ), LittleEndian::read_f32( &entry[ 8.. ] ) )
+        } )
+        .collect();
+}
+
+/// Sums the weights of the values of the set found from the sorted query keys.
+pub fn overlap(
+    set: &[u8],
+    query: &[u64],
+) -> f32
+{
+    let count = LittleEndian::read_u32( set ) as usize;
+    let mut total = 0.0;
+    for i in 0..count
+    {
+        let entry = &set[ 4 + i * ENTRY_LEN.. ];
+        if query.binary_search( &LittleEndian::read_u64( entry ) ).is_ok()
+        {
+            total += LittleEndian::read_f32( &entry[ 8.. ] );
+        }
+    }
+    return total;
+}
+
+/// Tests if any value of the set is found from the sorted query keys.
+pub fn any(
+    set: &[u8],
+    query: &[u64],
+    strategy: Strategy,
+) -> bool
+{
+    let count = LittleEndian::read_u32( set ) as usize;
+    return intersect::any_sorted( count, |i| LittleEndian::read_u64( &set[ 4 + i * ENTRY_LEN.. ] ), query, strategy );
+}
+
+/// Evaluates weighted sets with CPU.
+/// A set matches when the weights of its values found from the test set sum up to at least the threshold.
+/// Without a threshold any common value is a match.
+pub fn evaluate<T>(
+    data: &[u8],
+    test_set: &[T],
+    threshold: Option<f32>,
+    strategy: Strategy,
+    preload: PreloadMode,
+    thread_count: usize,
+) -> EvaluationResult
+where
+    T: OrderedBits,
+{
+    let query = encoding::sorted_keys( test_set );
+
+    // Load the data into the memory?
+    let preloaded;
+    let data = if preload == PreloadMode::Full
+    {
+        preloaded = data.to_vec();
+        &preloaded[..]
+    }
+    else
+    {
+        data
+    };
+    let sets = attach( data );
+
+    return evaluate_in_pool( &sets, data.len() as u64, preload, thread_count, |s| match threshold
+    {
+        Some( threshold ) => overlap( s, &query ) >= threshold,
+        None => any( s, &query, strategy ),
+    } );
+}
+
+/// Calls the function with the values of each set. The weights are ignored.
+pub fn for_each_set<T, F>(
+    data: &[u8],
+    mut f: F,
+)
+where
+    T: OrderedBits,
+    F: FnMut( &[T] ),
+{
+    for set in attach( data )
+    {
+        let values: Vec<T> = decode( set ).into_iter().map( |( k, _ )| T::from_ordered_bits( k ) ).collect();
+        f( &values );
+    }
+}
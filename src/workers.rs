@@ -0,0 +1,133 @@
+extern crate byteorder;
+extern crate ro_scalar_set;
+extern crate std;
+
+use std::io::BufWriter;
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::os::unix::io::{FromRawFd, IntoRawFd};
+#[cfg(unix)]
+use std::process::{Command, Stdio};
+
+use byteorder::{NativeEndian, WriteBytesExt};
+
+use evaluation;
+use mapping::MappedFile;
+use server::CorpusSpec;
+
+/// Environment variable holding the file of the shared index in the worker processes.
+const INDEX_VARIABLE: &'static str = "SCALAR_SET_EVAL_INDEX";
+
+/// Environment variable holding the number of the worker process.
+const WORKER_VARIABLE: &'static str = "SCALAR_SET_EVAL_WORKER";
+
+/// Serves the corpus with worker processes instead of threads.
+/// The offsets of the sets are indexed once into a file in shared memory and the worker processes,
+/// started with the same arguments, map the set file and the index and accept the connections
+/// from the socket inherited as their standard input. The pages of the mappings are shared by the workers.
+#[cfg(unix)]
+pub fn serve_with_workers(
+    spec: &CorpusSpec,
+    address: &str,
+    workers: usize,
+)
+{
+    let start = std::time::Instant::now();
+    let file = MappedFile::open( &spec.file ).expect( "Failed to map the file" );
+    let offsets = if spec.floats
+    {
+        evaluation::set_offsets::<f32>( file.as_slice() )
+    }
+    else
+    {
+        evaluation::set_offsets::<i32>( file.as_slice() )
+    };
+    let index = shared_directory().join( format!( "scalar_set_eval-{}.index", std::process::id() ) );
+    write_index( &index, &offsets ).expect( "Writing the index failed." );
+    let elapsed = std::time::Instant::now().duration_since( start );
+    println!( "Indexed {} sets in {} ms", offsets.len(), elapsed.as_secs() * 1000 + ( elapsed.subsec_nanos() / 1000000 ) as u64 );
+
+    let listener = TcpListener::bind( address ).expect( "Failed to bind the address." );
+    println!( "Serving {} as {} at {} with {} worker processes...", spec.file, spec.name, address, workers );
+    let executable = std::env::current_exe().expect( "Failed to locate the executable." );
+    let mut children = Vec::with_capacity( workers );
+    for worker in 0..workers
+    {
+        let socket = listener.try_clone().expect( "Failed to share the socket." );
+        let child = Command::new( &executable )
+                .args( std::env::args().skip( 1 ) )
+                .env( INDEX_VARIABLE, &index )
+                .env( WORKER_VARIABLE, worker.to_string() )
+                .stdin( unsafe { Stdio::from_raw_fd( socket.into_raw_fd() ) } )
+                .spawn()
+                .expect( "Failed to start a worker process." );
+        children.push( child );
+    }
+    for mut child in children
+    {
+        if let Err( e ) = child.wait()
+        {
+            println!( "Waiting for a worker process failed: {}", e );
+        }
+    }
+    std::fs::remove_file( &index ).ok();
+}
+
+/// Worker processes are only supported on Unix.
+#[cfg(not(unix))]
+pub fn serve_with_workers(
+    _spec: &CorpusSpec,
+    _address: &str,
+    _workers: usize,
+)
+{
+    panic!( "Serving with worker processes requires a Unix system." );
+}
+
+/// Gets the number of this worker process and the mapped index of the sets
+/// when this process was started as a worker.
+pub fn shared_index() -> Option<( usize, MappedFile )>
+{
+    let index = std::env::var( INDEX_VARIABLE ).ok()?;
+    let worker = std::env::var( WORKER_VARIABLE ).ok().and_then( |w| w.parse::<usize>().ok() ).unwrap_or( 0 );
+    return Some( ( worker, MappedFile::open( &index ).expect( "Failed to map the index." ) ) );
+}
+
+/// Takes the listening socket inherited by the worker process as its standard input.
+#[cfg(unix)]
+pub fn inherited_listener() -> TcpListener
+{
+    return unsafe { TcpListener::from_raw_fd( 0 ) };
+}
+
+/// Worker processes are only supported on Unix.
+#[cfg(not(unix))]
+pub fn inherited_listener() -> TcpListener
+{
+    panic!( "Serving with worker processes requires a Unix system." );
+}
+
+/// Gets the directory of the shared index. The memory file system is preferred when available.
+fn shared_directory() -> std::path::PathBuf
+{
+    let shm = std::path::Path::new( "/dev/shm" );
+    if shm.is_dir()
+    {
+        return shm.to_path_buf();
+    }
+    return std::env::temp_dir();
+}
+
+/// Writes the offsets in the byte order of the machine for mapping them directly in the workers.
+fn write_index(
+    file: &std::path::Path,
+    offsets: &[u64],
+) -> std::io::Result<()>
+{
+    let mut writer = BufWriter::new( std::fs::File::create( file )? );
+    for offset in offsets
+    {
+        writer.write_u64::<NativeEndian>( *offset )?;
+    }
+    return Ok( () );
+}
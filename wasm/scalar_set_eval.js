@@ -0,0 +1,44 @@
+// Thin JavaScript wrapper of the WebAssembly build of the CPU evaluation.
+// Build the module with:
+// cargo build --lib --release --target wasm32-unknown-unknown --features wasm
+
+// Loads the module from the given URL.
+export async function load( url )
+{
+    const { instance } = await WebAssembly.instantiateStreaming( fetch( url ) );
+    return new Evaluator( instance.exports );
+}
+
+export class Evaluator
+{
+    constructor( exports )
+    {
+        this.exports = exports;
+    }
+
+    // Counts the sets of the raw set file contents with any of the integer values.
+    evaluate( data, values )
+    {
+        const bytes = new Uint8Array( data );
+        const query = Int32Array.from( values );
+        const dataPointer = this.copy( bytes );
+        const queryPointer = this.copy( new Uint8Array( query.buffer ) );
+        try
+        {
+            return this.exports.sse_evaluate_buffer( dataPointer, bytes.length, queryPointer, query.length );
+        }
+        finally
+        {
+            this.exports.sse_free( dataPointer, bytes.length );
+            this.exports.sse_free( queryPointer, query.byteLength );
+        }
+    }
+
+    // Copies the bytes into the memory of the module.
+    copy( bytes )
+    {
+        const pointer = this.exports.sse_alloc( bytes.length );
+        new Uint8Array( this.exports.memory.buffer, pointer, bytes.length ).set( bytes );
+        return pointer;
+    }
+}